@@ -0,0 +1,173 @@
+//! Times a few programs exercising the hot paths of the evaluator and counts the allocations they
+//! make, to compare changes to it:
+//!
+//! ```bash
+//! cargo run --release --example benchmark
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use monkey_interpreter::{
+    evaluator::{environment::Environment, Evaluator},
+    parse,
+};
+
+/// Times each program is evaluated, the fastest run being reported.
+const RUNS: usize = 5;
+
+/// Calls simulated by the binding comparison, each binding the same parameters again.
+const CALLS: usize = 100_000;
+const CALL_PARAMETERS: [&str; 3] = ["first", "second", "third"];
+
+/// System allocator counting the allocations made through it.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Functions nested in one another around the loop of the scope lookup program, each binding
+/// `SCOPE_WIDTH` names.
+const SCOPE_DEPTH: usize = 30;
+const SCOPE_WIDTH: usize = 20;
+
+/// Parameters of the function of the identifier program, each referenced once in its body and
+/// in each call to it.
+const PARAMETERS: usize = 200;
+
+fn programs() -> Vec<(&'static str, String)> {
+    vec![
+        (
+            "fib(25)",
+            "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(25)".into(),
+        ),
+        (
+            "push 10k elements",
+            r#"let fill = fn(array, n) { if (n == 0) { array } else { fill(push(array, "element"), n - 1) } }; len(fill([], 10000))"#
+                .into(),
+        ),
+        (
+            "lookups 30 scopes up",
+            format!(
+                "let a = 1; let o = 2; let z = 3; {}for (i in 0..100000) {{ a; o; z; a; o; z }}; a{}",
+                (0..SCOPE_DEPTH)
+                    .map(|depth| {
+                        let bindings = (0..SCOPE_WIDTH)
+                            .map(|index| {
+                                let name = format!("name_{}_{}", letters(depth), letters(index));
+                                format!("let {name} = {index}; ")
+                            })
+                            .collect::<String>();
+                        format!("(fn() {{ {bindings}")
+                    })
+                    .collect::<String>(),
+                " })()".repeat(SCOPE_DEPTH)
+            ),
+        ),
+        (
+            "200 parameters",
+            format!(
+                "let sum = fn({}) {{ {} }}; for (i in 0..2000) {{ sum({}) }}",
+                (0..PARAMETERS)
+                    .map(|index| format!("parameter_{}", letters(index)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                (0..PARAMETERS)
+                    .map(|index| format!("parameter_{}", letters(index)))
+                    .collect::<Vec<_>>()
+                    .join(" + "),
+                vec!["i"; PARAMETERS].join(", "),
+            ),
+        ),
+    ]
+}
+
+/// Digits of `number` spelled as letters, identifiers not being able to hold digits.
+fn letters(number: usize) -> String {
+    number
+        .to_string()
+        .bytes()
+        .map(|digit| char::from(digit - b'0' + b'a'))
+        .collect()
+}
+
+fn main() {
+    for (name, source) in programs() {
+        let parsing = fastest(|| {
+            parse(&source).expect("benchmark programs parse");
+        });
+
+        let program = parse(&source).expect("benchmark programs parse");
+        let mut evaluate = || {
+            program
+                .eval(&Environment::shared())
+                .expect("benchmark programs evaluate");
+        };
+        let evaluation = fastest(&mut evaluate);
+        let allocations = allocations(evaluate);
+
+        println!(
+            "{name}: parsed in {parsing:?}, evaluated in {evaluation:?} with {allocations} allocations"
+        );
+    }
+
+    // Binding the parameters of a call the way function calls did before `set_ref`, which
+    // allocates a key for each of them even once the scope already holds it
+    let mut environment = Environment::new();
+    let owned = allocations(|| {
+        for call in 0..CALLS {
+            for name in CALL_PARAMETERS {
+                environment.set(name.to_string(), (call as i64).into());
+            }
+        }
+    });
+    let mut environment = Environment::new();
+    let borrowed = allocations(|| {
+        for call in 0..CALLS {
+            for name in CALL_PARAMETERS {
+                environment.set_ref(name, (call as i64).into());
+            }
+        }
+    });
+    println!("binding {CALLS} calls: {owned} allocations with set, {borrowed} with set_ref");
+}
+
+/// Allocations made by one run of `run`.
+fn allocations(run: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    run();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// Fastest of `RUNS` runs of `run`.
+fn fastest(mut run: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or(Duration::ZERO)
+}
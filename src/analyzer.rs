@@ -0,0 +1,649 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    ast::{BlockStatement, Expression, Pattern, Spanned, Statement},
+    evaluator::builtins::Builtin,
+    lexer::Span,
+    parser::{Diagnostic, Program},
+};
+
+/// Warnings about the identifiers of `program` that can't be bound when they are evaluated, and
+/// about the bindings and parameters it never uses, before running it. Names starting with `_`
+/// can be left unused.
+///
+/// Scopes follow the evaluation: a program, each function call, each `if` or `else` block and
+/// each iteration of a loop get their own, the one of a `for` loop binding its variable. Bindings
+/// made later in a scope are seen by the functions defined in it, which may only be called once
+/// they are made, but not by the code running in it directly.
+pub fn analyze(program: &Program) -> Vec<Diagnostic> {
+    analyze_with(program, AnalysisOptions::default())
+}
+
+/// Like [`analyze`], also reporting the optional warnings turned on in `options`.
+pub fn analyze_with(program: &Program, options: AnalysisOptions) -> Vec<Diagnostic> {
+    Analyzer::new(BTreeSet::new(), true, options).analyze(program)
+}
+
+/// Like [`analyze_with`], for a program evaluated in an environment already binding `bound`,
+/// e.g. the one of a REPL session. Its top-level bindings aren't reported as unused, later
+/// programs evaluated in the same environment being able to use them, nor as redeclaring the
+/// ones of the environment.
+pub fn analyze_in(
+    program: &Program,
+    bound: &BTreeSet<String>,
+    options: AnalysisOptions,
+) -> Vec<Diagnostic> {
+    Analyzer::new(bound.clone(), false, options).analyze(program)
+}
+
+/// Warnings of the analysis that are off unless asked for.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct AnalysisOptions<'a> {
+    /// Source the program was parsed from, set to report the `let`s and parameters shadowing a
+    /// binding of an enclosing scope, giving the line of that binding, and the `let`s binding a
+    /// name again in the same scope. Neither changes what the program does.
+    pub shadowing: Option<&'a str>,
+}
+
+/// Identifier not bound yet when it was used.
+struct Use {
+    name: String,
+    span: Span,
+    /// Whether the scope it was used in binds it afterwards
+    declared_later: bool,
+    /// Whether it was used within a function defined in the scope holding it, rather than
+    /// directly in that scope
+    nested: bool,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum BindingKind {
+    Variable,
+    Parameter,
+    /// Loop variables, never reported as unused
+    Other,
+    /// Bindings of the environment the program is evaluated in, which it can freely rebind
+    Environment,
+}
+
+struct Binding {
+    name: String,
+    kind: BindingKind,
+    /// Span of the `let` or of the function making it
+    span: Span,
+    used: bool,
+}
+
+#[derive(Default)]
+struct Scope {
+    /// Bindings made so far, the last one of a name shadowing the previous ones
+    bindings: Vec<Binding>,
+    /// Names used directly in the scope so far
+    uses: Vec<String>,
+    /// Uses left to resolve once all the bindings of the scope are known
+    pending: Vec<Use>,
+}
+
+impl Scope {
+    /// Last binding of `name` made so far.
+    fn latest(&self, name: &str) -> Option<&Binding> {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|binding| binding.name == name)
+    }
+
+    fn binding(&mut self, name: &str) -> Option<&mut Binding> {
+        self.bindings
+            .iter_mut()
+            .rev()
+            .find(|binding| binding.name == name)
+    }
+}
+
+struct Analyzer<'a> {
+    scopes: Vec<Scope>,
+    /// Whether unused top-level bindings are reported
+    report_program_scope: bool,
+    options: AnalysisOptions<'a>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Analyzer<'a> {
+    fn new(
+        bound: BTreeSet<String>,
+        report_program_scope: bool,
+        options: AnalysisOptions<'a>,
+    ) -> Self {
+        let bindings = bound
+            .into_iter()
+            .map(|name| Binding {
+                name,
+                kind: BindingKind::Environment,
+                span: Span::default(),
+                used: true,
+            })
+            .collect();
+
+        Self {
+            scopes: vec![Scope {
+                bindings,
+                ..Default::default()
+            }],
+            report_program_scope,
+            options,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn analyze(mut self, program: &Program) -> Vec<Diagnostic> {
+        self.statements(&program.statements);
+
+        for unresolved in self.close_scope() {
+            self.diagnostics.push(if unresolved.declared_later {
+                Diagnostic::warning(
+                    "use-before-declaration",
+                    format!("`{}` is used before being declared", unresolved.name),
+                    unresolved.span,
+                )
+            } else {
+                Diagnostic::warning(
+                    "undefined-identifier",
+                    format!("`{}` is never declared", unresolved.name),
+                    unresolved.span,
+                )
+            });
+        }
+
+        self.diagnostics.sort_by_key(|diagnostic| diagnostic.span);
+        self.diagnostics
+    }
+
+    fn scope(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("the program scope is never closed early")
+    }
+
+    fn declare(&mut self, name: &str, kind: BindingKind, span: Span) {
+        if let Some(source) = self.options.shadowing {
+            self.report_shadowing(name, kind, span, source);
+        }
+
+        self.scope().bindings.push(Binding {
+            name: name.to_string(),
+            kind,
+            span,
+            used: false,
+        });
+    }
+
+    /// Reports the binding of `name` about to be made if it shadows a binding of an enclosing
+    /// scope made so far, or rebinds one of the current scope.
+    fn report_shadowing(&mut self, name: &str, kind: BindingKind, span: Span, source: &str) {
+        let binding = match kind {
+            BindingKind::Variable => "let",
+            BindingKind::Parameter => "parameter",
+            BindingKind::Other | BindingKind::Environment => return,
+        };
+        let (scope, enclosing) = self
+            .scopes
+            .split_last()
+            .expect("the program scope is never closed early");
+        let diagnostic = if let Some(previous) = scope.latest(name) {
+            if previous.kind == BindingKind::Environment {
+                return;
+            }
+            Diagnostic::warning(
+                "redeclared-binding",
+                format!("`{name}` is redeclared in the same scope"),
+                span,
+            )
+        } else if let Some(previous) = enclosing.iter().rev().find_map(|scope| scope.latest(name)) {
+            let mut message =
+                format!("{binding} `{name}` shadows a binding from an enclosing scope");
+            if previous.kind != BindingKind::Environment {
+                let (line, _) = previous.span.line_column(source);
+                message += &format!(" at line {line}");
+            }
+            Diagnostic::warning("shadowed-binding", message, span)
+        } else {
+            return;
+        };
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn use_name(&mut self, name: &str, span: Span) {
+        self.resolve(Use {
+            name: name.to_string(),
+            span,
+            declared_later: false,
+            nested: false,
+        });
+    }
+
+    /// Resolves `used` against the bindings of the current scope made so far, leaving it pending
+    /// when none matches.
+    fn resolve(&mut self, used: Use) {
+        let scope = self.scope();
+        scope.uses.push(used.name.clone());
+        if let Some(binding) = scope.binding(&used.name) {
+            binding.used = true;
+            return;
+        }
+        if Builtin::from_str(&used.name).is_some() {
+            return;
+        }
+
+        scope.pending.push(used);
+    }
+
+    /// Pops the innermost scope, reporting its unused bindings, and returns the uses it couldn't
+    /// resolve.
+    fn close_scope(&mut self) -> Vec<Use> {
+        let mut scope = self.scopes.pop().expect("scopes are closed once each");
+
+        let mut unresolved = Vec::new();
+        for mut pending in std::mem::take(&mut scope.pending) {
+            if scope.binding(&pending.name).is_some() {
+                // A function can be called after any of the bindings of the name it captures
+                if pending.nested {
+                    for binding in scope.bindings.iter_mut() {
+                        binding.used |= binding.name == pending.name;
+                    }
+                    continue;
+                }
+                pending.declared_later = true;
+            }
+            unresolved.push(pending);
+        }
+
+        if !self.scopes.is_empty() || self.report_program_scope {
+            self.report_unused(&scope.bindings);
+        }
+
+        unresolved
+    }
+
+    fn report_unused(&mut self, bindings: &[Binding]) {
+        for binding in bindings {
+            if binding.used || binding.name.starts_with('_') {
+                continue;
+            }
+
+            let diagnostic = match binding.kind {
+                BindingKind::Variable => Diagnostic::warning(
+                    "unused-variable",
+                    format!("unused variable `{}`", binding.name),
+                    binding.span,
+                ),
+                BindingKind::Parameter => Diagnostic::warning(
+                    "unused-parameter",
+                    format!("unused parameter `{}`", binding.name),
+                    binding.span,
+                ),
+                BindingKind::Other | BindingKind::Environment => continue,
+            };
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn statements(&mut self, statements: &[Spanned<Statement>]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn block(&mut self, block: &BlockStatement) {
+        self.statements(&block.statements);
+    }
+
+    /// Analyzes a block evaluated in a scope of its own, in place: the uses it leaves unresolved
+    /// are resolved as if they were made in the enclosing scope at the end of the block.
+    fn scoped_block(&mut self, block: &BlockStatement) {
+        self.scoped(|analyzer| analyzer.block(block));
+    }
+
+    /// Analyzes code evaluated in a scope of its own, like [`Self::scoped_block`].
+    fn scoped(&mut self, analyze: impl FnOnce(&mut Self)) {
+        self.scopes.push(Scope::default());
+        analyze(self);
+        for unresolved in self.close_scope() {
+            self.resolve(unresolved);
+        }
+    }
+
+    fn statement(&mut self, statement: &Spanned<Statement>) {
+        let span = statement.span;
+        match &statement.node {
+            Statement::Let { name, value } | Statement::Const { name, value } => {
+                self.expression(value);
+                self.declare(name, BindingKind::Variable, span);
+            }
+            Statement::Destructure { pattern, value } => {
+                self.expression(value);
+                let names = match pattern {
+                    Pattern::Hash(bindings) => bindings.iter().map(|(_, name)| name).collect(),
+                    Pattern::Array(names) => names.iter().collect::<Vec<_>>(),
+                };
+                for name in names {
+                    self.declare(name, BindingKind::Variable, span);
+                }
+            }
+            Statement::Return { value }
+            | Statement::Throw { value }
+            | Statement::Expression { value } => self.expression(value),
+        }
+    }
+
+    fn expression(&mut self, expression: &Spanned<Expression>) {
+        match &expression.node {
+            Expression::Identifier(name) | Expression::Postfix { name, .. } => {
+                self.use_name(name, expression.span)
+            }
+            Expression::PrefixOperator { expression, .. } => self.expression(expression),
+            Expression::InfixOperator {
+                lh_expression,
+                rh_expression,
+                ..
+            } => {
+                self.expression(lh_expression);
+                self.expression(rh_expression);
+            }
+            // Quoted code isn't evaluated where it appears
+            Expression::FunctionCall { name, .. } if is_quote(name) => {}
+            Expression::FunctionCall { name, arguments } => {
+                self.expression(name);
+                for argument in arguments {
+                    self.expression(argument);
+                }
+            }
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                self.expression(condition);
+                self.scoped_block(consequence);
+                if let Some(alternative) = alternative {
+                    self.scoped_block(alternative);
+                }
+            }
+            Expression::For {
+                variable,
+                iterable,
+                body,
+            } => {
+                self.expression(iterable);
+                self.scoped(|analyzer| {
+                    analyzer.declare(variable, BindingKind::Other, expression.span);
+                    analyzer.block(body);
+                });
+            }
+            Expression::While { condition, body } => {
+                self.expression(condition);
+                self.scoped_block(body);
+            }
+            Expression::Function { arguments, body } => {
+                self.scopes.push(Scope::default());
+                for argument in arguments {
+                    self.declare(argument, BindingKind::Parameter, expression.span);
+                }
+                self.block(body);
+                // Seen from the enclosing scope, they're used within the function
+                let unresolved = self.close_scope().into_iter().map(|used| Use {
+                    nested: true,
+                    ..used
+                });
+                self.scope().pending.extend(unresolved);
+            }
+            Expression::Array(elements) => {
+                for element in elements {
+                    self.expression(element);
+                }
+            }
+            Expression::Index { left, index } | Expression::OptionalIndex { left, index } => {
+                self.expression(left);
+                self.expression(index);
+            }
+            Expression::Range { start, end, .. } => {
+                self.expression(start);
+                self.expression(end);
+            }
+            Expression::HashLiteral(entries) => {
+                for (key, value) in entries {
+                    self.expression(key);
+                    self.expression(value);
+                }
+            }
+            // Macros are expanded before the analysis, their bodies being code to generate
+            Expression::Macro { .. }
+            | Expression::Integer(_)
+            | Expression::BigInt(_)
+            | Expression::Boolean(_)
+            | Expression::Null
+            | Expression::String(_) => {}
+        }
+    }
+}
+
+fn is_quote(callee: &Expression) -> bool {
+    matches!(callee, Expression::Identifier(name) if &**name == "quote")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::*;
+
+    /// Warnings of `input` about unused bindings, or about the other problems when not `unused`,
+    /// as `code: message`.
+    fn diagnostics(input: &str, unused: bool) -> Vec<String> {
+        analyze(&parse(input).unwrap())
+            .into_iter()
+            .filter(|diagnostic| diagnostic.code.starts_with("unused-") == unused)
+            .map(|diagnostic| format!("{}: {}", diagnostic.code, diagnostic.message))
+            .collect()
+    }
+
+    fn warnings(input: &str) -> Vec<String> {
+        diagnostics(input, false)
+    }
+
+    fn unused(input: &str) -> Vec<String> {
+        diagnostics(input, true)
+    }
+
+    #[test]
+    fn test_analyze_undefined_identifiers() {
+        let tests: [(&str, &[&str]); 14] = [
+            ("let a = 1; a + len([])", &[]),
+            ("puts(b)", &["undefined-identifier: `b` is never declared"]),
+            (
+                "let f = fn(x) { x + y }; f(1)",
+                &["undefined-identifier: `y` is never declared"],
+            ),
+            ("let a = a + 1", &["use-before-declaration: `a` is used before being declared"]),
+            ("c++; let c = 1;", &["use-before-declaration: `c` is used before being declared"]),
+            ("let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }", &[]),
+            ("let even = fn(n) { if (n == 0) { true } else { odd(n - 1) } }; let odd = fn(n) { !even(n) }", &[]),
+            ("let f = fn() { fn() { g } }; let g = 1", &[]),
+            ("if (true) { let d = 1 }; d", &["undefined-identifier: `d` is never declared"]),
+            ("let d = 1; if (true) { d; let d = 2; d }", &[]),
+            ("for (i in 0..3) { if (i > 0) { puts(previous) }; let previous = i }", &["use-before-declaration: `previous` is used before being declared"]),
+            ("for (i in 0..3) { i }; i", &["undefined-identifier: `i` is never declared"]),
+            ("let [q, r] = [1, 2]; let {name} = {}; q + r + name", &[]),
+            ("let len = 1; quote(nope + len)", &[]),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(warnings(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_analyze_scopes() {
+        assert_eq!(
+            warnings("let f = fn(x) { let y = 1; x + y }; x + y"),
+            [
+                "undefined-identifier: `x` is never declared",
+                "undefined-identifier: `y` is never declared"
+            ]
+        );
+        // Shadowing a later binding of the enclosing scope
+        assert_eq!(
+            warnings("let f = fn() { e; let e = 2; }; let e = 1;"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            warnings("let f = fn() { e; let e = 2; };"),
+            ["use-before-declaration: `e` is used before being declared"]
+        );
+        assert_eq!(
+            warnings("while (x < 3) { let x = 1 }"),
+            ["undefined-identifier: `x` is never declared"]
+        );
+
+        let program = parse("a + b").unwrap();
+        let diagnostics = analyze_in(
+            &program,
+            &BTreeSet::from(["a".to_string()]),
+            AnalysisOptions::default(),
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].span, Span::new(4, 5));
+    }
+
+    #[test]
+    fn test_analyze_unused_bindings() {
+        let tests: [(&str, &[&str]); 12] = [
+            ("let a = 1; puts(a)", &[]),
+            (
+                "let tmp = 1; let _ignored = 2;",
+                &["unused-variable: unused variable `tmp`"],
+            ),
+            (
+                "let x = 1; let x = 2; x",
+                &["unused-variable: unused variable `x`"],
+            ),
+            (
+                "let [q, r] = [1, 2]; q",
+                &["unused-variable: unused variable `r`"],
+            ),
+            ("let n = 1; let f = fn() { n }; f()", &[]),
+            ("let f = fn() { f() }; f", &[]),
+            (
+                "let total = 0; for (i in 0..3) { let total = total + i }; total",
+                &["unused-variable: unused variable `total`"],
+            ),
+            (
+                "let i = 0; while (i < 3) { let i = i + 1 }",
+                &["unused-variable: unused variable `i`"],
+            ),
+            ("let c = 0; c++", &[]),
+            (
+                "let y = 1; if (y) { let y = 2; let z = 3; y }",
+                &["unused-variable: unused variable `z`"],
+            ),
+            (
+                "let f = fn(a, b, _c) { a }; f(1, 2, 3)",
+                &["unused-parameter: unused parameter `b`"],
+            ),
+            (
+                "let f = fn(a) { let unused = a; 1 }; f(1)",
+                &["unused-variable: unused variable `unused`"],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(unused(input), expected, "{input}");
+        }
+
+        let diagnostics = analyze(&parse("let a = 1;\nlet b = 2;\na").unwrap());
+        assert_eq!(
+            diagnostics[0].render("let a = 1;\nlet b = 2;\na", "main.mk"),
+            "warning[unused-variable]: unused variable `b`\n  --> main.mk:2:1"
+        );
+
+        let program = parse("let a = 1; let f = fn(x) { 1 };").unwrap();
+        assert_eq!(
+            analyze_in(&program, &BTreeSet::new(), AnalysisOptions::default())
+                .into_iter()
+                .map(|diagnostic| diagnostic.message)
+                .collect::<Vec<_>>(),
+            ["unused parameter `x`"]
+        );
+    }
+
+    /// Shadowing warnings of `input` as `code: message`, evaluated in an environment binding
+    /// `bound`.
+    fn shadowing(input: &str, bound: &[&str]) -> Vec<String> {
+        let bound = bound.iter().map(ToString::to_string).collect();
+        let options = AnalysisOptions {
+            shadowing: Some(input),
+        };
+        analyze_in(&parse(input).unwrap(), &bound, options)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.code.ends_with("-binding"))
+            .map(|diagnostic| format!("{}: {}", diagnostic.code, diagnostic.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_analyze_shadowing() {
+        let tests: [(&str, &[&str]); 10] = [
+            ("let x = 1; let y = 2; x + y", &[]),
+            (
+                "let x = 1; let x = true; x",
+                &["redeclared-binding: `x` is redeclared in the same scope"],
+            ),
+            (
+                "let x = 1;\nlet f = fn() { let x = 2; x };\nf() + x",
+                &["shadowed-binding: let `x` shadows a binding from an enclosing scope at line 1"],
+            ),
+            (
+                "let total = 0;\n\nlet add = fn(total, n) { total + n };\nadd(total, 1)",
+                &["shadowed-binding: parameter `total` shadows a binding from an enclosing scope at line 1"],
+            ),
+            (
+                "let a = 1;\nif (a) {\n  let a = 2;\n  if (a) { let a = 3; a }\n}",
+                &[
+                    "shadowed-binding: let `a` shadows a binding from an enclosing scope at line 1",
+                    "shadowed-binding: let `a` shadows a binding from an enclosing scope at line 3",
+                ],
+            ),
+            (
+                "let f = fn(a, a) { let a = 1; a }; f(1, 2)",
+                &[
+                    "redeclared-binding: `a` is redeclared in the same scope",
+                    "redeclared-binding: `a` is redeclared in the same scope",
+                ],
+            ),
+            (
+                "let [p, q] = [1, 2]; let {p} = {}; p + q",
+                &["redeclared-binding: `p` is redeclared in the same scope"],
+            ),
+            // Loop variables are never reported as shadowing
+            ("let i = 0; for (i in 0..3) { i }; for (i in 0..3) { i }", &[]),
+            // A binding made after a function is defined isn't shadowed by it
+            ("let f = fn() { let g = 1; g }; let g = 2; f() + g", &[]),
+            ("let x = 1; x", &[]),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(shadowing(input, &[]), expected, "{input}");
+        }
+
+        // Rebinding a name of the environment is how a REPL redefines it
+        assert_eq!(shadowing("let x = true; x", &["x"]), Vec::<String>::new());
+        assert_eq!(
+            shadowing("let f = fn(x) { x }; f(1)", &["x"]),
+            ["shadowed-binding: parameter `x` shadows a binding from an enclosing scope"]
+        );
+
+        // Off unless asked for
+        assert_eq!(warnings("let x = 1; let x = true; x"), Vec::<String>::new());
+    }
+}
@@ -1,6 +1,10 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, rc::Rc};
 
-use crate::{ast::statement::BlockStatement, lexer::Token};
+use crate::{
+    ast::{statement::BlockStatement, Spanned},
+    bigint::BigInt,
+    lexer::{quote, Token},
+};
 
 #[derive(PartialEq, Eq, Debug, Clone, Ord, PartialOrd)]
 #[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
@@ -9,45 +13,122 @@ use crate::{ast::statement::BlockStatement, lexer::Token};
     serde(tag = "kind", content = "value", rename_all = "snake_case")
 )]
 pub enum Expression {
-    Integer(isize),
-    Identifier(String),
+    Integer(
+        #[cfg_attr(
+            target_family = "wasm",
+            serde(serialize_with = "crate::wasm::serialization::integer")
+        )]
+        i64,
+    ),
+    /// Integer literal too large for an `i64`
+    #[cfg_attr(target_family = "wasm", serde(rename = "integer"))]
+    BigInt(BigInt),
+    Identifier(Rc<str>),
     Boolean(bool),
+    Null,
     String(String),
     PrefixOperator {
         operator: Token,
-        expression: Box<Expression>,
+        expression: Box<Spanned<Expression>>,
     },
     InfixOperator {
         operator: Token,
-        rh_expression: Box<Expression>,
-        lh_expression: Box<Expression>,
+        rh_expression: Box<Spanned<Expression>>,
+        lh_expression: Box<Spanned<Expression>>,
     },
     FunctionCall {
-        name: Box<Expression>,
-        arguments: Vec<Expression>,
+        name: Box<Spanned<Expression>>,
+        arguments: Vec<Spanned<Expression>>,
     },
     If {
-        condition: Box<Expression>,
+        condition: Box<Spanned<Expression>>,
         consequence: BlockStatement,
         alternative: Option<BlockStatement>,
     },
+    /// `for (variable in iterable) { body }`, evaluating to NULL
+    For {
+        variable: Rc<str>,
+        iterable: Box<Spanned<Expression>>,
+        body: BlockStatement,
+    },
+    /// `while (condition) { body }`, evaluating to NULL
+    While {
+        condition: Box<Spanned<Expression>>,
+        body: BlockStatement,
+    },
+    /// `name++` or `name--`, updating the binding and evaluating to its previous value
+    Postfix {
+        operator: Token,
+        name: Rc<str>,
+    },
     Function {
-        arguments: Vec<String>,
+        arguments: Vec<Rc<str>>,
+        body: BlockStatement,
+    },
+    /// `macro(arguments) { body }`, which [`crate::evaluator::macros::define_macros`] binds when
+    /// it is the value of a top-level `let`
+    Macro {
+        arguments: Vec<Rc<str>>,
         body: BlockStatement,
     },
-    Array(Vec<Expression>),
+    Array(Vec<Spanned<Expression>>),
     Index {
-        left: Box<Expression>,
-        index: Box<Expression>,
+        left: Box<Spanned<Expression>>,
+        index: Box<Spanned<Expression>>,
+    },
+    /// `start..end`, or `start..=end` when `inclusive`
+    Range {
+        start: Box<Spanned<Expression>>,
+        end: Box<Spanned<Expression>>,
+        inclusive: bool,
+    },
+    /// `left?[index]` or `left?.member`, evaluating to NULL instead of erroring when `left` is NULL
+    OptionalIndex {
+        left: Box<Spanned<Expression>>,
+        index: Box<Spanned<Expression>>,
     },
     #[cfg_attr(target_family = "wasm", serde(with = "crate::wasm::serialization"))]
-    HashLiteral(BTreeMap<Expression, Expression>),
+    HashLiteral(BTreeMap<Spanned<Expression>, Spanned<Expression>>),
 }
 
+impl Expression {
+    /// Name of the variant, as in the serialized AST, e.g. `infix_operator`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Integer(_) | Self::BigInt(_) => "integer",
+            Self::Identifier(_) => "identifier",
+            Self::Boolean(_) => "boolean",
+            Self::Null => "null",
+            Self::String(_) => "string",
+            Self::PrefixOperator { .. } => "prefix_operator",
+            Self::InfixOperator { .. } => "infix_operator",
+            Self::FunctionCall { .. } => "function_call",
+            Self::If { .. } => "if",
+            Self::For { .. } => "for",
+            Self::While { .. } => "while",
+            Self::Postfix { .. } => "postfix",
+            Self::Function { .. } => "function",
+            Self::Macro { .. } => "macro",
+            Self::Array(_) => "array",
+            Self::Index { .. } => "index",
+            Self::Range { .. } => "range",
+            Self::OptionalIndex { .. } => "optional_index",
+            Self::HashLiteral(_) => "hash_literal",
+        }
+    }
+}
+
+/// Written on a single line, or with `{:#}` in the pretty mode of [`Statement`] for the blocks
+/// it contains.
+///
+/// [`Statement`]: crate::ast::Statement
 impl std::fmt::Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pretty = f.alternate();
+
         match self {
             Self::Integer(value) => write!(f, "{value}"),
+            Self::BigInt(value) => write!(f, "{value}"),
             Self::Identifier(value) => write!(f, "{value}"),
             Self::Boolean(value) => {
                 if *value {
@@ -56,22 +137,28 @@ impl std::fmt::Display for Expression {
                     write!(f, "false")
                 }
             }
-            Self::String(value) => write!(f, r#""{value}""#),
+            Self::Null => write!(f, "null"),
+            Self::String(value) => write!(f, "{}", quote(value)),
             Self::PrefixOperator {
                 operator,
                 expression,
             } => {
-                write!(f, "({operator}{expression})")
+                write!(f, "({operator}{})", Nested(expression, pretty))
             }
             Self::InfixOperator {
                 operator,
                 rh_expression,
                 lh_expression,
-            } => write!(f, "({lh_expression} {operator} {rh_expression})"),
+            } => write!(
+                f,
+                "({} {operator} {})",
+                Nested(lh_expression, pretty),
+                Nested(rh_expression, pretty)
+            ),
             Self::FunctionCall { name, arguments } => {
-                write!(f, "{name}(")?;
+                write!(f, "{}(", Nested(name, pretty))?;
                 for (index, argument) in arguments.iter().enumerate() {
-                    write!(f, "{argument}")?;
+                    write!(f, "{}", Nested(argument, pretty))?;
                     if index != arguments.len() - 1 {
                         write!(f, ", ")?;
                     }
@@ -83,19 +170,57 @@ impl std::fmt::Display for Expression {
                 consequence,
                 alternative,
             } => {
-                write!(f, "if ({condition}) {{{consequence}}}")?;
+                write!(
+                    f,
+                    "if ({}) {{{}}}",
+                    Nested(condition, pretty),
+                    Nested(consequence, pretty)
+                )?;
                 if let Some(alternative) = alternative {
-                    write!(f, "else {{{alternative}}}")?;
+                    let separator = if pretty { " " } else { "" };
+                    write!(f, "{separator}else {{{}}}", Nested(alternative, pretty))?;
                 }
                 Ok(())
             }
+            Self::For {
+                variable,
+                iterable,
+                body,
+            } => write!(
+                f,
+                "for ({variable} in {}) {{{}}}",
+                Nested(iterable, pretty),
+                Nested(body, pretty)
+            ),
+            Self::While { condition, body } => {
+                write!(
+                    f,
+                    "while ({}) {{{}}}",
+                    Nested(condition, pretty),
+                    Nested(body, pretty)
+                )
+            }
+            Self::Postfix { operator, name } => write!(f, "({name}{operator})"),
             Self::Function { arguments, body } => {
-                write!(f, "fn({}) {{{body}}}", arguments.join(", "))
+                write!(
+                    f,
+                    "fn({}) {{{}}}",
+                    arguments.join(", "),
+                    Nested(body, pretty)
+                )
+            }
+            Self::Macro { arguments, body } => {
+                write!(
+                    f,
+                    "macro({}) {{{}}}",
+                    arguments.join(", "),
+                    Nested(body, pretty)
+                )
             }
             Self::Array(elements) => {
                 write!(f, "[")?;
                 for (index, element) in elements.iter().enumerate() {
-                    write!(f, "{element}")?;
+                    write!(f, "{}", Nested(element, pretty))?;
                     if index != elements.len() - 1 {
                         write!(f, ", ")?;
                     }
@@ -103,12 +228,28 @@ impl std::fmt::Display for Expression {
                 write!(f, "]")
             }
             Self::Index { left, index } => {
-                write!(f, "({left}[{index}])")
+                write!(f, "({}[{}])", Nested(left, pretty), Nested(index, pretty))
+            }
+            Self::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let operator = if *inclusive { "..=" } else { ".." };
+                write!(
+                    f,
+                    "({}{operator}{})",
+                    Nested(start, pretty),
+                    Nested(end, pretty)
+                )
+            }
+            Self::OptionalIndex { left, index } => {
+                write!(f, "({}?[{}])", Nested(left, pretty), Nested(index, pretty))
             }
             Self::HashLiteral(elements) => {
                 write!(f, "{{")?;
                 for (index, (key, value)) in elements.iter().enumerate() {
-                    write!(f, "{key}: {value}")?;
+                    write!(f, "{}: {}", Nested(key, pretty), Nested(value, pretty))?;
                     if index != elements.len() - 1 {
                         write!(f, ", ")?;
                     }
@@ -119,8 +260,21 @@ impl std::fmt::Display for Expression {
     }
 }
 
-impl From<isize> for Expression {
-    fn from(value: isize) -> Self {
+/// Writes a node within another in the same mode, `{:#}` having to be passed down explicitly.
+pub(crate) struct Nested<'a, T>(pub &'a T, pub bool);
+
+impl<T: std::fmt::Display> std::fmt::Display for Nested<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.1 {
+            write!(f, "{:#}", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+impl From<i64> for Expression {
+    fn from(value: i64) -> Self {
         Self::Integer(value)
     }
 }
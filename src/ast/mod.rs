@@ -1,5 +1,47 @@
 mod expression;
+mod modify;
+mod spanned;
 mod statement;
+mod tree;
 
 pub use expression::Expression;
-pub use statement::{BlockStatement, Statement};
+pub use modify::{modify, modify_block, modify_statement, modify_statements};
+pub use spanned::Spanned;
+pub(crate) use statement::{pretty_lines, write_inline};
+pub use statement::{BlockStatement, Pattern, Statement};
+pub use tree::pretty_print;
+
+/// Reconstructed source of an AST node, truncated to `width` characters so that it can be
+/// embedded in a message.
+pub fn describe(node: &impl std::fmt::Display, width: usize) -> String {
+    let source = node.to_string();
+    if source.chars().count() <= width {
+        return source;
+    }
+
+    let mut truncated = source.chars().take(width - 1).collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let expression = Expression::Identifier("count".into());
+
+        assert_eq!(describe(&expression, 5), "count");
+        assert_eq!(describe(&expression, 4), "cou…");
+        assert_eq!(
+            describe(
+                &Statement::Expression {
+                    value: expression.into()
+                },
+                10
+            ),
+            "count"
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use crate::ast::{BlockStatement, Expression, Spanned, Statement};
+
+/// Rewrites `expression` from the bottom up: the expressions it contains first, including the
+/// ones in the bodies of functions and macros, then the expression itself, each one through
+/// `modifier`. Stops at the first error of `modifier`.
+pub fn modify<E>(
+    expression: Spanned<Expression>,
+    modifier: &mut impl FnMut(Spanned<Expression>) -> Result<Spanned<Expression>, E>,
+) -> Result<Spanned<Expression>, E> {
+    let Spanned { node, span } = expression;
+
+    let node = match node {
+        Expression::PrefixOperator {
+            operator,
+            expression,
+        } => Expression::PrefixOperator {
+            operator,
+            expression: boxed(*expression, modifier)?,
+        },
+        Expression::InfixOperator {
+            operator,
+            rh_expression,
+            lh_expression,
+        } => {
+            let lh_expression = boxed(*lh_expression, modifier)?;
+            Expression::InfixOperator {
+                operator,
+                rh_expression: boxed(*rh_expression, modifier)?,
+                lh_expression,
+            }
+        }
+        Expression::FunctionCall { name, arguments } => Expression::FunctionCall {
+            name: boxed(*name, modifier)?,
+            arguments: all(arguments, modifier)?,
+        },
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => Expression::If {
+            condition: boxed(*condition, modifier)?,
+            consequence: modify_block(consequence, modifier)?,
+            alternative: alternative
+                .map(|alternative| modify_block(alternative, modifier))
+                .transpose()?,
+        },
+        Expression::For {
+            variable,
+            iterable,
+            body,
+        } => Expression::For {
+            variable,
+            iterable: boxed(*iterable, modifier)?,
+            body: modify_block(body, modifier)?,
+        },
+        Expression::While { condition, body } => Expression::While {
+            condition: boxed(*condition, modifier)?,
+            body: modify_block(body, modifier)?,
+        },
+        Expression::Function { arguments, body } => Expression::Function {
+            arguments,
+            body: modify_block(body, modifier)?,
+        },
+        Expression::Macro { arguments, body } => Expression::Macro {
+            arguments,
+            body: modify_block(body, modifier)?,
+        },
+        Expression::Array(elements) => Expression::Array(all(elements, modifier)?),
+        Expression::Index { left, index } => Expression::Index {
+            left: boxed(*left, modifier)?,
+            index: boxed(*index, modifier)?,
+        },
+        Expression::OptionalIndex { left, index } => Expression::OptionalIndex {
+            left: boxed(*left, modifier)?,
+            index: boxed(*index, modifier)?,
+        },
+        Expression::Range {
+            start,
+            end,
+            inclusive,
+        } => Expression::Range {
+            start: boxed(*start, modifier)?,
+            end: boxed(*end, modifier)?,
+            inclusive,
+        },
+        Expression::HashLiteral(entries) => Expression::HashLiteral(
+            entries
+                .into_iter()
+                .map(|(key, value)| Ok((modify(key, modifier)?, modify(value, modifier)?)))
+                .collect::<Result<_, E>>()?,
+        ),
+        node @ (Expression::Integer(_)
+        | Expression::BigInt(_)
+        | Expression::Identifier(_)
+        | Expression::Boolean(_)
+        | Expression::Null
+        | Expression::String(_)
+        | Expression::Postfix { .. }) => node,
+    };
+
+    modifier(Spanned { node, span })
+}
+
+/// Rewrites the expressions of `statement` through [`modify`].
+pub fn modify_statement<E>(
+    statement: Statement,
+    modifier: &mut impl FnMut(Spanned<Expression>) -> Result<Spanned<Expression>, E>,
+) -> Result<Statement, E> {
+    Ok(match statement {
+        Statement::Let { name, value } => Statement::Let {
+            name,
+            value: modify(value, modifier)?,
+        },
+        Statement::Const { name, value } => Statement::Const {
+            name,
+            value: modify(value, modifier)?,
+        },
+        Statement::Destructure { pattern, value } => Statement::Destructure {
+            pattern,
+            value: modify(value, modifier)?,
+        },
+        Statement::Return { value } => Statement::Return {
+            value: modify(value, modifier)?,
+        },
+        Statement::Throw { value } => Statement::Throw {
+            value: modify(value, modifier)?,
+        },
+        Statement::Expression { value } => Statement::Expression {
+            value: modify(value, modifier)?,
+        },
+    })
+}
+
+/// Rewrites the expressions of the statements of `block` through [`modify`].
+pub fn modify_block<E>(
+    block: BlockStatement,
+    modifier: &mut impl FnMut(Spanned<Expression>) -> Result<Spanned<Expression>, E>,
+) -> Result<BlockStatement, E> {
+    Ok(BlockStatement {
+        statements: modify_statements(block.statements, modifier)?,
+        comments: block.comments,
+    })
+}
+
+/// Rewrites the expressions of `statements` through [`modify`].
+pub fn modify_statements<E>(
+    statements: Vec<Spanned<Statement>>,
+    modifier: &mut impl FnMut(Spanned<Expression>) -> Result<Spanned<Expression>, E>,
+) -> Result<Vec<Spanned<Statement>>, E> {
+    statements
+        .into_iter()
+        .map(|Spanned { node, span }| Ok(Spanned::new(modify_statement(node, modifier)?, span)))
+        .collect()
+}
+
+fn boxed<E>(
+    expression: Spanned<Expression>,
+    modifier: &mut impl FnMut(Spanned<Expression>) -> Result<Spanned<Expression>, E>,
+) -> Result<Box<Spanned<Expression>>, E> {
+    modify(expression, modifier).map(Box::new)
+}
+
+fn all<E>(
+    expressions: Vec<Spanned<Expression>>,
+    modifier: &mut impl FnMut(Spanned<Expression>) -> Result<Spanned<Expression>, E>,
+) -> Result<Vec<Spanned<Expression>>, E> {
+    expressions
+        .into_iter()
+        .map(|expression| modify(expression, modifier))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use crate::parse;
+
+    use super::*;
+
+    /// Turns every `1` into a `2`.
+    fn one_into_two(expression: Spanned<Expression>) -> Result<Spanned<Expression>, Infallible> {
+        Ok(match expression.node {
+            Expression::Integer(1) => Spanned::new(Expression::Integer(2), expression.span),
+            _ => expression,
+        })
+    }
+
+    #[test]
+    fn test_modify() {
+        let tests = [
+            ("1", "2"),
+            ("1 + 3; -1", "(2 + 3);(-2)"),
+            ("f(1, [1, {1: 1}])", "f(2, [2, {2: 2}])"),
+            ("a[1]; a?[1]; 1..=1", "(a[2]);(a?[2]);(2..=2)"),
+            (
+                "if (1) { 1 } else { let a = 1; }",
+                "if (2) {2}else {let a = 2;}",
+            ),
+            (
+                "for (i in 1..3) { 1 }; while (1) { return 1; }",
+                "for (i in (2..3)) {2};while (2) {return 2;}",
+            ),
+            (
+                "fn(a) { throw 1 }; macro(a) { const b = 1; }",
+                "fn(a) {throw 2;};macro(a) {const b = 2;}",
+            ),
+            ("let [a, b] = [1, 3]", "let [a, b] = [2, 3];"),
+        ];
+
+        for (input, expected) in tests {
+            let program = parse(input).unwrap();
+            let statements = modify_statements(program.statements, &mut one_into_two).unwrap();
+
+            assert_eq!(
+                crate::parser::Program {
+                    statements,
+                    ..Default::default()
+                }
+                .to_string(),
+                expected,
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_modify_stops_at_first_error() {
+        let mut visited = Vec::new();
+        let statements = parse("f(a, b, c)").unwrap().statements;
+        let result = modify_statements(statements, &mut |expression| {
+            visited.push(expression.to_string());
+            match &expression.node {
+                Expression::Identifier(name) if &**name == "b" => Err("b"),
+                _ => Ok(expression),
+            }
+        });
+
+        assert_eq!(result.err(), Some("b"));
+        assert_eq!(visited, ["f", "a", "b"]);
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    cmp::Ordering,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    ast::{Expression, Statement},
+    lexer::Span,
+};
+
+/// A node of the AST along with the span of the source it was parsed from.
+///
+/// The span is where the node came from rather than what it is, so it is ignored when comparing
+/// nodes, a program equal to another however it was laid out. Nodes built rather than parsed,
+/// through [`From`], have an empty span.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
+pub struct Spanned<T> {
+    #[cfg_attr(target_family = "wasm", serde(flatten))]
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Self { node, span }
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.node
+    }
+}
+
+impl<T> DerefMut for Spanned<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.node
+    }
+}
+
+impl<T: Into<Expression>> From<T> for Spanned<Expression> {
+    fn from(node: T) -> Self {
+        Self::new(node.into(), Span::default())
+    }
+}
+
+impl From<Statement> for Spanned<Statement> {
+    fn from(node: Statement) -> Self {
+        Self::new(node, Span::default())
+    }
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<T: PartialEq> PartialEq<T> for Spanned<T> {
+    fn eq(&self, other: &T) -> bool {
+        self.node == *other
+    }
+}
+
+impl<T: Eq> Eq for Spanned<T> {}
+
+impl<T: PartialOrd> PartialOrd for Spanned<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.node.partial_cmp(&other.node)
+    }
+}
+
+impl<T: Ord> Ord for Spanned<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.node.cmp(&other.node)
+    }
+}
+
+/// Writes the node alone, in the same mode.
+impl<T: std::fmt::Display> std::fmt::Display for Spanned<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.node.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans_are_ignored_by_comparisons() {
+        let parsed = Spanned::new(Expression::Integer(1), Span::new(4, 5));
+
+        assert_eq!(parsed, Spanned::from(Expression::Integer(1)));
+        assert_eq!(parsed, Expression::Integer(1));
+        assert_ne!(
+            parsed,
+            Spanned::new(Expression::Integer(2), Span::new(4, 5))
+        );
+        assert!(parsed < Spanned::new(Expression::Integer(2), Span::new(0, 1)));
+    }
+}
@@ -1,4 +1,9 @@
-use crate::ast::expression::Expression;
+use std::{collections::BTreeMap, rc::Rc};
+
+use crate::ast::{
+    expression::{Expression, Nested},
+    Spanned,
+};
 
 #[derive(PartialEq, Eq, Debug, Clone, Ord, PartialOrd)]
 #[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
@@ -7,32 +12,147 @@ use crate::ast::expression::Expression;
     serde(tag = "kind", content = "value", rename_all = "snake_case")
 )]
 pub enum Statement {
-    Let { name: String, value: Expression },
-    Return { value: Expression },
-    Expression { value: Expression },
+    Let {
+        name: Rc<str>,
+        value: Spanned<Expression>,
+    },
+    Const {
+        name: Rc<str>,
+        value: Spanned<Expression>,
+    },
+    Destructure {
+        pattern: Pattern,
+        value: Spanned<Expression>,
+    },
+    Return {
+        value: Spanned<Expression>,
+    },
+    Throw {
+        value: Spanned<Expression>,
+    },
+    Expression {
+        value: Spanned<Expression>,
+    },
+}
+
+/// Binding target of a destructuring `let`.
+#[derive(PartialEq, Eq, Debug, Clone, Ord, PartialOrd)]
+#[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
+#[cfg_attr(
+    target_family = "wasm",
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
+pub enum Pattern {
+    /// `{name, age: years}`, pairs of string hash key and the name it gets bound to
+    Hash(Vec<(String, Rc<str>)>),
+    /// `[quotient, remainder]`, names bound to the elements of an array of the same length
+    Array(Vec<Rc<str>>),
 }
 
+/// Written on a single line, or with `{:#}` in the pretty mode, ending every statement with a
+/// `;` and laying out the blocks it contains over multiple lines, with their comments.
 impl std::fmt::Display for Statement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pretty = f.alternate();
+
         match self {
-            Self::Let { name, value } => write!(f, "let {name} = {value};"),
-            Self::Return { value } => write!(f, "return {value};"),
+            Self::Let { name, value } => write!(f, "let {name} = {};", Nested(value, pretty)),
+            Self::Const { name, value } => write!(f, "const {name} = {};", Nested(value, pretty)),
+            Self::Destructure { pattern, value } => {
+                write!(f, "let {pattern} = {};", Nested(value, pretty))
+            }
+            Self::Return { value } => write!(f, "return {};", Nested(value, pretty)),
+            Self::Throw { value } => write!(f, "throw {};", Nested(value, pretty)),
+            Self::Expression { value } if f.alternate() => write!(f, "{value:#};"),
             Self::Expression { value } => write!(f, "{value}"),
         }
     }
 }
 
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hash(bindings) => {
+                write!(f, "{{")?;
+                for (index, (key, binding)) in bindings.iter().enumerate() {
+                    if **key == **binding {
+                        write!(f, "{key}")?;
+                    } else {
+                        write!(f, "{key}: {binding}")?;
+                    }
+                    if index != bindings.len() - 1 {
+                        write!(f, ", ")?;
+                    }
+                }
+                write!(f, "}}")
+            }
+            Self::Array(bindings) => write!(f, "[{}]", bindings.join(", ")),
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Clone, Ord, PartialOrd, Default)]
 #[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
 pub struct BlockStatement {
-    pub statements: Vec<Statement>,
+    pub statements: Vec<Spanned<Statement>>,
+    /// Lines of the `//` comments preceding the statements, by index, the ones past the last
+    /// statement being before the closing `}`. Only kept by
+    /// [`crate::parser::Parser::with_comments`]
+    #[cfg_attr(target_family = "wasm", serde(skip))]
+    pub comments: BTreeMap<usize, Vec<String>>,
 }
 
+/// Statements of the block without its braces. The pretty mode puts them on their own lines,
+/// indented, along with their comments.
 impl std::fmt::Display for BlockStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for statement in &self.statements {
-            write!(f, "{statement}")?;
+        if !f.alternate() {
+            return write_inline(f, &self.statements);
+        }
+
+        let lines = pretty_lines(&self.statements, &self.comments);
+        if !lines.is_empty() {
+            writeln!(f)?;
+            for line in lines.lines() {
+                writeln!(f, "    {line}")?;
+            }
         }
         Ok(())
     }
 }
+
+/// `statements` on a single line, the expression statements being followed by a `;` when
+/// another statement comes after them so that they don't run into it.
+pub(crate) fn write_inline(
+    f: &mut std::fmt::Formatter<'_>,
+    statements: &[Spanned<Statement>],
+) -> std::fmt::Result {
+    for (index, statement) in statements.iter().enumerate() {
+        write!(f, "{statement}")?;
+        if matches!(statement.node, Statement::Expression { .. }) && index + 1 != statements.len() {
+            write!(f, ";")?;
+        }
+    }
+    Ok(())
+}
+
+/// `statements` in the pretty mode, one per line, each preceded by its `comments`.
+pub(crate) fn pretty_lines(
+    statements: &[Spanned<Statement>],
+    comments: &BTreeMap<usize, Vec<String>>,
+) -> String {
+    let mut lines = String::new();
+    let write_comments = |lines: &mut String, index| {
+        for comment in comments.get(&index).into_iter().flatten() {
+            lines.push_str(&format!("//{comment}\n"));
+        }
+    };
+
+    for (index, statement) in statements.iter().enumerate() {
+        write_comments(&mut lines, index);
+        lines.push_str(&format!("{statement:#}\n"));
+    }
+    write_comments(&mut lines, statements.len());
+
+    lines
+}
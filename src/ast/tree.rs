@@ -0,0 +1,258 @@
+use crate::{
+    ast::{BlockStatement, Expression, Statement},
+    lexer::quote,
+    parser::Program,
+};
+
+/// Indented tree of the nodes of `program`, one per line, showing how it was parsed. Children
+/// are indented by two spaces under their parent, prefixed by their role when it isn't obvious.
+pub fn pretty_print(program: &Program) -> String {
+    let mut tree = Tree::default();
+    tree.node(0, None, "Program");
+    for statement in &program.statements {
+        tree.statement(1, None, statement);
+    }
+    tree.lines
+}
+
+#[derive(Default)]
+struct Tree {
+    lines: String,
+}
+
+impl Tree {
+    fn node(&mut self, depth: usize, role: Option<&str>, label: &str) {
+        self.lines.push_str(&"  ".repeat(depth));
+        if let Some(role) = role {
+            self.lines.push_str(role);
+            self.lines.push_str(": ");
+        }
+        self.lines.push_str(label);
+        self.lines.push('\n');
+    }
+
+    fn block(&mut self, depth: usize, role: Option<&str>, block: &BlockStatement) {
+        self.node(depth, role, "Block");
+        for statement in &block.statements {
+            self.statement(depth + 1, None, statement);
+        }
+    }
+
+    fn statement(&mut self, depth: usize, role: Option<&str>, statement: &Statement) {
+        let (label, value) = match statement {
+            Statement::Let { name, value } => (format!("Let({name})"), value),
+            Statement::Const { name, value } => (format!("Const({name})"), value),
+            Statement::Destructure { pattern, value } => (format!("Destructure({pattern})"), value),
+            Statement::Return { value } => ("Return".into(), value),
+            Statement::Throw { value } => ("Throw".into(), value),
+            Statement::Expression { value } => ("Expression".into(), value),
+        };
+
+        self.node(depth, role, &label);
+        self.expression(depth + 1, None, value);
+    }
+
+    fn expression(&mut self, depth: usize, role: Option<&str>, expression: &Expression) {
+        let children = depth + 1;
+
+        match expression {
+            Expression::Integer(value) => self.node(depth, role, &format!("Integer({value})")),
+            Expression::BigInt(value) => self.node(depth, role, &format!("Integer({value})")),
+            Expression::Identifier(name) => self.node(depth, role, &format!("Identifier({name})")),
+            Expression::Boolean(value) => self.node(depth, role, &format!("Boolean({value})")),
+            Expression::Null => self.node(depth, role, "Null"),
+            Expression::String(value) => {
+                self.node(depth, role, &format!("String({})", quote(value)))
+            }
+            Expression::PrefixOperator {
+                operator,
+                expression,
+            } => {
+                self.node(depth, role, &format!("PrefixOperator({operator})"));
+                self.expression(children, None, expression);
+            }
+            Expression::InfixOperator {
+                operator,
+                lh_expression,
+                rh_expression,
+            } => {
+                self.node(depth, role, &format!("InfixOperator({operator})"));
+                self.expression(children, None, lh_expression);
+                self.expression(children, None, rh_expression);
+            }
+            Expression::FunctionCall { name, arguments } => {
+                self.node(depth, role, "FunctionCall");
+                self.expression(children, Some("callee"), name);
+                for argument in arguments {
+                    self.expression(children, Some("argument"), argument);
+                }
+            }
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                self.node(depth, role, "If");
+                self.expression(children, Some("condition"), condition);
+                self.block(children, Some("consequence"), consequence);
+                if let Some(alternative) = alternative {
+                    self.block(children, Some("alternative"), alternative);
+                }
+            }
+            Expression::For {
+                variable,
+                iterable,
+                body,
+            } => {
+                self.node(depth, role, &format!("For({variable})"));
+                self.expression(children, Some("iterable"), iterable);
+                self.block(children, Some("body"), body);
+            }
+            Expression::While { condition, body } => {
+                self.node(depth, role, "While");
+                self.expression(children, Some("condition"), condition);
+                self.block(children, Some("body"), body);
+            }
+            Expression::Postfix { operator, name } => {
+                self.node(depth, role, &format!("Postfix({name}{operator})"))
+            }
+            Expression::Function { arguments, body } => {
+                self.node(depth, role, &format!("Function({})", arguments.join(", ")));
+                self.block(children, Some("body"), body);
+            }
+            Expression::Macro { arguments, body } => {
+                self.node(depth, role, &format!("Macro({})", arguments.join(", ")));
+                self.block(children, Some("body"), body);
+            }
+            Expression::Array(elements) => {
+                self.node(depth, role, "Array");
+                for element in elements {
+                    self.expression(children, None, element);
+                }
+            }
+            Expression::Index { left, index } | Expression::OptionalIndex { left, index } => {
+                let label = match expression {
+                    Expression::OptionalIndex { .. } => "OptionalIndex",
+                    _ => "Index",
+                };
+                self.node(depth, role, label);
+                self.expression(children, Some("left"), left);
+                self.expression(children, Some("index"), index);
+            }
+            Expression::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let operator = if *inclusive { "..=" } else { ".." };
+                self.node(depth, role, &format!("Range({operator})"));
+                self.expression(children, None, start);
+                self.expression(children, None, end);
+            }
+            Expression::HashLiteral(entries) => {
+                self.node(depth, role, "HashLiteral");
+                for (key, value) in entries {
+                    self.node(children, None, "Entry");
+                    self.expression(children + 1, Some("key"), key);
+                    self.expression(children + 1, Some("value"), value);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::*;
+
+    fn tree(input: &str) -> String {
+        pretty_print(&parse(input).expect("Failed to parse program"))
+    }
+
+    #[test]
+    fn test_pretty_print_precedence() {
+        assert_eq!(
+            tree("let a = -b + c * d;"),
+            "Program
+  Let(a)
+    InfixOperator(+)
+      PrefixOperator(-)
+        Identifier(b)
+      InfixOperator(*)
+        Identifier(c)
+        Identifier(d)
+"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_nested_ifs() {
+        assert_eq!(
+            tree("if (a) { if (b) { 1 } else { 2 } }"),
+            "Program
+  Expression
+    If
+      condition: Identifier(a)
+      consequence: Block
+        Expression
+          If
+            condition: Identifier(b)
+            consequence: Block
+              Expression
+                Integer(1)
+            alternative: Block
+              Expression
+                Integer(2)
+"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_functions() {
+        assert_eq!(
+            tree("let add = fn(a, b) { return a + b; }; add(1, 2)"),
+            "Program
+  Let(add)
+    Function(a, b)
+      body: Block
+        Return
+          InfixOperator(+)
+            Identifier(a)
+            Identifier(b)
+  Expression
+    FunctionCall
+      callee: Identifier(add)
+      argument: Integer(1)
+      argument: Integer(2)
+"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_arrays_and_hashes() {
+        assert_eq!(
+            tree(r#"[1, [true, null]][0]; {"b": "two", "a": [1]}"#),
+            r#"Program
+  Expression
+    Index
+      left: Array
+        Integer(1)
+        Array
+          Boolean(true)
+          Null
+      index: Integer(0)
+  Expression
+    HashLiteral
+      Entry
+        key: String("a")
+        value: Array
+          Integer(1)
+      Entry
+        key: String("b")
+        value: String("two")
+"#
+        );
+    }
+}
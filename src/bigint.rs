@@ -0,0 +1,427 @@
+//! Integers of any size, which Monkey integers are promoted to when they don't fit in 64 bits.
+
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    ops::{Add, Mul, Neg, Sub},
+};
+
+/// Base of the chunks of decimal digits converted at once, the largest power of ten in a limb.
+const DECIMAL_CHUNK: u32 = 1_000_000_000;
+const DECIMAL_CHUNK_DIGITS: usize = 9;
+
+/// Integer of any size, as a sign and a magnitude made of 32-bit limbs, least significant first.
+/// It is kept normalized, without most significant zero limbs and with zero not negative, so
+/// that equal integers have equal representations.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Default)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+
+        Self {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    /// Parses decimal digits, with an optional leading `-`.
+    pub fn parse(source: &str) -> Option<Self> {
+        let (negative, digits) = match source.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, source),
+        };
+        if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut magnitude = Vec::new();
+        // The first chunk takes the digits left over so that the others are all full
+        let first = match digits.len() % DECIMAL_CHUNK_DIGITS {
+            0 => DECIMAL_CHUNK_DIGITS,
+            len => len,
+        };
+        let mut rest = digits;
+        let mut len = first;
+        while !rest.is_empty() {
+            let (chunk, remaining) = rest.split_at(len);
+            mul_add_small(&mut magnitude, 10u32.pow(len as u32), chunk.parse().ok()?);
+            rest = remaining;
+            len = DECIMAL_CHUNK_DIGITS;
+        }
+
+        Some(Self::new(negative, magnitude))
+    }
+
+    /// The integer when it fits in an `i64`.
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u64, |value, &limb| (value << 32) | u64::from(limb));
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    /// Bytes taken by the limbs of the magnitude.
+    pub fn heap_size(&self) -> usize {
+        self.magnitude.len() * std::mem::size_of::<u32>()
+    }
+
+    /// Quotient truncated towards zero, as for `i64`, or `None` when dividing by zero.
+    pub fn checked_div(&self, divisor: &Self) -> Option<Self> {
+        if divisor.magnitude.is_empty() {
+            return None;
+        }
+
+        let (quotient, _) = div_rem(&self.magnitude, &divisor.magnitude);
+        Some(Self::new(self.negative != divisor.negative, quotient))
+    }
+
+    /// Sum of `self` and `other` negated when `subtract`.
+    fn add_signed(&self, other: &Self, subtract: bool) -> Self {
+        let other_negative = other.negative != subtract;
+
+        if self.negative == other_negative {
+            return Self::new(self.negative, add(&self.magnitude, &other.magnitude));
+        }
+
+        match compare(&self.magnitude, &other.magnitude) {
+            Ordering::Less => Self::new(other_negative, sub(&other.magnitude, &self.magnitude)),
+            _ => Self::new(self.negative, sub(&self.magnitude, &other.magnitude)),
+        }
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let magnitude = value.unsigned_abs();
+        Self::new(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: Self) -> BigInt {
+        self.add_signed(other, false)
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: Self) -> BigInt {
+        self.add_signed(other, true)
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: Self) -> BigInt {
+        BigInt::new(
+            self.negative != other.negative,
+            mul(&self.magnitude, &other.magnitude),
+        )
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        let negative = !self.negative;
+        BigInt::new(negative, self.magnitude)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare(&self.magnitude, &other.magnitude),
+            (true, true) => compare(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Decimal digits, with a leading `-` when negative.
+impl Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.magnitude.is_empty() {
+            return write!(f, "0");
+        }
+
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            let (quotient, remainder) = div_rem_small(&magnitude, DECIMAL_CHUNK);
+            chunks.push(remainder);
+            magnitude = quotient;
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{first}")?;
+        }
+        for chunk in chunks {
+            write!(f, "{chunk:0width$}", width = DECIMAL_CHUNK_DIGITS)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialized as a string of its digits, as a JavaScript number would round it.
+#[cfg(target_family = "wasm")]
+impl serde::Serialize for BigInt {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+fn trimmed(magnitude: &[u32]) -> &[u32] {
+    let len = magnitude
+        .iter()
+        .rposition(|&limb| limb != 0)
+        .map_or(0, |position| position + 1);
+    &magnitude[..len]
+}
+
+fn compare(left: &[u32], right: &[u32]) -> Ordering {
+    let (left, right) = (trimmed(left), trimmed(right));
+    left.len()
+        .cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let (long, short) = if left.len() >= right.len() {
+        (left, right)
+    } else {
+        (right, left)
+    };
+
+    let mut sum = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (index, &limb) in long.iter().enumerate() {
+        let total = u64::from(limb) + u64::from(short.get(index).copied().unwrap_or(0)) + carry;
+        sum.push(total as u32);
+        carry = total >> 32;
+    }
+    sum.push(carry as u32);
+    sum
+}
+
+/// `left - right`, where `left` is at least `right`.
+fn sub(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(left.len());
+    let mut borrow = 0i64;
+    for (index, &limb) in left.iter().enumerate() {
+        let mut total =
+            i64::from(limb) - i64::from(right.get(index).copied().unwrap_or(0)) - borrow;
+        borrow = 0;
+        if total < 0 {
+            total += 1 << 32;
+            borrow = 1;
+        }
+        difference.push(total as u32);
+    }
+    difference
+}
+
+fn mul(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut product = vec![0u32; left.len() + right.len()];
+    for (i, &a) in left.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &b) in right.iter().enumerate() {
+            let total = u64::from(a) * u64::from(b) + u64::from(product[i + j]) + carry;
+            product[i + j] = total as u32;
+            carry = total >> 32;
+        }
+        product[i + right.len()] = carry as u32;
+    }
+    product
+}
+
+/// Multiplies `magnitude` by `factor` and adds `addend`, in place.
+fn mul_add_small(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = u64::from(addend);
+    for limb in magnitude.iter_mut() {
+        let total = u64::from(*limb) * u64::from(factor) + carry;
+        *limb = total as u32;
+        carry = total >> 32;
+    }
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+fn div_rem_small(dividend: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder = 0u64;
+    for (index, &limb) in dividend.iter().enumerate().rev() {
+        let current = (remainder << 32) | u64::from(limb);
+        quotient[index] = (current / u64::from(divisor)) as u32;
+        remainder = current % u64::from(divisor);
+    }
+
+    let len = trimmed(&quotient).len();
+    quotient.truncate(len);
+    (quotient, remainder as u32)
+}
+
+/// Quotient and remainder of a division by a non-zero `divisor`, bit by bit unless the divisor
+/// fits in a limb.
+fn div_rem(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let divisor = trimmed(divisor);
+    if let [divisor] = divisor {
+        let (quotient, remainder) = div_rem_small(dividend, *divisor);
+        return (quotient, vec![remainder]);
+    }
+
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder: Vec<u32> = Vec::with_capacity(divisor.len() + 1);
+    for bit in (0..dividend.len() * 32).rev() {
+        // remainder = remainder * 2 + the next bit of the dividend
+        let mut carry = (dividend[bit / 32] >> (bit % 32)) & 1;
+        for limb in remainder.iter_mut() {
+            let shifted = (*limb >> 31) & 1;
+            *limb = (*limb << 1) | carry;
+            carry = shifted;
+        }
+        if carry > 0 {
+            remainder.push(carry);
+        }
+
+        if compare(&remainder, divisor) != Ordering::Less {
+            remainder = sub(&remainder, divisor);
+            let len = trimmed(&remainder).len();
+            remainder.truncate(len);
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(source: &str) -> BigInt {
+        BigInt::parse(source).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        for source in [
+            "0",
+            "1",
+            "-1",
+            "4294967296",
+            "9223372036854775808",
+            "-9223372036854775809",
+            "1000000000",
+            "123456789012345678901234567890",
+            "-100000000000000000000000000000000000000",
+        ] {
+            assert_eq!(big(source).to_string(), source);
+        }
+
+        assert_eq!(big("000123").to_string(), "123");
+        assert_eq!(big("-0"), BigInt::default());
+        assert_eq!(BigInt::parse(""), None);
+        assert_eq!(BigInt::parse("-"), None);
+        assert_eq!(BigInt::parse("12a"), None);
+    }
+
+    #[test]
+    fn test_i64_conversions() {
+        for value in [0, 1, -1, i64::MAX, i64::MIN, 1 << 32, -(1 << 32)] {
+            assert_eq!(BigInt::from(value).to_i64(), Some(value));
+            assert_eq!(BigInt::from(value).to_string(), value.to_string());
+        }
+
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(big("-9223372036854775809").to_i64(), None);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let max = BigInt::from(i64::MAX);
+        let one = BigInt::from(1);
+
+        assert_eq!(&max + &one, big("9223372036854775808"));
+        assert_eq!(&(&max + &one) - &one, max);
+        assert_eq!(&one - &max, BigInt::from(-i64::MAX + 1));
+        assert_eq!(&BigInt::from(-5) + &BigInt::from(5), BigInt::default());
+        assert_eq!(&BigInt::from(-5) - &BigInt::from(-7), BigInt::from(2));
+        assert_eq!(&max * &max, big("85070591730234615847396907784232501249"));
+        assert_eq!(&max * &BigInt::from(-2), big("-18446744073709551614"));
+        assert_eq!(-max.clone(), BigInt::from(-i64::MAX));
+
+        let dividend = big("85070591730234615847396907784232501250");
+        assert_eq!(dividend.checked_div(&max), Some(max.clone()));
+        assert_eq!((-dividend.clone()).checked_div(&max), Some(-max.clone()));
+        assert_eq!(
+            dividend.checked_div(&BigInt::from(-10)),
+            Some(big("-8507059173023461584739690778423250125"))
+        );
+        assert_eq!(
+            BigInt::from(7).checked_div(&big("18446744073709551616")),
+            Some(BigInt::default())
+        );
+        assert_eq!(dividend.checked_div(&BigInt::default()), None);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut values = [
+            "5",
+            "-18446744073709551616",
+            "18446744073709551616",
+            "0",
+            "-5",
+        ]
+        .map(big)
+        .to_vec();
+        values.sort();
+
+        assert_eq!(
+            values,
+            [
+                "-18446744073709551616",
+                "-5",
+                "0",
+                "5",
+                "18446744073709551616"
+            ]
+            .map(big)
+        );
+    }
+}
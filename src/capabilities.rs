@@ -0,0 +1,389 @@
+/// Version of the interpreter, as published on crates.io and npm.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A language capability scripts and embedders can check for, with a snippet exercising it.
+pub struct Feature {
+    pub name: &'static str,
+    pub example: &'static str,
+}
+
+/// Registry of the language capabilities, to be updated alongside the feature work, each builtin
+/// being one named after it. Every example is evaluated by the tests, so an entry can't outlive
+/// its feature, and every builtin must have one calling it.
+pub const FEATURES: &[Feature] = &[
+    Feature {
+        name: "array_destructuring",
+        example: "let [a, b] = [1, 2]; a + b",
+    },
+    Feature {
+        name: "bigint",
+        example: "9223372036854775807 + 1",
+    },
+    Feature {
+        name: "block_scopes",
+        example: "let a = 1; if (true) { let a = 2; a }; a",
+    },
+    Feature {
+        name: "chars",
+        example: r#"chars("abc")"#,
+    },
+    Feature {
+        name: "chr",
+        example: "chr(97)",
+    },
+    Feature {
+        name: "comments",
+        example: "// The answer\n42",
+    },
+    Feature {
+        name: "const",
+        example: "const a = 1; a",
+    },
+    Feature {
+        name: "contains",
+        example: r#"contains("team", "ea")"#,
+    },
+    Feature {
+        name: "delete",
+        example: r#"delete({"a": 1}, "a")"#,
+    },
+    Feature {
+        name: "ends_with",
+        example: r#"ends_with("main.mk", ".mk")"#,
+    },
+    Feature {
+        name: "exit",
+        example: "exit(0)",
+    },
+    Feature {
+        name: "filter",
+        example: "filter([1, 2, 3], fn(x) { x > 1 })",
+    },
+    Feature {
+        name: "find",
+        example: "find([1, 2, 3], fn(x) { x > 1 })",
+    },
+    Feature {
+        name: "first",
+        example: "first([1, 2])",
+    },
+    Feature {
+        name: "flatten",
+        example: "flatten([[1], [2, [3]]])",
+    },
+    Feature {
+        name: "flush",
+        example: r#"print("a"); flush()"#,
+    },
+    Feature {
+        name: "has_key",
+        example: r#"has_key({"a": 1}, "a")"#,
+    },
+    Feature {
+        name: "hash_destructuring",
+        example: r#"let {a, b: c} = {"a": 1, "b": 2}; a + c"#,
+    },
+    Feature {
+        name: "index_of",
+        example: "index_of([1, 2], 2)",
+    },
+    Feature {
+        name: "insert",
+        example: r#"insert({"a": 1}, "b", 2)"#,
+    },
+    Feature {
+        name: "int",
+        example: r#"int("42")"#,
+    },
+    Feature {
+        name: "join",
+        example: r#"join(["a", "b"], ",")"#,
+    },
+    Feature {
+        name: "keys",
+        example: r#"keys({"a": 1})"#,
+    },
+    Feature {
+        name: "last",
+        example: "last([1, 2])",
+    },
+    Feature {
+        name: "len",
+        example: r#"len("abc")"#,
+    },
+    Feature {
+        name: "loops",
+        example: "for (i in [1, 2]) { i }",
+    },
+    Feature {
+        name: "lower",
+        example: r#"lower("ABC")"#,
+    },
+    Feature {
+        name: "macros",
+        example: "let twice = macro(x) { quote(unquote(x) * 2) }; twice(21)",
+    },
+    Feature {
+        name: "map",
+        example: "map([1, 2], fn(x) { x * 2 })",
+    },
+    Feature {
+        name: "merge",
+        example: r#"merge({"a": 1}, {"b": 2})"#,
+    },
+    Feature {
+        name: "method_calls",
+        example: "[1, 2].push(3).len()",
+    },
+    Feature {
+        name: "negative_indices",
+        example: "[1, 2, 3][-1]",
+    },
+    Feature {
+        name: "null_coalescing",
+        example: r#"{"a": 1}["b"] ?? 2"#,
+    },
+    Feature {
+        name: "null_literal",
+        example: "[null, 1][0] ?? 2",
+    },
+    Feature {
+        name: "optional_chaining",
+        example: r#"{"a": 1}?.b?.c"#,
+    },
+    Feature {
+        name: "ord",
+        example: r#"ord("a")"#,
+    },
+    Feature {
+        name: "pop",
+        example: "pop([1, 2])",
+    },
+    Feature {
+        name: "postfix_operators",
+        example: "let i = 0; i++; i--; i++; i",
+    },
+    Feature {
+        name: "print",
+        example: r#"print("a")"#,
+    },
+    Feature {
+        name: "push",
+        example: "push([1], 2)",
+    },
+    Feature {
+        name: "puts",
+        example: r#"puts("a")"#,
+    },
+    Feature {
+        name: "ranges",
+        example: "[1, 2, 3][0..=1].len()",
+    },
+    Feature {
+        name: "read_line",
+        example: r#"read_line() ?? """#,
+    },
+    Feature {
+        name: "reduce",
+        example: "reduce([1, 2], 0, fn(total, x) { total + x })",
+    },
+    Feature {
+        name: "remove",
+        example: "remove([1, 2], 0)",
+    },
+    Feature {
+        name: "replace",
+        example: r#"replace("aab", "a", "b")"#,
+    },
+    Feature {
+        name: "rest",
+        example: "rest([1, 2])",
+    },
+    Feature {
+        name: "reverse",
+        example: "reverse([1, 2])",
+    },
+    Feature {
+        name: "shebang",
+        example: "#!/usr/bin/env monkey-interpreter\n1",
+    },
+    Feature {
+        name: "slice",
+        example: "slice([1, 2, 3], 1)",
+    },
+    Feature {
+        name: "sort",
+        example: "sort([2, 1])",
+    },
+    Feature {
+        name: "split",
+        example: r#"split("a,b", ",")"#,
+    },
+    Feature {
+        name: "starts_with",
+        example: r#"starts_with("main.mk", "main")"#,
+    },
+    Feature {
+        name: "str",
+        example: "str(1)",
+    },
+    Feature {
+        name: "string_comparison",
+        example: r#""apple" < "banana" == ("a" != "b")"#,
+    },
+    Feature {
+        name: "string_escapes",
+        example: r#""say \"hi\"\n""#,
+    },
+    Feature {
+        name: "string_indexing",
+        example: r#""héllo"[1]"#,
+    },
+    Feature {
+        name: "substring",
+        example: r#"substring("hello", 1, 3)"#,
+    },
+    Feature {
+        name: "tail_calls",
+        example: "let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(5000)",
+    },
+    Feature {
+        name: "throw",
+        example: "let check = fn(x) { if (x < 0) { throw x; } x }; check(1)",
+    },
+    Feature {
+        name: "trailing_commas",
+        example: "[1, 2,]",
+    },
+    Feature {
+        name: "trim",
+        example: r#"trim(" a ")"#,
+    },
+    Feature {
+        name: "trim_left",
+        example: r#"trim_left(" a")"#,
+    },
+    Feature {
+        name: "trim_right",
+        example: r#"trim_right("a ")"#,
+    },
+    Feature {
+        name: "type",
+        example: "type(1)",
+    },
+    Feature {
+        name: "unary_plus",
+        example: "+1 - -1",
+    },
+    Feature {
+        name: "unique",
+        example: "unique([1, 1])",
+    },
+    Feature {
+        name: "upper",
+        example: r#"upper("abc")"#,
+    },
+    Feature {
+        name: "values",
+        example: r#"values({"a": 1})"#,
+    },
+    Feature {
+        name: "version",
+        example: r#"version()["version"]"#,
+    },
+    Feature {
+        name: "while_loops",
+        example: "let i = 0; while (i < 3) { i++ }; i",
+    },
+    Feature {
+        name: "zip",
+        example: r#"zip([1, 2], ["a", "b"])"#,
+    },
+];
+
+/// Capabilities that depend on how the crate was compiled rather than on the language.
+const COMPILED_FEATURES: &[(&str, bool)] = &[("wasm", cfg!(target_family = "wasm"))];
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
+pub struct Capabilities {
+    pub version: &'static str,
+    pub features: Vec<&'static str>,
+}
+
+pub fn capabilities() -> Capabilities {
+    let language = FEATURES.iter().map(|feature| feature.name);
+    let compiled = COMPILED_FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name);
+
+    Capabilities {
+        version: VERSION,
+        features: language.chain(compiled).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluator::{
+            builtins::Builtin,
+            context::{EvalContext, EvalOptions},
+            environment::Environment,
+            error::EvalError,
+            macros::define_and_expand_macros,
+            sink::{Buffering, Sink},
+            Evaluator,
+        },
+        parse,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = capabilities();
+
+        assert_eq!(capabilities.version, env!("CARGO_PKG_VERSION"));
+        assert!(capabilities.features.contains(&"optional_chaining"));
+        assert_eq!(
+            capabilities.features.contains(&"wasm"),
+            cfg!(target_family = "wasm")
+        );
+    }
+
+    #[test]
+    fn test_feature_examples() {
+        for feature in FEATURES {
+            let program = parse(feature.example)
+                .unwrap_or_else(|errors| panic!("{}: {errors:?}", feature.name));
+            let environment = Environment::shared();
+            let mut context = EvalContext::with_io(
+                EvalOptions::default(),
+                std::io::empty(),
+                Sink::new(std::io::sink(), Buffering::Unbuffered),
+            );
+            let result = define_and_expand_macros(program, &environment, &mut context)
+                .and_then(|program| program.eval_with_context(&environment, &mut context));
+
+            assert!(
+                matches!(result, Ok(_) | Err(EvalError::Exit(0))),
+                "{}: {result:?}",
+                feature.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_builtin_is_a_feature() {
+        for name in Builtin::NAMES {
+            let feature = FEATURES.iter().find(|feature| feature.name == *name);
+
+            assert!(
+                feature.is_some_and(|feature| feature.example.contains(&format!("{name}("))),
+                "{name} has no feature calling it"
+            );
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use monkey_interpreter::parse_with_diagnostics;
+
+#[derive(Default)]
+struct CheckOptions {
+    json: bool,
+    deny_warnings: bool,
+}
+
+pub fn run(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let mut path = None;
+    let mut options = CheckOptions::default();
+
+    for arg in args {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--deny-warnings" => options.deny_warnings = true,
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown flag for check: {flag}").into())
+            }
+            file => path = Some(file),
+        }
+    }
+
+    let path = path.ok_or("missing file to check")?;
+    let source = std::fs::read_to_string(path)?;
+
+    if check(&source, path, &options, &mut io::stdout().lock())? {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Writes the parser diagnostics of `source` to `output`, returning whether it passed.
+fn check(
+    source: &str,
+    path: &str,
+    options: &CheckOptions,
+    output: &mut impl Write,
+) -> io::Result<bool> {
+    let mut report = parse_with_diagnostics(source);
+    if options.deny_warnings {
+        report.deny_warnings();
+    }
+
+    if options.json {
+        writeln!(output, "{}", report.to_json(source))?;
+    } else {
+        for diagnostic in report.diagnostics.iter() {
+            writeln!(output, "{}", diagnostic.render(source, path))?;
+        }
+        writeln!(
+            output,
+            "{path}: {} error(s), {} warning(s)",
+            report.errors().count(),
+            report.warnings().count()
+        )?;
+    }
+
+    Ok(!report.has_errors())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_check(source: &str, options: &CheckOptions) -> (bool, String) {
+        let mut output = Vec::new();
+        let passed = check(source, "main.mk", options, &mut output).unwrap();
+        (passed, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_check_renders_diagnostics() {
+        let (passed, output) =
+            run_check("let a = {1: 1, 1: 2};\nlet b = 5", &CheckOptions::default());
+
+        assert!(passed);
+        assert_eq!(
+            output,
+            "warning[duplicate-key]: duplicate key 1 in hash literal, the last value wins\n  --> main.mk:1:16\nmain.mk: 0 error(s), 1 warning(s)\n"
+        );
+    }
+
+    #[test]
+    fn test_check_fails_on_errors() {
+        let (passed, output) = run_check("let x = ;", &CheckOptions::default());
+
+        assert!(!passed);
+        assert!(
+            output.ends_with("main.mk: 1 error(s), 0 warning(s)\n"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn test_check_lines_count_shebang() {
+        let (passed, output) = run_check(
+            "#!/usr/bin/env monkey\nlet x = ;\n# 5",
+            &CheckOptions::default(),
+        );
+
+        assert!(!passed);
+        assert_eq!(
+            output,
+            "error[no-prefix-parser]: no expression statement parser for ;\n  --> main.mk:2:9\nerror[no-prefix-parser]: no expression statement parser for #\n  --> main.mk:3:1\nmain.mk: 2 error(s), 0 warning(s)\n"
+        );
+    }
+
+    #[test]
+    fn test_check_deny_warnings() {
+        let options = CheckOptions {
+            deny_warnings: true,
+            ..Default::default()
+        };
+        let (passed, output) = run_check("{1: 1, 1: 2}", &options);
+
+        assert!(!passed);
+        assert!(output.starts_with("error[duplicate-key]"), "{output}");
+    }
+
+    #[test]
+    fn test_check_json() {
+        let options = CheckOptions {
+            json: true,
+            ..Default::default()
+        };
+        let (passed, output) = run_check("let a = 1;", &options);
+
+        assert!(passed);
+        assert_eq!(output, "{\"has_program\":true,\"diagnostics\":[]}\n");
+    }
+}
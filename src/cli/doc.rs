@@ -0,0 +1,124 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use monkey_interpreter::doc::{extract, to_json, to_markdown, FunctionDoc};
+
+#[derive(Default)]
+struct DocOptions {
+    json: bool,
+    private: bool,
+}
+
+pub fn run(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let mut path = None;
+    let mut options = DocOptions::default();
+
+    for arg in args {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--private" => options.private = true,
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown flag for doc: {flag}").into())
+            }
+            file => path = Some(file),
+        }
+    }
+
+    let path = path.ok_or("missing file to document")?;
+    let source = std::fs::read_to_string(path)?;
+
+    if doc(&source, path, &options, &mut io::stdout().lock())? {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Writes the documentation of the functions of `source` to `output`, returning whether it
+/// could be parsed.
+fn doc(
+    source: &str,
+    path: &str,
+    options: &DocOptions,
+    output: &mut impl Write,
+) -> io::Result<bool> {
+    let functions = match extract(source) {
+        Ok(functions) => functions,
+        Err(diagnostics) => {
+            for diagnostic in diagnostics {
+                writeln!(output, "{}", diagnostic.render(source, path))?;
+            }
+            return Ok(false);
+        }
+    };
+
+    let functions = functions
+        .into_iter()
+        .filter(|function| options.private || function.is_public())
+        .collect::<Vec<FunctionDoc>>();
+
+    if options.json {
+        writeln!(output, "{}", to_json(&functions))?;
+    } else {
+        write!(output, "{}", to_markdown(path, &functions))?;
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = r#"// Greets someone.
+let greet = fn(name) { "hello " + name };
+
+let shout = fn(text) { text + "!" };
+
+// Not for the outside world.
+let _prefix = fn() { "hello " };"#;
+
+    fn run_doc(source: &str, options: &DocOptions) -> (bool, String) {
+        let mut output = Vec::new();
+        let passed = doc(source, "greetings.mk", options, &mut output).unwrap();
+        (passed, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_doc_skips_private_functions() {
+        let (passed, output) = run_doc(FIXTURE, &DocOptions::default());
+
+        assert!(passed);
+        assert_eq!(
+            output,
+            "# greetings.mk\n\n## `greet(name)`\n\nGreets someone.\n\n## `shout(text)`\n"
+        );
+    }
+
+    #[test]
+    fn test_doc_private_json() {
+        let options = DocOptions {
+            json: true,
+            private: true,
+        };
+        let (passed, output) = run_doc(FIXTURE, &options);
+
+        assert!(passed);
+        assert_eq!(
+            output,
+            r#"{"functions":[{"name":"greet","parameters":["name"],"comment":"Greets someone."},{"name":"shout","parameters":["text"],"comment":null},{"name":"_prefix","parameters":[],"comment":"Not for the outside world."}]}
+"#
+        );
+    }
+
+    #[test]
+    fn test_doc_reports_parse_errors() {
+        let (passed, output) = run_doc("let f = fn( {};", &DocOptions::default());
+
+        assert!(!passed);
+        assert!(output.starts_with("error["), "{output}");
+    }
+}
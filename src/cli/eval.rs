@@ -0,0 +1,466 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+    process::ExitCode,
+    time::Instant,
+};
+
+use monkey_interpreter::{
+    analyzer::{analyze_with, AnalysisOptions},
+    ast::describe,
+    evaluator::{
+        context::{self, EvalContext},
+        coverage::Coverage,
+        environment::Environment,
+        error::EvalError,
+        macros::define_and_expand_macros,
+        object::Object,
+        profiler::Profiler,
+        Evaluator,
+    },
+    parse,
+    parser::Program,
+};
+
+/// Maximum number of characters of a statement shown in the progress lines.
+const STATEMENT_WIDTH: usize = 40;
+
+/// How the evaluation of a file ended, which gives the exit status of the process.
+#[derive(PartialEq, Eq, Debug)]
+enum Outcome {
+    Success,
+    Failure,
+    /// The program called `exit` with this code
+    Exit(i32),
+}
+
+impl Outcome {
+    fn of(result: &Result<Option<Object>, EvalError>) -> Self {
+        match result {
+            Ok(_) => Self::Success,
+            Err(EvalError::Exit(code)) => Self::Exit(*code),
+            Err(_) => Self::Failure,
+        }
+    }
+}
+
+impl From<Outcome> for ExitCode {
+    fn from(outcome: Outcome) -> Self {
+        match outcome {
+            Outcome::Success => ExitCode::SUCCESS,
+            Outcome::Failure => ExitCode::FAILURE,
+            Outcome::Exit(code) => super::exit_code(code),
+        }
+    }
+}
+
+#[derive(Default)]
+struct EvalOptions {
+    verbose_statements: bool,
+    time: bool,
+    /// Reports the calls and evaluations counted and timed once the program ended
+    profile: bool,
+    /// Prints the source annotated with how many times its statements ran once the program ended
+    coverage: bool,
+    /// Reports the identifiers that can't be bound before evaluating
+    warnings: bool,
+    /// Also reports the bindings shadowing or redeclaring another one, implying `warnings`
+    shadowing: bool,
+    /// Sources over this many bytes are rejected, unlimited when not set
+    max_source_size: Option<usize>,
+}
+
+pub fn run(args: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let mut path = None;
+    let mut options = EvalOptions::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--verbose-statements" => options.verbose_statements = true,
+            "--time" => options.time = true,
+            "--profile" => options.profile = true,
+            "--coverage" => options.coverage = true,
+            "--warnings" => options.warnings = true,
+            "--shadowing" => {
+                options.warnings = true;
+                options.shadowing = true;
+            }
+            "--max-source-size" => {
+                let size = args.next().ok_or("missing value for --max-source-size")?;
+                let size = size
+                    .parse()
+                    .map_err(|_| format!("invalid value for --max-source-size: {size}"))?;
+                options.max_source_size = Some(size);
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown flag for eval: {flag}").into())
+            }
+            file => path = Some(file),
+        }
+    }
+
+    let path = path.ok_or("missing file to evaluate")?;
+    let source = std::fs::read_to_string(path)?;
+
+    Ok(eval(&source, path, &options, &mut io::stdout().lock())?.into())
+}
+
+/// Evaluates `source`, writing its result (or one progress line per top-level statement when
+/// verbose) to `output`, returning how it ended.
+fn eval(
+    source: &str,
+    path: &str,
+    options: &EvalOptions,
+    output: &mut impl Write,
+) -> io::Result<Outcome> {
+    let eval_options = context::EvalOptions {
+        max_source_bytes: options.max_source_size.unwrap_or(usize::MAX),
+        max_stack_bytes: crate::MAX_STACK_BYTES,
+        ..Default::default()
+    };
+    if let Err(diagnostic) = eval_options.check_source_size(source) {
+        writeln!(output, "{}", diagnostic.render(source, path))?;
+        return Ok(Outcome::Failure);
+    }
+
+    let program = match parse(source) {
+        Ok(program) => program,
+        Err(diagnostics) => {
+            for diagnostic in diagnostics {
+                writeln!(output, "{}", diagnostic.render(source, path))?;
+            }
+            return Ok(Outcome::Failure);
+        }
+    };
+
+    let environment = Environment::shared();
+    let mut context = EvalContext::new(eval_options);
+    if options.profile {
+        context.profiler = Some(Profiler::default());
+    }
+    let program = match define_and_expand_macros(program, &environment, &mut context) {
+        Ok(program) => program,
+        Err(EvalError::Exit(code)) => return Ok(Outcome::Exit(code)),
+        Err(error) => {
+            writeln!(output, "{error}")?;
+            return Ok(Outcome::Failure);
+        }
+    };
+
+    if options.warnings {
+        let analysis = AnalysisOptions {
+            shadowing: options.shadowing.then_some(source),
+        };
+        for diagnostic in analyze_with(&program, analysis) {
+            writeln!(output, "{}", diagnostic.render(source, path))?;
+        }
+    }
+
+    let coverage = options.coverage.then(|| Coverage::install(&mut context));
+
+    if !options.verbose_statements {
+        let start = Instant::now();
+        let result = program.eval_with_context(&environment, &mut context);
+        match &result {
+            Ok(Some(value)) => writeln!(output, "{value}")?,
+            Ok(None) | Err(EvalError::Exit(_)) => {}
+            Err(error) => writeln!(output, "{error}")?,
+        }
+        if options.time {
+            writeln!(output, "finished in {:?}", start.elapsed())?;
+        }
+        write_reports(&program, source, &context, coverage.as_ref(), output)?;
+        return Ok(Outcome::of(&result));
+    }
+
+    let total = program.statements.len();
+    let mut start = Instant::now();
+    let mut outcome = Outcome::Success;
+
+    for (index, (statement, result)) in program
+        .eval_statement_by_statement(&environment, &mut context)
+        .enumerate()
+    {
+        let elapsed = start.elapsed();
+        let value = match &result {
+            Ok(Some(value)) => value.to_string(),
+            Ok(None) => "(no value)".into(),
+            Err(error) => error.to_string(),
+        };
+        let statement_outcome = Outcome::of(&result);
+
+        write!(
+            output,
+            "[{}/{total}] {} => {value}",
+            index + 1,
+            describe(statement, STATEMENT_WIDTH)
+        )?;
+        if options.time {
+            write!(output, " ({elapsed:?})")?;
+        }
+        writeln!(output)?;
+        output.flush()?;
+
+        if statement_outcome != Outcome::Success {
+            outcome = statement_outcome;
+            break;
+        }
+
+        start = Instant::now();
+    }
+
+    write_reports(&program, source, &context, coverage.as_ref(), output)?;
+    Ok(outcome)
+}
+
+/// Writes what was profiled and covered during the evaluation of `program`, when asked to.
+fn write_reports(
+    program: &Program,
+    source: &str,
+    context: &EvalContext,
+    coverage: Option<&Coverage>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    if let Some(profiler) = &context.profiler {
+        write!(output, "{}", profiler.report())?;
+    }
+
+    if let Some(coverage) = coverage {
+        let report = coverage.report(program);
+        write!(output, "{}", report.annotate(source))?;
+        writeln!(
+            output,
+            "{} of {} statements covered",
+            report.covered(),
+            report.statements.len()
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = r#"let a = 5;
+let b = a * 2;
+let greeting = "a rather long greeting that does not fit on one line";
+puts(greeting);
+a + b;"#;
+
+    fn run_eval(source: &str, options: &EvalOptions) -> (Outcome, String) {
+        let mut output = Vec::new();
+        let outcome = eval(source, "main.mk", options, &mut output).unwrap();
+        (outcome, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_eval_prints_final_value() {
+        let (outcome, output) = run_eval("let a = 5; a * 2", &EvalOptions::default());
+
+        assert_eq!(outcome, Outcome::Success);
+        assert_eq!(output, "10\n");
+    }
+
+    #[test]
+    fn test_eval_with_shebang() {
+        let options = EvalOptions {
+            verbose_statements: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            run_eval(&format!("#!/usr/bin/env monkey\n{FIXTURE}"), &options),
+            run_eval(FIXTURE, &options)
+        );
+    }
+
+    #[test]
+    fn test_eval_verbose_statements() {
+        let options = EvalOptions {
+            verbose_statements: true,
+            ..Default::default()
+        };
+        let (outcome, output) = run_eval(FIXTURE, &options);
+
+        assert_eq!(outcome, Outcome::Success);
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            vec![
+                "[1/5] let a = 5; => 5",
+                "[2/5] let b = (a * 2); => 10",
+                r#"[3/5] let greeting = "a rather long greeting … => a rather long greeting that does not fit on one line"#,
+                "[4/5] puts(greeting) => null",
+                "[5/5] (a + b) => 15",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_eval_verbose_statements_stops_on_error() {
+        let options = EvalOptions {
+            verbose_statements: true,
+            time: true,
+            ..Default::default()
+        };
+        let (outcome, output) = run_eval("let a = 1; a + c; a;", &options);
+
+        assert_eq!(outcome, Outcome::Failure);
+        assert_eq!(output.lines().count(), 2);
+        assert!(
+            output
+                .lines()
+                .nth(1)
+                .unwrap()
+                .starts_with("[2/3] (a + c) => Error: identifier not found: c ("),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn test_eval_max_source_size() {
+        let options = EvalOptions {
+            max_source_size: Some(FIXTURE.len()),
+            ..Default::default()
+        };
+        assert_eq!(
+            run_eval(FIXTURE, &options),
+            (Outcome::Success, "15\n".into())
+        );
+
+        let options = EvalOptions {
+            max_source_size: Some(FIXTURE.len() - 1),
+            ..Default::default()
+        };
+        let (outcome, output) = run_eval(FIXTURE, &options);
+
+        assert_eq!(outcome, Outcome::Failure);
+        assert_eq!(
+            output,
+            format!(
+                "error[source-too-large]: source too large ({} bytes, limit {})\n  --> main.mk:1:1\n",
+                FIXTURE.len(),
+                FIXTURE.len() - 1
+            )
+        );
+    }
+
+    #[test]
+    fn test_eval_warnings() {
+        let options = EvalOptions {
+            warnings: true,
+            ..Default::default()
+        };
+        let (outcome, output) = run_eval("let f = fn() {\n    b\n};\n1", &options);
+
+        assert_eq!(outcome, Outcome::Success);
+        assert_eq!(
+            output,
+            "warning[unused-variable]: unused variable `f`\n  --> main.mk:1:1\nwarning[undefined-identifier]: `b` is never declared\n  --> main.mk:2:5\n1\n"
+        );
+    }
+
+    #[test]
+    fn test_eval_shadowing_warnings() {
+        let source = "let n = 1;\nlet double = fn(n) { n * 2 };\ndouble(n)";
+        let options = EvalOptions {
+            warnings: true,
+            shadowing: true,
+            ..Default::default()
+        };
+        let (outcome, output) = run_eval(source, &options);
+
+        assert_eq!(outcome, Outcome::Success);
+        assert_eq!(
+            output,
+            "warning[shadowed-binding]: parameter `n` shadows a binding from an enclosing scope at line 1\n  --> main.mk:2:14\n2\n"
+        );
+
+        let options = EvalOptions {
+            warnings: true,
+            ..Default::default()
+        };
+        assert_eq!(run_eval(source, &options), (Outcome::Success, "2\n".into()));
+    }
+
+    #[test]
+    fn test_eval_profile() {
+        let source = "let double = fn(x) { x * 2 }; double(1) + double(2)";
+        let options = EvalOptions {
+            profile: true,
+            ..Default::default()
+        };
+        let (outcome, output) = run_eval(source, &options);
+        let lines = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(outcome, Outcome::Success);
+        assert_eq!(lines[..2], ["6", "functions"]);
+        assert!(lines[2].starts_with("  double "), "{output}");
+        assert!(lines[2].contains(" 2 calls "), "{output}");
+        assert!(lines.contains(&"expressions"));
+
+        let options = EvalOptions {
+            verbose_statements: true,
+            ..options
+        };
+        let (_, output) = run_eval(source, &options);
+        assert!(output.contains("\nfunctions\n"), "{output}");
+    }
+
+    #[test]
+    fn test_eval_coverage() {
+        let source = "let a = 1;\nif (a > 1) { a } else { 0 }\nlet never = fn() {\n  a\n};";
+        let options = EvalOptions {
+            coverage: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            run_eval(source, &options),
+            (
+                Outcome::Success,
+                [
+                    "fn() { a }",
+                    "    1 | let a = 1;",
+                    "##### | if (a > 1) { a } else { 0 }",
+                    "    1 | let never = fn() {",
+                    "##### |   a",
+                    "    - | };",
+                    "4 of 6 statements covered\n",
+                ]
+                .join("\n")
+            )
+        );
+    }
+
+    #[test]
+    fn test_eval_exit() {
+        let source = "let a = 1; let quit = fn() { exit(3) }; quit(); a";
+        assert_eq!(
+            run_eval(source, &EvalOptions::default()),
+            (Outcome::Exit(3), String::new())
+        );
+
+        let options = EvalOptions {
+            verbose_statements: true,
+            ..Default::default()
+        };
+        let (outcome, output) = run_eval(source, &options);
+        assert_eq!(outcome, Outcome::Exit(3));
+        assert_eq!(
+            output.lines().nth(2),
+            Some("[3/4] quit() => exited with code 3")
+        );
+        assert_eq!(output.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_eval_reports_parse_errors() {
+        let (outcome, output) = run_eval("let x = ;", &EvalOptions::default());
+
+        assert_eq!(outcome, Outcome::Failure);
+        assert!(output.starts_with("error[no-prefix-parser]"), "{output}");
+    }
+}
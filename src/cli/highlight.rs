@@ -0,0 +1,205 @@
+use monkey_interpreter::lexer::{Lexer, Span, Token};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Style {
+    Keyword,
+    String,
+    Number,
+    /// Bracket next to the cursor, and the one it pairs with
+    MatchingBracket,
+}
+
+impl Style {
+    fn ansi_code(&self) -> &'static str {
+        match self {
+            Self::Keyword => "\x1b[35m",
+            Self::String => "\x1b[32m",
+            Self::Number => "\x1b[33m",
+            Self::MatchingBracket => "\x1b[1;4m",
+        }
+    }
+}
+
+fn style(token: &Token) -> Option<Style> {
+    match token {
+        token if token.is_keyword() => Some(Style::Keyword),
+        Token::String(_) => Some(Style::String),
+        Token::Integer(_) | Token::BigInt(_) => Some(Style::Number),
+        _ => None,
+    }
+}
+
+fn is_opening_bracket(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::LeftParen | Token::LeftBracket | Token::LeftBrace | Token::QuestionBracket
+    )
+}
+
+fn is_closing_bracket(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::RightParen | Token::RightBracket | Token::RightBrace
+    )
+}
+
+/// Styled spans of `source`, in order, with the bracket right before or else right after the
+/// `cursor` emphasized alongside its pair. Incomplete input, such as an unterminated string, is
+/// styled as far as it goes.
+pub fn highlight(source: &str, cursor: Option<usize>) -> Vec<(Span, Style)> {
+    let length = source.chars().count();
+    let mut lexer = Lexer::new(source.into());
+    let mut tokens = Vec::new();
+
+    loop {
+        let token = lexer.next_token();
+        if token == Token::Eof {
+            break;
+        }
+        let span = lexer.span();
+        tokens.push((token, Span::new(span.start, span.end.min(length))));
+    }
+
+    let matching = cursor
+        .and_then(|cursor| {
+            let before = tokens.iter().position(|(token, span)| {
+                span.end == cursor && (is_opening_bracket(token) || is_closing_bracket(token))
+            });
+            before.or_else(|| {
+                tokens.iter().position(|(token, span)| {
+                    span.start == cursor && (is_opening_bracket(token) || is_closing_bracket(token))
+                })
+            })
+        })
+        .and_then(|index| Some((index, matching_bracket(&tokens, index)?)));
+
+    tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (token, span))| {
+            let is_matching =
+                matching.is_some_and(|(bracket, pair)| index == bracket || index == pair);
+            if is_matching {
+                Some((*span, Style::MatchingBracket))
+            } else {
+                style(token).map(|style| (*span, style))
+            }
+        })
+        .collect()
+}
+
+/// Index of the bracket pairing with the one at `index`, searching forward for an opening one
+/// and backward for a closing one.
+fn matching_bracket(tokens: &[(Token, Span)], index: usize) -> Option<usize> {
+    let mut depth = 0;
+
+    if is_opening_bracket(&tokens[index].0) {
+        for (offset, (token, _)) in tokens[index..].iter().enumerate() {
+            if is_opening_bracket(token) {
+                depth += 1;
+            } else if is_closing_bracket(token) {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index + offset);
+                }
+            }
+        }
+    } else {
+        for (position, (token, _)) in tokens[..=index].iter().enumerate().rev() {
+            if is_closing_bracket(token) {
+                depth += 1;
+            } else if is_opening_bracket(token) {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(position);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Wraps the `highlights` of `source` in ANSI escape codes.
+pub fn render(source: &str, highlights: &[(Span, Style)]) -> String {
+    let mut rendered = String::with_capacity(source.len());
+    let mut highlights = highlights.iter().peekable();
+
+    for (index, ch) in source.chars().enumerate() {
+        if let Some((span, style)) = highlights.peek() {
+            if span.start == index {
+                rendered.push_str(style.ansi_code());
+            }
+        }
+
+        rendered.push(ch);
+
+        if let Some((span, _)) = highlights.peek() {
+            if span.end == index + 1 {
+                rendered.push_str("\x1b[0m");
+                highlights.next();
+            }
+        }
+    }
+
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_unterminated_string() {
+        assert_eq!(
+            highlight(r#"let s = "abc"#, None),
+            vec![
+                (Span::new(0, 3), Style::Keyword),
+                (Span::new(8, 12), Style::String),
+            ]
+        );
+        assert_eq!(
+            highlight(r#"puts("a", 12"#, None),
+            vec![
+                (Span::new(5, 8), Style::String),
+                (Span::new(10, 12), Style::Number),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_matching_brackets() {
+        let source = "f([1, (2)])";
+        let brackets = |cursor| {
+            highlight(source, Some(cursor))
+                .into_iter()
+                .filter(|(_, style)| *style == Style::MatchingBracket)
+                .map(|(span, _)| span.start)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(brackets(2), vec![1, 10]);
+        assert_eq!(brackets(11), vec![1, 10]);
+        assert_eq!(brackets(9), vec![6, 8]);
+        assert_eq!(brackets(3), vec![2, 9]);
+        assert_eq!(brackets(4), Vec::<usize>::new());
+        assert_eq!(
+            highlight("f([1, (2)", Some(2))
+                .iter()
+                .filter(|(_, style)| *style == Style::MatchingBracket)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let source = r#"let s = "a";"#;
+
+        assert_eq!(
+            render(source, &highlight(source, None)),
+            "\x1b[35mlet\x1b[0m s = \x1b[32m\"a\"\x1b[0m;"
+        );
+        assert_eq!(render(source, &[]), source);
+    }
+}
@@ -0,0 +1,31 @@
+use std::{error::Error, process::ExitCode};
+
+mod check;
+mod doc;
+mod eval;
+mod highlight;
+mod repl;
+
+const USAGE: &str = "usage: monkey-interpreter [check <file> [--json] [--deny-warnings] | doc <file> [--json] [--private] | [eval] <file> [--verbose-statements] [--time] [--profile] [--coverage] [--warnings] [--max-source-size <bytes>] [--shadowing] | [--warnings] [--shadowing]]";
+
+/// Exit status of the process for a `code` given to `exit`. Only its low byte would be kept, so
+/// the codes that don't fit in one are a failure rather than wrapping around, maybe to success.
+fn exit_code(code: i32) -> ExitCode {
+    u8::try_from(code).map_or(ExitCode::FAILURE, ExitCode::from)
+}
+
+pub fn run(args: Vec<String>) -> Result<ExitCode, Box<dyn Error>> {
+    let Some((command, rest)) = args.split_first() else {
+        return repl::run(&[]);
+    };
+
+    match command.as_str() {
+        "--warnings" | "--shadowing" => repl::run(&args),
+        "check" => check::run(rest),
+        "doc" => doc::run(rest),
+        "eval" => eval::run(rest),
+        // Lets scripts starting with a `#!/usr/bin/env monkey-interpreter` line run directly
+        file if std::path::Path::new(file).is_file() => eval::run(&args),
+        _ => Err(format!("unknown command: {command}\n{USAGE}").into()),
+    }
+}
@@ -0,0 +1,461 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitCode},
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::cli::highlight::{highlight, render};
+use monkey_interpreter::{
+    analyzer::{analyze_in, AnalysisOptions},
+    ast::{Pattern, Statement},
+    evaluator::{
+        context::{EvalContext, EvalOptions},
+        environment::Environment,
+        error::EvalError,
+        macros::define_and_expand_macros,
+        Evaluator,
+    },
+    lexer::Lexer,
+    parser::Parser,
+};
+
+const PROMPT: &str = "λ> ";
+
+/// Editor used when neither `$EDITOR` nor `$VISUAL` is set.
+const DEFAULT_EDITOR: &str = if cfg!(windows) { "notepad" } else { "vi" };
+
+/// Starts the REPL with the flags in `args`: `--warnings` reports the problems the analysis finds
+/// in each input, and `--shadowing` also the bindings shadowing or redeclaring another one.
+pub fn run(args: &[String]) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let mut warnings = false;
+    let mut shadowing = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "--warnings" => warnings = true,
+            "--shadowing" => {
+                warnings = true;
+                shadowing = true;
+            }
+            flag => return Err(format!("unknown flag for the REPL: {flag}").into()),
+        }
+    }
+
+    start(Session {
+        warnings,
+        shadowing,
+        colors: std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        ..Default::default()
+    })
+}
+
+/// Runs `session` until the input ends or an input calls `exit`, whose code it returns.
+fn start(mut session: Session) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    println!("Hello, this is the Monkey programming language!");
+    println!("Let's get started!\n");
+
+    let colors = session.colors;
+
+    loop {
+        print!("{PROMPT}");
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input)? == 0 {
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        if colors {
+            // Without a line editor the input can only be colorized once entered, by
+            // rewriting the line it was typed on
+            let line = input.trim_end();
+            let cursor = line.chars().count();
+            println!(
+                "\x1b[1A\r\x1b[2K{PROMPT}{}",
+                render(line, &highlight(line, Some(cursor)))
+            );
+        }
+
+        let mut output = io::stdout().lock();
+        match input.trim().strip_prefix(":edit") {
+            Some(name) if name.is_empty() || name.starts_with(' ') => {
+                let name = Some(name.trim()).filter(|name| !name.is_empty());
+                session.edit(name, launch_editor, &mut output)?;
+            }
+            _ => {
+                session.eval(&input, &mut output)?;
+            }
+        }
+
+        if let Some(code) = session.exit_code {
+            return Ok(super::exit_code(code));
+        }
+    }
+}
+
+/// Opens `path` in the editor of the user, waiting for it to be closed.
+fn launch_editor(path: &Path) -> io::Result<()> {
+    let editor = std::env::var("EDITOR")
+        .or_else(|_| std::env::var("VISUAL"))
+        .unwrap_or_else(|_| DEFAULT_EDITOR.into());
+    // Editors are commonly configured with their flags, e.g. `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(DEFAULT_EDITOR);
+
+    let status = Command::new(program).args(words).arg(path).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{editor} exited with {status}")));
+    }
+
+    Ok(())
+}
+
+/// Bindings of the REPL, alongside the source that defined each of them.
+#[derive(Default)]
+struct Session {
+    environment: Rc<RefCell<Environment>>,
+    definitions: BTreeMap<String, String>,
+    /// Whether to report the problems the analysis finds in the inputs, after their value
+    warnings: bool,
+    /// Whether the warnings include the bindings shadowing or redeclaring another one, except
+    /// for the ones rebinding a name of an earlier input
+    shadowing: bool,
+    /// Whether the output goes to a terminal showing colors, the warnings being dimmed
+    colors: bool,
+    /// Code an input called `exit` with, ending the session
+    exit_code: Option<i32>,
+}
+
+impl Session {
+    /// Parses and evaluates `input`, writing its value or errors to `output`. Returns the names
+    /// it bound, or `None` if it failed.
+    fn eval(&mut self, input: &str, output: &mut impl Write) -> io::Result<Option<Vec<String>>> {
+        let lexer = Lexer::new(input.into());
+        let mut parser = Parser::new(lexer);
+        let Some(program) = parser.parse_program() else {
+            writeln!(output, "Error parsing program")?;
+            return Ok(None);
+        };
+
+        if !parser.errors.is_empty() {
+            writeln!(output, "Woops! We ran into some monkey business here!\n")?;
+            writeln!(output, "Parser errors:")?;
+            for error in parser.errors.iter() {
+                writeln!(output, "- {error}")?;
+            }
+            return Ok(None);
+        }
+
+        let expanded =
+            define_and_expand_macros(program, &self.environment, &mut EvalContext::default());
+        let program = match expanded {
+            Ok(program) => program,
+            Err(EvalError::Exit(code)) => {
+                self.exit_code = Some(code);
+                return Ok(None);
+            }
+            Err(error) => {
+                writeln!(output, "{error}")?;
+                return Ok(None);
+            }
+        };
+
+        let warnings = if self.warnings {
+            let options = AnalysisOptions {
+                shadowing: self.shadowing.then_some(input),
+            };
+            analyze_in(
+                &program,
+                &self.environment.borrow().names(usize::MAX),
+                options,
+            )
+        } else {
+            Vec::new()
+        };
+
+        let options = EvalOptions {
+            max_stack_bytes: crate::MAX_STACK_BYTES,
+            ..Default::default()
+        };
+        let evaluated =
+            program.eval_with_context(&self.environment, &mut EvalContext::new(options));
+        match &evaluated {
+            Ok(Some(value)) => writeln!(output, "{value}")?,
+            Ok(None) => {}
+            Err(EvalError::Exit(code)) => self.exit_code = Some(*code),
+            Err(error) => writeln!(output, "{error}")?,
+        }
+        for warning in warnings {
+            let warning = warning.render(input, "<repl>");
+            if self.colors {
+                writeln!(output, "\x1b[2m{warning}\x1b[0m")?;
+            } else {
+                writeln!(output, "{warning}")?;
+            }
+        }
+        if evaluated.is_err() {
+            return Ok(None);
+        }
+
+        // Keeps the source as typed when it's only the binding, and falls back to the
+        // statement as parsed when the input holds more than that
+        let single_statement = program.statements.len() == 1;
+        let mut bound = Vec::new();
+        for statement in program.statements.iter() {
+            let source = if single_statement {
+                input.trim().to_string()
+            } else {
+                statement.to_string()
+            };
+            for name in bound_names(statement) {
+                self.definitions.insert(name.into(), source.clone());
+                bound.push(name.to_string());
+            }
+        }
+
+        Ok(Some(bound))
+    }
+
+    /// Writes the definition of `name`, or nothing without a name, to a temporary file, lets
+    /// `editor` change it, then evaluates what it was changed to. Errors are reported to
+    /// `output`, leaving the bindings as they were when the edited source doesn't parse.
+    fn edit(
+        &mut self,
+        name: Option<&str>,
+        editor: impl FnOnce(&Path) -> io::Result<()>,
+        output: &mut impl Write,
+    ) -> io::Result<()> {
+        let source = match name {
+            Some(name) => match self.definitions.get(name) {
+                Some(source) => format!("{source}\n"),
+                None => {
+                    writeln!(output, "no definition of {name} to edit")?;
+                    return Ok(());
+                }
+            },
+            None => String::new(),
+        };
+
+        let path = scratch_path();
+        std::fs::write(&path, source)?;
+        let edited = editor(&path).and_then(|_| std::fs::read_to_string(&path));
+        let _ = std::fs::remove_file(&path);
+
+        let edited = match edited {
+            Ok(edited) => edited,
+            Err(error) => {
+                writeln!(output, "could not edit: {error}")?;
+                return Ok(());
+            }
+        };
+
+        let Some(bound) = self.eval(&edited, output)? else {
+            return Ok(());
+        };
+
+        match name {
+            Some(name) if bound.iter().any(|bound| bound == name) => {
+                writeln!(output, "redefined {name}")
+            }
+            Some(name) => writeln!(output, "warning: the edited source does not rebind {name}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Names a statement binds in the environment.
+fn bound_names(statement: &Statement) -> Vec<&str> {
+    match statement {
+        Statement::Let { name, .. } | Statement::Const { name, .. } => vec![name],
+        Statement::Destructure { pattern, .. } => match pattern {
+            Pattern::Hash(bindings) => bindings.iter().map(|(_, name)| &**name).collect(),
+            Pattern::Array(names) => names.iter().map(|name| &**name).collect(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Temporary file unique to this process and edit.
+fn scratch_path() -> PathBuf {
+    static EDITS: AtomicUsize = AtomicUsize::new(0);
+
+    std::env::temp_dir().join(format!(
+        "monkey-edit-{}-{}.mk",
+        std::process::id(),
+        EDITS.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(session: &mut Session, input: &str) -> String {
+        let mut output = Vec::new();
+        session.eval(input, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn edit(session: &mut Session, name: Option<&str>, replacement: &str) -> (String, String) {
+        let mut output = Vec::new();
+        let mut original = String::new();
+
+        session
+            .edit(
+                name,
+                |path| {
+                    original = std::fs::read_to_string(path)?;
+                    std::fs::write(path, replacement)
+                },
+                &mut output,
+            )
+            .unwrap();
+
+        (original, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_eval_echoes_bound_values() {
+        let mut session = Session::default();
+
+        assert_eq!(eval(&mut session, "let x = 5;"), "5\n");
+        assert_eq!(eval(&mut session, "let [a, b] = [x, 2];"), "");
+        assert_eq!(eval(&mut session, ""), "");
+        assert_eq!(eval(&mut session, "a + b"), "7\n");
+    }
+
+    #[test]
+    fn test_eval_echoes_strings() {
+        let mut session = Session::default();
+
+        assert_eq!(eval(&mut session, r#""a, \"b\"""#), "a, \"b\"\n");
+        assert_eq!(
+            eval(&mut session, r#"["a, \"b\"", "c"]"#),
+            "[\"a, \\\"b\\\"\", \"c\"]\n"
+        );
+        assert_eq!(
+            eval(&mut session, r#"{"k": "line\nbreak"}"#),
+            "{\"k\": \"line\\nbreak\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_eval_exit_ends_session() {
+        let mut session = Session::default();
+
+        assert_eq!(eval(&mut session, "let a = 1;"), "1\n");
+        assert_eq!(session.exit_code, None);
+        assert_eq!(eval(&mut session, "if (a == 1) { exit(a + 1) }"), "");
+        assert_eq!(session.exit_code, Some(2));
+    }
+
+    #[test]
+    fn test_eval_warnings() {
+        let mut session = Session {
+            warnings: true,
+            ..Default::default()
+        };
+
+        assert_eq!(eval(&mut session, "let a = 1;"), "1\n");
+        assert_eq!(
+            eval(&mut session, "let f = fn() { a + b };"),
+            "fn() { (a + b) }\nwarning[undefined-identifier]: `b` is never declared\n  --> <repl>:1:20\n"
+        );
+        assert_eq!(
+            eval(&mut session, "c"),
+            "Error: identifier not found: c\nwarning[undefined-identifier]: `c` is never declared\n  --> <repl>:1:1\n"
+        );
+
+        session.colors = true;
+        assert_eq!(
+            eval(&mut session, "let g = fn(x) { 1 };"),
+            "fn(x) { 1 }\n\x1b[2mwarning[unused-parameter]: unused parameter `x`\n  --> <repl>:1:9\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_eval_shadowing_warnings() {
+        let mut session = Session {
+            warnings: true,
+            shadowing: true,
+            ..Default::default()
+        };
+
+        assert_eq!(eval(&mut session, "let x = 1;"), "1\n");
+        assert_eq!(eval(&mut session, "let x = true;"), "true\n");
+        assert_eq!(
+            eval(&mut session, "let f = fn(x) { x }; f(2)"),
+            "2\nwarning[shadowed-binding]: parameter `x` shadows a binding from an enclosing scope\n  --> <repl>:1:9\n"
+        );
+        assert_eq!(
+            eval(&mut session, "let y = 1; let y = y + 1;"),
+            "2\nwarning[redeclared-binding]: `y` is redeclared in the same scope\n  --> <repl>:1:12\n"
+        );
+
+        session.shadowing = false;
+        assert_eq!(eval(&mut session, "let z = 1; let z = 2;"), "2\n");
+    }
+
+    #[test]
+    fn test_edit_redefines_binding() {
+        let mut session = Session::default();
+        eval(&mut session, "let add = fn(a, b) { a + b };\n");
+
+        let (original, output) = edit(
+            &mut session,
+            Some("add"),
+            "let add = fn(a, b) {\n  a * b\n};\n",
+        );
+
+        assert_eq!(original, "let add = fn(a, b) { a + b };\n");
+        assert_eq!(output, "fn(a, b) { (a * b) }\nredefined add\n");
+        assert_eq!(eval(&mut session, "add(2, 3)"), "6\n");
+
+        let (original, _) = edit(&mut session, Some("add"), "let add = fn(a, b) { a - b };");
+        assert_eq!(original, "let add = fn(a, b) {\n  a * b\n};\n");
+    }
+
+    #[test]
+    fn test_edit_parse_error_keeps_binding() {
+        let mut session = Session::default();
+        eval(&mut session, "let one = 1; let add = fn(a, b) { a + b };");
+
+        let (original, output) = edit(&mut session, Some("add"), "let add = fn(a, b) { a * };");
+
+        assert_eq!(original, "let add = fn(a, b) {(a + b)};\n");
+        assert!(output.starts_with("Woops!"), "{output}");
+        assert_eq!(eval(&mut session, "add(2, 3)"), "5\n");
+        assert_eq!(edit(&mut session, Some("add"), "").0, original);
+    }
+
+    #[test]
+    fn test_edit_scratch_buffer() {
+        let mut session = Session::default();
+
+        let (original, output) = edit(&mut session, None, "let a = 2;\na * 21");
+
+        assert_eq!(original, "");
+        assert_eq!(output, "42\n");
+        assert_eq!(eval(&mut session, "a"), "2\n");
+    }
+
+    #[test]
+    fn test_edit_unknown_or_not_rebound() {
+        let mut session = Session::default();
+
+        assert_eq!(
+            edit(&mut session, Some("missing"), "").1,
+            "no definition of missing to edit\n"
+        );
+
+        eval(&mut session, "let a = 1;");
+        assert_eq!(
+            edit(&mut session, Some("a"), "let b = 2;").1,
+            "2\nwarning: the edited source does not rebind a\n"
+        );
+        assert_eq!(eval(&mut session, "[a, b]"), "[1, 2]\n");
+    }
+}
@@ -0,0 +1,255 @@
+use std::rc::Rc;
+
+use crate::{
+    ast::{Expression, Statement},
+    lexer::{Comment, Lexer, Span, Token},
+    parser::{escape_json, parse, Diagnostic},
+};
+
+/// A function bound at the top level of a source, with the comment documenting it.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct FunctionDoc {
+    pub name: String,
+    pub parameters: Vec<String>,
+    /// Lines of the `//` comments right above the binding, without their `//` and the space
+    /// following it
+    pub comment: Option<String>,
+}
+
+impl FunctionDoc {
+    /// Whether the function is meant to be used by other sources, its name not starting with `_`.
+    pub fn is_public(&self) -> bool {
+        !self.name.starts_with('_')
+    }
+
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, self.parameters.join(", "))
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            r#"{{"name":"{}","parameters":[{}],"comment":{}}}"#,
+            escape_json(&self.name),
+            self.parameters
+                .iter()
+                .map(|parameter| format!(r#""{}""#, escape_json(parameter)))
+                .collect::<Vec<_>>()
+                .join(","),
+            self.comment
+                .as_ref()
+                .map(|comment| format!(r#""{}""#, escape_json(comment)))
+                .unwrap_or_else(|| "null".into())
+        )
+    }
+}
+
+/// Functions bound by the top-level `let` and `const` statements of `source`, in order.
+pub fn extract(source: &str) -> Result<Vec<FunctionDoc>, Vec<Diagnostic>> {
+    let program = parse(source)?;
+    let (mut bindings, comments) = top_level_bindings(source);
+
+    let documented = program
+        .statements
+        .iter()
+        .filter_map(|statement| match &statement.node {
+            Statement::Let { name, value } | Statement::Const { name, value } => {
+                match &value.node {
+                    Expression::Function { arguments, .. } => Some((name, arguments)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .map(|(name, parameters)| {
+            let start = bindings
+                .iter()
+                .position(|(binding, _)| binding == name)
+                .map(|index| bindings.remove(index).1);
+
+            FunctionDoc {
+                name: name.to_string(),
+                parameters: parameters.iter().map(ToString::to_string).collect(),
+                comment: start.and_then(|start| preceding_comment(source, &comments, start)),
+            }
+        })
+        .collect();
+
+    Ok(documented)
+}
+
+/// Names bound at the top level with the span of the `let` or `const` binding them, alongside
+/// every comment of `source`.
+fn top_level_bindings(source: &str) -> (Vec<(Rc<str>, Span)>, Vec<Comment>) {
+    let mut lexer = Lexer::new(source.into());
+    let mut bindings = Vec::new();
+    let mut depth = 0usize;
+    let mut binding_start = None;
+
+    loop {
+        let token = lexer.next_token();
+        let span = lexer.span();
+
+        match token {
+            Token::Eof => break,
+            Token::Let | Token::Const if depth == 0 => {
+                binding_start = Some(span);
+                continue;
+            }
+            Token::Identifier(name) => {
+                if let Some(start) = binding_start {
+                    bindings.push((name, start));
+                }
+            }
+            Token::LeftParen | Token::LeftBrace | Token::LeftBracket | Token::QuestionBracket => {
+                depth += 1
+            }
+            Token::RightParen | Token::RightBrace | Token::RightBracket => {
+                depth = depth.saturating_sub(1)
+            }
+            _ => {}
+        }
+
+        binding_start = None;
+    }
+
+    (bindings, lexer.comments().to_vec())
+}
+
+/// The block of comments on the lines right above `span`, each alone on its line. A blank line
+/// or code in between ends the block.
+fn preceding_comment(source: &str, comments: &[Comment], span: Span) -> Option<String> {
+    let (line, _) = span.line_column(source);
+    let mut expected_line = line.checked_sub(1)?;
+    let mut lines = Vec::new();
+
+    for comment in comments
+        .iter()
+        .rev()
+        .skip_while(|comment| comment.span.start >= span.start)
+    {
+        let (comment_line, _) = comment.span.line_column(source);
+        if comment_line != expected_line || !starts_line(source, comment.span) {
+            break;
+        }
+
+        lines.push(comment.text.strip_prefix(' ').unwrap_or(&comment.text));
+        expected_line -= 1;
+    }
+
+    if lines.is_empty() {
+        return None;
+    }
+
+    lines.reverse();
+    Some(lines.join("\n"))
+}
+
+/// Whether only whitespace precedes `span` on its line.
+fn starts_line(source: &str, span: Span) -> bool {
+    let before = source.chars().take(span.start).collect::<Vec<_>>();
+    before
+        .iter()
+        .rev()
+        .take_while(|ch| **ch != '\n')
+        .all(|ch| ch.is_whitespace())
+}
+
+/// Markdown listing the signature and comment of every function in `functions`.
+pub fn to_markdown(title: &str, functions: &[FunctionDoc]) -> String {
+    let mut markdown = format!("# {title}\n");
+
+    for function in functions {
+        markdown.push_str(&format!("\n## `{}`\n", function.signature()));
+        if let Some(comment) = &function.comment {
+            markdown.push_str(&format!("\n{comment}\n"));
+        }
+    }
+
+    markdown
+}
+
+pub fn to_json(functions: &[FunctionDoc]) -> String {
+    format!(
+        r#"{{"functions":[{}]}}"#,
+        functions
+            .iter()
+            .map(FunctionDoc::to_json)
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = r#"// Adds two numbers.
+//
+// Works with strings too.
+let add = fn(a, b) { a + b };
+
+// Unrelated note
+
+let double = fn(x) {
+    // Not documentation either
+    let inner = fn(y) { y };
+    add(x, x)
+};
+let count = 2; // Not a function
+
+// Internal helper.
+  // Indented, still part of it
+const _helper = fn() { count };
+let answer = add(40, 2);"#;
+
+    #[test]
+    fn test_extract() {
+        assert_eq!(
+            extract(FIXTURE),
+            Ok(vec![
+                FunctionDoc {
+                    name: "add".into(),
+                    parameters: vec!["a".into(), "b".into()],
+                    comment: Some("Adds two numbers.\n\nWorks with strings too.".into()),
+                },
+                FunctionDoc {
+                    name: "double".into(),
+                    parameters: vec!["x".into()],
+                    comment: None,
+                },
+                FunctionDoc {
+                    name: "_helper".into(),
+                    parameters: vec![],
+                    comment: Some("Internal helper.\nIndented, still part of it".into()),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_extract_trailing_comment_is_not_documentation() {
+        let functions = extract("let a = 1; // About a\nlet f = fn() { a };").unwrap();
+
+        assert_eq!(functions[0].comment, None);
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let functions = extract(FIXTURE).unwrap();
+
+        assert_eq!(
+            to_markdown("utils.mk", &functions),
+            "# utils.mk\n\n## `add(a, b)`\n\nAdds two numbers.\n\nWorks with strings too.\n\n## `double(x)`\n\n## `_helper()`\n\nInternal helper.\nIndented, still part of it\n"
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let functions = extract(FIXTURE).unwrap();
+
+        assert_eq!(
+            to_json(&functions),
+            r#"{"functions":[{"name":"add","parameters":["a","b"],"comment":"Adds two numbers.\n\nWorks with strings too."},{"name":"double","parameters":["x"],"comment":null},{"name":"_helper","parameters":[],"comment":"Internal helper.\nIndented, still part of it"}]}"#
+        );
+    }
+}
@@ -1,170 +1,2937 @@
-use crate::evaluator::object::{Object, NULL};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    bigint::BigInt,
+    capabilities::capabilities,
+    evaluator::{
+        call_function,
+        context::EvalContext,
+        error::EvalError,
+        object::{BuiltinFunction, HashKey, Object, NULL},
+        out_of_range, position, slice, slice_positions,
+    },
+    lexer::quote,
+};
 
 pub enum Builtin {
     Len,
     Puts,
+    Print,
+    Flush,
+    ReadLine,
     Exit,
     First,
     Last,
     Rest,
     Push,
+    Pop,
+    Slice,
+    Version,
+    Type,
+    Str,
+    Int,
+    Split,
+    Join,
+    Upper,
+    Lower,
+    Trim,
+    TrimLeft,
+    TrimRight,
+    Replace,
+    Contains,
+    StartsWith,
+    EndsWith,
+    Substring,
+    Chars,
+    Ord,
+    Chr,
+    Keys,
+    Values,
+    HasKey,
+    Delete,
+    Remove,
+    Merge,
+    Insert,
+    Sort,
+    Reverse,
+    Map,
+    Filter,
+    Reduce,
+    IndexOf,
+    Find,
+    Flatten,
+    Zip,
+    Unique,
 }
 
 impl Builtin {
+    /// Names the builtins are bound to, as accepted by [`Self::from_str`].
+    pub const NAMES: &[&str] = &[
+        "len",
+        "puts",
+        "print",
+        "flush",
+        "read_line",
+        "exit",
+        "first",
+        "last",
+        "rest",
+        "push",
+        "pop",
+        "slice",
+        "version",
+        "type",
+        "str",
+        "int",
+        "split",
+        "join",
+        "upper",
+        "lower",
+        "trim",
+        "trim_left",
+        "trim_right",
+        "replace",
+        "contains",
+        "starts_with",
+        "ends_with",
+        "substring",
+        "chars",
+        "ord",
+        "chr",
+        "keys",
+        "values",
+        "has_key",
+        "delete",
+        "remove",
+        "merge",
+        "insert",
+        "sort",
+        "reverse",
+        "map",
+        "filter",
+        "reduce",
+        "index_of",
+        "find",
+        "flatten",
+        "zip",
+        "unique",
+    ];
+
     pub const fn get(&self) -> Object {
-        let function = match self {
+        Object::Builtin(self.function())
+    }
+
+    pub const fn function(&self) -> BuiltinFunction {
+        match self {
             Self::Len => builtin_len,
             Self::Puts => builtin_puts,
+            Self::Print => builtin_print,
+            Self::Flush => builtin_flush,
+            Self::ReadLine => builtin_read_line,
             Self::Exit => builtin_exit,
             Self::First => builtin_first,
             Self::Last => builtin_last,
             Self::Rest => builtin_rest,
             Self::Push => builtin_push,
-        };
-
-        Object::Builtin(function)
+            Self::Pop => builtin_pop,
+            Self::Slice => builtin_slice,
+            Self::Version => builtin_version,
+            Self::Type => builtin_type,
+            Self::Str => builtin_str,
+            Self::Int => builtin_int,
+            Self::Split => builtin_split,
+            Self::Join => builtin_join,
+            Self::Upper => builtin_upper,
+            Self::Lower => builtin_lower,
+            Self::Trim => builtin_trim,
+            Self::TrimLeft => builtin_trim_left,
+            Self::TrimRight => builtin_trim_right,
+            Self::Replace => builtin_replace,
+            Self::Contains => builtin_contains,
+            Self::StartsWith => builtin_starts_with,
+            Self::EndsWith => builtin_ends_with,
+            Self::Substring => builtin_substring,
+            Self::Chars => builtin_chars,
+            Self::Ord => builtin_ord,
+            Self::Chr => builtin_chr,
+            Self::Keys => builtin_keys,
+            Self::Values => builtin_values,
+            Self::HasKey => builtin_has_key,
+            Self::Delete => builtin_delete,
+            Self::Remove => builtin_remove,
+            Self::Merge => builtin_merge,
+            Self::Insert => builtin_insert,
+            Self::Sort => builtin_sort,
+            Self::Reverse => builtin_reverse,
+            Self::Map => builtin_map,
+            Self::Filter => builtin_filter,
+            Self::Reduce => builtin_reduce,
+            Self::IndexOf => builtin_index_of,
+            Self::Find => builtin_find,
+            Self::Flatten => builtin_flatten,
+            Self::Zip => builtin_zip,
+            Self::Unique => builtin_unique,
+        }
     }
 
     pub fn from_str(name: &str) -> Option<Self> {
         match name {
             "len" => Some(Self::Len),
             "puts" => Some(Self::Puts),
+            "print" => Some(Self::Print),
+            "flush" => Some(Self::Flush),
+            "read_line" => Some(Self::ReadLine),
             "exit" => Some(Self::Exit),
             "first" => Some(Self::First),
             "last" => Some(Self::Last),
             "rest" => Some(Self::Rest),
             "push" => Some(Self::Push),
+            "pop" => Some(Self::Pop),
+            "slice" => Some(Self::Slice),
+            "version" => Some(Self::Version),
+            "type" => Some(Self::Type),
+            "str" => Some(Self::Str),
+            "int" => Some(Self::Int),
+            "split" => Some(Self::Split),
+            "join" => Some(Self::Join),
+            "upper" => Some(Self::Upper),
+            "lower" => Some(Self::Lower),
+            "trim" => Some(Self::Trim),
+            "trim_left" => Some(Self::TrimLeft),
+            "trim_right" => Some(Self::TrimRight),
+            "replace" => Some(Self::Replace),
+            "contains" => Some(Self::Contains),
+            "starts_with" => Some(Self::StartsWith),
+            "ends_with" => Some(Self::EndsWith),
+            "substring" => Some(Self::Substring),
+            "chars" => Some(Self::Chars),
+            "ord" => Some(Self::Ord),
+            "chr" => Some(Self::Chr),
+            "keys" => Some(Self::Keys),
+            "values" => Some(Self::Values),
+            "has_key" => Some(Self::HasKey),
+            "delete" => Some(Self::Delete),
+            "remove" => Some(Self::Remove),
+            "merge" => Some(Self::Merge),
+            "insert" => Some(Self::Insert),
+            "sort" => Some(Self::Sort),
+            "reverse" => Some(Self::Reverse),
+            "map" => Some(Self::Map),
+            "filter" => Some(Self::Filter),
+            "reduce" => Some(Self::Reduce),
+            "index_of" => Some(Self::IndexOf),
+            "find" => Some(Self::Find),
+            "flatten" => Some(Self::Flatten),
+            "zip" => Some(Self::Zip),
+            "unique" => Some(Self::Unique),
             _ => None,
         }
     }
 }
 
-fn builtin_len(arguments: &[Object]) -> Option<Object> {
+fn builtin_len(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
     if arguments.len() != 1 {
-        return Some(Object::Error(format!(
+        return Err(format!(
             "wrong number of arguments. Got {}, expected 1",
             arguments.len()
-        )));
+        )
+        .into());
     }
 
     match &arguments[0] {
-        Object::String(value) => Some((value.len() as isize).into()),
-        Object::Array(value) => Some((value.len() as isize).into()),
-        Object::Error(value) => Some(Object::Error(value.clone())),
-        _ => Some(Object::Error(format!(
+        Object::String(value) => Ok((value.chars().count() as i64).into()),
+        Object::Array(value) => Ok((value.len() as i64).into()),
+        Object::Hash(value) => Ok((value.len() as i64).into()),
+        Object::Range { start, end } if end <= start => Ok(0.into()),
+        Object::Range { start, end } => Ok(match end.checked_sub(*start) {
+            Some(length) => length.into(),
+            None => (&BigInt::from(*end) - &BigInt::from(*start)).into(),
+        }),
+        // Rather than the length of an empty collection, which a missing value isn't
+        Object::Null => Err(r#"argument to "len" not supported, got NULL"#.into()),
+        _ => Err(format!(
             "argument to \"len\" not supported, got {}",
             arguments[0].kind()
-        ))),
+        )
+        .into()),
     }
 }
 
-fn builtin_puts(arguments: &[Object]) -> Option<Object> {
+fn builtin_puts(arguments: &[Object], context: &mut EvalContext) -> Result<Object, EvalError> {
     for argument in arguments {
-        println!("{}", argument.inspect());
+        if let Err(error) = context.output.write_str(&format!("{argument}\n")) {
+            return Err(format!("failed to write output: {error}").into());
+        }
+    }
+    Ok(NULL)
+}
+
+fn builtin_print(arguments: &[Object], context: &mut EvalContext) -> Result<Object, EvalError> {
+    for argument in arguments {
+        if let Err(error) = context.output.write_str(&argument.to_string()) {
+            return Err(format!("failed to write output: {error}").into());
+        }
+    }
+    Ok(NULL)
+}
+
+fn builtin_flush(arguments: &[Object], context: &mut EvalContext) -> Result<Object, EvalError> {
+    if !arguments.is_empty() {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 0",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match context.output.flush() {
+        Ok(()) => Ok(NULL),
+        Err(error) => Err(format!("failed to write output: {error}").into()),
     }
-    Some(NULL)
 }
 
-fn builtin_exit(arguments: &[Object]) -> Option<Object> {
+fn builtin_read_line(arguments: &[Object], context: &mut EvalContext) -> Result<Object, EvalError> {
+    if !arguments.is_empty() {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 0",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match context.read_line() {
+        Ok(line) => Ok(line.map(Object::from).unwrap_or_default()),
+        Err(error) => Err(format!("failed to read input: {error}").into()),
+    }
+}
+
+fn builtin_exit(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
     if arguments.len() > 1 {
-        return Some(Object::Error(format!(
+        return Err(format!(
             "wrong number of arguments. Got {}, expected 0 or 1",
             arguments.len()
-        )));
+        )
+        .into());
     }
 
     match arguments.first().unwrap_or(&Object::Integer(0)) {
-        Object::Integer(value) => std::process::exit(*value as i32),
-        Object::Error(value) => Some(Object::Error(value.clone())),
-        _ => Some(Object::Error(format!(
+        Object::Integer(value) => match i32::try_from(*value) {
+            Ok(code) => Err(EvalError::Exit(code)),
+            Err(_) => Err(out_of_range(&(*value).into(), "an exit code")),
+        },
+        Object::BigInt(value) => Err(out_of_range(value, "an exit code")),
+        _ => Err(format!(
             "argument to \"exit\" not supported, got {}",
             arguments[0].kind()
-        ))),
+        )
+        .into()),
     }
 }
 
-fn builtin_first(arguments: &[Object]) -> Option<Object> {
+fn builtin_first(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
     if arguments.len() != 1 {
-        return Some(Object::Error(format!(
+        return Err(format!(
             "wrong number of arguments. Got {}, expected 1",
             arguments.len()
-        )));
+        )
+        .into());
     }
 
     match &arguments[0] {
-        Object::Array(value) => Some(value.first().cloned().unwrap_or_default()),
-        Object::Error(value) => Some(Object::Error(value.clone())),
-        _ => Some(Object::Error(format!(
+        Object::Array(value) => Ok(value.first().cloned().unwrap_or_default()),
+        _ => Err(format!(
             "argument to \"first\" not supported, got {}",
             arguments[0].kind()
-        ))),
+        )
+        .into()),
     }
 }
 
-fn builtin_last(arguments: &[Object]) -> Option<Object> {
+fn builtin_last(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
     if arguments.len() != 1 {
-        return Some(Object::Error(format!(
+        return Err(format!(
             "wrong number of arguments. Got {}, expected 1",
             arguments.len()
-        )));
+        )
+        .into());
     }
 
     match &arguments[0] {
-        Object::Array(value) => Some(value.last().cloned().unwrap_or_default()),
-        Object::Error(value) => Some(Object::Error(value.clone())),
-        _ => Some(Object::Error(format!(
+        Object::Array(value) => Ok(value.last().cloned().unwrap_or_default()),
+        _ => Err(format!(
             "argument to \"last\" not supported, got {}",
             arguments[0].kind()
-        ))),
+        )
+        .into()),
     }
 }
 
-fn builtin_rest(arguments: &[Object]) -> Option<Object> {
+fn builtin_rest(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
     if arguments.len() != 1 {
-        return Some(Object::Error(format!(
+        return Err(format!(
             "wrong number of arguments. Got {}, expected 1",
             arguments.len()
-        )));
+        )
+        .into());
     }
 
     match &arguments[0] {
         Object::Array(value) => {
             let Some((_, rest)) = value.split_at_checked(1) else {
-                return Some(NULL);
+                return Ok(NULL);
             };
 
-            Some(rest.to_vec().into())
+            Ok(rest.to_vec().into())
         }
-        Object::Error(value) => Some(Object::Error(value.clone())),
-        _ => Some(Object::Error(format!(
+        _ => Err(format!(
             "argument to \"rest\" not supported, got {}",
             arguments[0].kind()
-        ))),
+        )
+        .into()),
     }
 }
 
-fn builtin_push(arguments: &[Object]) -> Option<Object> {
+fn builtin_push(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
     if arguments.len() != 2 {
-        return Some(Object::Error(format!(
+        return Err(format!(
             "wrong number of arguments. Got {}, expected 2",
             arguments.len()
-        )));
+        )
+        .into());
     }
 
     match (&arguments[0], &arguments[1]) {
         (Object::Array(value), item_to_push) => {
-            let mut new_array = value.clone();
+            let mut new_array = value.to_vec();
             new_array.push(item_to_push.clone());
-            Some(Object::Array(new_array))
+            Ok(new_array.into())
         }
-        (Object::Error(value), Object::Error(items)) => {
-            Some(Object::Error(format!("{value} {items}")))
-        }
-        (Object::Error(value), _) => Some(Object::Error(value.clone())),
-        (_, Object::Error(items)) => Some(Object::Error(items.clone())),
-        _ => Some(Object::Error(format!(
+        _ => Err(format!(
             "argument to \"push\" not supported, got {}",
             arguments[0].kind()
-        ))),
+        )
+        .into()),
+    }
+}
+
+/// `pop(array)`, a copy of `array` without its last element, or NULL when it is empty like for
+/// [`rest`](builtin_rest), which leaves out the first one instead.
+fn builtin_pop(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 1 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match &arguments[0] {
+        Object::Array(value) => {
+            let Some((_, rest)) = value.split_last() else {
+                return Ok(NULL);
+            };
+
+            Ok(rest.to_vec().into())
+        }
+        _ => Err(format!(
+            "argument to \"pop\" not supported, got {}",
+            arguments[0].kind()
+        )
+        .into()),
+    }
+}
+
+/// `sort(array)` or `sort(array, comparator)`, a sorted copy of `array`. Without a comparator,
+/// the elements must all be integers or all be strings, compared by value or lexicographically.
+/// A comparator is called with two elements and returns a negative integer when the first one
+/// goes before the second, a positive one when it goes after, or 0 when either order will do.
+/// Equal elements keep their order.
+fn builtin_sort(arguments: &[Object], context: &mut EvalContext) -> Result<Object, EvalError> {
+    if !(1..=2).contains(&arguments.len()) {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1 or 2",
+            arguments.len()
+        )
+        .into());
+    }
+
+    let elements = match &arguments[0] {
+        Object::Array(elements) => elements.to_vec(),
+        argument => {
+            return Err(format!(
+                "argument to \"sort\" not supported, got {}",
+                argument.kind()
+            )
+            .into())
+        }
+    };
+
+    let sorted = match arguments.get(1) {
+        None => merge_sort(elements, &mut |left, right| compare(left, right)),
+        Some(comparator @ (Object::Function { .. } | Object::Builtin(_))) => {
+            merge_sort(elements, &mut |left, right| match call_function(
+                comparator,
+                vec![left.clone(), right.clone()],
+                context,
+            )? {
+                Object::Integer(order) => Ok(order.cmp(&0)),
+                Object::BigInt(order) => Ok(order.cmp(&BigInt::from(0))),
+                order => Err(format!(
+                    "comparator of \"sort\" must return an INTEGER, got {}",
+                    order.kind()
+                )
+                .into()),
+            })
+        }
+        Some(argument) => {
+            return Err(format!(
+                "argument to \"sort\" not supported, got {}",
+                argument.kind()
+            )
+            .into())
+        }
+    }?;
+
+    Ok(sorted.into())
+}
+
+/// `reverse(items)`, a copy of an array with its elements in the reverse order, or of a string
+/// with its characters in the reverse order.
+fn builtin_reverse(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 1 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match &arguments[0] {
+        Object::Array(elements) => Ok(elements.iter().rev().cloned().collect::<Vec<_>>().into()),
+        Object::String(value) => Ok(value.chars().rev().collect::<String>().into()),
+        argument => Err(format!(
+            "argument to \"reverse\" not supported, got {}",
+            argument.kind()
+        )
+        .into()),
+    }
+}
+
+/// Order of two elements sorted without a comparator, which must both be integers or strings.
+fn compare(left: &Object, right: &Object) -> Result<Ordering, EvalError> {
+    match (left, right) {
+        (Object::Integer(_) | Object::BigInt(_), Object::Integer(_) | Object::BigInt(_))
+        | (Object::String(_), Object::String(_)) => {
+            Ok(HashKey::try_from(left)?.cmp(&HashKey::try_from(right)?))
+        }
+        _ => Err(format!("cannot compare {} with {}", left.kind(), right.kind()).into()),
+    }
+}
+
+/// Sorts `elements` with `compare`, keeping the order of equal ones and stopping at the first
+/// error, so that a comparator isn't called again once it failed.
+fn merge_sort(
+    mut elements: Vec<Object>,
+    compare: &mut impl FnMut(&Object, &Object) -> Result<Ordering, EvalError>,
+) -> Result<Vec<Object>, EvalError> {
+    if elements.len() <= 1 {
+        return Ok(elements);
+    }
+
+    let right = elements.split_off(elements.len() / 2);
+    let mut left = merge_sort(elements, compare)?.into_iter().peekable();
+    let mut right = merge_sort(right, compare)?.into_iter().peekable();
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    while let (Some(first), Some(second)) = (left.peek(), right.peek()) {
+        // Taking from the left unless the right one goes strictly before keeps ties in order
+        let next = if compare(second, first)? == Ordering::Less {
+            right.next()
+        } else {
+            left.next()
+        };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+
+    Ok(merged)
+}
+
+/// `flatten(array)` or `flatten(array, depth)`, the elements of `array` with the ones which are
+/// arrays replaced by their own elements, `depth` levels deep or 1 level without one. Elements
+/// which aren't arrays are kept as they are at every level.
+fn builtin_flatten(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if !(1..=2).contains(&arguments.len()) {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1 or 2",
+            arguments.len()
+        )
+        .into());
+    }
+
+    let depth = match arguments.get(1) {
+        None => 1,
+        Some(Object::Integer(depth)) if *depth < 0 => {
+            return Err(format!("depth of \"flatten\" must not be negative, got {depth}").into())
+        }
+        Some(Object::Integer(depth)) => *depth as usize,
+        Some(Object::BigInt(depth)) => return Err(out_of_range(depth, "a depth")),
+        Some(argument) => {
+            return Err(format!(
+                "argument to \"flatten\" not supported, got {}",
+                argument.kind()
+            )
+            .into())
+        }
+    };
+
+    match &arguments[0] {
+        Object::Array(elements) => {
+            let mut flattened = Vec::with_capacity(elements.len());
+            flatten(elements, depth, &mut flattened);
+            Ok(flattened.into())
+        }
+        argument => Err(format!(
+            "argument to \"flatten\" not supported, got {}",
+            argument.kind()
+        )
+        .into()),
+    }
+}
+
+/// `zip(first, second, ...)`, an array with, for each index, an array of the elements of every
+/// array argument at that index, as long as the shortest of them.
+fn builtin_zip(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() < 2 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2 or more",
+            arguments.len()
+        )
+        .into());
+    }
+
+    let arrays = arguments
+        .iter()
+        .map(|argument| match argument {
+            Object::Array(elements) => Ok(elements),
+            _ => Err(format!(
+                "argument to \"zip\" not supported, got {}",
+                argument.kind()
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let length = arrays.iter().map(|elements| elements.len()).min();
+
+    Ok((0..length.unwrap_or_default())
+        .map(|index| {
+            arrays
+                .iter()
+                .map(|elements| elements[index].clone())
+                .collect::<Vec<_>>()
+                .into()
+        })
+        .collect::<Vec<Object>>()
+        .into())
+}
+
+/// `unique(array)`, the elements of `array` without the ones equal to an earlier one, compared
+/// like `contains` does. The elements usable as hash keys are looked up in a set, while each
+/// comparison of the others, e.g. arrays, with the earlier unique ones is a step.
+fn builtin_unique(arguments: &[Object], context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 1 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1",
+            arguments.len()
+        )
+        .into());
+    }
+
+    let Object::Array(elements) = &arguments[0] else {
+        return Err(format!(
+            "argument to \"unique\" not supported, got {}",
+            arguments[0].kind()
+        )
+        .into());
+    };
+
+    let mut keys = HashSet::new();
+    let mut others: Vec<&Object> = Vec::new();
+    let mut unique = Vec::new();
+    for element in elements.iter() {
+        let is_new = match HashKey::try_from(element) {
+            Ok(key) => keys.insert(key),
+            Err(_) => {
+                let mut is_new = true;
+                for other in &others {
+                    context.step()?;
+                    if *other == element {
+                        is_new = false;
+                        break;
+                    }
+                }
+                if is_new {
+                    others.push(element);
+                }
+                is_new
+            }
+        };
+
+        if is_new {
+            unique.push(element.clone());
+        }
+    }
+
+    Ok(unique.into())
+}
+
+/// Pushes `elements` to `flattened`, replacing the arrays among them by their own elements
+/// `depth` levels deep.
+fn flatten(elements: &[Object], depth: usize, flattened: &mut Vec<Object>) {
+    for element in elements {
+        match element {
+            Object::Array(inner) if depth > 0 => flatten(inner, depth - 1, flattened),
+            element => flattened.push(element.clone()),
+        }
+    }
+}
+
+/// `map(array, function)`, the results of calling `function` with each element of `array`.
+fn builtin_map(arguments: &[Object], context: &mut EvalContext) -> Result<Object, EvalError> {
+    let [elements, function] = arguments else {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2",
+            arguments.len()
+        )
+        .into());
+    };
+    let (elements, function) = callback_arguments("map", elements, function, 1)?;
+
+    elements
+        .iter()
+        .map(|element| call_function(function, vec![element.clone()], context))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Object::from)
+}
+
+/// `filter(array, predicate)`, the elements of `array` for which `predicate` returns a truthy
+/// value, in order.
+fn builtin_filter(arguments: &[Object], context: &mut EvalContext) -> Result<Object, EvalError> {
+    let [elements, predicate] = arguments else {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2",
+            arguments.len()
+        )
+        .into());
+    };
+    let (elements, predicate) = callback_arguments("filter", elements, predicate, 1)?;
+
+    let mut kept = Vec::new();
+    for element in elements {
+        if call_function(predicate, vec![element.clone()], context)?.is_truthy() {
+            kept.push(element.clone());
+        }
+    }
+
+    Ok(kept.into())
+}
+
+/// `reduce(array, initial, function)`, the value accumulated by calling `function` with the
+/// value so far, starting with `initial`, and each element of `array` in turn.
+fn builtin_reduce(arguments: &[Object], context: &mut EvalContext) -> Result<Object, EvalError> {
+    let [elements, initial, function] = arguments else {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 3",
+            arguments.len()
+        )
+        .into());
+    };
+    let (elements, function) = callback_arguments("reduce", elements, function, 2)?;
+
+    let mut accumulated = initial.clone();
+    for element in elements {
+        accumulated = call_function(function, vec![accumulated, element.clone()], context)?;
+    }
+
+    Ok(accumulated)
+}
+
+/// `index_of(array, element)`, the index of the first element of `array` equal to `element`,
+/// compared like `contains` does, or -1 when there is none.
+fn builtin_index_of(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    let [elements, element] = arguments else {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2",
+            arguments.len()
+        )
+        .into());
+    };
+    let Object::Array(elements) = elements else {
+        return Err(format!(
+            "argument to \"index_of\" not supported, got {}",
+            elements.kind()
+        )
+        .into());
+    };
+
+    Ok(elements
+        .iter()
+        .position(|candidate| candidate == element)
+        .map_or(-1, |index| index as i64)
+        .into())
+}
+
+/// `find(array, predicate)`, the first element of `array` for which `predicate` returns a truthy
+/// value, or NULL when there is none. The elements after it aren't given to `predicate`.
+fn builtin_find(arguments: &[Object], context: &mut EvalContext) -> Result<Object, EvalError> {
+    let [elements, predicate] = arguments else {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2",
+            arguments.len()
+        )
+        .into());
+    };
+    let (elements, predicate) = callback_arguments("find", elements, predicate, 1)?;
+
+    for element in elements {
+        if call_function(predicate, vec![element.clone()], context)?.is_truthy() {
+            return Ok(element.clone());
+        }
+    }
+
+    Ok(NULL)
+}
+
+/// Elements of the array and function given to `map`, `filter`, `reduce` or `find`. Unlike
+/// when called directly, a function written in Monkey must take exactly the `arity` arguments
+/// it is called with, so that one meant for another of these builtins isn't silently accepted.
+/// A builtin checks its own arguments when called.
+fn callback_arguments<'a>(
+    name: &str,
+    elements: &'a Object,
+    function: &'a Object,
+    arity: usize,
+) -> Result<(&'a [Object], &'a Object), EvalError> {
+    let Object::Array(elements) = elements else {
+        return Err(format!(
+            "argument to \"{name}\" not supported, got {}",
+            elements.kind()
+        )
+        .into());
+    };
+
+    match function {
+        Object::Function { parameters, .. } if parameters.len() != arity => Err(format!(
+            "function given to \"{name}\" must take {arity} {}, takes {}",
+            if arity == 1 { "argument" } else { "arguments" },
+            parameters.len()
+        )
+        .into()),
+        Object::Function { .. } | Object::Builtin(_) => Ok((elements, function)),
+        _ => Err(format!(
+            "argument to \"{name}\" not supported, got {}",
+            function.kind()
+        )
+        .into()),
+    }
+}
+
+/// `slice(items, start)` or `slice(items, start, end)`, the elements of an array or the
+/// characters of a string from `start` up to `end`, or up to the end without one.
+fn builtin_slice(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if !(2..=3).contains(&arguments.len()) {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2 or 3",
+            arguments.len()
+        )
+        .into());
+    }
+
+    let [start, end] = slice_bounds("slice", &arguments[1..])?;
+
+    match &arguments[0] {
+        Object::Array(value) => Ok(slice(value, start, end).to_vec().into()),
+        Object::String(value) => Ok(substring(value, start, end).into()),
+        _ => Err(format!(
+            "argument to \"slice\" not supported, got {}",
+            arguments[0].kind()
+        )
+        .into()),
+    }
+}
+
+/// `substring(string, start)` or `substring(string, start, end)`, like [`builtin_slice`] but
+/// only for strings.
+fn builtin_substring(
+    arguments: &[Object],
+    _context: &mut EvalContext,
+) -> Result<Object, EvalError> {
+    if !(2..=3).contains(&arguments.len()) {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2 or 3",
+            arguments.len()
+        )
+        .into());
+    }
+
+    let [start, end] = slice_bounds("substring", &arguments[1..])?;
+
+    match &arguments[0] {
+        Object::String(value) => Ok(substring(value, start, end).into()),
+        _ => Err(format!(
+            "argument to \"substring\" not supported, got {}",
+            arguments[0].kind()
+        )
+        .into()),
+    }
+}
+
+/// The start and end bounds given to the builtin called `name`, from the start up to the end
+/// when they aren't.
+fn slice_bounds(name: &str, arguments: &[Object]) -> Result<[i64; 2], EvalError> {
+    let mut bounds = [0, i64::MAX];
+    for (bound, argument) in bounds.iter_mut().zip(arguments) {
+        match argument {
+            Object::Integer(value) => *bound = *value,
+            Object::BigInt(value) => return Err(out_of_range(value, "a slice bound")),
+            _ => {
+                return Err(format!(
+                    "argument to \"{name}\" not supported, got {}",
+                    argument.kind()
+                )
+                .into())
+            }
+        }
+    }
+    Ok(bounds)
+}
+
+/// Characters of `value` from `start` up to `end`, following the conventions of [`slice`].
+fn substring(value: &str, start: i64, end: i64) -> String {
+    let positions = slice_positions(start, end, value.chars().count());
+    value
+        .chars()
+        .skip(positions.start)
+        .take(positions.len())
+        .collect()
+}
+
+fn builtin_version(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if !arguments.is_empty() {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 0",
+            arguments.len()
+        )
+        .into());
+    }
+
+    let capabilities = capabilities();
+    let features = capabilities
+        .features
+        .into_iter()
+        .map(Object::from)
+        .collect::<Vec<_>>();
+
+    Ok(HashMap::from([
+        ("version".into(), capabilities.version.into()),
+        ("features".into(), features.into()),
+    ])
+    .into())
+}
+
+/// `type(value)`, the kind of `value` as a string, e.g. `"INTEGER"`.
+fn builtin_type(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 1 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1",
+            arguments.len()
+        )
+        .into());
+    }
+
+    Ok(arguments[0].kind().into())
+}
+
+/// `str(value)`, `value` as shown by [`Object::inspect`]: a string is returned as is, while the
+/// strings nested in an array or hash are quoted, as Monkey literals.
+fn builtin_str(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 1 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1",
+            arguments.len()
+        )
+        .into());
+    }
+
+    Ok(arguments[0].inspect().into())
+}
+
+/// `int(value)`, an integer as is, a boolean as 1 or 0, or a string of decimal digits parsed
+/// once trimmed, with an optional sign. Like integer literals, strings too large for 64 bits give
+/// arbitrarily large integers.
+fn builtin_int(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 1 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match &arguments[0] {
+        value @ (Object::Integer(_) | Object::BigInt(_)) => Ok(value.clone()),
+        Object::Boolean(value) => Ok(i64::from(*value).into()),
+        Object::String(value) => {
+            let trimmed = value.trim();
+            let digits = match trimmed.strip_prefix('+') {
+                Some(digits) if !digits.starts_with('-') => digits,
+                _ => trimmed,
+            };
+
+            match BigInt::parse(digits) {
+                Some(integer) => Ok(integer.into()),
+                None => Err(format!("cannot convert {} to INTEGER", quote(value)).into()),
+            }
+        }
+        _ => Err(format!(
+            "argument to \"int\" not supported, got {}",
+            arguments[0].kind()
+        )
+        .into()),
+    }
+}
+
+/// `split(string, separator)`, the parts of `string` between the occurrences of `separator`, or
+/// its characters when `separator` is empty.
+fn builtin_split(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 2 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match (&arguments[0], &arguments[1]) {
+        (Object::String(value), Object::String(separator)) if separator.is_empty() => {
+            Ok(characters(value))
+        }
+        (Object::String(value), Object::String(separator)) => Ok(value
+            .split(&**separator)
+            .map(Object::from)
+            .collect::<Vec<_>>()
+            .into()),
+        (Object::String(_), argument) | (argument, _) => Err(format!(
+            "argument to \"split\" not supported, got {}",
+            argument.kind()
+        )
+        .into()),
+    }
+}
+
+/// `chars(string)`, the characters of `string` as strings of one character each.
+fn builtin_chars(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    let [value] = string_arguments("chars", arguments)?;
+    Ok(characters(value))
+}
+
+/// Array of the characters of `value`, as many as its [`len`](builtin_len).
+fn characters(value: &str) -> Object {
+    value
+        .chars()
+        .map(|char| Object::from(char.to_string()))
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// `ord(character)`, the Unicode code point of a string of a single character.
+fn builtin_ord(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    let [value] = string_arguments("ord", arguments)?;
+
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(char), None) => Ok(i64::from(u32::from(char)).into()),
+        _ => Err(format!(
+            "argument to \"ord\" must be a single character, got {} characters",
+            value.chars().count()
+        )
+        .into()),
+    }
+}
+
+/// `chr(code_point)`, the string of the character of a Unicode code point, which can't be a
+/// surrogate.
+fn builtin_chr(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 1 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1",
+            arguments.len()
+        )
+        .into());
+    }
+
+    let char = match &arguments[0] {
+        Object::Integer(value) => u32::try_from(*value).ok().and_then(char::from_u32),
+        Object::BigInt(_) => None,
+        argument => {
+            return Err(
+                format!("argument to \"chr\" not supported, got {}", argument.kind()).into(),
+            )
+        }
+    };
+
+    match char {
+        Some(char) => Ok(char.to_string().into()),
+        None => Err(format!("invalid Unicode code point: {}", arguments[0]).into()),
+    }
+}
+
+/// `keys(hash)`, the keys of `hash` in the order it is shown in.
+fn builtin_keys(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    let entries = hash_entries("keys", arguments)?;
+    Ok(entries
+        .into_iter()
+        .map(|(key, _)| Object::from(key.clone()))
+        .collect::<Vec<_>>()
+        .into())
+}
+
+/// `values(hash)`, the values of `hash` in the order of their [`keys`](builtin_keys).
+fn builtin_values(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    let entries = hash_entries("values", arguments)?;
+    Ok(entries
+        .into_iter()
+        .map(|(_, value)| value.clone())
+        .collect::<Vec<_>>()
+        .into())
+}
+
+/// `has_key(hash, key)`, whether `hash` has an entry for `key`, even one mapping it to NULL,
+/// which indexing it doesn't tell apart from a missing one. Whether an array has an element is
+/// told by [`contains`](builtin_contains) instead.
+fn builtin_has_key(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 2 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match &arguments[0] {
+        Object::Hash(entries) => Ok(entries
+            .contains_key(&HashKey::try_from(&arguments[1])?)
+            .into()),
+        argument => Err(format!(
+            "argument to \"has_key\" not supported, got {}",
+            argument.kind()
+        )
+        .into()),
+    }
+}
+
+/// `insert(hash, key, value)`, a copy of `hash` with `key` mapped to `value`, replacing the
+/// entry it may already have, as `push` does for arrays.
+fn builtin_insert(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 3 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 3",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match &arguments[0] {
+        Object::Hash(entries) => {
+            let key = HashKey::try_from(&arguments[1])?;
+            let mut entries = HashMap::clone(entries);
+            entries.insert(key, arguments[2].clone());
+            Ok(entries.into())
+        }
+        argument => Err(format!(
+            "argument to \"insert\" not supported, got {}",
+            argument.kind()
+        )
+        .into()),
+    }
+}
+
+/// `delete(hash, key)`, a copy of `hash` without the entry for `key`, if it has one.
+fn builtin_delete(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 2 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match &arguments[0] {
+        Object::Hash(entries) => {
+            let key = HashKey::try_from(&arguments[1])?;
+            let mut entries = HashMap::clone(entries);
+            entries.remove(&key);
+            Ok(entries.into())
+        }
+        argument => Err(format!(
+            "argument to \"delete\" not supported, got {}",
+            argument.kind()
+        )
+        .into()),
+    }
+}
+
+/// `remove(array, index)`, a copy of `array` without the element at `index`, negative ones
+/// counting from the end. Like indexing, an index out of the array gives NULL.
+fn builtin_remove(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 2 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match (&arguments[0], &arguments[1]) {
+        (Object::Array(elements), Object::Integer(index)) => {
+            let Some(index) = position(*index, elements.len()) else {
+                return Ok(NULL);
+            };
+
+            let mut elements = elements.to_vec();
+            elements.remove(index);
+            Ok(elements.into())
+        }
+        (Object::Array(_), Object::BigInt(index)) => Err(out_of_range(index, "an index")),
+        (Object::Array(_), argument) | (argument, _) => Err(format!(
+            "argument to \"remove\" not supported, got {}",
+            argument.kind()
+        )
+        .into()),
+    }
+}
+
+/// `merge(first, second, ...)`, a hash with the entries of every hash argument, those of the
+/// later ones replacing those of the earlier ones for the same key. Values are replaced as a
+/// whole, the hashes within them aren't merged.
+fn builtin_merge(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() < 2 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2 or more",
+            arguments.len()
+        )
+        .into());
+    }
+
+    let mut merged = HashMap::new();
+    for argument in arguments {
+        match argument {
+            Object::Hash(entries) => merged.extend(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone())),
+            ),
+            _ => {
+                return Err(format!(
+                    "argument to \"merge\" not supported, got {}",
+                    argument.kind()
+                )
+                .into())
+            }
+        }
+    }
+
+    Ok(merged.into())
+}
+
+/// Entries of the single hash argument of the builtin called `name`, sorted by key as when the
+/// hash is shown so that they don't depend on how it is stored.
+fn hash_entries<'a>(
+    name: &str,
+    arguments: &'a [Object],
+) -> Result<Vec<(&'a HashKey, &'a Object)>, EvalError> {
+    if arguments.len() != 1 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match &arguments[0] {
+        Object::Hash(entries) => {
+            let mut entries = entries.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(key, _)| *key);
+            Ok(entries)
+        }
+        argument => Err(format!(
+            "argument to \"{name}\" not supported, got {}",
+            argument.kind()
+        )
+        .into()),
+    }
+}
+
+/// `join(strings, separator)`, the strings of an array with `separator` in between. Elements
+/// that aren't strings are rejected rather than converted, which `str` does explicitly.
+fn builtin_join(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if arguments.len() != 2 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 2",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match (&arguments[0], &arguments[1]) {
+        (Object::Array(elements), Object::String(separator)) => {
+            let mut strings = Vec::with_capacity(elements.len());
+            for element in elements.iter() {
+                match element {
+                    Object::String(value) => strings.push(&**value),
+                    _ => {
+                        return Err(format!(
+                            "element of \"join\" not supported, got {}",
+                            element.kind()
+                        )
+                        .into())
+                    }
+                }
+            }
+
+            Ok(strings.join(separator).into())
+        }
+        (Object::Array(_), argument) | (argument, _) => Err(format!(
+            "argument to \"join\" not supported, got {}",
+            argument.kind()
+        )
+        .into()),
+    }
+}
+
+/// Applies `transform` to the single string argument of the builtin called `name`.
+fn map_string(
+    name: &str,
+    arguments: &[Object],
+    transform: impl FnOnce(&str) -> String,
+) -> Result<Object, EvalError> {
+    if arguments.len() != 1 {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1",
+            arguments.len()
+        )
+        .into());
+    }
+
+    match &arguments[0] {
+        Object::String(value) => Ok(transform(value).into()),
+        argument => Err(format!(
+            "argument to \"{name}\" not supported, got {}",
+            argument.kind()
+        )
+        .into()),
+    }
+}
+
+/// `upper(string)`, following the Unicode case mappings, which can change the length of the
+/// string, e.g. `upper("ß")` is `"SS"`.
+fn builtin_upper(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    map_string("upper", arguments, str::to_uppercase)
+}
+
+/// `lower(string)`, following the Unicode case mappings, which can change the length of the
+/// string.
+fn builtin_lower(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    map_string("lower", arguments, str::to_lowercase)
+}
+
+/// `trim(string)`, without its leading and trailing Unicode whitespace.
+fn builtin_trim(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    map_string("trim", arguments, |value| value.trim().to_string())
+}
+
+fn builtin_trim_left(
+    arguments: &[Object],
+    _context: &mut EvalContext,
+) -> Result<Object, EvalError> {
+    map_string("trim_left", arguments, |value| {
+        value.trim_start().to_string()
+    })
+}
+
+fn builtin_trim_right(
+    arguments: &[Object],
+    _context: &mut EvalContext,
+) -> Result<Object, EvalError> {
+    map_string("trim_right", arguments, |value| {
+        value.trim_end().to_string()
+    })
+}
+
+/// The `N` arguments of the builtin called `name`, which must all be strings.
+fn string_arguments<'a, const N: usize>(
+    name: &str,
+    arguments: &'a [Object],
+) -> Result<[&'a str; N], EvalError> {
+    let Ok(arguments) = <&[Object; N]>::try_from(arguments) else {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected {N}",
+            arguments.len()
+        )
+        .into());
+    };
+
+    let mut strings = [""; N];
+    for (string, argument) in strings.iter_mut().zip(arguments) {
+        match argument {
+            Object::String(value) => *string = value,
+            _ => {
+                return Err(format!(
+                    "argument to \"{name}\" not supported, got {}",
+                    argument.kind()
+                )
+                .into())
+            }
+        }
+    }
+    Ok(strings)
+}
+
+/// `replace(string, from, to)`, `string` with every occurrence of `from` replaced by `to`, from
+/// left to right without overlapping. An empty `from` is rejected, as it occurs everywhere.
+fn builtin_replace(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    let [value, from, to] = string_arguments("replace", arguments)?;
+    if from.is_empty() {
+        return Err("cannot replace an empty string".into());
+    }
+
+    Ok(value.replace(from, to).into())
+}
+
+/// `contains(string, needle)` whether `needle` occurs in `string`, or `contains(array, element)`
+/// whether an element of `array` is equal to `element`.
+fn builtin_contains(arguments: &[Object], _context: &mut EvalContext) -> Result<Object, EvalError> {
+    if let [Object::Array(elements), element] = arguments {
+        return Ok(elements.contains(element).into());
+    }
+
+    let [value, needle] = string_arguments("contains", arguments)?;
+    Ok(value.contains(needle).into())
+}
+
+fn builtin_starts_with(
+    arguments: &[Object],
+    _context: &mut EvalContext,
+) -> Result<Object, EvalError> {
+    let [value, prefix] = string_arguments("starts_with", arguments)?;
+    Ok(value.starts_with(prefix).into())
+}
+
+fn builtin_ends_with(
+    arguments: &[Object],
+    _context: &mut EvalContext,
+) -> Result<Object, EvalError> {
+    let [value, suffix] = string_arguments("ends_with", arguments)?;
+    Ok(value.ends_with(suffix).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        evaluator::{
+            context::{EvalContext, EvalOptions},
+            environment::Environment,
+            sink::{Buffering, Sink},
+            Evaluator,
+        },
+        parse,
+    };
+
+    use super::*;
+
+    fn eval(input: &str) -> Result<Option<Object>, EvalError> {
+        let program = parse(input).unwrap_or_else(|errors| panic!("{input}: {errors:?}"));
+        let mut context = EvalContext::with_io(
+            EvalOptions::default(),
+            std::io::empty(),
+            Sink::new(std::io::sink(), Buffering::Unbuffered),
+        );
+        program.eval_with_context(&Environment::shared(), &mut context)
+    }
+
+    #[test]
+    fn test_builtins_in_expression_positions() {
+        let tests: &[(&str, Object)] = &[
+            (
+                r#"{len("a"): 1}"#,
+                HashMap::from([(1.into(), 1.into())]).into(),
+            ),
+            (
+                r#"{"a": len("ab")}"#,
+                HashMap::from([("a".into(), 2.into())]).into(),
+            ),
+            (r#"[len("a"), first([2])]"#, vec![1.into(), 2.into()].into()),
+            (r#"[1, 2, 3][len("ab")]"#, 3.into()),
+            (r#"{"a": 1}[first(["a"])]"#, 1.into()),
+            ("if (first([])) { 1 } else { 2 }", 2.into()),
+            ("if (len([1])) { 1 } else { 2 }", 1.into()),
+            ("len(rest([1, 2, 3]))", 2.into()),
+            (r#"fn() { return len("abc"); }()"#, 3.into()),
+            (
+                "let count = fn(items) { len(items) }; count([1, 2])",
+                2.into(),
+            ),
+            ("len(1..4) + len([1])", 4.into()),
+            ("len({})", 0.into()),
+            (r#"len({"a": 1, "b": 2})"#, 2.into()),
+            (r#"len({"a": 1, "a": 2, 1: 3, true: 4})"#, 3.into()),
+            (
+                r#"let key = fn(n) { "k" + str(n) }; len({key(1): 1, key(2): 2, key(1): 3})"#,
+                2.into(),
+            ),
+            ("puts(exit)", NULL),
+            ("puts(puts(1))", NULL),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.clone())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_builtin_names() {
+        for name in Builtin::NAMES {
+            assert!(Builtin::from_str(name).is_some(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_builtins_as_values() {
+        let tests: &[(&str, Object)] = &[
+            (r#"let size = len; size("abc")"#, 3.into()),
+            ("[len, first][1]([5, 6])", 5.into()),
+            (r#"{"f": last}["f"]([5, 6])"#, 6.into()),
+            (
+                "let apply = fn(f, x) { f(x) }; apply(len, [1, 2])",
+                2.into(),
+            ),
+            ("fn() { rest }()([1, 2])", vec![2.into()].into()),
+            (
+                r#"let map = fn(arr, f) {
+                    let iter = fn(arr, mapped) {
+                        if (len(arr) == 0) { return mapped; }
+                        iter(rest(arr), push(mapped, f(first(arr))))
+                    };
+                    iter(arr, [])
+                };
+                map(["a", "bb", ""], len)"#,
+                vec![1.into(), 2.into(), 0.into()].into(),
+            ),
+            ("len", Object::Builtin(builtin_len)),
+            ("let len = fn(x) { 42 }; len([1])", 42.into()),
+            (
+                "let len = fn(x) { 42 }; let size = len; [1].size()",
+                42.into(),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.clone())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_pop() {
+        let tests: &[(&str, Object)] = &[
+            ("pop([1, 2, 3])", vec![1.into(), 2.into()].into()),
+            ("pop([1])", vec![].into()),
+            ("pop([])", NULL),
+            ("rest([])", NULL),
+            ("pop(push([1], 2))", vec![1.into()].into()),
+            ("let a = [1, 2]; pop(a); a", vec![1.into(), 2.into()].into()),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.clone())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_sort() {
+        let tests: &[(&str, &str)] = &[
+            ("sort([3, 1, 2])", "[1, 2, 3]"),
+            (
+                "sort([-1, 99999999999999999999, 0, -99999999999999999999])",
+                "[-99999999999999999999, -1, 0, 99999999999999999999]",
+            ),
+            (
+                r#"sort(["b", "a", "ab", "B", ""])"#,
+                r#"["", "B", "a", "ab", "b"]"#,
+            ),
+            ("sort([])", "[]"),
+            ("sort([true])", "[true]"),
+            ("sort([2, 1, 2, 1])", "[1, 1, 2, 2]"),
+            ("sort([3, 1, 2], fn(a, b) { b - a })", "[3, 2, 1]"),
+            (
+                r#"sort(["ccc", "a", "bb"], fn(a, b) { len(a) - len(b) })"#,
+                r#"["a", "bb", "ccc"]"#,
+            ),
+            (
+                r#"sort([[2, "a"], [1, "b"], [2, "c"], [1, "d"], [2, "e"]], fn(a, b) { a[0] - b[0] })"#,
+                r#"[[1, "b"], [1, "d"], [2, "a"], [2, "c"], [2, "e"]]"#,
+            ),
+            ("sort([1, 2, 3], fn(a, b) { 0 })", "[1, 2, 3]"),
+            (
+                "let by_second = fn(a, b) { a[1] - b[1] }; sort([[1, 2], [2, 1]], by_second)",
+                "[[2, 1], [1, 2]]",
+            ),
+            ("let a = [2, 1]; sort(a); a", "[2, 1]"),
+            ("sort([1], fn(a, b) { true })", "[1]"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map(|value| value.unwrap().inspect()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_reverse() {
+        let tests: &[(&str, &str)] = &[
+            ("reverse([1, 2, 3])", "[3, 2, 1]"),
+            (
+                r#"reverse([1, "a", [true], {"k": null}, null])"#,
+                r#"[null, {"k": null}, [true], "a", 1]"#,
+            ),
+            ("reverse([])", "[]"),
+            ("reverse([1])", "[1]"),
+            (r#"reverse("abc")"#, "cba"),
+            (r#"reverse("héllo 日本")"#, "本日 olléh"),
+            (r#"reverse("")"#, ""),
+            (r#"reverse("a")"#, "a"),
+            ("let a = [1, 2]; reverse(a); a", "[1, 2]"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map(|value| value.unwrap().inspect()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+
+        for value in [
+            "[]",
+            "[1]",
+            r#"[1, "b", [3], null]"#,
+            r#""""#,
+            r#""a""#,
+            r#""héllo 👋""#,
+        ] {
+            let input = format!("let x = {value}; reverse(reverse(x))");
+            assert_eq!(
+                eval(&input).map(|value| value.unwrap().inspect()),
+                eval(value).map(|value| value.unwrap().inspect()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_map_filter_and_reduce() {
+        let tests: &[(&str, &str)] = &[
+            ("map([1, 2, 3], fn(x) { x * x })", "[1, 4, 9]"),
+            ("let squared = fn(x) { x * x }; map([], squared)", "[]"),
+            (r#"map(["a", "bc", ""], len)"#, "[1, 2, 0]"),
+            ("map([[1, 2], [3]], first)", "[1, 3]"),
+            ("let a = [1, 2]; map(a, fn(x) { x + 1 }); a", "[1, 2]"),
+            (
+                r#"filter([1, "a", null, true, [2], 0, false], fn(x) { type(x) == "INTEGER" })"#,
+                "[1, 0]",
+            ),
+            (
+                r#"filter([1, "a", null, true, [2], 0, false], fn(x) { x })"#,
+                r#"[1, "a", true, [2], 0]"#,
+            ),
+            ("filter([[1], [], [2]], first)", "[[1], [2]]"),
+            ("filter([1, 2], fn(x) { false })", "[]"),
+            (
+                "reduce([1, 2, 3, 4, 5], 0, fn(total, element) { total + element })",
+                "15",
+            ),
+            ("reduce([], 0, fn(total, element) { total + element })", "0"),
+            ("reduce([1, 2, 3], [], push)", "[1, 2, 3]"),
+            (
+                "let sum = fn(total, element) { total + element }; reduce(map(filter([1, 2, 3, 4, 5, 6], fn(x) { x > 3 }), fn(x) { x * 10 }), 0, sum)",
+                "150",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map(|value| value.unwrap().inspect()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_index_of_and_find() {
+        let tests: &[(&str, &str)] = &[
+            ("index_of([1, 2, 3, 2], 2)", "1"),
+            ("index_of([1, 2, 3], 4)", "-1"),
+            ("index_of([], 1)", "-1"),
+            (r#"index_of([1, "2", 2], 2)"#, "2"),
+            ("index_of([1, null, null], null)", "1"),
+            ("index_of([1, [2, [3]], [2, [3]]], [2, [3]])", "1"),
+            (r#"index_of([{"a": 1}, {"a": [1]}], {"a": [1]})"#, "1"),
+            ("index_of([1, 2], [1, 2])", "-1"),
+            ("find([1, 2, 3, 4], fn(x) { x > 2 })", "3"),
+            ("find([1, 2], fn(x) { x > 2 })", "null"),
+            ("find([], fn(x) { true })", "null"),
+            ("find([null, false, 0], fn(x) { true })", "null"),
+            ("find([[], [1], [2]], first)", "[1]"),
+            (r#"find([1, "a"], fn(x) { x + 1 > 1 })"#, "1"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map(|value| value.unwrap().inspect()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_flatten() {
+        let tests: &[(&str, &str)] = &[
+            ("flatten([[1, 2], [3], []])", "[1, 2, 3]"),
+            ("flatten([1, [2, [3, [4]]], 5])", "[1, 2, [3, [4]], 5]"),
+            ("flatten([1, [2, [3, [4]]], 5], 2)", "[1, 2, 3, [4], 5]"),
+            ("flatten([1, [2, [3, [4]]], 5], 1000000)", "[1, 2, 3, 4, 5]"),
+            ("flatten([1, [2, [3]]], 0)", "[1, [2, [3]]]"),
+            (
+                r#"flatten([null, ["a", {"k": [1]}], [true, 1..3]])"#,
+                r#"[null, "a", {"k": [1]}, true, 1..3]"#,
+            ),
+            ("flatten([1, 2, 3])", "[1, 2, 3]"),
+            ("flatten([1, 2, 3], 5)", "[1, 2, 3]"),
+            ("flatten([])", "[]"),
+            ("flatten([[], [[]]], 3)", "[]"),
+            ("let a = [[1], [2]]; flatten(a); a", "[[1], [2]]"),
+            (
+                "flatten(map([1, 2, 3], fn(x) { [x, x * 10] }))",
+                "[1, 10, 2, 20, 3, 30]",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map(|value| value.unwrap().inspect()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_zip() {
+        let tests: &[(&str, &str)] = &[
+            (r#"zip(["a", "b"], [1, 2])"#, r#"[["a", 1], ["b", 2]]"#),
+            ("zip([1, 2, 3], [4])", "[[1, 4]]"),
+            ("zip([1], [4, 5, 6])", "[[1, 4]]"),
+            ("zip([], [1, 2])", "[]"),
+            ("zip([], [])", "[]"),
+            (
+                r#"zip([1, 2], ["a", "b", "c"], [true, false])"#,
+                r#"[[1, "a", true], [2, "b", false]]"#,
+            ),
+            ("zip([[1], null], [{}, 1..2])", "[[[1], {}], [null, 1..2]]"),
+            (
+                r#"let keys = ["a", "b"]; let values = [1, 2]; map(zip(keys, values), fn(pair) { pair[0] + str(pair[1]) })"#,
+                r#"["a1", "b2"]"#,
+            ),
+            (
+                r#"let pairs = zip(["a", "b"], [1, 2]); [map(pairs, first), map(pairs, last)]"#,
+                r#"[["a", "b"], [1, 2]]"#,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map(|value| value.unwrap().inspect()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unique() {
+        let tests: &[(&str, &str)] = &[
+            (r#"unique([1, 1, "1", true, 1])"#, r#"[1, "1", true]"#),
+            ("unique([3, 1, 3, 2, 1])", "[3, 1, 2]"),
+            ("unique([null, false, null, 0])", "[null, false, 0]"),
+            (
+                "unique([[1, [2]], [1], [1, [2]], []])",
+                "[[1, [2]], [1], []]",
+            ),
+            (
+                r#"unique([{"a": [1]}, {"a": [2]}, {"a": [1]}, {}])"#,
+                r#"[{"a": [1]}, {"a": [2]}, {}]"#,
+            ),
+            (r#"unique([1, "a", [2]])"#, r#"[1, "a", [2]]"#),
+            ("unique([])", "[]"),
+            ("let a = [1, 1]; unique(a); a", "[1, 1]"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map(|value| value.unwrap().inspect()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unique_steps() {
+        let program = parse("unique([[1], [2], [3], [1], 1, 1])").unwrap();
+        let mut context = EvalContext::default();
+        program
+            .eval_with_context(&Environment::shared(), &mut context)
+            .unwrap();
+        let steps = context.stats.steps;
+
+        let mut context = EvalContext::new(EvalOptions {
+            max_steps: steps - 1,
+            ..Default::default()
+        });
+        assert_eq!(
+            program
+                .eval_with_context(&Environment::shared(), &mut context)
+                .map_err(|error| error.cause().clone()),
+            Err(format!("evaluation budget exceeded after {} steps", steps - 1).into())
+        );
+
+        let steps = |input: &str| {
+            let mut context = EvalContext::default();
+            parse(input)
+                .unwrap()
+                .eval_with_context(&Environment::shared(), &mut context)
+                .unwrap();
+            context.stats.steps
+        };
+        assert_eq!(
+            steps("let a = [1]; let b = [2]; let c = [3]; unique([a, b, c])")
+                - steps("let a = [1]; let b = [2]; let c = [3]; len([a, b, c])"),
+            3,
+            "one step per comparison of the elements that aren't hash keys"
+        );
+
+        let elements = (0..100_000).map(Object::from).collect::<Vec<_>>();
+        assert_eq!(
+            builtin_unique(&[elements.clone().into()], &mut EvalContext::default()),
+            Ok(elements.into())
+        );
+    }
+
+    #[test]
+    fn test_slice() {
+        let tests: &[(&str, Object)] = &[
+            (
+                "slice([1, 2, 3, 4, 5], 1, 3)",
+                vec![2.into(), 3.into()].into(),
+            ),
+            ("slice([1, 2, 3], 1)", vec![2.into(), 3.into()].into()),
+            (
+                "slice([1, 2, 3], 0)",
+                vec![1.into(), 2.into(), 3.into()].into(),
+            ),
+            (
+                "slice([1, 2, 3], 0, 10)",
+                vec![1.into(), 2.into(), 3.into()].into(),
+            ),
+            ("slice([1, 2, 3], -2)", vec![2.into(), 3.into()].into()),
+            ("slice([1, 2, 3], 0, -1)", vec![1.into(), 2.into()].into()),
+            ("slice([1, 2, 3], -10, 1)", vec![1.into()].into()),
+            ("slice([1, 2, 3], 2, 1)", vec![].into()),
+            ("slice([1, 2, 3], 5)", vec![].into()),
+            ("slice([], 0)", vec![].into()),
+            (r#"slice("héllo", 1, 3)"#, "él".into()),
+            (r#"slice("héllo", -3)"#, "llo".into()),
+            (r#"slice("abc", 4)"#, "".into()),
+            (
+                "let a = [1, 2]; slice(a, 0); a",
+                vec![1.into(), 2.into()].into(),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.clone())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_type() {
+        let tests = [
+            ("type(5)", "INTEGER"),
+            ("type(-9223372036854775809)", "INTEGER"),
+            ("type(99999999999999999999)", "INTEGER"),
+            ("type(true)", "BOOLEAN"),
+            (r#"type("x")"#, "STRING"),
+            ("type(fn(x) { x })", "FUNCTION"),
+            ("type(len)", "BUILTIN"),
+            ("type(type)", "BUILTIN"),
+            ("type(quote(1 + 2))", "QUOTE"),
+            ("type(macro(x) { x })", "MACRO"),
+            ("type([1])", "ARRAY"),
+            (r#"type({"a": 1})"#, "HASH"),
+            ("type(1..3)", "RANGE"),
+            ("type(null)", "NULL"),
+            ("type(puts())", "NULL"),
+            ("type(type(1))", "STRING"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.into())), "{input}");
+        }
+
+        assert_eq!(
+            eval(r#"let x = [1]; if (type(x) == "ARRAY") { len(x) } else { 0 }"#),
+            Ok(Some(1.into()))
+        );
+    }
+
+    #[test]
+    fn test_str() {
+        let tests = [
+            ("str(42)", "42"),
+            ("str(-7)", "-7"),
+            ("str(99999999999999999999)", "99999999999999999999"),
+            ("str(true)", "true"),
+            ("str(false)", "false"),
+            (r#"str("x")"#, "x"),
+            (r#"str("")"#, ""),
+            ("str([1, 2])", "[1, 2]"),
+            (r#"str(["a", [true]])"#, r#"["a", [true]]"#),
+            (r#"str({"a": "b"})"#, r#"{"a": "b"}"#),
+            ("str(1..3)", "1..3"),
+            ("str(null)", "null"),
+            ("str(fn(x) { x })", "fn(x) { x }"),
+            ("str(len)", "builtin function"),
+            ("str(quote(1 + 2))", "QUOTE((1 + 2))"),
+            (r#""count: " + str(3)"#, "count: 3"),
+            ("str(str(1))", "1"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.into())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_int() {
+        let tests: &[(&str, Object)] = &[
+            (r#"int("42")"#, 42.into()),
+            (r#"int("  -7 ")"#, (-7).into()),
+            (r#"int("+7")"#, 7.into()),
+            (r#"int("\t0\n")"#, 0.into()),
+            ("int(true)", 1.into()),
+            ("int(false)", 0.into()),
+            ("int(3)", 3.into()),
+            (r#"int("9223372036854775807")"#, i64::MAX.into()),
+            (
+                r#"int("-9223372036854775808") == -9223372036854775807 - 1"#,
+                true.into(),
+            ),
+            (
+                r#"int("99999999999999999999") == 99999999999999999999"#,
+                true.into(),
+            ),
+            (r#"int("9223372036854775808") - 1"#, i64::MAX.into()),
+            ("let n = 123456; int(str(n)) == n", true.into()),
+            (
+                "let n = -99999999999999999999; int(str(n)) == n",
+                true.into(),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.clone())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_split_and_join() {
+        let strings = |values: &[&str]| -> Object {
+            values
+                .iter()
+                .map(|value| Object::from(*value))
+                .collect::<Vec<_>>()
+                .into()
+        };
+        let tests: &[(&str, Object)] = &[
+            (r#"split("a,b,c", ",")"#, strings(&["a", "b", "c"])),
+            (r#"split("a, b", ", ")"#, strings(&["a", "b"])),
+            (r#"split("abc", ",")"#, strings(&["abc"])),
+            (r#"split(",a,", ",")"#, strings(&["", "a", ""])),
+            (r#"split("", ",")"#, strings(&[""])),
+            (r#"split("héllo", "")"#, strings(&["h", "é", "l", "l", "o"])),
+            (r#"split("", "")"#, strings(&[])),
+            (r#"split("a→b", "→")"#, strings(&["a", "b"])),
+            (r#"join(["a", "b"], "-")"#, "a-b".into()),
+            (r#"join(["a"], ", ")"#, "a".into()),
+            (r#"join([], ",")"#, "".into()),
+            (r#"join(["a", "", "b"], "")"#, "ab".into()),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.clone())), "{input}");
+        }
+
+        for value in ["a,b,c", "", ",", "a,,b", "é,ü"] {
+            for separator in [",", ""] {
+                let input = format!(
+                    "let s = {}; join(split(s, {}), {}) == s",
+                    quote(value),
+                    quote(separator),
+                    quote(separator)
+                );
+                assert_eq!(eval(&input), Ok(Some(true.into())), "{input}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_case_and_trim() {
+        let tests = [
+            (r#"upper("abc")"#, "ABC"),
+            (r#"upper("ABC")"#, "ABC"),
+            (r#"upper("a1-b")"#, "A1-B"),
+            (r#"upper("")"#, ""),
+            (r#"upper("héllo")"#, "HÉLLO"),
+            (r#"upper("straße")"#, "STRASSE"),
+            (r#"lower("ABC")"#, "abc"),
+            (r#"lower("abc")"#, "abc"),
+            (r#"lower("ÉCOLE")"#, "école"),
+            (r#"lower("")"#, ""),
+            (r#"trim("  x \n")"#, "x"),
+            (r#"trim("\t a  b \r\n")"#, "a  b"),
+            (r#"trim("x")"#, "x"),
+            (r#"trim("   ")"#, ""),
+            (r#"trim("")"#, ""),
+            (r#"trim_left("  a b  ")"#, "a b  "),
+            (r#"trim_right("  a b  ")"#, "  a b"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.into())), "{input}");
+        }
+
+        assert_eq!(eval(r#"len(upper("ß"))"#), Ok(Some(2.into())));
+    }
+
+    #[test]
+    fn test_string_search() {
+        let tests: &[(&str, Object)] = &[
+            (r#"replace("a-b-c", "-", "+")"#, "a+b+c".into()),
+            (r#"replace("aaa", "aa", "b")"#, "ba".into()),
+            (r#"replace("abab", "ab", "")"#, "".into()),
+            (r#"replace("abc", "x", "y")"#, "abc".into()),
+            (r#"replace("héllo wörld", "ö", "o")"#, "héllo world".into()),
+            (r#"replace("→→", "→", "->")"#, "->->".into()),
+            (r#"replace("", "a", "b")"#, "".into()),
+            (r#"contains("hello", "ell")"#, true.into()),
+            (r#"contains("hello", "")"#, true.into()),
+            (r#"contains("hello", "elo")"#, false.into()),
+            (r#"contains("aaa", "aa")"#, true.into()),
+            (r#"contains("naïve", "ï")"#, true.into()),
+            ("contains([1, 2, 3], 2)", true.into()),
+            ("contains([1, 2, 3], 4)", false.into()),
+            (r#"contains([1, "2"], 2)"#, false.into()),
+            ("contains([[1], null], [1])", true.into()),
+            ("contains([], null)", false.into()),
+            (r#"contains(["a", "b"], "b")"#, true.into()),
+            (r#"starts_with("hello", "he")"#, true.into()),
+            (r#"starts_with("hello", "")"#, true.into()),
+            (r#"starts_with("hello", "lo")"#, false.into()),
+            (r#"starts_with("é", "e")"#, false.into()),
+            (r#"ends_with("hello", "lo")"#, true.into()),
+            (r#"ends_with("aaa", "aa")"#, true.into()),
+            (r#"ends_with("hello", "he")"#, false.into()),
+            (r#"ends_with("", "")"#, true.into()),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.clone())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_substring() {
+        let tests = [
+            (r#"substring("hello", 0, 3)"#, "hel"),
+            (r#"substring("hello", 1)"#, "ello"),
+            (r#"substring("héllo", 1, 3)"#, "él"),
+            (r#"substring("日本語", 1, 2)"#, "本"),
+            (r#"substring("hello", 2, 100)"#, "llo"),
+            (r#"substring("hello", 100)"#, ""),
+            (r#"substring("hello", -3)"#, "llo"),
+            (r#"substring("hello", 0, -1)"#, "hell"),
+            (r#"substring("hello", -100, 2)"#, "he"),
+            (r#"substring("hello", 3, 1)"#, ""),
+            (r#"substring("hello", 2, 2)"#, ""),
+            (r#"substring("", 0, 1)"#, ""),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.into())), "{input}");
+        }
+
+        assert_eq!(
+            eval(r#"let s = "héllo"; substring(s, 1, 2) == s[1]"#),
+            Ok(Some(true.into()))
+        );
+    }
+
+    #[test]
+    fn test_chars() {
+        let strings = |values: &[&str]| -> Object {
+            values
+                .iter()
+                .map(|value| Object::from(*value))
+                .collect::<Vec<_>>()
+                .into()
+        };
+        let tests: &[(&str, Object)] = &[
+            (r#"chars("abc")"#, strings(&["a", "b", "c"])),
+            (r#"chars("héllo")"#, strings(&["h", "é", "l", "l", "o"])),
+            (r#"chars("a b")"#, strings(&["a", " ", "b"])),
+            (r#"chars("")"#, strings(&[])),
+            (r#"rest(chars("日本"))"#, strings(&["本"])),
+            (r#"chars("héllo")[1]"#, "é".into()),
+            (
+                r#"let map = fn(arr, f) {
+                    let iter = fn(arr, mapped) {
+                        if (len(arr) == 0) { return mapped; }
+                        iter(rest(arr), push(mapped, f(first(arr))))
+                    };
+                    iter(arr, [])
+                };
+                join(map(chars("héllo"), upper), ".")"#,
+                "H.É.L.L.O".into(),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.clone())), "{input}");
+        }
+
+        for value in ["", "abc", "héllo", "日本語", "👋🏽"] {
+            let input = format!("let s = {}; len(chars(s)) == len(s)", quote(value));
+            assert_eq!(eval(&input), Ok(Some(true.into())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_ord_and_chr() {
+        let tests: &[(&str, Object)] = &[
+            (r#"ord("A")"#, 65.into()),
+            (r#"ord("é")"#, 233.into()),
+            (r#"ord("👋")"#, 0x1F44B.into()),
+            (r#"ord("\n")"#, 10.into()),
+            ("chr(97)", "a".into()),
+            ("chr(0)", "\0".into()),
+            ("chr(8594)", "→".into()),
+            ("chr(1114111)", "\u{10FFFF}".into()),
+            (r#"chr(ord("a") + 1)"#, "b".into()),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.clone())), "{input}");
+        }
+
+        for value in ["a", "Z", " ", "~", "é", "ß", "日", "→", "👋"] {
+            let input = format!("let c = {}; chr(ord(c)) == c", quote(value));
+            assert_eq!(eval(&input), Ok(Some(true.into())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_keys_and_values() {
+        let tests: &[(&str, Object)] = &[
+            (
+                r#"keys({"b": 1, "a": 2, 3: 3, true: 4})"#,
+                vec![3.into(), true.into(), "a".into(), "b".into()].into(),
+            ),
+            (
+                r#"values({"b": 1, "a": 2, 3: 3, true: 4})"#,
+                vec![3.into(), 4.into(), 2.into(), 1.into()].into(),
+            ),
+            ("keys({})", vec![].into()),
+            ("values({})", vec![].into()),
+            (r#"values({"a": null})"#, vec![NULL].into()),
+            (
+                r#"let reduce = fn(arr, initial, f) {
+                    let iter = fn(arr, accumulated) {
+                        if (len(arr) == 0) { return accumulated; }
+                        iter(rest(arr), f(accumulated, first(arr)))
+                    };
+                    iter(arr, initial)
+                };
+                let prices = {"apple": 3, "pear": 4, "plum": 5};
+                reduce(values(prices), 0, fn(total, price) { total + price })"#,
+                12.into(),
+            ),
+            (
+                r#"let map = fn(arr, f) {
+                    let iter = fn(arr, mapped) {
+                        if (len(arr) == 0) { return mapped; }
+                        iter(rest(arr), push(mapped, f(first(arr))))
+                    };
+                    iter(arr, [])
+                };
+                let h = {"one": 1, "two": 2, 3: [3]};
+                map(keys(h), fn(key) { [key, h[key]] })"#,
+                vec![
+                    vec![3.into(), vec![3.into()].into()].into(),
+                    vec!["one".into(), 1.into()].into(),
+                    vec!["two".into(), 2.into()].into(),
+                ]
+                .into(),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.clone())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_has_key() {
+        let tests = [
+            (r#"has_key({"a": 1}, "a")"#, true),
+            (r#"has_key({"a": 1}, "b")"#, false),
+            (r#"has_key({"a": null}, "a")"#, true),
+            ("has_key({1: 1, true: 2}, 1)", true),
+            ("has_key({1: 1, true: 2}, true)", true),
+            ("has_key({1: 1}, false)", false),
+            (r#"has_key({1: 1}, "1")"#, false),
+            (
+                "has_key({99999999999999999999: 1}, 99999999999999999999)",
+                true,
+            ),
+            (r#"has_key({}, "a")"#, false),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.into())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_delete_and_remove() {
+        let tests: &[(&str, Object)] = &[
+            (r#"delete({"a": 1}, "a")"#, HashMap::new().into()),
+            (
+                r#"delete({"a": 1, "b": 2}, "a")"#,
+                HashMap::from([("b".into(), 2.into())]).into(),
+            ),
+            (
+                r#"delete({"a": 1}, "b")"#,
+                HashMap::from([("a".into(), 1.into())]).into(),
+            ),
+            (
+                "delete({1: 1, true: 2}, true)",
+                HashMap::from([(1.into(), 1.into())]).into(),
+            ),
+            (
+                r#"let h = {"a": 1}; delete(h, "a"); h"#,
+                HashMap::from([("a".into(), 1.into())]).into(),
+            ),
+            ("remove([1, 2, 3], 0)", vec![2.into(), 3.into()].into()),
+            ("remove([1, 2, 3], 2)", vec![1.into(), 2.into()].into()),
+            ("remove([1, 2, 3], -1)", vec![1.into(), 2.into()].into()),
+            ("remove([1, 2, 3], -3)", vec![2.into(), 3.into()].into()),
+            ("remove([1], 0)", vec![].into()),
+            ("remove([1, 2, 3], 3)", NULL),
+            ("remove([1, 2, 3], -4)", NULL),
+            ("remove([], 0)", NULL),
+            (
+                "let a = [1, 2]; remove(a, 0); a",
+                vec![1.into(), 2.into()].into(),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.clone())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let tests: &[(&str, &str)] = &[
+            (r#"insert({}, "a", 1)"#, r#"{"a": 1}"#),
+            (r#"insert({"a": 1}, "b", 2)"#, r#"{"a": 1, "b": 2}"#),
+            (r#"insert({"a": 1}, "a", [2])"#, r#"{"a": [2]}"#),
+            (r#"insert({"a": 1}, "a", null)"#, r#"{"a": null}"#),
+            (
+                r#"insert(insert({}, 1, "one"), true, "yes")"#,
+                r#"{1: "one", true: "yes"}"#,
+            ),
+            (
+                r#"let h = {"a": 1}; insert(h, "a", 2); insert(h, "b", 3); h"#,
+                r#"{"a": 1}"#,
+            ),
+            (
+                r#"let reduce = fn(arr, initial, f) {
+                    let iter = fn(arr, accumulated) {
+                        if (len(arr) == 0) { return accumulated; }
+                        iter(rest(arr), f(accumulated, first(arr)))
+                    };
+                    iter(arr, initial)
+                };
+                let count = fn(words) {
+                    reduce(words, {}, fn(counts, word) {
+                        let seen = if (has_key(counts, word)) { counts[word] } else { 0 };
+                        insert(counts, word, seen + 1)
+                    })
+                };
+                count(split("a b a c a b", " "))"#,
+                r#"{"a": 3, "b": 2, "c": 1}"#,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map(|value| value.unwrap().inspect()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let tests: &[(&str, &str)] = &[
+            (r#"merge({"a": 1}, {"b": 2})"#, r#"{"a": 1, "b": 2}"#),
+            (
+                r#"merge({"a": 1, "b": 2}, {"b": 3})"#,
+                r#"{"a": 1, "b": 3}"#,
+            ),
+            (r#"merge({}, {"a": 1})"#, r#"{"a": 1}"#),
+            (r#"merge({"a": 1}, {})"#, r#"{"a": 1}"#),
+            ("merge({}, {})", "{}"),
+            (
+                r#"merge({"a": 1, "b": 1}, {"b": 2, "c": 2}, {"c": 3})"#,
+                r#"{"a": 1, "b": 2, "c": 3}"#,
+            ),
+            (
+                r#"merge({1: "a"}, {true: "b"}, {1: "c"})"#,
+                r#"{1: "c", true: "b"}"#,
+            ),
+            (
+                r#"merge({"db": {"host": "a", "port": 1}}, {"db": {"host": "b"}})"#,
+                r#"{"db": {"host": "b"}}"#,
+            ),
+            (
+                r#"let a = {"a": 1}; let b = {"a": 2}; merge(a, b); [a, b]"#,
+                r#"[{"a": 1}, {"a": 2}]"#,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map(|value| value.unwrap().inspect()),
+                Ok(expected.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_builtin_errors() {
+        let tests = &[
+            (
+                "first(puts(1))",
+                r#"argument to "first" not supported, got NULL"#,
+            ),
+            (
+                "len(len)",
+                r#"argument to "len" not supported, got BUILTIN"#,
+            ),
+            (
+                "len(true)",
+                r#"argument to "len" not supported, got BOOLEAN"#,
+            ),
+            ("len(null)", r#"argument to "len" not supported, got NULL"#),
+            (
+                r#"len({"a": 1}["b"])"#,
+                r#"argument to "len" not supported, got NULL"#,
+            ),
+            (
+                "len({}, {})",
+                "wrong number of arguments. Got 2, expected 1",
+            ),
+            ("{len: 1}", "unusable as hash key: BUILTIN"),
+            (r#"{"a": 1}[len]"#, "unusable as hash key: BUILTIN"),
+            (
+                r#"exit("a")"#,
+                r#"argument to "exit" not supported, got STRING"#,
+            ),
+            (
+                "exit(1, 2)",
+                "wrong number of arguments. Got 2, expected 0 or 1",
+            ),
+            (
+                "exit(4294967296)",
+                "integer 4294967296 out of range for an exit code",
+            ),
+            (
+                "exit(-2147483649)",
+                "integer -2147483649 out of range for an exit code",
+            ),
+            (
+                "slice([1])",
+                "wrong number of arguments. Got 1, expected 2 or 3",
+            ),
+            (
+                "slice([1], 0, 1, 2)",
+                "wrong number of arguments. Got 4, expected 2 or 3",
+            ),
+            (
+                "slice(1, 0)",
+                r#"argument to "slice" not supported, got INTEGER"#,
+            ),
+            (
+                r#"slice([1], "a")"#,
+                r#"argument to "slice" not supported, got STRING"#,
+            ),
+            (
+                "slice([1], 0, null)",
+                r#"argument to "slice" not supported, got NULL"#,
+            ),
+            (
+                "slice([1], 0, 9223372036854775808)",
+                "integer 9223372036854775808 out of range for a slice bound",
+            ),
+            ("type()", "wrong number of arguments. Got 0, expected 1"),
+            ("str(1, 2)", "wrong number of arguments. Got 2, expected 1"),
+            ("int()", "wrong number of arguments. Got 0, expected 1"),
+            (r#"int("abc")"#, r#"cannot convert "abc" to INTEGER"#),
+            (r#"int("")"#, r#"cannot convert "" to INTEGER"#),
+            (r#"int("  ")"#, r#"cannot convert "  " to INTEGER"#),
+            (r#"int("1.5")"#, r#"cannot convert "1.5" to INTEGER"#),
+            (r#"int("1 2")"#, r#"cannot convert "1 2" to INTEGER"#),
+            (r#"int("+-1")"#, r#"cannot convert "+-1" to INTEGER"#),
+            (r#"int("--1")"#, r#"cannot convert "--1" to INTEGER"#),
+            ("int(null)", r#"argument to "int" not supported, got NULL"#),
+            (
+                r#"split("a")"#,
+                "wrong number of arguments. Got 1, expected 2",
+            ),
+            (
+                r#"split(1, ",")"#,
+                r#"argument to "split" not supported, got INTEGER"#,
+            ),
+            (
+                r#"split("a", null)"#,
+                r#"argument to "split" not supported, got NULL"#,
+            ),
+            (
+                r#"join(["a"], ",", 1)"#,
+                "wrong number of arguments. Got 3, expected 2",
+            ),
+            (
+                r#"join("a", ",")"#,
+                r#"argument to "join" not supported, got STRING"#,
+            ),
+            (
+                r#"join(["a"], 1)"#,
+                r#"argument to "join" not supported, got INTEGER"#,
+            ),
+            ("upper()", "wrong number of arguments. Got 0, expected 1"),
+            (
+                r#"lower("a", "b")"#,
+                "wrong number of arguments. Got 2, expected 1",
+            ),
+            (
+                "upper(1)",
+                r#"argument to "upper" not supported, got INTEGER"#,
+            ),
+            (
+                "lower([])",
+                r#"argument to "lower" not supported, got ARRAY"#,
+            ),
+            (
+                "trim(null)",
+                r#"argument to "trim" not supported, got NULL"#,
+            ),
+            (
+                "trim_left(true)",
+                r#"argument to "trim_left" not supported, got BOOLEAN"#,
+            ),
+            (
+                "trim_right(len)",
+                r#"argument to "trim_right" not supported, got BUILTIN"#,
+            ),
+            (
+                r#"replace("a", "b")"#,
+                "wrong number of arguments. Got 2, expected 3",
+            ),
+            (r#"replace("a", "", "b")"#, "cannot replace an empty string"),
+            (
+                r#"replace("a", 1, "b")"#,
+                r#"argument to "replace" not supported, got INTEGER"#,
+            ),
+            (
+                r#"contains("a")"#,
+                "wrong number of arguments. Got 1, expected 2",
+            ),
+            (
+                "contains([1], 1, 2)",
+                "wrong number of arguments. Got 3, expected 2",
+            ),
+            (
+                r#"contains("a", 1)"#,
+                r#"argument to "contains" not supported, got INTEGER"#,
+            ),
+            (
+                r#"contains({"a": 1}, "a")"#,
+                r#"argument to "contains" not supported, got HASH"#,
+            ),
+            (
+                r#"starts_with(1, "a")"#,
+                r#"argument to "starts_with" not supported, got INTEGER"#,
+            ),
+            (
+                r#"ends_with("a", ["a"])"#,
+                r#"argument to "ends_with" not supported, got ARRAY"#,
+            ),
+            (
+                r#"substring("a")"#,
+                "wrong number of arguments. Got 1, expected 2 or 3",
+            ),
+            (
+                "substring([1], 0)",
+                r#"argument to "substring" not supported, got ARRAY"#,
+            ),
+            (
+                r#"substring("a", "0")"#,
+                r#"argument to "substring" not supported, got STRING"#,
+            ),
+            (
+                r#"substring("a", 0, 99999999999999999999)"#,
+                "integer 99999999999999999999 out of range for a slice bound",
+            ),
+            ("chars()", "wrong number of arguments. Got 0, expected 1"),
+            (
+                "chars(1)",
+                r#"argument to "chars" not supported, got INTEGER"#,
+            ),
+            (
+                r#"chars(["a"])"#,
+                r#"argument to "chars" not supported, got ARRAY"#,
+            ),
+            ("ord()", "wrong number of arguments. Got 0, expected 1"),
+            ("ord(65)", r#"argument to "ord" not supported, got INTEGER"#),
+            (
+                r#"ord("ab")"#,
+                r#"argument to "ord" must be a single character, got 2 characters"#,
+            ),
+            (
+                r#"ord("")"#,
+                r#"argument to "ord" must be a single character, got 0 characters"#,
+            ),
+            ("chr(1, 2)", "wrong number of arguments. Got 2, expected 1"),
+            (
+                r#"chr("a")"#,
+                r#"argument to "chr" not supported, got STRING"#,
+            ),
+            ("chr(-1)", "invalid Unicode code point: -1"),
+            ("chr(55296)", "invalid Unicode code point: 55296"),
+            ("chr(57343)", "invalid Unicode code point: 57343"),
+            ("chr(1114112)", "invalid Unicode code point: 1114112"),
+            ("chr(4294967393)", "invalid Unicode code point: 4294967393"),
+            (
+                "chr(99999999999999999999)",
+                "invalid Unicode code point: 99999999999999999999",
+            ),
+            ("keys()", "wrong number of arguments. Got 0, expected 1"),
+            (
+                "has_key({})",
+                "wrong number of arguments. Got 1, expected 2",
+            ),
+            (
+                "has_key([1], 1)",
+                r#"argument to "has_key" not supported, got ARRAY"#,
+            ),
+            (
+                r#"has_key("a", "a")"#,
+                r#"argument to "has_key" not supported, got STRING"#,
+            ),
+            ("has_key({}, [1])", "unusable as hash key: ARRAY"),
+            ("has_key({}, null)", "unusable as hash key: NULL"),
+            ("delete({})", "wrong number of arguments. Got 1, expected 2"),
+            (
+                "sort()",
+                "wrong number of arguments. Got 0, expected 1 or 2",
+            ),
+            (
+                "sort([], len, len)",
+                "wrong number of arguments. Got 3, expected 1 or 2",
+            ),
+            (
+                r#"sort("ba")"#,
+                r#"argument to "sort" not supported, got STRING"#,
+            ),
+            (
+                "sort([1], 1)",
+                r#"argument to "sort" not supported, got INTEGER"#,
+            ),
+            (r#"sort([1, "a"])"#, "cannot compare STRING with INTEGER"),
+            ("sort([true, false])", "cannot compare BOOLEAN with BOOLEAN"),
+            ("sort([[1], [2]])", "cannot compare ARRAY with ARRAY"),
+            (
+                "sort([1, 2], fn(a, b) { a < b })",
+                r#"comparator of "sort" must return an INTEGER, got BOOLEAN"#,
+            ),
+            (
+                "sort([1, 2], fn() { null })",
+                r#"comparator of "sort" must return an INTEGER, got NULL"#,
+            ),
+            (
+                "sort([1, 2], len)",
+                "wrong number of arguments. Got 2, expected 1",
+            ),
+            ("reverse()", "wrong number of arguments. Got 0, expected 1"),
+            (
+                "reverse(1..3)",
+                r#"argument to "reverse" not supported, got RANGE"#,
+            ),
+            (
+                "reverse({})",
+                r#"argument to "reverse" not supported, got HASH"#,
+            ),
+            ("map([1])", "wrong number of arguments. Got 1, expected 2"),
+            (
+                "filter([1], len, len)",
+                "wrong number of arguments. Got 3, expected 2",
+            ),
+            (
+                "reduce([1], len)",
+                "wrong number of arguments. Got 2, expected 3",
+            ),
+            (
+                r#"map("ab", len)"#,
+                r#"argument to "map" not supported, got STRING"#,
+            ),
+            (
+                "filter([1], 1)",
+                r#"argument to "filter" not supported, got INTEGER"#,
+            ),
+            (
+                "reduce({}, 0, len)",
+                r#"argument to "reduce" not supported, got HASH"#,
+            ),
+            (
+                "map([1], fn(a, b) { a })",
+                r#"function given to "map" must take 1 argument, takes 2"#,
+            ),
+            (
+                "filter([1], fn() { true })",
+                r#"function given to "filter" must take 1 argument, takes 0"#,
+            ),
+            (
+                "reduce([1], 0, fn(a) { a })",
+                r#"function given to "reduce" must take 2 arguments, takes 1"#,
+            ),
+            (
+                r#"map(["a"], push)"#,
+                "wrong number of arguments. Got 1, expected 2",
+            ),
+            (
+                "reduce([1], 0, len)",
+                "wrong number of arguments. Got 2, expected 1",
+            ),
+            (
+                "index_of([1])",
+                "wrong number of arguments. Got 1, expected 2",
+            ),
+            (
+                r#"index_of("abc", "b")"#,
+                r#"argument to "index_of" not supported, got STRING"#,
+            ),
+            (
+                "find([1], len, len)",
+                "wrong number of arguments. Got 3, expected 2",
+            ),
+            (
+                "find(null, len)",
+                r#"argument to "find" not supported, got NULL"#,
+            ),
+            (
+                "find([1], true)",
+                r#"argument to "find" not supported, got BOOLEAN"#,
+            ),
+            (
+                "find([1], fn(a, b) { a })",
+                r#"function given to "find" must take 1 argument, takes 2"#,
+            ),
+            (
+                "flatten()",
+                "wrong number of arguments. Got 0, expected 1 or 2",
+            ),
+            (
+                "flatten([], 1, 2)",
+                "wrong number of arguments. Got 3, expected 1 or 2",
+            ),
+            (
+                r#"flatten("ab")"#,
+                r#"argument to "flatten" not supported, got STRING"#,
+            ),
+            (
+                "flatten(1..3)",
+                r#"argument to "flatten" not supported, got RANGE"#,
+            ),
+            (
+                r#"flatten([], "1")"#,
+                r#"argument to "flatten" not supported, got STRING"#,
+            ),
+            (
+                "flatten([], -1)",
+                r#"depth of "flatten" must not be negative, got -1"#,
+            ),
+            (
+                "flatten([], 99999999999999999999)",
+                "integer 99999999999999999999 out of range for a depth",
+            ),
+            (
+                "zip()",
+                "wrong number of arguments. Got 0, expected 2 or more",
+            ),
+            (
+                "zip([1])",
+                "wrong number of arguments. Got 1, expected 2 or more",
+            ),
+            (
+                r#"zip([1], "a")"#,
+                r#"argument to "zip" not supported, got STRING"#,
+            ),
+            (
+                "zip(1..3, [1])",
+                r#"argument to "zip" not supported, got RANGE"#,
+            ),
+            ("unique()", "wrong number of arguments. Got 0, expected 1"),
+            (
+                "unique([], [])",
+                "wrong number of arguments. Got 2, expected 1",
+            ),
+            (
+                r#"unique("aab")"#,
+                r#"argument to "unique" not supported, got STRING"#,
+            ),
+            (
+                "unique({})",
+                r#"argument to "unique" not supported, got HASH"#,
+            ),
+            (
+                "pop([], [])",
+                "wrong number of arguments. Got 2, expected 1",
+            ),
+            (
+                r#"pop("ab")"#,
+                r#"argument to "pop" not supported, got STRING"#,
+            ),
+            (
+                r#"insert({}, "a")"#,
+                "wrong number of arguments. Got 2, expected 3",
+            ),
+            (
+                "insert([], 0, 1)",
+                r#"argument to "insert" not supported, got ARRAY"#,
+            ),
+            ("insert({}, [1], 1)", "unusable as hash key: ARRAY"),
+            ("insert({}, {}, 1)", "unusable as hash key: HASH"),
+            (
+                "merge({})",
+                "wrong number of arguments. Got 1, expected 2 or more",
+            ),
+            (
+                "merge({}, [])",
+                r#"argument to "merge" not supported, got ARRAY"#,
+            ),
+            (
+                "merge(null, {})",
+                r#"argument to "merge" not supported, got NULL"#,
+            ),
+            (
+                r#"delete([1], 0)"#,
+                r#"argument to "delete" not supported, got ARRAY"#,
+            ),
+            ("delete({}, fn() {})", "unusable as hash key: FUNCTION"),
+            (
+                "remove([1], 0, 1)",
+                "wrong number of arguments. Got 3, expected 2",
+            ),
+            (
+                r#"remove({"a": 1}, "a")"#,
+                r#"argument to "remove" not supported, got HASH"#,
+            ),
+            (
+                r#"remove([1], "0")"#,
+                r#"argument to "remove" not supported, got STRING"#,
+            ),
+            (
+                "remove([1], 99999999999999999999)",
+                "integer 99999999999999999999 out of range for an index",
+            ),
+            (
+                "values({}, {})",
+                "wrong number of arguments. Got 2, expected 1",
+            ),
+            (
+                "keys([1])",
+                r#"argument to "keys" not supported, got ARRAY"#,
+            ),
+            (
+                "values(null)",
+                r#"argument to "values" not supported, got NULL"#,
+            ),
+            (
+                r#"join(["a", 1], ",")"#,
+                r#"element of "join" not supported, got INTEGER"#,
+            ),
+            ("int([1])", r#"argument to "int" not supported, got ARRAY"#),
+            ("type(1, 2)", "wrong number of arguments. Got 2, expected 1"),
+            ("len(missing)", "identifier not found: missing"),
+            ("nothing(1)", "function not found: nothing"),
+            ("let one = 1; one(1)", "not a function: one is INTEGER"),
+            ("[1][0](1)", "not a function: INTEGER"),
+            (
+                "len(1)(1)",
+                r#"argument to "len" not supported, got INTEGER"#,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Err((*expected).into()), "{input}");
+        }
+    }
+    #[test]
+    fn test_exit_stops_the_program() {
+        let tests = [
+            ("exit(); let after = 1;", 0),
+            ("exit(3); let after = 1;", 3),
+            ("if (true) { exit(4) } let after = 1;", 4),
+            (
+                "let quit = fn(code) { exit(code); 1 }; let f = fn() { [quit(5)] }; let after = f();",
+                5,
+            ),
+            (
+                "let apply = fn(f, x) { f(x) }; let after = apply(fn(x) { exit(x) }, 6);",
+                6,
+            ),
+            ("for (i in 0..10) { if (i == 2) { exit(i) } }; let after = 1;", 2),
+            ("let after = fn() { return exit(7) }();", 7),
+            (
+                "let after = map([1, 8, 9], fn(x) { if (x > 1) { exit(x) } x });",
+                8,
+            ),
+        ];
+
+        for (input, code) in tests {
+            let program = parse(input).unwrap();
+            let environment = Environment::shared();
+            let result = program.eval(&environment);
+
+            assert_eq!(result, Err(EvalError::Exit(code)), "{input}");
+            assert_eq!(environment.borrow().get("after"), None, "{input}");
+        }
+    }
+
+    /// An error in an argument stops the call before the builtin runs, rather than being handed
+    /// to it as a value.
+    #[test]
+    fn test_builtin_argument_errors() {
+        let tests: &[(&str, EvalError)] = &[
+            ("len(foobar)", "identifier not found: foobar".into()),
+            ("puts(1, foobar)", "identifier not found: foobar".into()),
+            (
+                "push([], 1 + true)",
+                "type mismatch: INTEGER + BOOLEAN in '(1 + true)'".into(),
+            ),
+            (
+                "slice([1, 2], 0, -true)",
+                "unknown operator: -BOOLEAN in '(-true)'".into(),
+            ),
+            ("len([1, foobar])", "identifier not found: foobar".into()),
+            ("type(foobar)", "identifier not found: foobar".into()),
+            ("upper(foobar)", "identifier not found: foobar".into()),
+            ("keys(foobar)", "identifier not found: foobar".into()),
+            ("pop(foobar)", "identifier not found: foobar".into()),
+            ("reverse(foobar)", "identifier not found: foobar".into()),
+            ("filter(foobar, len)", "identifier not found: foobar".into()),
+            ("flatten(foobar)", "identifier not found: foobar".into()),
+            ("flatten([], foobar)", "identifier not found: foobar".into()),
+            ("unique(foobar)", "identifier not found: foobar".into()),
+            (
+                "zip([1], [2], foobar)",
+                "identifier not found: foobar".into(),
+            ),
+            (
+                "index_of([1], foobar)",
+                "identifier not found: foobar".into(),
+            ),
+            (
+                r#"find([1, 2, 3], fn(x) { if (x > 1) { throw "no" } false })"#,
+                EvalError::Thrown(Box::new("no".into())),
+            ),
+            (
+                "map([1, 2], fn(x) { foobar })",
+                "identifier not found: foobar".into(),
+            ),
+            (
+                r#"map([1, "a"], fn(x) { x + 1 })"#,
+                "type mismatch: STRING + INTEGER in '(x + 1)'".into(),
+            ),
+            (
+                r#"reduce([1, 2], 0, fn(total, x) { if (x > 1) { throw "no" } total + x })"#,
+                EvalError::Thrown(Box::new("no".into())),
+            ),
+            (
+                "sort([1, 2], fn(a, b) { foobar })",
+                "identifier not found: foobar".into(),
+            ),
+            ("delete({}, foobar)", "identifier not found: foobar".into()),
+            ("remove(foobar, 0)", "identifier not found: foobar".into()),
+            (
+                "str(1 + true)",
+                "type mismatch: INTEGER + BOOLEAN in '(1 + true)'".into(),
+            ),
+            (
+                r#"first(fn() { throw "no" }())"#,
+                EvalError::Thrown(Box::new("no".into())),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map_err(|error| error.cause().clone()),
+                Err(expected.clone()),
+                "{input}"
+            );
+        }
     }
 }
@@ -0,0 +1,745 @@
+use std::{
+    io::{self, BufRead},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    evaluator::{
+        error::EvalError, object::Object, observer::EvalObserver, profiler::Profiler, sink::Sink,
+    },
+    lexer::Span,
+    parser::Diagnostic,
+};
+
+/// Limits applied while evaluating a program.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct EvalOptions {
+    /// Budget for the estimated bytes of every array, hash and string built during the
+    /// evaluation. Nothing is ever given back, so this bounds the cumulative allocations rather
+    /// than the live ones.
+    pub max_total_allocated_bytes: usize,
+    /// Most elements an array built during the evaluation can hold.
+    pub max_array_length: usize,
+    /// Most bytes of UTF-8 a string built during the evaluation can take.
+    pub max_string_length: usize,
+    /// Most entries a hash built during the evaluation can hold.
+    pub max_hash_entries: usize,
+    /// Largest source accepted, in bytes of UTF-8. The lexer keeps a single copy of the source
+    /// as characters, so lexing it takes at most 4 times this much memory on top of the source.
+    pub max_source_bytes: usize,
+    /// Budget for the statements and expressions evaluated, so that a runaway program such as
+    /// an infinite loop errors out rather than hanging.
+    pub max_steps: usize,
+    /// Deepest nesting of function calls, so that a runaway recursion errors out rather than
+    /// overflowing the stack. Calls in tail position reuse their caller's depth.
+    pub max_call_depth: usize,
+    /// Deepest nesting of expressions being evaluated, counting the ones of every function call
+    /// in progress, so that deeply nested code errors out rather than overflowing the stack.
+    pub max_nesting_depth: usize,
+    /// Most bytes of stack the evaluation can take, measured from its first expression, as
+    /// the depths above don't say how much stack each level takes, which also depends on the
+    /// build. The default fits the 2 MiB stack of a thread spawned by the standard library
+    /// along with the frames of the code evaluating the program.
+    pub max_stack_bytes: usize,
+}
+
+impl EvalOptions {
+    /// Rejects a `source` larger than [`Self::max_source_bytes`], to be checked before lexing it.
+    pub fn check_source_size(&self, source: &str) -> Result<(), Diagnostic> {
+        if source.len() <= self.max_source_bytes {
+            return Ok(());
+        }
+
+        Err(Diagnostic::error(
+            "source-too-large",
+            format!(
+                "source too large ({} bytes, limit {})",
+                source.len(),
+                self.max_source_bytes
+            ),
+            Span::default(),
+        ))
+    }
+}
+
+impl Default for EvalOptions {
+    fn default() -> Self {
+        Self {
+            max_total_allocated_bytes: usize::MAX,
+            max_array_length: usize::MAX,
+            max_string_length: usize::MAX,
+            max_hash_entries: usize::MAX,
+            max_source_bytes: usize::MAX,
+            max_steps: usize::MAX,
+            max_call_depth: 1000,
+            max_nesting_depth: 3_000,
+            max_stack_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// Figures gathered while evaluating a program.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct EvalStats {
+    /// Estimated bytes of every array, hash and string built so far, see [`Object::estimated_size`]
+    pub allocated_bytes: usize,
+    /// Statements and expressions evaluated so far
+    pub steps: usize,
+}
+
+/// State threaded through a whole evaluation, shared by every scope and function call.
+pub struct EvalContext {
+    pub options: EvalOptions,
+    pub stats: EvalStats,
+    /// Where `puts` and `print` write to
+    pub output: Sink,
+    /// Where `read_line` reads from, the standard input when not set
+    input: Option<Box<dyn BufRead>>,
+    /// Told about each statement, call and expression result as they are evaluated, if set
+    pub observer: Option<Box<dyn EvalObserver>>,
+    /// Counts and times the evaluation of each function and expression, if set
+    pub profiler: Option<Profiler>,
+    /// Function calls currently being evaluated
+    call_depth: usize,
+    /// Expressions currently being evaluated
+    nesting_depth: usize,
+    /// Stack address of the outermost expression being evaluated
+    stack_base: usize,
+}
+
+impl EvalContext {
+    /// Context reading from the standard input and writing to the standard output.
+    pub fn new(options: EvalOptions) -> Self {
+        Self {
+            options,
+            stats: EvalStats::default(),
+            output: Sink::stdout(),
+            input: None,
+            observer: None,
+            profiler: None,
+            call_depth: 0,
+            nesting_depth: 0,
+            stack_base: 0,
+        }
+    }
+
+    pub fn with_io(options: EvalOptions, input: impl BufRead + 'static, output: Sink) -> Self {
+        Self {
+            options,
+            stats: EvalStats::default(),
+            output,
+            input: Some(Box::new(input)),
+            observer: None,
+            profiler: None,
+            call_depth: 0,
+            nesting_depth: 0,
+            stack_base: 0,
+        }
+    }
+
+    /// Reads a line from the input, without its line ending, or `None` once the input is
+    /// exhausted. Buffered output is flushed first so that prompts show up before blocking.
+    pub(crate) fn read_line(&mut self) -> io::Result<Option<String>> {
+        self.output.flush()?;
+
+        let mut line = String::new();
+        let read = match &mut self.input {
+            Some(input) => input.read_line(&mut line)?,
+            None => io::stdin().read_line(&mut line)?,
+        };
+        if read == 0 {
+            return Ok(None);
+        }
+
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+
+        Ok(Some(line))
+    }
+
+    /// Charges one evaluation step to the step budget, failing once it is exhausted.
+    pub(crate) fn step(&mut self) -> Result<(), EvalError> {
+        self.stats.steps += 1;
+
+        if self.stats.steps > self.options.max_steps {
+            return Err(format!(
+                "evaluation budget exceeded after {} steps",
+                self.options.max_steps
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates `call` one function call deeper, failing instead once
+    /// [`EvalOptions::max_call_depth`] is reached. The depth is given back whatever the outcome.
+    pub(crate) fn nested<T>(
+        &mut self,
+        call: impl FnOnce(&mut Self) -> Result<T, EvalError>,
+    ) -> Result<T, EvalError> {
+        if self.call_depth >= self.options.max_call_depth {
+            return Err(format!(
+                "maximum recursion depth exceeded ({})",
+                self.options.max_call_depth
+            )
+            .into());
+        }
+
+        self.call_depth += 1;
+        let result = call(self);
+        self.call_depth -= 1;
+        result
+    }
+
+    /// Evaluates `expression` one expression deeper, failing instead once
+    /// [`EvalOptions::max_nesting_depth`] is reached or more than
+    /// [`EvalOptions::max_stack_bytes`] of stack is used. The depth is given back whatever the
+    /// outcome.
+    pub(crate) fn deeper<T>(
+        &mut self,
+        expression: impl FnOnce(&mut Self) -> Result<T, EvalError>,
+    ) -> Result<T, EvalError> {
+        if self.nesting_depth >= self.options.max_nesting_depth {
+            return Err(format!(
+                "expression nesting too deep (limit {})",
+                self.options.max_nesting_depth
+            )
+            .into());
+        }
+
+        let stack = stack_address();
+        if self.nesting_depth == 0 {
+            self.stack_base = stack;
+        }
+        if self.stack_base.abs_diff(stack) > self.options.max_stack_bytes {
+            return Err(format!(
+                "expression nesting too deep for the stack (limit {} bytes)",
+                self.options.max_stack_bytes
+            )
+            .into());
+        }
+
+        self.nesting_depth += 1;
+        let result = expression(self);
+        self.nesting_depth -= 1;
+        result
+    }
+
+    /// Calls `hook` on the observer, if there is one.
+    pub(crate) fn observe(&mut self, hook: impl FnOnce(&mut dyn EvalObserver)) {
+        if let Some(observer) = &mut self.observer {
+            hook(observer.as_mut());
+        }
+    }
+
+    /// Instant to time something from, only read when profiling.
+    pub(crate) fn profile_start(&self) -> Option<Instant> {
+        self.profiler.as_ref().map(|_| Instant::now())
+    }
+
+    /// Calls `record` on the profiler with the time elapsed since `start`, when profiling.
+    pub(crate) fn profile(
+        &mut self,
+        start: Option<Instant>,
+        record: impl FnOnce(&mut Profiler, Duration),
+    ) {
+        if let (Some(start), Some(profiler)) = (start, &mut self.profiler) {
+            record(profiler, start.elapsed());
+        }
+    }
+
+    /// Charges a newly built `object` to the memory budget, returning it unless it is larger
+    /// than its kind allows or the budget is exceeded.
+    pub(crate) fn track(&mut self, object: Object) -> Result<Object, EvalError> {
+        let length = match &object {
+            Object::Array(elements) => {
+                Some(("array", elements.len(), self.options.max_array_length))
+            }
+            Object::String(value) => Some(("string", value.len(), self.options.max_string_length)),
+            Object::Hash(entries) => Some(("hash", entries.len(), self.options.max_hash_entries)),
+            Object::BigInt(_) => None,
+            _ => return Ok(object),
+        };
+        if let Some((kind, length, limit)) = length {
+            if length > limit {
+                return Err(format!("{kind} size limit exceeded ({limit})").into());
+            }
+        }
+
+        self.stats.allocated_bytes = self
+            .stats
+            .allocated_bytes
+            .saturating_add(object.estimated_size());
+
+        if self.stats.allocated_bytes > self.options.max_total_allocated_bytes {
+            return Err(format!(
+                "memory budget exceeded ({} bytes allocated, limit {})",
+                self.stats.allocated_bytes, self.options.max_total_allocated_bytes
+            )
+            .into());
+        }
+
+        Ok(object)
+    }
+}
+
+/// Address of a local of this function, within the frame right below the one of its caller, so
+/// that comparing two of them tells how much stack was used in between. It works the same
+/// whichever way the stack grows.
+#[inline(never)]
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+impl Default for EvalContext {
+    fn default() -> Self {
+        Self::new(EvalOptions::default())
+    }
+}
+
+impl std::fmt::Debug for EvalContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EvalContext")
+            .field("options", &self.options)
+            .field("stats", &self.stats)
+            .field("output", &self.output)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        collections::VecDeque,
+        io::{Read, Write},
+        rc::Rc,
+    };
+
+    use crate::{
+        evaluator::{environment::Environment, object::NULL, sink::Buffering, Evaluator},
+        lexer::Lexer,
+        parser::Parser,
+    };
+
+    use super::*;
+
+    /// Everything shown on a terminal, the output written to it and the input typed into it, in
+    /// the order it happened.
+    #[derive(Clone, Default)]
+    struct Terminal(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Terminal {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Lines typed into the `terminal` one at a time, as they are read.
+    struct TypedInput {
+        lines: VecDeque<Vec<u8>>,
+        terminal: Terminal,
+    }
+
+    impl Read for TypedInput {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.fill_buf()?.read(buf)?;
+            self.consume(read);
+            Ok(read)
+        }
+    }
+
+    impl BufRead for TypedInput {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            Ok(self.lines.front().map(Vec::as_slice).unwrap_or_default())
+        }
+
+        fn consume(&mut self, amount: usize) {
+            let Some(line) = self.lines.front_mut() else {
+                return;
+            };
+            self.terminal.write_all(&line[..amount]).unwrap();
+            line.drain(..amount);
+            if line.is_empty() {
+                self.lines.pop_front();
+            }
+        }
+    }
+
+    const PROMPTS: &str = r#"print("name: ");
+let name = read_line();
+puts("hello " + name);
+print("age: ");
+let age = read_line();
+print(name, " is ", age);
+read_line()"#;
+
+    fn run_prompts(buffering: Buffering) -> (Result<Option<Object>, EvalError>, String) {
+        let terminal = Terminal::default();
+        let input = TypedInput {
+            lines: VecDeque::from([b"Ada\n".to_vec(), b"36\r\n".to_vec()]),
+            terminal: terminal.clone(),
+        };
+        let mut context = EvalContext::with_io(
+            EvalOptions::default(),
+            input,
+            Sink::new(terminal.clone(), buffering),
+        );
+
+        let result = eval(PROMPTS, &mut context);
+        drop(context);
+
+        let shown = String::from_utf8(terminal.0.borrow().clone()).unwrap();
+        (result, shown)
+    }
+
+    /// Builds 200 arrays of 10 elements each, returning "done".
+    fn medium_arrays() -> String {
+        let mut input = (0..200)
+            .map(|index| format!("let a = [{}];\n", vec![index.to_string(); 10].join(", ")))
+            .collect::<String>();
+        input.push_str(r#""done""#);
+        input
+    }
+
+    fn eval(input: &str, context: &mut EvalContext) -> Result<Option<Object>, EvalError> {
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+        program
+            .eval_with_context(&Environment::shared(), context)
+            .map_err(|error| error.cause().clone())
+    }
+
+    #[test]
+    fn test_memory_budget_exceeded() {
+        let mut context = EvalContext::new(EvalOptions {
+            max_total_allocated_bytes: 50_000,
+            ..Default::default()
+        });
+
+        let Err(EvalError::Message(error)) = eval(&medium_arrays(), &mut context) else {
+            panic!("expected the memory budget to be exceeded");
+        };
+
+        assert!(
+            error.starts_with("memory budget exceeded"),
+            "unexpected error: {error}"
+        );
+        assert!(context.stats.allocated_bytes > 50_000);
+    }
+
+    #[test]
+    fn test_collection_size_limits() {
+        let mut context = EvalContext::new(EvalOptions {
+            max_array_length: 3,
+            max_string_length: 4,
+            max_hash_entries: 2,
+            ..Default::default()
+        });
+
+        let tests = [
+            (
+                "[1, 2, 3]",
+                Ok(Some(vec![1.into(), 2.into(), 3.into()].into())),
+            ),
+            ("[1, 2, 3, 4]", Err("array size limit exceeded (3)")),
+            (
+                "push([1, 2], 3)",
+                Ok(Some(vec![1.into(), 2.into(), 3.into()].into())),
+            ),
+            ("push([1, 2, 3], 4)", Err("array size limit exceeded (3)")),
+            // Nesting doesn't add up, each array being within the limit
+            (
+                "let a = [1, 2, 3]; [a, a, a]",
+                Ok(Some(
+                    vec![vec![1.into(), 2.into(), 3.into()].into(); 3].into(),
+                )),
+            ),
+            (r#""ab" + "cd""#, Ok(Some("abcd".into()))),
+            (r#""ab" + "cde""#, Err("string size limit exceeded (4)")),
+            // Literals are bounded by the size of the source instead
+            (r#""abcde""#, Ok(Some("abcde".into()))),
+            (r#"{"a": 1, "b": 2}["b"]"#, Ok(Some(2.into()))),
+            (
+                r#"{"a": 1, "b": 2, "c": 3}"#,
+                Err("hash size limit exceeded (2)"),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input, &mut context),
+                expected.map_err(EvalError::from),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_step_budget_exceeded() {
+        let mut context = EvalContext::new(EvalOptions {
+            max_steps: 1_000,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            eval("let f = fn() { f() }; f();", &mut context),
+            Err("evaluation budget exceeded after 1000 steps".into())
+        );
+        assert_eq!(context.stats.steps, 1_001);
+
+        let mut context = EvalContext::new(EvalOptions {
+            max_steps: 6,
+            ..Default::default()
+        });
+        assert_eq!(eval("let a = 1 + 2; a", &mut context), Ok(Some(3.into())));
+        assert_eq!(context.stats.steps, 6);
+    }
+
+    #[test]
+    fn test_tail_statements_are_steps() {
+        let steps = |input: &str| {
+            let mut context = EvalContext::default();
+            eval(input, &mut context).unwrap();
+            context.stats.steps
+        };
+
+        for tail in ["g()", "if (true) { g() }", "g() + 1"] {
+            assert_eq!(
+                steps(&format!(
+                    "let g = fn() {{ 1 }}; let f = fn() {{ {tail} }}; f()"
+                )) + 2,
+                steps(&format!(
+                    "let g = fn() {{ 1 }}; let f = fn() {{ let r = {tail}; r }}; f()"
+                )),
+                "{tail}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_max_call_depth() {
+        let sum = "let sum = fn(n) { if (n == 0) { 0 } else { n + sum(n - 1) } };";
+        let mut context = EvalContext::new(EvalOptions {
+            max_call_depth: 20,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            eval(&format!("{sum} sum(19)"), &mut context),
+            Ok(Some(190.into()))
+        );
+        assert_eq!(
+            eval(&format!("{sum} sum(20)"), &mut context),
+            Err("maximum recursion depth exceeded (20)".into())
+        );
+        assert_eq!(
+            eval(&format!("{sum} sum(19) + sum(19)"), &mut context),
+            Ok(Some(380.into())),
+            "the depth is given back when a call returns or fails"
+        );
+        assert_eq!(
+            eval(
+                "let count = fn(n) { if (n == 0) { 0 } else { count(n - 1) } }; count(100)",
+                &mut context
+            ),
+            Ok(Some(0.into())),
+            "calls in tail position don't nest"
+        );
+    }
+
+    #[test]
+    fn test_max_nesting_depth() {
+        let nested = |depth: usize| format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+        let mut context = EvalContext::new(EvalOptions {
+            max_nesting_depth: 50,
+            ..Default::default()
+        });
+
+        assert!(eval(&nested(49), &mut context).is_ok());
+        assert_eq!(
+            eval(&nested(50), &mut context),
+            Err("expression nesting too deep (limit 50)".into())
+        );
+        assert_eq!(
+            eval(&vec!["1"; 51].join(" + "), &mut context),
+            Err("expression nesting too deep (limit 50)".into())
+        );
+        assert_eq!(
+            eval("let f = fn(n) { [[[[f(n - 1)]]]] }; f(1)", &mut context),
+            Err("expression nesting too deep (limit 50)".into()),
+            "the expressions of every call in progress count"
+        );
+        assert!(
+            eval(&nested(49), &mut context).is_ok(),
+            "the depth is given back when an expression is evaluated or fails"
+        );
+    }
+
+    #[test]
+    fn test_max_stack_bytes() {
+        let nested = |depth: usize| format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+        let mut context = EvalContext::new(EvalOptions {
+            max_stack_bytes: 1024,
+            ..Default::default()
+        });
+
+        assert_eq!(eval("1", &mut context), Ok(Some(1.into())));
+        assert_eq!(
+            eval(&nested(100), &mut context),
+            Err("expression nesting too deep for the stack (limit 1024 bytes)".into())
+        );
+        assert_eq!(
+            eval("let f = fn(n) { f(n - 1) + 1 }; f(1)", &mut context),
+            Err("expression nesting too deep for the stack (limit 1024 bytes)".into())
+        );
+        assert_eq!(
+            eval("1", &mut context),
+            Ok(Some(1.into())),
+            "the stack is measured from the first expression of each evaluation"
+        );
+    }
+
+    /// Runaway and deeply nested programs error out with the default limits, or run to the end,
+    /// within the stack of a thread spawned with the default size.
+    #[test]
+    fn test_default_limits_fit_the_stack() {
+        let programs = [
+            "let f = fn(n) { -(-(-(f(n - 1)))) }; f(1)".to_string(),
+            "let f = fn(n) { [[[[f(n - 1)]]]] }; f(1)".to_string(),
+            "let f = fn(n) { n + f(n - 1) }; f(1)".to_string(),
+            "let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } }; f(990)".to_string(),
+            format!("{}1{}", "[".repeat(500), "]".repeat(500)),
+        ];
+
+        std::thread::spawn(move || {
+            for source in programs {
+                let program = crate::parse(&source).unwrap();
+                if let Err(error) = program.eval(&Environment::shared()) {
+                    let EvalError::Message(error) = error.cause() else {
+                        panic!("{source}: {error:?}");
+                    };
+                    assert!(
+                        error.starts_with("expression nesting too deep")
+                            || error.starts_with("maximum recursion depth exceeded"),
+                        "{source}: {error}"
+                    );
+                }
+            }
+
+            let source = format!("let a = 1; {}", vec!["a"; 50_000].join(" + "));
+            assert_eq!(
+                crate::parse(&source).unwrap_err()[0].message,
+                "expression nesting too deep (limit 512)"
+            );
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn test_default_budget_is_unaffected() {
+        let mut context = EvalContext::default();
+
+        assert_eq!(
+            eval(&medium_arrays(), &mut context),
+            Ok(Some("done".into()))
+        );
+        assert!(context.stats.allocated_bytes > 200 * 10 * std::mem::size_of::<Object>());
+    }
+
+    #[test]
+    fn test_allocated_bytes_is_monotonic() {
+        let input = r#"let a = [1, 2, 3];
+let b = {"a": a, "b": "x" + "y"};
+let c = push(a, 4);
+let d = 1 + 2;
+let e = rest(c);"#;
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+        let environment = Environment::shared();
+        let mut context = EvalContext::default();
+
+        let mut allocated = vec![context.stats.allocated_bytes];
+        for statement in program.statements.iter() {
+            statement
+                .eval_with_context(&environment, &mut context)
+                .unwrap();
+            allocated.push(context.stats.allocated_bytes);
+        }
+
+        assert!(allocated.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(allocated[3], allocated[4], "integers are not tracked");
+        assert!(allocated[5] > allocated[4]);
+    }
+
+    #[test]
+    fn test_wrapping_does_not_charge_the_elements_again() {
+        let input = format!(
+            "let a = [{}]; let b = [a, a, a]; let c = [b, b]",
+            vec!["\"element\""; 1000].join(", ")
+        );
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse_program().expect("Failed to parse program");
+        let environment = Environment::shared();
+        let mut context = EvalContext::default();
+
+        let mut allocated = vec![context.stats.allocated_bytes];
+        for statement in program.statements.iter() {
+            statement
+                .eval_with_context(&environment, &mut context)
+                .unwrap();
+            allocated.push(context.stats.allocated_bytes);
+        }
+
+        let object = std::mem::size_of::<Object>();
+        assert!(allocated[1] - allocated[0] > 1000 * object);
+        assert_eq!(allocated[2] - allocated[1], 4 * object);
+        assert_eq!(allocated[3] - allocated[2], 3 * object);
+    }
+
+    #[test]
+    fn test_prompts_show_before_reading() {
+        let expected = "name: Ada\nhello Ada\nage: 36\r\nAda is 36";
+
+        assert_eq!(
+            run_prompts(Buffering::Line),
+            (Ok(Some(NULL)), expected.into())
+        );
+        assert_eq!(
+            run_prompts(Buffering::Unbuffered),
+            (Ok(Some(NULL)), expected.into())
+        );
+    }
+
+    #[test]
+    fn test_flush() {
+        let terminal = Terminal::default();
+        let mut context = EvalContext::with_io(
+            EvalOptions::default(),
+            io::empty(),
+            Sink::new(terminal.clone(), Buffering::Line),
+        );
+
+        eval(r#"print("a"); puts("b"); print("c")"#, &mut context).unwrap();
+        assert_eq!(*terminal.0.borrow(), b"ab\n");
+
+        eval("flush()", &mut context).unwrap();
+        assert_eq!(*terminal.0.borrow(), b"ab\nc");
+
+        assert_eq!(
+            eval("flush(1)", &mut context),
+            Err("wrong number of arguments. Got 1, expected 0".into())
+        );
+    }
+}
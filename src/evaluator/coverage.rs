@@ -0,0 +1,309 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use crate::{
+    ast::{BlockStatement, Expression, Spanned, Statement},
+    evaluator::{
+        context::EvalContext, environment::Environment, error::EvalError,
+        macros::define_and_expand_macros, object::Object, observer::EvalObserver, Evaluator,
+    },
+    lexer::Span,
+    parser::Program,
+};
+
+/// Counts how many times each statement is evaluated, by span, once installed in a context.
+pub struct Coverage {
+    hits: Rc<RefCell<BTreeMap<Span, usize>>>,
+}
+
+/// Observer feeding a [`Coverage`].
+struct Recorder {
+    hits: Rc<RefCell<BTreeMap<Span, usize>>>,
+}
+
+/// How many times each statement of a program was evaluated.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct CoverageReport {
+    /// Every statement of the program, including the ones in the blocks and function bodies it
+    /// contains, in source order
+    pub statements: Vec<StatementHits>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct StatementHits {
+    pub span: Span,
+    pub hits: usize,
+}
+
+impl EvalObserver for Recorder {
+    fn on_statement(&mut self, statement: &Spanned<Statement>, _environment: &Environment) {
+        *self.hits.borrow_mut().entry(statement.span).or_default() += 1;
+    }
+}
+
+impl Coverage {
+    /// Starts counting the statements evaluated with `context`, replacing its observer.
+    pub fn install(context: &mut EvalContext) -> Self {
+        let hits = Rc::default();
+        context.observer = Some(Box::new(Recorder {
+            hits: Rc::clone(&hits),
+        }));
+        Self { hits }
+    }
+
+    /// Coverage of the statements of `program`, which should be the one evaluated, once its
+    /// macros are expanded. Statements without a span, built rather than parsed, are left out.
+    pub fn report(&self, program: &Program) -> CoverageReport {
+        let mut spans = Vec::new();
+        collect_statements(&program.statements, &mut spans);
+        spans.sort();
+        spans.dedup();
+
+        let hits = self.hits.borrow();
+        CoverageReport {
+            statements: spans
+                .into_iter()
+                .map(|span| StatementHits {
+                    span,
+                    hits: hits.get(&span).copied().unwrap_or_default(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl CoverageReport {
+    /// Statements never evaluated.
+    pub fn uncovered(&self) -> impl Iterator<Item = &StatementHits> {
+        self.statements
+            .iter()
+            .filter(|statement| statement.hits == 0)
+    }
+
+    /// Number of statements evaluated at least once.
+    pub fn covered(&self) -> usize {
+        self.statements.len() - self.uncovered().count()
+    }
+
+    /// `source` with each line prefixed by the fewest times a statement starting on it was
+    /// evaluated, `#####` when one never was, or `-` when no statement starts on it.
+    pub fn annotate(&self, source: &str) -> String {
+        let mut lines = BTreeMap::<usize, usize>::new();
+        for statement in &self.statements {
+            let (line, _) = statement.span.line_column(source);
+            let hits = lines.entry(line).or_insert(usize::MAX);
+            *hits = (*hits).min(statement.hits);
+        }
+
+        source
+            .lines()
+            .enumerate()
+            .map(|(index, line)| {
+                let marker = match lines.get(&(index + 1)) {
+                    None => "-".to_string(),
+                    Some(0) => "#####".to_string(),
+                    Some(hits) => hits.to_string(),
+                };
+                format!("{marker:>5} | {line}\n")
+            })
+            .collect()
+    }
+}
+
+/// Evaluates `program` in a fresh environment, returning its result along with the coverage of
+/// its statements.
+pub fn run_with_coverage(program: Program) -> (Result<Option<Object>, EvalError>, CoverageReport) {
+    let environment = Environment::shared();
+    let mut context = EvalContext::default();
+    let program = match define_and_expand_macros(program, &environment, &mut context) {
+        Ok(program) => program,
+        Err(error) => return (Err(error), CoverageReport::default()),
+    };
+
+    let coverage = Coverage::install(&mut context);
+    let result = program.eval_with_context(&environment, &mut context);
+    (result, coverage.report(&program))
+}
+
+fn collect_statements(statements: &[Spanned<Statement>], spans: &mut Vec<Span>) {
+    for statement in statements {
+        if statement.span != Span::default() {
+            spans.push(statement.span);
+        }
+
+        match &statement.node {
+            Statement::Let { value, .. }
+            | Statement::Const { value, .. }
+            | Statement::Destructure { value, .. }
+            | Statement::Return { value }
+            | Statement::Throw { value }
+            | Statement::Expression { value } => collect_expression(value, spans),
+        }
+    }
+}
+
+fn collect_block(block: &BlockStatement, spans: &mut Vec<Span>) {
+    collect_statements(&block.statements, spans);
+}
+
+/// Collects the statements of the blocks within `expression`.
+fn collect_expression(expression: &Expression, spans: &mut Vec<Span>) {
+    match expression {
+        Expression::Integer(_)
+        | Expression::BigInt(_)
+        | Expression::Identifier(_)
+        | Expression::Boolean(_)
+        | Expression::Null
+        | Expression::String(_)
+        | Expression::Postfix { .. }
+        // Only evaluated once expanded, which the program to report on already is
+        | Expression::Macro { .. } => {}
+        Expression::PrefixOperator { expression, .. } => collect_expression(expression, spans),
+        Expression::InfixOperator {
+            lh_expression,
+            rh_expression,
+            ..
+        } => {
+            collect_expression(lh_expression, spans);
+            collect_expression(rh_expression, spans);
+        }
+        Expression::FunctionCall { name, arguments } => {
+            collect_expression(name, spans);
+            for argument in arguments {
+                collect_expression(argument, spans);
+            }
+        }
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            collect_expression(condition, spans);
+            collect_block(consequence, spans);
+            if let Some(alternative) = alternative {
+                collect_block(alternative, spans);
+            }
+        }
+        Expression::For { iterable, body, .. } => {
+            collect_expression(iterable, spans);
+            collect_block(body, spans);
+        }
+        Expression::While { condition, body } => {
+            collect_expression(condition, spans);
+            collect_block(body, spans);
+        }
+        Expression::Function { body, .. } => collect_block(body, spans),
+        Expression::Array(elements) => {
+            for element in elements {
+                collect_expression(element, spans);
+            }
+        }
+        Expression::Index { left, index } | Expression::OptionalIndex { left, index } => {
+            collect_expression(left, spans);
+            collect_expression(index, spans);
+        }
+        Expression::Range { start, end, .. } => {
+            collect_expression(start, spans);
+            collect_expression(end, spans);
+        }
+        Expression::HashLiteral(pairs) => {
+            for (key, value) in pairs {
+                collect_expression(key, spans);
+                collect_expression(value, spans);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::*;
+
+    fn coverage(input: &str) -> (Result<Option<Object>, EvalError>, CoverageReport) {
+        run_with_coverage(parse(input).unwrap())
+    }
+
+    /// Source of the statements never evaluated.
+    fn uncovered(input: &str, report: &CoverageReport) -> Vec<String> {
+        report
+            .uncovered()
+            .map(|statement| {
+                input
+                    .chars()
+                    .skip(statement.span.start)
+                    .take(statement.span.end - statement.span.start)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_untaken_else_is_uncovered() {
+        let input = "let sign = fn(n) {
+    if (n > 0) {
+        let positive = 1;
+        positive
+    } else {
+        -1
+    }
+};
+sign(2);
+sign(3);";
+        let (result, report) = coverage(input);
+
+        assert_eq!(result, Ok(Some(1.into())));
+        assert_eq!(uncovered(input, &report), ["-1"]);
+        assert_eq!((report.covered(), report.statements.len()), (6, 7));
+        assert_eq!(
+            report
+                .statements
+                .iter()
+                .map(|statement| statement.hits)
+                .collect::<Vec<_>>(),
+            [1, 2, 2, 2, 0, 1, 1]
+        );
+    }
+
+    #[test]
+    fn test_uncalled_function_is_uncovered() {
+        let input = "let unused = fn() { let a = 1; a }; let used = fn() { 2 }; used()";
+        let (_, report) = coverage(input);
+
+        assert_eq!(uncovered(input, &report), ["let a = 1;", "a"]);
+    }
+
+    #[test]
+    fn test_tail_expressions_are_covered() {
+        let input = "let double = fn(x) {
+    x
+        * 2
+};
+let twice = fn(x) { double(double(x)) };
+let pick = fn(x) { if (x) { twice(x) } else { 0 } };
+pick(1)";
+        let (result, report) = coverage(input);
+
+        assert_eq!(result, Ok(Some(4.into())));
+        assert_eq!(uncovered(input, &report), ["0"]);
+        assert_eq!(
+            report.annotate(input).lines().take(3).collect::<Vec<_>>(),
+            [
+                "    1 | let double = fn(x) {",
+                "    2 |     x",
+                "    - |         * 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_annotate() {
+        let input = "let a = 1;\nif (a > 1) {\n  a\n} else {\n  0\n}\n";
+        let (_, report) = coverage(input);
+
+        assert_eq!(
+            report.annotate(input),
+            "    1 | let a = 1;\n    1 | if (a > 1) {\n##### |   a\n    - | } else {\n    1 |   0\n    - | }\n"
+        );
+    }
+}
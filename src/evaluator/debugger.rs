@@ -0,0 +1,291 @@
+use std::{cell::RefCell, collections::BTreeSet, rc::Rc};
+
+use crate::{
+    ast::{Spanned, Statement},
+    evaluator::{
+        context::EvalContext,
+        environment::Environment,
+        error::{EvalError, Frame},
+        flow::Flow,
+        macros::define_and_expand_macros,
+        object::Object,
+        observer::EvalObserver,
+        Evaluator,
+    },
+    parser::Program,
+};
+
+/// Evaluates a program one top-level statement at a time, pausing in between so that its
+/// environment can be inspected, e.g. to teach what each statement does.
+///
+/// Each step runs a whole statement, stepping over the function calls it makes. The statements
+/// evaluated within these calls are reported with the step, so that stepping into them can be
+/// replayed.
+pub struct Debugger {
+    program: Program,
+    environment: Rc<RefCell<Environment>>,
+    context: EvalContext,
+    /// Statements evaluated within function calls during the current step
+    inner: Rc<RefCell<Vec<InnerStatement>>>,
+    breakpoints: BTreeSet<usize>,
+    /// Index of the next statement to run
+    next: usize,
+    /// Whether the program ended early, with a `return`, an error or an exit
+    done: bool,
+}
+
+/// What running a top-level statement did.
+#[derive(Debug)]
+pub struct StepResult {
+    /// Index of the statement among the top-level ones
+    pub index: usize,
+    pub statement: Spanned<Statement>,
+    pub result: Result<Option<Object>, EvalError>,
+    /// Statements evaluated within the function calls the statement made, in order
+    pub inner: Vec<InnerStatement>,
+}
+
+/// Statement evaluated within a function call.
+#[derive(PartialEq, Debug, Clone)]
+pub struct InnerStatement {
+    /// Function calls in progress, 1 for a statement of a function called by the top-level one
+    pub depth: usize,
+    pub statement: Statement,
+}
+
+/// Records the statements evaluated within function calls.
+struct Recorder {
+    inner: Rc<RefCell<Vec<InnerStatement>>>,
+    depth: usize,
+}
+
+impl EvalObserver for Recorder {
+    fn on_statement(&mut self, statement: &Spanned<Statement>, _environment: &Environment) {
+        if self.depth > 0 {
+            self.inner.borrow_mut().push(InnerStatement {
+                depth: self.depth,
+                statement: statement.node.clone(),
+            });
+        }
+    }
+
+    fn on_call(&mut self, _frame: &Frame, _arguments: &[Object]) {
+        self.depth += 1;
+    }
+
+    fn on_return(&mut self, _frame: &Frame) {
+        self.depth -= 1;
+    }
+}
+
+impl Debugger {
+    /// Debugger paused before the first statement of `program`, whose macros are expanded right
+    /// away. The observer of `context` is replaced by the one of the debugger.
+    pub fn new(program: Program, mut context: EvalContext) -> Result<Self, EvalError> {
+        let environment = Environment::shared();
+        let program = define_and_expand_macros(program, &environment, &mut context)?;
+
+        let inner = Rc::default();
+        context.observer = Some(Box::new(Recorder {
+            inner: Rc::clone(&inner),
+            depth: 0,
+        }));
+
+        Ok(Self {
+            program,
+            environment,
+            context,
+            inner,
+            breakpoints: BTreeSet::new(),
+            next: 0,
+            done: false,
+        })
+    }
+
+    /// Program being debugged, once its macros are expanded.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Environment the program is evaluated in, as left by the statements run so far.
+    pub fn environment(&self) -> &Rc<RefCell<Environment>> {
+        &self.environment
+    }
+
+    /// Index of the statement the debugger is paused before, `None` once the program ended.
+    pub fn position(&self) -> Option<usize> {
+        (!self.done && self.next < self.program.statements.len()).then_some(self.next)
+    }
+
+    /// Pauses [`Self::run_to_breakpoint`] before the top-level statement at `index`.
+    pub fn set_breakpoint(&mut self, index: usize) {
+        self.breakpoints.insert(index);
+    }
+
+    pub fn clear_breakpoint(&mut self, index: usize) {
+        self.breakpoints.remove(&index);
+    }
+
+    /// Runs the next statement, or returns `None` once the program ended.
+    pub fn step(&mut self) -> Option<StepResult> {
+        let index = self.position()?;
+        let statement = &self.program.statements[index];
+
+        let flow = Flow::of(statement.eval_with_context(&self.environment, &mut self.context));
+        self.done = flow.is_early_exit();
+        self.next += 1;
+        // Whatever the statement printed is complete by the time it is paused after
+        let _ = self.context.output.flush();
+
+        Some(StepResult {
+            index,
+            statement: statement.clone(),
+            result: flow.absorb_return(),
+            inner: std::mem::take(&mut self.inner.borrow_mut()),
+        })
+    }
+
+    /// Runs statements until the next one has a breakpoint or the program ended, returning what
+    /// each of them did. Always runs at least one, to go past the breakpoint it is paused at.
+    pub fn run_to_breakpoint(&mut self) -> Vec<StepResult> {
+        let mut steps = Vec::new();
+
+        while let Some(step) = self.step() {
+            steps.push(step);
+            if self
+                .position()
+                .is_some_and(|next| self.breakpoints.contains(&next))
+            {
+                break;
+            }
+        }
+
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::*;
+
+    const PROGRAM: &str = "let a = 1;
+let add = fn(x, y) { let sum = x + y; sum };
+let b = add(a, 2);
+let c = if (b > 2) { add(b, b) } else { 0 };
+a + b + c";
+
+    fn start(input: &str) -> Debugger {
+        Debugger::new(parse(input).unwrap(), EvalContext::default()).unwrap()
+    }
+
+    fn bound(debugger: &Debugger, name: &str) -> Option<Object> {
+        debugger.environment().borrow().get(name)
+    }
+
+    #[test]
+    fn test_step() {
+        let mut debugger = start(PROGRAM);
+        assert_eq!(debugger.position(), Some(0));
+        assert_eq!(bound(&debugger, "a"), None);
+
+        let step = debugger.step().unwrap();
+        assert_eq!((step.index, step.result), (0, Ok(Some(1.into()))));
+        assert_eq!(step.statement.to_string(), "let a = 1;");
+        assert!(step.inner.is_empty());
+        assert_eq!(bound(&debugger, "a"), Some(1.into()));
+        assert_eq!(bound(&debugger, "b"), None);
+
+        debugger.step().unwrap();
+        let step = debugger.step().unwrap();
+        assert_eq!(step.index, 2);
+        assert_eq!(bound(&debugger, "b"), Some(3.into()));
+        assert_eq!(
+            step.inner
+                .iter()
+                .map(|inner| (inner.depth, inner.statement.to_string()))
+                .collect::<Vec<_>>(),
+            [(1, "let sum = (x + y);".into()), (1, "sum".into())],
+            "the statements of the call stepped over"
+        );
+        assert_eq!(bound(&debugger, "sum"), None);
+
+        let step = debugger.step().unwrap();
+        assert_eq!(step.inner.len(), 2);
+        assert_eq!(bound(&debugger, "c"), Some(6.into()));
+
+        let step = debugger.step().unwrap();
+        assert_eq!(step.result, Ok(Some(10.into())));
+        assert_eq!(debugger.position(), None);
+        assert!(debugger.step().is_none());
+    }
+
+    #[test]
+    fn test_nested_calls_are_deeper() {
+        let mut debugger =
+            start("let f = fn(n) { if (n > 0) { let m = n - 1; f(m) + 1 } else { 0 } }; f(2)");
+        debugger.step();
+
+        let step = debugger.step().unwrap();
+        assert_eq!(step.result, Ok(Some(2.into())));
+        assert_eq!(
+            step.inner
+                .iter()
+                .map(|inner| inner.depth)
+                .collect::<Vec<_>>(),
+            [1, 1, 1, 2, 2, 2, 3, 3]
+        );
+    }
+
+    #[test]
+    fn test_run_to_breakpoint() {
+        let mut debugger = start(PROGRAM);
+        debugger.set_breakpoint(3);
+        debugger.set_breakpoint(4);
+
+        let steps = debugger.run_to_breakpoint();
+        assert_eq!(
+            steps.iter().map(|step| step.index).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+        assert_eq!(debugger.position(), Some(3));
+        assert_eq!(bound(&debugger, "b"), Some(3.into()));
+        assert_eq!(bound(&debugger, "c"), None);
+
+        let steps = debugger.run_to_breakpoint();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(debugger.position(), Some(4));
+        assert_eq!(bound(&debugger, "c"), Some(6.into()));
+
+        let steps = debugger.run_to_breakpoint();
+        assert_eq!(steps[0].result, Ok(Some(10.into())));
+        assert_eq!(debugger.position(), None);
+        assert!(debugger.run_to_breakpoint().is_empty());
+
+        let mut debugger = start(PROGRAM);
+        debugger.set_breakpoint(2);
+        debugger.clear_breakpoint(2);
+        assert_eq!(debugger.run_to_breakpoint().len(), 5);
+    }
+
+    #[test]
+    fn test_stops_on_early_exit() {
+        let mut debugger = start("let a = 1; a + missing; let b = 2;");
+        debugger.step();
+
+        let step = debugger.step().unwrap();
+        assert_eq!(
+            step.result.map_err(|error| error.cause().clone()),
+            Err("identifier not found: missing".into())
+        );
+        assert_eq!(debugger.position(), None);
+        assert!(debugger.step().is_none());
+
+        let mut debugger = start("let a = 1; return a; let b = 2;");
+        let steps = debugger.run_to_breakpoint();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[1].result, Ok(Some(1.into())));
+        assert_eq!(bound(&debugger, "b"), None);
+    }
+}
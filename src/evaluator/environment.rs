@@ -1,39 +1,337 @@
-use std::collections::BTreeMap;
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+    hash::{BuildHasherDefault, Hasher},
+    rc::Rc,
+};
 
 use crate::evaluator::object::Object;
 
-#[derive(PartialEq, Eq, Debug, Clone, Ord, PartialOrd)]
-#[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
+/// Hasher in the style of rustc's FxHash, multiplying and rotating word by word, which is much
+/// faster than the default SipHash on the short names of bindings. Scripts naming their
+/// bindings to collide only slow themselves down.
+#[derive(Default)]
+struct NameHasher(u64);
+
+impl NameHasher {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+    fn add(&mut self, word: u64) {
+        self.0 = (self.0.rotate_left(5) ^ word).wrapping_mul(Self::SEED);
+    }
+}
+
+impl Hasher for NameHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, byte: u8) {
+        self.add(byte.into());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+type Names = BuildHasherDefault<NameHasher>;
+
+/// Bindings of a scope. Scopes are shared as `Rc<RefCell<Environment>>` between the code running
+/// in them and the functions defined there, so that a function sees the bindings made after it
+/// and can update the ones it captured.
+#[derive(Clone, Default)]
 pub struct Environment {
-    store: BTreeMap<String, Object>,
-    parent: Option<Box<Environment>>,
+    store: HashMap<Rc<str>, Object, Names>,
+    /// Names bound with `const` in this scope, which can't be bound again in it
+    constants: HashSet<Rc<str>, Names>,
+    parent: Option<Rc<RefCell<Environment>>>,
+    /// Read-only scope shared with other environments, looked up after the parent chain.
+    /// Bindings of it that get reassigned are copied into this scope first.
+    base: Option<Rc<RefCell<Environment>>>,
 }
 
 impl Environment {
     pub fn new() -> Self {
         Self {
-            store: BTreeMap::new(),
+            store: HashMap::default(),
+            constants: HashSet::default(),
             parent: None,
+            base: None,
+        }
+    }
+
+    /// New global scope, ready to be evaluated in.
+    pub fn shared() -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self::new()))
+    }
+
+    /// Empty scope layered over `base`, which is shared rather than cloned.
+    pub fn with_base(base: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            base: Some(base),
+            ..Self::new()
         }
     }
 
-    pub fn new_child(&self) -> Self {
+    /// Empty scope nested in `parent`, e.g. the one of a function call in the scope the function
+    /// was defined in.
+    pub fn new_child(parent: &Rc<RefCell<Environment>>) -> Self {
         Self {
-            store: BTreeMap::new(),
-            parent: Some(Box::new(self.clone())),
+            parent: Some(parent.clone()),
+            ..Self::new()
         }
     }
 
     pub fn set(&mut self, name: String, value: Object) {
-        self.store.insert(name, value);
+        self.store.insert(name.into(), value);
+    }
+
+    /// Binds `name` in the current scope, only allocating the key when it isn't already bound.
+    pub fn set_ref(&mut self, name: &str, value: Object) {
+        if let Some(slot) = self.store.get_mut(name) {
+            *slot = value;
+        } else {
+            self.store.insert(name.into(), value);
+        }
+    }
+
+    /// Binds the interned `name` in the current scope, sharing it as the key instead of
+    /// allocating one.
+    pub(crate) fn bind(&mut self, name: &Rc<str>, value: Object) {
+        self.store.insert(name.clone(), value);
+    }
+
+    /// Binds `name` in the current scope and marks it as constant.
+    pub fn set_const(&mut self, name: &str, value: Object) {
+        self.set_ref(name, value);
+        self.constants.insert(name.into());
     }
 
-    pub fn get(&self, name: &str) -> Option<&Object> {
-        let value = self.store.get(name);
-        if value.is_some() {
-            return value;
+    /// Whether `name` is a constant of the current scope, constants of parent scopes can be
+    /// shadowed.
+    pub fn is_constant(&self, name: &str) -> bool {
+        self.constants.contains(name)
+    }
+
+    /// Replaces the value of the innermost binding of `name`, refusing to touch constants.
+    pub fn assign(&mut self, name: &str, value: Object) -> Result<(), String> {
+        if let Some(slot) = self.store.get_mut(name) {
+            if self.constants.contains(name) {
+                return Err(format!("cannot reassign constant {name}"));
+            }
+            *slot = value;
+            return Ok(());
         }
 
-        self.parent.as_ref().and_then(|parent| parent.get(name))
+        if let Some(parent) = &self.parent {
+            return parent.borrow_mut().assign(name, value);
+        }
+
+        let (constant, bound) = match &self.base {
+            Some(base) => {
+                let base = base.borrow();
+                (base.is_constant(name), base.get(name).is_some())
+            }
+            None => (false, false),
+        };
+
+        if constant {
+            Err(format!("cannot reassign constant {name}"))
+        } else if bound {
+            self.set_ref(name, value);
+            Ok(())
+        } else {
+            Err(format!("identifier not found: {name}"))
+        }
+    }
+
+    /// Value of the innermost binding of `name`, walking up the parent chain.
+    pub fn get(&self, name: &str) -> Option<Object> {
+        if let Some(value) = self.store.get(name) {
+            return Some(value.clone());
+        }
+
+        match &self.parent {
+            Some(parent) => parent.borrow().get(name),
+            None => self.base.as_ref().and_then(|base| base.borrow().get(name)),
+        }
+    }
+
+    /// Names bound in this scope or an enclosing one, at most `limit` of them, gathered from the
+    /// innermost scope outwards.
+    pub fn names(&self, limit: usize) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        self.collect_names(&mut names, limit);
+        names
+    }
+
+    fn collect_names(&self, names: &mut BTreeSet<String>, limit: usize) {
+        // Sorted so that the names kept when there are more than `limit` don't depend on the
+        // order of the store
+        let mut own = self.store.keys().collect::<Vec<_>>();
+        own.sort();
+        for name in own {
+            if names.len() >= limit {
+                return;
+            }
+            names.insert(name.to_string());
+        }
+
+        match &self.parent {
+            Some(parent) => parent.borrow().collect_names(names, limit),
+            None => {
+                if let Some(base) = &self.base {
+                    base.borrow().collect_names(names, limit);
+                }
+            }
+        }
+    }
+
+    /// Applies `f` to the innermost binding of `name` in place, returning `None` if it isn't
+    /// bound in any scope.
+    pub fn update<T>(&mut self, name: &str, f: impl FnOnce(&mut Object) -> T) -> Option<T> {
+        if let Some(value) = self.store.get_mut(name) {
+            return Some(f(value));
+        }
+
+        if let Some(parent) = &self.parent {
+            return parent.borrow_mut().update(name, f);
+        }
+
+        let value = self.base.as_ref()?.borrow().get(name)?;
+        self.set_ref(name, value);
+        self.store.get_mut(name).map(f)
+    }
+}
+
+/// Only shows the names bound in the scope, as their values may be functions that captured it.
+impl fmt::Debug for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Environment")
+            .field("names", &self.store.keys().collect::<BTreeSet<_>>())
+            .field("parent", &self.parent)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Serialized without its base, which is the same for every environment built over it, with
+/// the bindings in the order of their names so that the playground shows them consistently.
+#[cfg(target_family = "wasm")]
+impl serde::Serialize for Environment {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        use std::collections::BTreeMap;
+
+        let parent = self.parent.as_ref().map(|parent| parent.borrow());
+        let mut state = serializer.serialize_struct("Environment", 3)?;
+        state.serialize_field("store", &self.store.iter().collect::<BTreeMap<_, _>>())?;
+        state.serialize_field("constants", &self.constants.iter().collect::<BTreeSet<_>>())?;
+        state.serialize_field("parent", &parent.as_deref())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_ref_overwrites_existing_binding() {
+        let mut environment = Environment::new();
+        environment.set_ref("x", 1.into());
+        environment.set_ref("x", 2.into());
+
+        assert_eq!(environment.get("x"), Some(2.into()));
+        assert_eq!(environment.store.len(), 1);
+    }
+
+    #[test]
+    fn test_update_walks_parent_chain() {
+        let parent = Environment::shared();
+        parent.borrow_mut().set_ref("x", 1.into());
+        let mut child = Environment::new_child(&parent);
+
+        let updated = child.update("x", |value| *value = 5.into());
+
+        assert_eq!(updated, Some(()));
+        assert_eq!(child.get("x"), Some(5.into()));
+        assert!(child.store.is_empty());
+        assert_eq!(parent.borrow().get("x"), Some(5.into()));
+    }
+
+    #[test]
+    fn test_update_prefers_innermost_binding() {
+        let parent = Environment::shared();
+        parent.borrow_mut().set_ref("x", 1.into());
+        let mut child = Environment::new_child(&parent);
+        child.set_ref("x", 2.into());
+
+        child.update("x", |value| *value = 3.into());
+
+        assert_eq!(child.get("x"), Some(3.into()));
+        assert_eq!(parent.borrow().get("x"), Some(1.into()));
+    }
+
+    #[test]
+    fn test_assign_respects_constants() {
+        let parent = Environment::shared();
+        parent.borrow_mut().set_const("PI", 3.into());
+        parent.borrow_mut().set_ref("x", 1.into());
+        let mut child = Environment::new_child(&parent);
+
+        assert_eq!(child.assign("x", 2.into()), Ok(()));
+        assert_eq!(parent.borrow().get("x"), Some(2.into()));
+        assert_eq!(
+            child.assign("PI", 4.into()),
+            Err("cannot reassign constant PI".into())
+        );
+        assert_eq!(
+            child.assign("y", 4.into()),
+            Err("identifier not found: y".into())
+        );
+
+        assert!(!child.is_constant("PI"));
+        child.set_ref("PI", 4.into());
+        assert_eq!(child.get("PI"), Some(4.into()));
+    }
+
+    #[test]
+    fn test_base_is_shadowed_not_mutated() {
+        let base = Environment::shared();
+        base.borrow_mut().set_ref("x", 1.into());
+        base.borrow_mut().set_const("PI", 3.into());
+
+        let mut first = Environment::with_base(base.clone());
+        let second = Rc::new(RefCell::new(Environment::with_base(base.clone())));
+        let mut second = Environment::new_child(&second);
+
+        first.update("x", |value| *value = 2.into());
+        assert_eq!(second.assign("x", 3.into()), Ok(()));
+
+        assert_eq!(first.get("x"), Some(2.into()));
+        assert_eq!(second.get("x"), Some(3.into()));
+        assert_eq!(base.borrow().get("x"), Some(1.into()));
+        assert_eq!(
+            first.assign("PI", 4.into()),
+            Err("cannot reassign constant PI".into())
+        );
+        assert_eq!(
+            first.assign("y", 4.into()),
+            Err("identifier not found: y".into())
+        );
+    }
+
+    #[test]
+    fn test_update_unbound_name() {
+        let mut environment = Environment::new_child(&Environment::shared());
+
+        assert_eq!(environment.update("x", |_| ()), None);
+        assert_eq!(environment.get("x"), None);
     }
 }
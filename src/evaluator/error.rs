@@ -0,0 +1,148 @@
+use std::fmt::{self, Display};
+
+use crate::{evaluator::object::Object, lexer::Span};
+
+/// Why an evaluation stopped before producing a value, unwinding all the way up through `?`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum EvalError {
+    /// Runtime error, e.g. an unknown identifier or mismatched operand types
+    Message(String),
+    /// Value of a `throw`
+    Thrown(Box<Object>),
+    /// Value of a `return`, from wherever it is within the expressions of a function body or of
+    /// the program, which ends there
+    Return(Box<Object>),
+    /// Code `exit` was called with, ending the program rather than the process: the embedder
+    /// decides what exiting means
+    Exit(i32),
+    /// `error`, raised within the function calls of `trace`
+    Traced {
+        error: Box<EvalError>,
+        /// Calls the error unwound out of, innermost first
+        trace: Vec<Frame>,
+    },
+}
+
+/// Function call an error unwound out of.
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
+pub struct Frame {
+    /// Name the function was called by, `<anonymous>` when it wasn't called by name
+    pub function: String,
+    /// Span of the callee at the call site
+    pub span: Span,
+}
+
+impl EvalError {
+    /// Records that the error unwound out of the call of `frame`. An exit isn't a failure of
+    /// the calls it leaves, so it doesn't record them.
+    pub(crate) fn unwound(self, frame: Frame) -> Self {
+        match self {
+            Self::Exit(code) => Self::Exit(code),
+            Self::Traced { error, mut trace } => {
+                trace.push(frame);
+                Self::Traced { error, trace }
+            }
+            error => Self::Traced {
+                error: Box::new(error),
+                trace: vec![frame],
+            },
+        }
+    }
+
+    /// The error as raised, without the calls it unwound out of.
+    pub fn cause(&self) -> &EvalError {
+        match self {
+            Self::Traced { error, .. } => error,
+            error => error,
+        }
+    }
+
+    /// Calls the error unwound out of, innermost first.
+    pub fn trace(&self) -> &[Frame] {
+        match self {
+            Self::Traced { trace, .. } => trace,
+            _ => &[],
+        }
+    }
+}
+
+/// Shown as `Error: ` followed by the message, or by the thrown value as displayed, then by one
+/// `in <function>` line per call it unwound out of, ending with `in <program>`. Consecutive calls
+/// of the same function, as in a recursion, share a line.
+impl Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Message(message) => write!(f, "Error: {message}"),
+            Self::Thrown(value) => write!(f, "Error: {value}"),
+            Self::Return(value) => write!(f, "returned {value}"),
+            Self::Exit(code) => write!(f, "exited with code {code}"),
+            Self::Traced { error, trace } => {
+                write!(f, "{error}")?;
+                for calls in trace.chunk_by(|a, b| a.function == b.function) {
+                    write!(f, "\nin {}", calls[0].function)?;
+                    if calls.len() > 1 {
+                        write!(f, " ({} times)", calls.len())?;
+                    }
+                }
+                write!(f, "\nin <program>")
+            }
+        }
+    }
+}
+
+impl From<String> for EvalError {
+    fn from(message: String) -> Self {
+        Self::Message(message)
+    }
+}
+
+impl From<&str> for EvalError {
+    fn from(message: &str) -> Self {
+        Self::Message(message.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval_error_display() {
+        assert_eq!(EvalError::from("foobar").to_string(), "Error: foobar");
+        assert_eq!(
+            EvalError::Thrown(Box::new("invalid input".into())).to_string(),
+            "Error: invalid input"
+        );
+        assert_eq!(
+            EvalError::Thrown(Box::new(vec!["a".into()].into())).to_string(),
+            r#"Error: ["a"]"#
+        );
+
+        let frame = |function: &str| Frame {
+            function: function.into(),
+            span: Span::default(),
+        };
+        let error = EvalError::from("foobar")
+            .unwound(frame("add"))
+            .unwound(frame("<anonymous>"));
+        assert_eq!(
+            error.to_string(),
+            "Error: foobar\nin add\nin <anonymous>\nin <program>"
+        );
+        assert_eq!(
+            EvalError::from("foobar")
+                .unwound(frame("sum"))
+                .unwound(frame("sum"))
+                .unwound(frame("main"))
+                .to_string(),
+            "Error: foobar\nin sum (2 times)\nin main\nin <program>"
+        );
+        assert_eq!(error.cause(), &EvalError::from("foobar"));
+        assert_eq!(error.trace(), &[frame("add"), frame("<anonymous>")]);
+
+        let exit = EvalError::Exit(3).unwound(frame("quit"));
+        assert_eq!(exit, EvalError::Exit(3));
+        assert_eq!(exit.to_string(), "exited with code 3");
+    }
+}
@@ -0,0 +1,209 @@
+use std::{cell::RefCell, ops::ControlFlow, rc::Rc};
+
+use crate::{
+    ast::{Spanned, Statement},
+    evaluator::{
+        context::EvalContext, environment::Environment, error::EvalError, object::Object, Evaluator,
+    },
+};
+
+/// How the evaluation of statements ended, telling the enclosing construct whether to carry on.
+/// Each boundary absorbs some flows and forwards the others, in one place:
+///
+/// - a program or function body absorbs a `return`, and stops on an error
+/// - a loop body goes on to the next iteration on a value, and forwards anything else
+/// - any other block forwards everything
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) enum Flow {
+    /// Completed normally, with the value of the last statement if it had one
+    Value(Option<Object>),
+    /// A `return` unwinding to the enclosing function, or to the top level
+    Return(Object),
+    /// An error, or a thrown value, unwinding all the way up
+    Error(EvalError),
+}
+
+impl Flow {
+    /// Classifies the result of evaluating a single statement, in which a `return` travels as
+    /// an [`EvalError::Return`], so that it leaves any expression it is nested in.
+    pub(crate) fn of(result: Result<Option<Object>, EvalError>) -> Self {
+        match result {
+            Ok(value) => Self::Value(value),
+            Err(EvalError::Return(value)) => Self::Return(*value),
+            Err(error) => Self::Error(error),
+        }
+    }
+
+    /// Whether the statements following this one are skipped.
+    pub(crate) fn is_early_exit(&self) -> bool {
+        !matches!(self, Self::Value(_))
+    }
+
+    /// Result at the boundary of a program or a function body, where a `return` ends.
+    pub(crate) fn absorb_return(self) -> Result<Option<Object>, EvalError> {
+        match self {
+            Self::Value(value) => Ok(value),
+            Self::Return(value) => Ok(Some(value)),
+            Self::Error(error) => Err(error),
+        }
+    }
+
+    /// Result of a block forwarding its early exit to the enclosing construct.
+    pub(crate) fn forward(self) -> Result<Option<Object>, EvalError> {
+        match self {
+            Self::Return(value) => Err(EvalError::Return(Box::new(value))),
+            flow => flow.absorb_return(),
+        }
+    }
+
+    /// Whether a loop goes on with its next iteration after its body ended with this flow, or
+    /// else what the loop evaluates to.
+    pub(crate) fn after_iteration(self) -> ControlFlow<Result<Object, EvalError>> {
+        match self {
+            Self::Value(_) => ControlFlow::Continue(()),
+            flow => ControlFlow::Break(flow.forward().map(Option::unwrap_or_default)),
+        }
+    }
+}
+
+/// Evaluates `statements` in order, up to the first one exiting early.
+pub(crate) fn eval_statements(
+    statements: &[Spanned<Statement>],
+    environment: &Rc<RefCell<Environment>>,
+    context: &mut EvalContext,
+) -> Flow {
+    let mut flow = Flow::Value(None);
+
+    for statement in statements {
+        flow = Flow::of(statement.eval_with_context(environment, context));
+        if flow.is_early_exit() {
+            break;
+        }
+    }
+
+    flow
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::*;
+
+    fn eval(input: &str) -> Result<Option<Object>, EvalError> {
+        let program = parse(input).expect("Failed to parse program");
+        program
+            .eval(&Environment::shared())
+            .map_err(|error| error.cause().clone())
+    }
+
+    #[test]
+    fn test_program_absorbs_return() {
+        assert_eq!(eval("1; return 2; 3"), Ok(Some(2.into())));
+        assert_eq!(
+            eval("if (true) { if (true) { return 2; } 3 } 4"),
+            Ok(Some(2.into()))
+        );
+        assert_eq!(
+            eval("let a = 1; a + b; 3"),
+            Err("identifier not found: b".into())
+        );
+    }
+
+    #[test]
+    fn test_top_level_return_ends_program() {
+        let tests = [
+            ("if (true) { return 1; } else { return 2; } 3", 1),
+            ("if (false) { return 1; } else { return 2; } 3", 2),
+            ("if (true) { if (true) { return 1; } 2 } 3", 1),
+            (r#"let h = {"a": if (true) { return 1; }}; 2"#, 1),
+            ("1 + if (true) { return 2; }; 3", 2),
+            ("-if (true) { return 3; }; 4", 3),
+            ("[0, if (true) { return 4; }][0]; 5", 4),
+            ("puts(if (true) { return 5; }); 6", 5),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.into())), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_return_within_expression_ends_function() {
+        let tests = [
+            ("let f = fn() { [1, if (true) { return 2; }] }; f()", 2),
+            (r#"let f = fn() { {"a": if (true) { return 3; }} }; f()"#, 3),
+            ("let f = fn() { 1 + if (true) { return 4; } }; [f()][0]", 4),
+            (
+                "let f = fn() { if (true) { if (true) { return 5; } } 6 }; f()",
+                5,
+            ),
+            (
+                "let f = fn(x) { x }; let g = fn() { f(if (true) { return 6; }) }; g()",
+                6,
+            ),
+            (
+                "let f = fn() { let a = if (true) { return 7; }; 8 }; f() + 0",
+                7,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input), Ok(Some(expected.into())), "{input}");
+        }
+
+        // The return ends the innermost function only, exactly once
+        assert_eq!(
+            eval("let f = fn() { return 1; }; let g = fn() { f() + 1 }; g()"),
+            Ok(Some(2.into()))
+        );
+    }
+
+    #[test]
+    fn test_function_absorbs_return() {
+        assert_eq!(
+            eval("let f = fn() { for (i in 0..10) { if (i == 2) { return i; } } 10 }; [f(), 5]"),
+            Ok(Some(vec![2.into(), 5.into()].into()))
+        );
+        assert_eq!(
+            eval("let f = fn() { while (true) { return 1; } }; f() + 1"),
+            Ok(Some(2.into()))
+        );
+        assert_eq!(
+            eval("let f = fn() { throw 1; 2 }; f(); 3"),
+            Err(EvalError::Thrown(Box::new(1.into())))
+        );
+    }
+
+    #[test]
+    fn test_loop_forwards_early_exits() {
+        assert_eq!(
+            eval("let i = 0; while (i < 3) { i++ }; i"),
+            Ok(Some(3.into()))
+        );
+        assert_eq!(
+            eval("for (i in 0..3) { if (i == 1) { return i * 10; } } 5"),
+            Ok(Some(10.into()))
+        );
+        assert_eq!(
+            eval("let i = 0; for (x in [1, 2, 3]) { i++; x + y; } i"),
+            Err("identifier not found: y".into())
+        );
+    }
+
+    #[test]
+    fn test_flow_of() {
+        assert_eq!(Flow::of(Ok(None)), Flow::Value(None));
+        assert_eq!(Flow::of(Ok(Some(1.into()))), Flow::Value(Some(1.into())));
+        assert_eq!(
+            Flow::of(Err(EvalError::Return(Box::new(1.into())))),
+            Flow::Return(1.into())
+        );
+        assert_eq!(Flow::of(Err("a".into())), Flow::Error(EvalError::from("a")));
+        assert_eq!(
+            Flow::Return(1.into()).forward(),
+            Err(EvalError::Return(Box::new(1.into())))
+        );
+        assert_eq!(Flow::Return(1.into()).absorb_return(), Ok(Some(1.into())));
+    }
+}
@@ -0,0 +1,465 @@
+//! `quote` and `unquote`, and the macros built on top of them, which are expanded before the
+//! program is evaluated.
+
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use crate::{
+    ast::{modify, modify_statements, Expression, Spanned, Statement},
+    evaluator::{
+        context::EvalContext,
+        environment::Environment,
+        error::{EvalError, Frame},
+        flow::eval_statements,
+        object::Object,
+        Evaluator,
+    },
+    parser::Program,
+};
+
+/// Whether calling `callee` quotes its argument, i.e. it is `quote` not bound to anything else.
+pub(crate) fn is_quote(callee: &Expression, environment: &Rc<RefCell<Environment>>) -> bool {
+    matches!(callee, Expression::Identifier(name) if &**name == "quote")
+        && environment.borrow().get("quote").is_none()
+}
+
+/// `quote(argument)`, the argument left unevaluated except for the `unquote(expression)` calls
+/// within it, replaced by the value of their expression.
+pub(crate) fn quote(
+    arguments: &[Spanned<Expression>],
+    environment: &Rc<RefCell<Environment>>,
+    context: &mut EvalContext,
+) -> Result<Object, EvalError> {
+    let [argument] = arguments else {
+        return Err(format!(
+            "wrong number of arguments. Got {}, expected 1",
+            arguments.len()
+        )
+        .into());
+    };
+
+    let quoted = modify(argument.clone(), &mut |expression| {
+        unquote(expression, environment, context)
+    })?;
+
+    Ok(Object::Quote(Box::new(quoted.node)))
+}
+
+fn unquote(
+    expression: Spanned<Expression>,
+    environment: &Rc<RefCell<Environment>>,
+    context: &mut EvalContext,
+) -> Result<Spanned<Expression>, EvalError> {
+    let Expression::FunctionCall { name, arguments } = &expression.node else {
+        return Ok(expression);
+    };
+    if !matches!(&name.node, Expression::Identifier(name) if &**name == "unquote") {
+        return Ok(expression);
+    }
+
+    let [argument] = arguments.as_slice() else {
+        return Err(format!(
+            "wrong number of arguments to unquote. Got {}, expected 1",
+            arguments.len()
+        )
+        .into());
+    };
+
+    let value = argument.eval_with_context(environment, context)?;
+    Ok(Spanned::new(into_expression(value)?, expression.span))
+}
+
+/// Expression evaluating to `value`, to be spliced into a quote.
+fn into_expression(value: Object) -> Result<Expression, EvalError> {
+    let expression = match value {
+        Object::Integer(value) => Expression::Integer(value),
+        Object::BigInt(value) => Expression::BigInt(value),
+        Object::Boolean(value) => Expression::Boolean(value),
+        Object::String(value) => Expression::String(value.to_string()),
+        Object::Null => Expression::Null,
+        Object::Quote(expression) => *expression,
+        Object::Array(elements) => Expression::Array(
+            elements
+                .iter()
+                .map(|element| into_expression(element.clone()).map(Spanned::from))
+                .collect::<Result<_, _>>()?,
+        ),
+        Object::Hash(entries) => Expression::HashLiteral(
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    Ok((
+                        into_expression(key.clone().into())?.into(),
+                        into_expression(value.clone())?.into(),
+                    ))
+                })
+                .collect::<Result<_, EvalError>>()?,
+        ),
+        Object::Range { start, end } => Expression::Range {
+            start: Box::new(start.into()),
+            end: Box::new(end.into()),
+            inclusive: false,
+        },
+        value => return Err(format!("cannot unquote {}", value.kind()).into()),
+    };
+
+    Ok(expression)
+}
+
+/// Binds the macros defined by the top-level `let` statements of `program` in `environment`,
+/// taking these statements out of the program.
+pub fn define_macros(
+    program: &mut Program,
+    environment: &Rc<RefCell<Environment>>,
+) -> Result<(), EvalError> {
+    let statements = std::mem::take(&mut program.statements);
+    let mut comments = std::mem::take(&mut program.comments);
+    let mut kept_comments = BTreeMap::<usize, Vec<String>>::new();
+
+    for (index, statement) in statements.into_iter().enumerate() {
+        // Comments before a macro definition move to the statement following it
+        if let Some(lines) = comments.remove(&index) {
+            kept_comments
+                .entry(program.statements.len())
+                .or_default()
+                .extend(lines);
+        }
+
+        let Statement::Let { name, value } = &statement.node else {
+            program.statements.push(statement);
+            continue;
+        };
+        let Expression::Macro { arguments, body } = &value.node else {
+            program.statements.push(statement);
+            continue;
+        };
+
+        if environment.borrow().is_constant(name) {
+            return Err(format!("cannot reassign constant {name}").into());
+        }
+
+        let definition = Object::Macro {
+            parameters: arguments.clone(),
+            environment: environment.clone(),
+            body: body.clone(),
+        };
+        environment.borrow_mut().bind(name, definition);
+    }
+
+    for lines in comments.into_values() {
+        kept_comments
+            .entry(program.statements.len())
+            .or_default()
+            .extend(lines);
+    }
+    program.comments = kept_comments;
+
+    Ok(())
+}
+
+/// Runs [`define_macros`] then [`expand_macros`] over `program`, as done before evaluating it.
+pub fn define_and_expand_macros(
+    mut program: Program,
+    environment: &Rc<RefCell<Environment>>,
+    context: &mut EvalContext,
+) -> Result<Program, EvalError> {
+    define_macros(&mut program, environment)?;
+    expand_macros(program, environment, context)
+}
+
+/// Replaces the calls of the macros bound in `environment` by what they return, evaluated with
+/// their arguments quoted.
+pub fn expand_macros(
+    program: Program,
+    environment: &Rc<RefCell<Environment>>,
+    context: &mut EvalContext,
+) -> Result<Program, EvalError> {
+    let statements = modify_statements(program.statements, &mut |expression| {
+        expand_call(expression, environment, context)
+    })?;
+
+    Ok(Program {
+        statements,
+        comments: program.comments,
+    })
+}
+
+fn expand_call(
+    expression: Spanned<Expression>,
+    environment: &Rc<RefCell<Environment>>,
+    context: &mut EvalContext,
+) -> Result<Spanned<Expression>, EvalError> {
+    let Expression::FunctionCall { name, arguments } = &expression.node else {
+        return Ok(expression);
+    };
+    let Expression::Identifier(name) = &name.node else {
+        return Ok(expression);
+    };
+    let definition = environment.borrow().get(name);
+    let Some(Object::Macro {
+        parameters,
+        environment: captured,
+        body,
+    }) = definition
+    else {
+        return Ok(expression);
+    };
+
+    let mut scope = Environment::new_child(&captured);
+    for (parameter, argument) in parameters.iter().zip(arguments) {
+        scope.bind(parameter, Object::Quote(Box::new(argument.node.clone())));
+    }
+
+    let frame = Frame {
+        function: name.to_string(),
+        span: expression.span,
+    };
+    let scope = Rc::new(RefCell::new(scope));
+    let expanded = eval_statements(&body.statements, &scope, context)
+        .absorb_return()
+        .map_err(|error| error.unwound(frame))?;
+
+    match expanded {
+        Some(Object::Quote(expanded)) => Ok(Spanned::new(*expanded, expression.span)),
+        expanded => Err(format!(
+            "macro {name} must return a quote, got {}",
+            expanded.unwrap_or_default().kind()
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lexer::Lexer, parse, parser::Parser};
+
+    use super::*;
+
+    fn eval(input: &str) -> Result<Option<Object>, EvalError> {
+        let environment = Environment::shared();
+        let mut context = EvalContext::default();
+        let program = parse(input).expect("Failed to parse program");
+
+        define_and_expand_macros(program, &environment, &mut context)?
+            .eval_with_context(&environment, &mut context)
+    }
+
+    fn quoted(source: &str) -> Object {
+        let program = parse(source).expect("Failed to parse program");
+        let Statement::Expression { value } = program.statements[0].node.clone() else {
+            panic!("{source} isn't an expression");
+        };
+        Object::Quote(Box::new(value.node))
+    }
+
+    #[test]
+    fn test_quote() {
+        let tests = [
+            ("quote(5)", "5"),
+            ("quote(5 + 8)", "(5 + 8)"),
+            ("quote(foobar)", "foobar"),
+            ("quote(foobar + barfoo)", "(foobar + barfoo)"),
+            ("quote(fn(x) { x }(1))", "fn(x) {x}(1)"),
+        ];
+
+        for (input, expected) in tests {
+            let value = eval(input).unwrap().unwrap();
+
+            assert_eq!(value, quoted(expected), "{input}");
+            assert_eq!(value.to_string(), format!("QUOTE({expected})"));
+        }
+    }
+
+    #[test]
+    fn test_quote_unquote() {
+        let tests = [
+            ("quote(unquote(4))", "4"),
+            ("quote(unquote(4 + 4))", "8"),
+            ("quote(8 + unquote(4 + 4))", "(8 + 8)"),
+            ("quote(unquote(4 + 4) + 8)", "(8 + 8)"),
+            ("let foobar = 8; quote(foobar)", "foobar"),
+            ("let foobar = 8; quote(unquote(foobar))", "8"),
+            ("quote(unquote(true))", "true"),
+            ("quote(unquote(true == false))", "false"),
+            ("quote(unquote(quote(4 + 4)))", "(4 + 4)"),
+            (
+                "let quoted = quote(4 + 4); quote(unquote(4 + 4) + unquote(quoted))",
+                "(8 + (4 + 4))",
+            ),
+            (r#"quote(unquote("a" + "b"))"#, r#""ab""#),
+            ("quote(unquote(null))", "null"),
+            (
+                r#"quote(unquote([1, "a", {true: 0..2}]))"#,
+                r#"[1, "a", {true: (0..2)}]"#,
+            ),
+            (
+                "quote(unquote(9223372036854775807 + 1))",
+                "9223372036854775808",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map(|value| value.map(|value| value.to_string())),
+                Ok(Some(format!("QUOTE({expected})"))),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_quote_errors() {
+        let tests = [
+            ("quote()", "wrong number of arguments. Got 0, expected 1"),
+            (
+                "quote(1, 2)",
+                "wrong number of arguments. Got 2, expected 1",
+            ),
+            (
+                "quote(unquote(1, 2))",
+                "wrong number of arguments to unquote. Got 2, expected 1",
+            ),
+            ("quote(unquote(fn(x) { x }))", "cannot unquote FUNCTION"),
+            ("quote(unquote(missing))", "identifier not found: missing"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map_err(|error| error.cause().clone()),
+                Err(expected.into()),
+                "{input}"
+            );
+        }
+
+        // Only special while `quote` isn't rebound
+        assert_eq!(
+            eval("let quote = fn(x) { x * 2 }; quote(2)"),
+            Ok(Some(4.into()))
+        );
+    }
+
+    #[test]
+    fn test_define_macros() {
+        let input = r#"
+            let number = 1;
+            let function = fn(x, y) { x + y };
+            // Defines a macro
+            let mymacro = macro(x, y) { x + y; };
+            number
+        "#;
+        let environment = Environment::shared();
+        let mut program = Parser::new(Lexer::new(input.into()))
+            .with_comments()
+            .parse_program()
+            .expect("Failed to parse program");
+
+        define_macros(&mut program, &environment).unwrap();
+
+        assert_eq!(program.statements.len(), 3);
+        assert_eq!(
+            program.comments,
+            BTreeMap::from([(2, vec![" Defines a macro".to_string()])])
+        );
+        assert_eq!(environment.borrow().get("number"), None);
+        assert_eq!(environment.borrow().get("function"), None);
+
+        let Some(Object::Macro {
+            parameters, body, ..
+        }) = environment.borrow().get("mymacro")
+        else {
+            panic!("mymacro isn't a macro");
+        };
+        assert_eq!(parameters, ["x".into(), "y".into()]);
+        assert_eq!(body.to_string(), "(x + y)");
+
+        let environment = Environment::shared();
+        environment.borrow_mut().set_const("m", 1.into());
+        assert_eq!(
+            define_macros(&mut parse("let m = macro() { 1 };").unwrap(), &environment),
+            Err("cannot reassign constant m".into())
+        );
+    }
+
+    #[test]
+    fn test_expand_macros() {
+        let tests = [
+            (
+                "let infixExpression = macro() { quote(1 + 2); }; infixExpression();",
+                "(1 + 2)",
+            ),
+            (
+                "let reverse = macro(a, b) { quote(unquote(b) - unquote(a)); }; reverse(2 + 2, 10 - 5);",
+                "((10 - 5) - (2 + 2))",
+            ),
+            (
+                r#"
+                let unless = macro(condition, consequence, alternative) {
+                    quote(if (!(unquote(condition))) {
+                        unquote(consequence);
+                    } else {
+                        unquote(alternative);
+                    });
+                };
+                unless(10 > 5, puts("not greater"), puts("greater"));
+                "#,
+                r#"if ((!(10 > 5))) {puts("not greater")}else {puts("greater")}"#,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let environment = Environment::shared();
+            let mut program = parse(input).expect("Failed to parse program");
+
+            define_macros(&mut program, &environment).unwrap();
+            let expanded =
+                expand_macros(program, &environment, &mut EvalContext::default()).unwrap();
+
+            assert_eq!(expanded.to_string(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_unless_macro() {
+        let input = r#"
+            let unless = macro(condition, consequence, alternative) {
+                quote(if (!(unquote(condition))) {
+                    unquote(consequence);
+                } else {
+                    unquote(alternative);
+                });
+            };
+
+            unless(10 > 5, "not greater", "greater")
+        "#;
+
+        assert_eq!(eval(input), Ok(Some("greater".into())));
+    }
+
+    #[test]
+    fn test_expand_macros_errors() {
+        let tests = [
+            (
+                "let m = macro() { 1 }; m()",
+                "macro m must return a quote, got INTEGER",
+            ),
+            (
+                "let m = macro() { 1 + true }; m()",
+                "type mismatch: INTEGER + BOOLEAN in '(1 + true)'",
+            ),
+            (
+                "let m = fn() { macro() { quote(1) } }(); m()",
+                "not a function: m is MACRO",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                eval(input).map_err(|error| error.cause().clone()),
+                Err(expected.into()),
+                "{input}"
+            );
+        }
+
+        let error = eval("let m = macro() { 1 + true }; m()").unwrap_err();
+        assert_eq!(error.trace()[0].function, "m");
+    }
+}
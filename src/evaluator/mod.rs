@@ -1,340 +1,995 @@
-use std::collections::BTreeMap;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ops::{ControlFlow, Range},
+    rc::Rc,
+};
 
 use crate::{
-    ast::{BlockStatement, Expression, Statement},
+    ast::{describe, BlockStatement, Expression, Pattern, Spanned, Statement},
+    bigint::BigInt,
     evaluator::{
+        context::EvalContext,
         environment::Environment,
-        object::{Object, FALSE, NULL, TRUE},
+        error::{EvalError, Frame},
+        flow::{eval_statements, Flow},
+        object::{HashKey, Object, NULL},
     },
-    lexer::Token,
+    lexer::{Span, Token},
     parser::Program,
 };
 
-mod builtins;
+pub(crate) mod builtins;
+pub mod context;
+pub mod coverage;
+pub mod debugger;
 pub mod environment;
+pub mod error;
+mod flow;
+pub mod macros;
 pub mod object;
+pub mod observer;
+pub mod profiler;
+pub mod sink;
+mod suggest;
 
 pub trait Evaluator {
-    fn eval(&self, environment: &mut Environment) -> Option<Object>;
+    /// What a successful evaluation yields: an expression always has a value, while a
+    /// statement or a sequence of them may not.
+    type Output;
+
+    fn eval_with_context(
+        &self,
+        environment: &Rc<RefCell<Environment>>,
+        context: &mut EvalContext,
+    ) -> Result<Self::Output, EvalError>;
+
+    /// Evaluates with the default, unlimited, options.
+    fn eval(&self, environment: &Rc<RefCell<Environment>>) -> Result<Self::Output, EvalError> {
+        self.eval_with_context(environment, &mut EvalContext::default())
+    }
 }
 
 impl Evaluator for Program {
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        let mut result = None;
-
-        for statement in self.statements.iter() {
-            result = statement.eval(environment);
+    type Output = Option<Object>;
+
+    fn eval_with_context(
+        &self,
+        environment: &Rc<RefCell<Environment>>,
+        context: &mut EvalContext,
+    ) -> Result<Option<Object>, EvalError> {
+        eval_statements(&self.statements, environment, context).absorb_return()
+    }
+}
 
-            if let Some(Object::Return(value)) = result {
-                return Some(*value);
-            } else if matches!(result, Some(Object::Error(_))) {
-                return result;
-            }
+impl Program {
+    /// Evaluates the top-level statements one at a time, yielding each statement alongside its
+    /// result as soon as it completes, with its output flushed. Iteration stops after a `return`
+    /// or an error.
+    pub fn eval_statement_by_statement<'a>(
+        &'a self,
+        environment: &'a Rc<RefCell<Environment>>,
+        context: &'a mut EvalContext,
+    ) -> StatementResults<'a> {
+        StatementResults {
+            statements: self.statements.iter(),
+            environment,
+            context,
+            done: false,
         }
-        result
     }
 }
 
-impl Evaluator for Vec<Statement> {
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        let mut result = None;
+pub struct StatementResults<'a> {
+    statements: std::slice::Iter<'a, Spanned<Statement>>,
+    environment: &'a Rc<RefCell<Environment>>,
+    context: &'a mut EvalContext,
+    done: bool,
+}
 
-        for statement in self {
-            result = statement.eval(environment);
+impl<'a> Iterator for StatementResults<'a> {
+    type Item = (&'a Spanned<Statement>, Result<Option<Object>, EvalError>);
 
-            if matches!(result, Some(Object::Return(_) | Object::Error(_))) {
-                return result;
-            }
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
         }
 
-        result
+        let statement = self.statements.next()?;
+        let flow = Flow::of(statement.eval_with_context(self.environment, self.context));
+        self.done = flow.is_early_exit();
+        let result = flow.absorb_return();
+        // Whatever the statement printed is complete by the time its result is yielded
+        let _ = self.context.output.flush();
+
+        Some((statement, result))
+    }
+}
+
+impl Evaluator for Spanned<Expression> {
+    type Output = Object;
+
+    fn eval_with_context(
+        &self,
+        environment: &Rc<RefCell<Environment>>,
+        context: &mut EvalContext,
+    ) -> Result<Object, EvalError> {
+        self.node.eval_with_context(environment, context)
+    }
+}
+
+impl Evaluator for Vec<Spanned<Statement>> {
+    type Output = Option<Object>;
+
+    fn eval_with_context(
+        &self,
+        environment: &Rc<RefCell<Environment>>,
+        context: &mut EvalContext,
+    ) -> Result<Option<Object>, EvalError> {
+        eval_statements(self, environment, context).forward()
     }
 }
 
+/// Evaluates the block in a scope of its own nested in `environment`, so that its bindings end
+/// with it while the outer ones stay visible, and updatable.
 impl Evaluator for BlockStatement {
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
-        self.statements.eval(environment)
+    type Output = Option<Object>;
+
+    fn eval_with_context(
+        &self,
+        environment: &Rc<RefCell<Environment>>,
+        context: &mut EvalContext,
+    ) -> Result<Option<Object>, EvalError> {
+        let scope = Rc::new(RefCell::new(Environment::new_child(environment)));
+        self.statements.eval_with_context(&scope, context)
+    }
+}
+
+impl Evaluator for Spanned<Statement> {
+    type Output = Option<Object>;
+
+    fn eval_with_context(
+        &self,
+        environment: &Rc<RefCell<Environment>>,
+        context: &mut EvalContext,
+    ) -> Result<Option<Object>, EvalError> {
+        self.enter(environment, context)?;
+        self.node.eval_node(environment, context)
+    }
+}
+
+impl Spanned<Statement> {
+    /// Counts the statement as a step and reports it to the observer, before evaluating it.
+    fn enter(
+        &self,
+        environment: &Rc<RefCell<Environment>>,
+        context: &mut EvalContext,
+    ) -> Result<(), EvalError> {
+        context.step()?;
+        context.observe(|observer| observer.on_statement(self, &environment.borrow()));
+        Ok(())
     }
 }
 
-impl Evaluator for Statement {
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
+impl Statement {
+    fn eval_node(
+        &self,
+        environment: &Rc<RefCell<Environment>>,
+        context: &mut EvalContext,
+    ) -> Result<Option<Object>, EvalError> {
         match self {
-            Statement::Expression { value } => value.eval(environment),
+            Statement::Expression { value } => {
+                value.eval_with_context(environment, context).map(Some)
+            }
+            Statement::Throw { value } => {
+                let value = value.eval_with_context(environment, context)?;
+                Err(EvalError::Thrown(Box::new(value)))
+            }
             Statement::Return { value } => {
-                let value = value.eval(environment);
-                if matches!(value, Some(Object::Error(_))) {
-                    value
-                } else {
-                    value.map(Box::new).map(Object::Return)
-                }
+                let value = value.eval_with_context(environment, context)?;
+                Err(EvalError::Return(Box::new(value)))
             }
             Statement::Let { name, value } => {
-                let value = value.eval(environment)?;
-                if matches!(value, Object::Error(_)) {
-                    return Some(value);
+                let value = value.eval_with_context(environment, context)?;
+
+                if environment.borrow().is_constant(name) {
+                    return Err(format!("cannot reassign constant {name}").into());
+                }
+
+                environment.borrow_mut().bind(name, value.clone());
+
+                Ok(Some(value))
+            }
+            Statement::Const { name, value } => {
+                let value = value.eval_with_context(environment, context)?;
+
+                if environment.borrow().is_constant(name) {
+                    return Err(format!("cannot reassign constant {name}").into());
+                }
+
+                environment.borrow_mut().set_const(name, value.clone());
+
+                Ok(Some(value))
+            }
+            Statement::Destructure { pattern, value } => {
+                let value = value.eval_with_context(environment, context)?;
+                eval_destructure(environment, pattern, value)?;
+
+                Ok(None)
+            }
+        }
+    }
+}
+
+fn eval_destructure(
+    environment: &Rc<RefCell<Environment>>,
+    pattern: &Pattern,
+    value: Object,
+) -> Result<(), EvalError> {
+    match (pattern, value) {
+        (Pattern::Hash(bindings), Object::Hash(map)) => {
+            for (key, binding) in bindings {
+                if environment.borrow().is_constant(binding) {
+                    return Err(format!("cannot reassign constant {binding}").into());
                 }
 
-                environment.set(name.clone(), value);
+                let value = map
+                    .get(&HashKey::from(key.as_str()))
+                    .cloned()
+                    .unwrap_or_default();
+                environment.borrow_mut().bind(binding, value);
+            }
+
+            Ok(())
+        }
+        (Pattern::Hash(_), value) => {
+            Err(format!("cannot destructure {} as a hash", value.kind()).into())
+        }
+        (Pattern::Array(bindings), Object::Array(elements)) => {
+            if bindings.len() != elements.len() {
+                return Err(format!(
+                    "cannot destructure an array of {} elements into {} names",
+                    elements.len(),
+                    bindings.len()
+                )
+                .into());
+            }
+
+            for (binding, value) in bindings.iter().zip(elements.iter()) {
+                if environment.borrow().is_constant(binding) {
+                    return Err(format!("cannot reassign constant {binding}").into());
+                }
 
-                None
+                environment.borrow_mut().bind(binding, value.clone());
             }
+
+            Ok(())
+        }
+        (Pattern::Array(_), value) => {
+            Err(format!("cannot destructure {} as an array", value.kind()).into())
         }
     }
 }
 
 impl Evaluator for Expression {
-    fn eval(&self, environment: &mut Environment) -> Option<Object> {
+    type Output = Object;
+
+    fn eval_with_context(
+        &self,
+        environment: &Rc<RefCell<Environment>>,
+        context: &mut EvalContext,
+    ) -> Result<Object, EvalError> {
+        context.step()?;
+        let start = context.profile_start();
+        let value = context.deeper(|context| self.eval_node(environment, context));
+        context.profile(start, |profiler, time| {
+            profiler.expression(self.kind(), time)
+        });
+        let value = value?;
+        context.observe(|observer| observer.on_expression_result(self, &value));
+        Ok(value)
+    }
+}
+
+impl Expression {
+    fn eval_node(
+        &self,
+        environment: &Rc<RefCell<Environment>>,
+        context: &mut EvalContext,
+    ) -> Result<Object, EvalError> {
         match self {
-            Expression::Integer(value) => Some((*value).into()),
-            Expression::Boolean(value) => Some((*value).into()),
-            Expression::String(value) => Some(value.clone().into()),
+            Expression::Integer(value) => Ok((*value).into()),
+            Expression::BigInt(value) => Ok(value.clone().into()),
+            Expression::Boolean(value) => Ok((*value).into()),
+            Expression::Null => Ok(NULL),
+            Expression::String(value) => Ok(value.clone().into()),
             Expression::Identifier(name) => {
-                if let Some(value) = environment.get(name) {
-                    Some(value.clone())
+                let value = environment.borrow().get(name);
+                if let Some(value) = value {
+                    Ok(value)
+                } else if let Some(builtin) = builtins::Builtin::from_str(name) {
+                    Ok(builtin.get())
                 } else {
-                    Some(Object::Error(format!("identifier not found: {}", name)))
+                    Err(suggest::not_found("identifier", name, &environment.borrow()).into())
                 }
             }
             Expression::PrefixOperator {
                 operator,
                 expression,
             } => {
-                let value = expression.eval(environment)?;
-                if matches!(value, Object::Error(_)) {
-                    return Some(value);
-                }
-                Some(eval_prefix_expression(operator, value))
+                let value = expression.eval_with_context(environment, context)?;
+                in_expression(eval_prefix_expression(operator, value), self)
             }
             Expression::InfixOperator {
                 operator,
                 lh_expression,
                 rh_expression,
             } => {
-                let lh_value = lh_expression.eval(environment)?;
-                if matches!(lh_value, Object::Error(_)) {
-                    return Some(lh_value);
-                }
-                let rh_value = rh_expression.eval(environment)?;
-                if matches!(rh_value, Object::Error(_)) {
-                    return Some(rh_value);
+                let lh_value = lh_expression.eval_with_context(environment, context)?;
+                if *operator == Token::DoubleQuestion {
+                    if lh_value != NULL {
+                        return Ok(lh_value);
+                    }
+                    return rh_expression.eval_with_context(environment, context);
                 }
-                Some(eval_infix_expression(operator, lh_value, rh_value))
+                let rh_value = rh_expression.eval_with_context(environment, context)?;
+                context.track(in_expression(
+                    eval_infix_expression(operator, lh_value, rh_value),
+                    self,
+                )?)
             }
             Expression::If {
                 condition,
                 consequence,
                 alternative,
             } => {
-                let condition = condition.eval(environment)?;
-                if matches!(condition, Object::Error(_)) {
-                    return Some(condition);
-                }
-                if condition.is_truthy() {
-                    consequence.eval(environment)
+                let condition = condition.eval_with_context(environment, context)?;
+                let value = if condition.is_truthy() {
+                    consequence.eval_with_context(environment, context)?
                 } else if let Some(alternative) = alternative {
-                    alternative.eval(environment)
+                    alternative.eval_with_context(environment, context)?
                 } else {
-                    Some(NULL)
+                    None
+                };
+
+                Ok(value.unwrap_or_default())
+            }
+            Expression::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let start = start.eval_with_context(environment, context)?;
+                let end = end.eval_with_context(environment, context)?;
+
+                match (start, end) {
+                    // Past the largest integer, an inclusive end has no exclusive equivalent
+                    (Object::Integer(start), Object::Integer(end)) if *inclusive => {
+                        match end.checked_add(1) {
+                            Some(end) => Ok(Object::Range { start, end }),
+                            None => Err(out_of_range(&end.into(), "an inclusive range end")),
+                        }
+                    }
+                    (Object::Integer(start), Object::Integer(end)) => {
+                        Ok(Object::Range { start, end })
+                    }
+                    (Object::BigInt(bound), Object::Integer(_) | Object::BigInt(_))
+                    | (Object::Integer(_), Object::BigInt(bound)) => {
+                        Err(out_of_range(&bound, "a range bound"))
+                    }
+                    (start, end) => Err(format!(
+                        "range bounds must be integers, got {}..{}",
+                        start.kind(),
+                        end.kind()
+                    )
+                    .into()),
                 }
             }
-            Expression::Function { arguments, body } => Some(Object::Function {
-                parameters: arguments.clone(),
-                environment: environment.clone(),
-                body: body.clone(),
-            }),
-            Expression::FunctionCall { name, arguments } => match *name.clone() {
-                Expression::Identifier(name) => {
-                    if let Some(Object::Function {
-                        parameters,
-                        environment: inner_env,
+            Expression::For {
+                variable,
+                iterable,
+                body,
+            } => {
+                let iterable = iterable.eval_with_context(environment, context)?;
+
+                match iterable {
+                    Object::Range { start, end } => eval_for(
+                        environment,
+                        context,
+                        variable,
+                        (start..end).map(Object::Integer),
                         body,
-                    }) = environment.get(&name)
-                    {
-                        return eval_function(
-                            inner_env,
-                            &mut environment.clone(),
-                            Some(&name),
-                            parameters,
-                            arguments,
-                            body,
-                        );
-                    }
+                    ),
+                    Object::Array(elements) => eval_for(
+                        environment,
+                        context,
+                        variable,
+                        elements.iter().cloned(),
+                        body,
+                    ),
+                    iterable => Err(format!("cannot iterate over {}", iterable.kind()).into()),
+                }
+            }
+            Expression::While { condition, body } => loop {
+                if !condition
+                    .eval_with_context(environment, context)?
+                    .is_truthy()
+                {
+                    break Ok(NULL);
+                }
 
-                    if let Some(builtin) = builtins::Builtin::from_str(&name) {
-                        if let Object::Builtin(function) = builtin.get() {
-                            let evaluated_arguments = arguments
-                                .iter()
-                                .map(|argument| argument.eval(environment))
-                                .collect::<Option<Vec<Object>>>()?;
-                            return function(&evaluated_arguments);
-                        }
+                let scope = Rc::new(RefCell::new(Environment::new_child(environment)));
+                let flow = eval_statements(&body.statements, &scope, context);
+                if let ControlFlow::Break(value) = flow.after_iteration() {
+                    return value;
+                }
+            },
+            Expression::Postfix { operator, name } => {
+                let value = environment.borrow().get(name);
+                let value = match value {
+                    Some(value @ (Object::Integer(_) | Object::BigInt(_))) => value,
+                    Some(value) => {
+                        return Err(format!(
+                            "unknown operator: {}{operator} in '{self}'",
+                            value.kind()
+                        )
+                        .into())
                     }
+                    None => {
+                        return Err(
+                            suggest::not_found("identifier", name, &environment.borrow()).into(),
+                        )
+                    }
+                };
+
+                let operator = match operator {
+                    Token::Increment => Token::PlusSign,
+                    _ => Token::MinusSign,
+                };
+                let updated = eval_infix_expression(&operator, value.clone(), 1.into())?;
 
-                    Some(Object::Error(format!("function not found: {}", name)))
+                environment.borrow_mut().assign(name, updated)?;
+
+                Ok(value)
+            }
+            Expression::Function { arguments, body } => Ok(Object::Function {
+                parameters: arguments.as_slice().into(),
+                environment: environment.clone(),
+                body: Rc::new(body.clone()),
+            }),
+            Expression::Macro { arguments, body } => Ok(Object::Macro {
+                parameters: arguments.clone(),
+                environment: environment.clone(),
+                body: body.clone(),
+            }),
+            Expression::FunctionCall { name, arguments } => {
+                if macros::is_quote(name, environment) {
+                    return macros::quote(arguments, environment, context);
                 }
-                Expression::Function {
-                    arguments: parameters,
-                    body,
-                } => eval_function(
-                    &Environment::new(),
-                    environment,
-                    None,
-                    &parameters,
-                    arguments,
-                    &body,
-                ),
-                _ => None,
-            },
-            Expression::Array(elements) => Some(Object::Array(
-                elements
+
+                let call = eval_call(name, arguments, environment, context)?;
+                context.nested(|context| apply(call, context))
+            }
+            Expression::Array(elements) => {
+                let elements = elements
                     .iter()
-                    .map(|element| element.eval(environment))
-                    .collect::<Option<Vec<Object>>>()?,
-            )),
-            Expression::Index { left, index } => {
-                let left = left.eval(environment)?;
-                if matches!(left, Object::Error(_)) {
-                    return Some(left);
-                }
+                    .map(|element| element.eval_with_context(environment, context))
+                    .collect::<Result<Vec<_>, _>>()?;
 
-                let index = index.eval(environment)?;
-                if matches!(index, Object::Error(_)) {
-                    return Some(index);
+                context.track(elements.into())
+            }
+            Expression::Index { left, index } | Expression::OptionalIndex { left, index } => {
+                let left = left.eval_with_context(environment, context)?;
+
+                if left == NULL && matches!(self, Expression::OptionalIndex { .. }) {
+                    return Ok(NULL);
                 }
 
+                let index = index.eval_with_context(environment, context)?;
+
                 if let (Object::Array(array), Object::Integer(index)) = (&left, &index) {
-                    Some(array.get(*index as usize).cloned().unwrap_or_default())
+                    let element = position(*index, array.len()).map(|index| &array[index]);
+                    Ok(element.cloned().unwrap_or_default())
+                } else if let (Object::Array(array), Object::Range { start, end }) = (&left, &index)
+                {
+                    context.track(slice(array, *start, *end).into())
+                } else if let (Object::String(string), Object::Integer(index)) = (&left, &index) {
+                    // By character rather than by byte, as `len` counts them, so that it can't
+                    // fall within one
+                    let character = position(*index, string.chars().count())
+                        .and_then(|index| string.chars().nth(index));
+                    Ok(character.map_or(NULL, |character| character.to_string().into()))
+                } else if let (Object::Array(_) | Object::String(_), Object::BigInt(index)) =
+                    (&left, &index)
+                {
+                    Err(out_of_range(index, "an index"))
                 } else if let Object::Hash(map) = &left {
-                    Some(map.get(&index).cloned().unwrap_or_default())
+                    let key = HashKey::try_from(&index)?;
+                    Ok(map.get(&key).cloned().unwrap_or_default())
                 } else {
-                    Some(Object::Error(format!(
+                    Err(format!(
                         "index operator not supported: {} With index of: {}",
                         left.kind(),
                         index.kind(),
-                    )))
+                    )
+                    .into())
                 }
             }
             Expression::HashLiteral(map) => {
-                let mut expression_map = BTreeMap::new();
+                let mut expression_map = HashMap::new();
 
                 for (key, value) in map {
-                    let evaluated_key = key.eval(environment)?;
-                    if matches!(evaluated_key, Object::Error(_)) {
-                        return Some(evaluated_key);
-                    }
-
-                    let evaluated_value = value.eval(environment)?;
-                    if matches!(evaluated_value, Object::Error(_)) {
-                        return Some(evaluated_value);
-                    }
+                    let evaluated_key = key.eval_with_context(environment, context)?;
+                    let evaluated_key = HashKey::try_from(&evaluated_key)?;
+                    let evaluated_value = value.eval_with_context(environment, context)?;
 
                     expression_map.insert(evaluated_key, evaluated_value);
                 }
 
-                Some(expression_map.into())
+                context.track(expression_map.into())
             }
         }
     }
 }
 
-fn eval_function(
-    fn_environment: &Environment,
-    outer_environment: &mut Environment,
-    name: Option<&str>,
-    parameters: &[String],
-    arguments: &[Expression],
+/// Evaluates `body` once per item, in a scope of its own for each iteration in which the item is
+/// bound to `variable`.
+fn eval_for(
+    environment: &Rc<RefCell<Environment>>,
+    context: &mut EvalContext,
+    variable: &Rc<str>,
+    items: impl Iterator<Item = Object>,
     body: &BlockStatement,
-) -> Option<Object> {
-    let mut environment = fn_environment.new_child();
+) -> Result<Object, EvalError> {
+    for item in items {
+        let mut scope = Environment::new_child(environment);
+        scope.bind(variable, item);
+
+        let flow = eval_statements(&body.statements, &Rc::new(RefCell::new(scope)), context);
+        if let ControlFlow::Break(value) = flow.after_iteration() {
+            return value;
+        }
+    }
+
+    Ok(NULL)
+}
+
+/// Position of `index` in a sequence of `length` elements, negative ones counting from its end
+/// so that `-1` is the last element, or `None` when it is out of the sequence.
+fn position(index: i64, length: usize) -> Option<usize> {
+    let index = if index < 0 {
+        index + length as i64
+    } else {
+        index
+    };
+    usize::try_from(index).ok().filter(|index| *index < length)
+}
+
+/// Error for an integer used where a machine-sized one is needed, as `what`, but too large for it.
+fn out_of_range(value: &BigInt, what: &str) -> EvalError {
+    format!("integer {value} out of range for {what}").into()
+}
+
+/// Positions of a sequence of `length` elements within `start..end`, empty when the range is
+/// reversed. Negative bounds count from the end, like indices do, and out of range ones are
+/// clamped to the sequence.
+fn slice_positions(start: i64, end: i64, length: usize) -> Range<usize> {
+    let length = length as i64;
+    let from_end = |bound: i64| if bound < 0 { bound + length } else { bound };
+    let start = from_end(start).clamp(0, length);
+    let end = from_end(end).clamp(start, length);
+
+    start as usize..end as usize
+}
+
+/// Elements of `array` whose index is within `start..end`.
+fn slice(array: &[Object], start: i64, end: i64) -> &[Object] {
+    &array[slice_positions(start, end, array.len())]
+}
+
+/// A call with its callee and arguments evaluated, ready to be applied.
+struct Call {
+    function: Object,
+    arguments: Vec<Object>,
+    /// Recorded on the errors unwinding out of the call
+    frame: Frame,
+}
+
+/// Value of a function body, unless it ends with a call, which is then left to the caller so
+/// that tail calls run in a loop rather than growing the stack.
+enum Tail {
+    Value(Object),
+    Call(Call),
+}
+
+/// Evaluates the callee and the `arguments` of a call, without applying it.
+fn eval_call(
+    callee: &Spanned<Expression>,
+    arguments: &[Spanned<Expression>],
+    environment: &Rc<RefCell<Environment>>,
+    context: &mut EvalContext,
+) -> Result<Call, EvalError> {
+    let function = match &callee.node {
+        Expression::Identifier(name) => {
+            let value = environment.borrow().get(name);
+            match value {
+                Some(value @ (Object::Function { .. } | Object::Builtin(_))) => value,
+                Some(value) => {
+                    return Err(format!("not a function: {name} is {}", value.kind()).into())
+                }
+                None => match builtins::Builtin::from_str(name) {
+                    Some(builtin) => builtin.get(),
+                    None => {
+                        return Err(
+                            suggest::not_found("function", name, &environment.borrow()).into()
+                        )
+                    }
+                },
+            }
+        }
+        callee => callee.eval_with_context(environment, context)?,
+    };
+
+    let arguments = arguments
+        .iter()
+        .map(|argument| argument.eval_with_context(environment, context))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    for (param, expression) in parameters.iter().zip(arguments) {
-        let value = expression.eval(outer_environment)?;
-        if matches!(value, Object::Error(_)) {
-            return Some(value);
+    let function_name = match &callee.node {
+        Expression::Identifier(name) => name.to_string(),
+        _ => "<anonymous>".into(),
+    };
+
+    Ok(Call {
+        function,
+        arguments,
+        frame: Frame {
+            function: function_name,
+            span: callee.span,
+        },
+    })
+}
+
+/// Applies `function`, written in Monkey or a builtin, to `arguments` on behalf of a builtin,
+/// such as the comparator given to `sort`. Not being called by name, it shows up as
+/// `<anonymous>` in the traces of the errors unwinding out of it, followed by the builtin.
+fn call_function(
+    function: &Object,
+    arguments: Vec<Object>,
+    context: &mut EvalContext,
+) -> Result<Object, EvalError> {
+    let call = Call {
+        function: function.clone(),
+        arguments,
+        frame: Frame {
+            function: "<anonymous>".into(),
+            span: Span::default(),
+        },
+    };
+    context.nested(|context| apply(call, context))
+}
+
+/// Most frames of the calls replaced by tail calls kept for the trace of an error, the ones
+/// after them being counted instead, so that a loop written as a tail recursion runs in constant
+/// memory.
+const MAX_TAIL_FRAMES: usize = 64;
+
+/// Applies `call`, then each call its function ends with in turn.
+fn apply(mut call: Call, context: &mut EvalContext) -> Result<Object, EvalError> {
+    // Frames of the calls replaced by tail calls, outermost first, for the trace of an error
+    let mut replaced = Vec::new();
+    let mut elided = 0;
+
+    loop {
+        let (parameters, captured, body) = match &call.function {
+            Object::Function {
+                parameters,
+                environment,
+                body,
+            } => (parameters, environment, body),
+            Object::Builtin(function) => {
+                let start = context.profile_start();
+                let result = function(&call.arguments, context);
+                context.profile(start, |profiler, time| {
+                    profiler.builtin(&call.frame.function, time)
+                });
+                return match result {
+                    Ok(value) => context.track(value),
+                    // Unwound out of a function the builtin called, such as the callback of `map`
+                    Err(error @ EvalError::Traced { .. }) => Err(unwind_tail_calls(
+                        error.unwound(call.frame),
+                        replaced,
+                        elided,
+                    )),
+                    Err(error) => Err(unwind_tail_calls(error, replaced, elided)),
+                };
+            }
+            value => return Err(format!("not a function: {}", value.kind()).into()),
+        };
+
+        context.observe(|observer| observer.on_call(&call.frame, &call.arguments));
+
+        let mut environment = Environment::new_child(captured);
+        for (parameter, argument) in parameters.iter().zip(call.arguments) {
+            environment.bind(parameter, argument);
+        }
+
+        let environment = Rc::new(RefCell::new(environment));
+        let start = context.profile_start();
+        let result = eval_body(&body.statements, &environment, context);
+        context.profile(start, |profiler, time| {
+            profiler.function(&call.frame.function, time)
+        });
+        context.observe(|observer| observer.on_return(&call.frame));
+
+        match result {
+            Ok(Tail::Value(value)) => return Ok(value),
+            Ok(Tail::Call(next)) => {
+                if replaced.len() < MAX_TAIL_FRAMES {
+                    replaced.push(call.frame);
+                } else {
+                    elided += 1;
+                }
+                call = next;
+            }
+            // A `return` nested in an expression, e.g. an argument of the call the body ends with
+            Err(EvalError::Return(value)) => return Ok(*value),
+            Err(error) => {
+                return Err(unwind_tail_calls(
+                    error.unwound(call.frame),
+                    replaced,
+                    elided,
+                ))
+            }
         }
-        environment.set(param.to_string(), value);
     }
+}
 
-    if let Some(name) = name {
-        environment.set(
-            name.to_string(),
-            outer_environment.get(name).unwrap().clone(),
-        );
+/// Records that `error` also unwound out of the calls `replaced` by tail calls, outermost first,
+/// and out of the `elided` ones made after them.
+fn unwind_tail_calls(error: EvalError, replaced: Vec<Frame>, elided: usize) -> EvalError {
+    let error = match elided {
+        0 => error,
+        elided => error.unwound(Frame {
+            function: format!("<{elided} frames elided by tail calls>"),
+            span: Span::default(),
+        }),
+    };
+    replaced.into_iter().rev().fold(error, EvalError::unwound)
+}
+
+/// Evaluates the statements of a function body, leaving out the call it ends with, if any.
+fn eval_body(
+    statements: &[Spanned<Statement>],
+    environment: &Rc<RefCell<Environment>>,
+    context: &mut EvalContext,
+) -> Result<Tail, EvalError> {
+    let Some((last, statements)) = statements.split_last() else {
+        return Ok(Tail::Value(NULL));
+    };
+
+    let flow = eval_statements(statements, environment, context);
+    if flow.is_early_exit() {
+        let value = flow.absorb_return()?;
+        return Ok(Tail::Value(value.unwrap_or_default()));
     }
 
-    let value = body.eval(&mut environment);
+    match &last.node {
+        Statement::Expression { value } | Statement::Return { value } => {
+            last.enter(environment, context)?;
+            eval_tail(value, environment, context)
+        }
+        _ => {
+            let value = Flow::of(last.eval_with_context(environment, context)).absorb_return()?;
+            Ok(Tail::Value(value.unwrap_or_default()))
+        }
+    }
+}
 
-    if let Some(Object::Return(value)) = value {
-        Some(*value)
-    } else {
-        value
+/// Evaluates `expression`, the value of a function body, leaving out the call it ends with.
+fn eval_tail(
+    expression: &Expression,
+    environment: &Rc<RefCell<Environment>>,
+    context: &mut EvalContext,
+) -> Result<Tail, EvalError> {
+    match expression {
+        Expression::FunctionCall { name, arguments } if !macros::is_quote(name, environment) => {
+            eval_tail_expression(expression, context, |context| {
+                eval_call(name, arguments, environment, context).map(Tail::Call)
+            })
+        }
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => eval_tail_expression(expression, context, |context| {
+            eval_tail_if(condition, consequence, alternative, environment, context)
+        }),
+        expression => Ok(Tail::Value(
+            expression.eval_with_context(environment, context)?,
+        )),
     }
 }
 
-fn eval_infix_expression(operator: &Token, lh_value: Object, rh_value: Object) -> Object {
+/// Evaluates with `eval` the call or `if` `expression` ending a function body, which is a step of
+/// its own and is profiled like any other expression, leaving out the call it ends with.
+fn eval_tail_expression(
+    expression: &Expression,
+    context: &mut EvalContext,
+    eval: impl FnOnce(&mut EvalContext) -> Result<Tail, EvalError>,
+) -> Result<Tail, EvalError> {
+    context.step()?;
+    let start = context.profile_start();
+    let tail = eval(context);
+    context.profile(start, |profiler, time| {
+        profiler.expression(expression.kind(), time)
+    });
+    tail
+}
+
+/// Evaluates the branch of an `if` ending a function body, leaving out the call it ends with.
+fn eval_tail_if(
+    condition: &Expression,
+    consequence: &BlockStatement,
+    alternative: &Option<BlockStatement>,
+    environment: &Rc<RefCell<Environment>>,
+    context: &mut EvalContext,
+) -> Result<Tail, EvalError> {
+    let condition = condition.eval_with_context(environment, context)?;
+    let block = if condition.is_truthy() {
+        consequence
+    } else if let Some(alternative) = alternative {
+        alternative
+    } else {
+        return Ok(Tail::Value(NULL));
+    };
+
+    let scope = Rc::new(RefCell::new(Environment::new_child(environment)));
+    eval_body(&block.statements, &scope, context)
+}
+
+/// Maximum number of characters of an expression quoted in an error.
+const DESCRIBE_WIDTH: usize = 40;
+
+/// Points an error produced by an operator at the `expression` that applied it.
+fn in_expression(
+    result: Result<Object, EvalError>,
+    expression: &Expression,
+) -> Result<Object, EvalError> {
+    result.map_err(|error| match error {
+        EvalError::Message(message) => EvalError::Message(format!(
+            "{message} in '{}'",
+            describe(expression, DESCRIBE_WIDTH)
+        )),
+        error => error,
+    })
+}
+
+fn eval_infix_expression(
+    operator: &Token,
+    lh_value: Object,
+    rh_value: Object,
+) -> Result<Object, EvalError> {
     match (lh_value, rh_value) {
         (Object::Integer(lh_integer), Object::Integer(rh_integer)) => {
             eval_integer_infix_expression(operator, lh_integer, rh_integer)
         }
+        (Object::Integer(lh_integer), Object::BigInt(rh_integer)) => {
+            eval_big_integer_infix_expression(operator, &lh_integer.into(), &rh_integer)
+        }
+        (Object::BigInt(lh_integer), Object::Integer(rh_integer)) => {
+            eval_big_integer_infix_expression(operator, &lh_integer, &rh_integer.into())
+        }
+        (Object::BigInt(lh_integer), Object::BigInt(rh_integer)) => {
+            eval_big_integer_infix_expression(operator, &lh_integer, &rh_integer)
+        }
         (Object::Boolean(lh_boolean), Object::Boolean(rh_boolean)) => match operator {
-            Token::Equal => (lh_boolean == rh_boolean).into(),
-            Token::NotEqual => (lh_boolean != rh_boolean).into(),
-            _ => Object::Error(format!("unknown operator: BOOLEAN {operator} BOOLEAN")),
+            Token::Equal => Ok((lh_boolean == rh_boolean).into()),
+            Token::NotEqual => Ok((lh_boolean != rh_boolean).into()),
+            _ => Err(format!("unknown operator: BOOLEAN {operator} BOOLEAN").into()),
         },
         (Object::String(lh_string), Object::String(rh_string)) => match operator {
-            Token::PlusSign => format!("{lh_string}{rh_string}").into(),
-            _ => Object::Error(format!("unknown operator: STRING {operator} STRING")),
+            Token::PlusSign => Ok(format!("{lh_string}{rh_string}").into()),
+            Token::Equal => Ok((lh_string == rh_string).into()),
+            Token::NotEqual => Ok((lh_string != rh_string).into()),
+            Token::LessThan => Ok((lh_string < rh_string).into()),
+            Token::GreaterThan => Ok((lh_string > rh_string).into()),
+            _ => Err(format!("unknown operator: STRING {operator} STRING").into()),
         },
-        (lh_value, rh_value) => Object::Error(format!(
+        (lh_value, rh_value) => Err(format!(
             "type mismatch: {} {operator} {}",
             lh_value.kind(),
             rh_value.kind()
-        )),
+        )
+        .into()),
+    }
+}
+
+/// Arithmetic overflowing an `i64`, or dividing by zero, is left to
+/// [`eval_big_integer_infix_expression`], which promotes the result to a [`BigInt`].
+fn eval_integer_infix_expression(
+    operator: &Token,
+    lh_integer: i64,
+    rh_integer: i64,
+) -> Result<Object, EvalError> {
+    let result = match operator {
+        Token::PlusSign => lh_integer.checked_add(rh_integer),
+        Token::MinusSign => lh_integer.checked_sub(rh_integer),
+        Token::Asterisk => lh_integer.checked_mul(rh_integer),
+        Token::Slash => lh_integer.checked_div(rh_integer),
+        Token::LessThan => return Ok((lh_integer < rh_integer).into()),
+        Token::GreaterThan => return Ok((lh_integer > rh_integer).into()),
+        Token::Equal => return Ok((lh_integer == rh_integer).into()),
+        Token::NotEqual => return Ok((lh_integer != rh_integer).into()),
+        _ => return Err(format!("unknown operator: INTEGER {operator} INTEGER").into()),
+    };
+
+    match result {
+        Some(result) => Ok(result.into()),
+        None => eval_big_integer_infix_expression(operator, &lh_integer.into(), &rh_integer.into()),
     }
 }
 
-fn eval_integer_infix_expression(operator: &Token, lh_integer: isize, rh_integer: isize) -> Object {
+/// Integers of any size, with the results back to [`Object::Integer`] when they fit in one.
+fn eval_big_integer_infix_expression(
+    operator: &Token,
+    lh_integer: &BigInt,
+    rh_integer: &BigInt,
+) -> Result<Object, EvalError> {
     match operator {
-        Token::PlusSign => (lh_integer + rh_integer).into(),
-        Token::MinusSign => (lh_integer - rh_integer).into(),
-        Token::Asterisk => (lh_integer * rh_integer).into(),
-        Token::Slash => (lh_integer / rh_integer).into(),
-        Token::LessThan => (lh_integer < rh_integer).into(),
-        Token::GreaterThan => (lh_integer > rh_integer).into(),
-        Token::Equal => (lh_integer == rh_integer).into(),
-        Token::NotEqual => (lh_integer != rh_integer).into(),
-        _ => Object::Error(format!("unknown operator: INTEGER {operator} INTEGER")),
+        Token::PlusSign => Ok((lh_integer + rh_integer).into()),
+        Token::MinusSign => Ok((lh_integer - rh_integer).into()),
+        Token::Asterisk => Ok((lh_integer * rh_integer).into()),
+        Token::Slash => lh_integer
+            .checked_div(rh_integer)
+            .map(Object::from)
+            .ok_or_else(|| "division by zero".into()),
+        Token::LessThan => Ok((lh_integer < rh_integer).into()),
+        Token::GreaterThan => Ok((lh_integer > rh_integer).into()),
+        Token::Equal => Ok((lh_integer == rh_integer).into()),
+        Token::NotEqual => Ok((lh_integer != rh_integer).into()),
+        _ => Err(format!("unknown operator: INTEGER {operator} INTEGER").into()),
     }
 }
 
-fn eval_prefix_expression(operator: &Token, value: Object) -> Object {
+fn eval_prefix_expression(operator: &Token, value: Object) -> Result<Object, EvalError> {
     match operator {
-        Token::ExclamationMark => eval_bang_operator_expression(value),
+        Token::ExclamationMark => Ok(eval_bang_operator_expression(value)),
         Token::MinusSign => eval_minus_sign_expression(value),
-        _ => Object::Error(format!("unknown operator: {operator}{}", value.kind())),
+        Token::PlusSign => eval_plus_sign_expression(value),
+        _ => Err(format!("unknown operator: {operator}{}", value.kind()).into()),
     }
 }
 
 fn eval_bang_operator_expression(value: Object) -> Object {
+    (!value.is_truthy()).into()
+}
+
+/// Identity of the numbers, only there for symmetry with `-`.
+fn eval_plus_sign_expression(value: Object) -> Result<Object, EvalError> {
     match value {
-        TRUE => FALSE,
-        FALSE => TRUE,
-        NULL => TRUE,
-        _ => FALSE,
+        Object::Integer(_) | Object::BigInt(_) => Ok(value),
+        _ => Err(format!("unknown operator: +{}", value.kind()).into()),
     }
 }
 
-fn eval_minus_sign_expression(value: Object) -> Object {
+fn eval_minus_sign_expression(value: Object) -> Result<Object, EvalError> {
     match value {
-        Object::Integer(value) => Object::Integer(-value),
-        _ => Object::Error(format!("unknown operator: -{}", value.kind())),
+        Object::Integer(value) => Ok(value
+            .checked_neg()
+            .map_or_else(|| (-BigInt::from(value)).into(), Object::from)),
+        Object::BigInt(value) => Ok((-value).into()),
+        _ => Err(format!("unknown operator: -{}", value.kind()).into()),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{lexer::Lexer, parser::Parser};
+    use crate::{
+        evaluator::object::{FALSE, TRUE},
+        lexer::Lexer,
+        parser::Parser,
+    };
 
     use super::*;
 
@@ -356,14 +1011,102 @@ mod tests {
             ("3 * 3 * 3 + 10", 37),
             ("3 * (3 * 3) + 10", 37),
             ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+            ("+5", 5),
+            ("let x = -3; +x", -3),
+            ("+-5 - -+5", 0),
         ];
 
-        for (input, expected) in tests.into_iter().cloned() {
+        for (input, expected) in tests.iter().cloned() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
-            let mut environment = Environment::new();
+            let environment = Environment::shared();
+
+            assert_eq!(program.eval(&environment), Ok(Some(expected.into())));
+        }
+    }
+
+    /// Integers are 64 bits wide whatever the target, so these hold on the 32-bit playground too.
+    #[test]
+    fn test_eval_integers_past_32_bits() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            ("2147483647 + 1", Ok(2_147_483_648.into())),
+            ("-2147483648 - 1", Ok((-2_147_483_649).into())),
+            ("65536 * 65536", Ok(4_294_967_296.into())),
+            ("9007199254740993 - 1", Ok(9_007_199_254_740_992.into())),
+            (
+                "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(20)",
+                Ok(2_432_902_008_176_640_000.into()),
+            ),
+            ("9223372036854775807", Ok(i64::MAX.into())),
+            ("4294967296 > 4294967295", Ok(TRUE)),
+            ("[1, 2][4294967296]", Ok(NULL)),
+            ("[1, 2][-4294967296]", Ok(NULL)),
+            (r#"{4294967296: "big"}[4294967296]"#, Ok("big".into())),
+            ("len(0..4294967296)", Ok(4_294_967_296.into())),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
 
-            assert_eq!(program.eval(&mut environment), Some(expected.into()));
+            assert_eq!(
+                program.eval(&Environment::shared()),
+                expected.clone().map(Some),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_eval_big_integers() {
+        let big = |digits: &str| Object::BigInt(BigInt::parse(digits).unwrap());
+        let factorial = "let fact = fn(n) { let iter = fn(n, f) { if (n < 2) { f } else { iter(n - 1, f * n) } }; iter(n, 1) };";
+
+        let tests = [
+            (
+                format!("{factorial} fact(100)"),
+                big("93326215443944152681699238856266700490715968264381621468592963895217599993229915608941463976156518286253697920827223758251185210916864000000000000000000000000"),
+            ),
+            (format!("{factorial} fact(100) / fact(98)"), 9900.into()),
+            ("9223372036854775807 + 1".into(), big("9223372036854775808")),
+            ("-9223372036854775807 - 2".into(), big("-9223372036854775809")),
+            ("(-9223372036854775807 - 1) / -1".into(), big("9223372036854775808")),
+            ("let min = -9223372036854775807 - 1; -min".into(), big("9223372036854775808")),
+            ("-9223372036854775808".into(), i64::MIN.into()),
+            ("9223372036854775808 - 1".into(), i64::MAX.into()),
+            ("100000000000000000000 * 0".into(), 0.into()),
+            ("+100000000000000000000".into(), big("100000000000000000000")),
+            ("100000000000000000000 / -3".into(), big("-33333333333333333333")),
+            ("9223372036854775808 == 9223372036854775807 + 1".into(), TRUE),
+            ("9223372036854775808 == 9223372036854775807".into(), FALSE),
+            ("9223372036854775808 != 9223372036854775807".into(), TRUE),
+            ("9223372036854775808 > 9223372036854775807".into(), TRUE),
+            ("9223372036854775807 < 9223372036854775808".into(), TRUE),
+            ("-9223372036854775809 < -9223372036854775807 - 1".into(), TRUE),
+            ("-9223372036854775809 > 0".into(), FALSE),
+            (
+                r#"{9223372036854775808: "big"}[9223372036854775807 + 1]"#.into(),
+                "big".into(),
+            ),
+            (
+                r#"{9223372036854775808: "big", 1: "small", -9223372036854775809: "negative"}"#.into(),
+                Object::from(HashMap::from([
+                    (HashKey::BigInt(BigInt::parse("9223372036854775808").unwrap()), "big".into()),
+                    (1.into(), "small".into()),
+                    (HashKey::BigInt(BigInt::parse("-9223372036854775809").unwrap()), "negative".into()),
+                ])),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.clone()));
+            let program = parser.parse_program().expect("Failed to parse program");
+
+            assert_eq!(
+                program.eval(&Environment::shared()),
+                Ok(Some(expected)),
+                "{input}"
+            );
         }
     }
 
@@ -392,12 +1135,31 @@ mod tests {
             ("(1 > 2) == false", TRUE),
         ];
 
-        for (input, expected) in tests.into_iter().cloned() {
+        for (input, expected) in tests.iter().cloned() {
+            let mut parser = Parser::new(Lexer::new(input.into()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(program.eval(&environment), Ok(Some(expected)));
+        }
+    }
+
+    #[test]
+    fn test_null_literal() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            ("null", Ok(NULL)),
+            ("!null", Ok(TRUE)),
+            ("[1, null][1]", Ok(NULL)),
+            ("null ?? 5", Ok(5.into())),
+            (r#"{null: 1}"#, Err("unusable as hash key: NULL".into())),
+        ];
+
+        for (input, expected) in tests.iter().cloned() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
-            let mut environment = Environment::new();
+            let environment = Environment::shared();
 
-            assert_eq!(program.eval(&mut environment), Some(expected));
+            assert_eq!(program.eval(&environment), expected.map(Some), "{input}");
         }
     }
 
@@ -412,12 +1174,12 @@ mod tests {
             ("!!5", TRUE),
         ];
 
-        for (input, expected) in tests.into_iter().cloned() {
+        for (input, expected) in tests.iter().cloned() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
-            let mut environment = Environment::new();
+            let environment = Environment::shared();
 
-            assert_eq!(program.eval(&mut environment), Some(expected));
+            assert_eq!(program.eval(&environment), Ok(Some(expected)));
         }
     }
 
@@ -433,14 +1195,14 @@ mod tests {
             ("if (1 < 2) { 10 } else { 20 }", 10.into()),
         ];
 
-        for (index, (input, expected)) in tests.into_iter().cloned().enumerate() {
+        for (index, (input, expected)) in tests.iter().cloned().enumerate() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
-            let mut environment = Environment::new();
+            let environment = Environment::shared();
 
             assert_eq!(
-                program.eval(&mut environment),
-                Some(expected.clone()),
+                program.eval(&environment),
+                Ok(Some(expected.clone())),
                 "test {}",
                 index
             );
@@ -462,14 +1224,14 @@ mod tests {
             }"#,
         ];
 
-        for (index, input) in tests.into_iter().cloned().enumerate() {
+        for (index, input) in tests.iter().cloned().enumerate() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
-            let mut environment = Environment::new();
+            let environment = Environment::shared();
 
             assert_eq!(
-                program.eval(&mut environment),
-                Some(10.into()),
+                program.eval(&environment),
+                Ok(Some(10.into())),
                 "test {}",
                 index
             );
@@ -479,17 +1241,24 @@ mod tests {
     #[test]
     fn test_error_handling() {
         let tests = &[
-            ("5 + true;", "type mismatch: INTEGER + BOOLEAN"),
-            ("5 + true; 5;", "type mismatch: INTEGER + BOOLEAN"),
-            ("-true", "unknown operator: -BOOLEAN"),
-            ("true + false", "unknown operator: BOOLEAN + BOOLEAN"),
+            ("5 + true;", "type mismatch: INTEGER + BOOLEAN in '(5 + true)'"),
+            ("5 + true; 5;", "type mismatch: INTEGER + BOOLEAN in '(5 + true)'"),
+            ("-true", "unknown operator: -BOOLEAN in '(-true)'"),
+            (r#"-"abc""#, "unknown operator: -STRING in '(-\"abc\")'"),
+            ("-[1, 2]", "unknown operator: -ARRAY in '(-[1, 2])'"),
+            ("let x = true; +x", "unknown operator: +BOOLEAN in '(+x)'"),
+            (r#"let x = "abc"; +x"#, "unknown operator: +STRING in '(+x)'"),
+            (
+                "true + false",
+                "unknown operator: BOOLEAN + BOOLEAN in '(true + false)'",
+            ),
             (
                 "let a = 234; true + false; 5",
-                "unknown operator: BOOLEAN + BOOLEAN",
+                "unknown operator: BOOLEAN + BOOLEAN in '(true + false)'",
             ),
             (
                 "if (10 > 1) { return true + false; }",
-                "unknown operator: BOOLEAN + BOOLEAN",
+                "unknown operator: BOOLEAN + BOOLEAN in '(true + false)'",
             ),
             (
                 r#"if (10 > 1) {
@@ -498,24 +1267,75 @@ mod tests {
                 }
                 return 1;
             }"#,
-                "unknown operator: BOOLEAN + BOOLEAN",
+                "unknown operator: BOOLEAN + BOOLEAN in '(true + false)'",
             ),
             ("foobar", "identifier not found: foobar"),
-            ("\"Hello\" - \"World\"", "unknown operator: STRING - STRING"),
+            (
+                "\"Hello\" - \"World\"",
+                "unknown operator: STRING - STRING in '(\"Hello\" - \"World\")'",
+            ),
+            (
+                "let count = 1; let isValid = true; 2 * (count + isValid)",
+                "type mismatch: INTEGER + BOOLEAN in '(count + isValid)'",
+            ),
+            (
+                "let isValid = true; -isValid",
+                "unknown operator: -BOOLEAN in '(-isValid)'",
+            ),
+            (
+                "let averyveryverylongname = 1; averyveryverylongname + averyveryverylongname == true",
+                "type mismatch: INTEGER == BOOLEAN in '((averyveryverylongname + averyveryvery…'",
+            ),
             (
                 "[1,2,3][true]",
                 "index operator not supported: ARRAY With index of: BOOLEAN",
             ),
+            ("{fn(x) { x }: 1}", "unusable as hash key: FUNCTION"),
+            ("{[1]: 1}", "unusable as hash key: ARRAY"),
+            (r#"{"a": 1, {}: 2}"#, "unusable as hash key: HASH"),
+            ("{null: 1}", "unusable as hash key: NULL"),
+            ("{0..2: 1}", "unusable as hash key: RANGE"),
+            (r#"{"a": 1}[fn(x) { x }]"#, "unusable as hash key: FUNCTION"),
+            (r#"{"a": 1}[[1]]"#, "unusable as hash key: ARRAY"),
+            (r#"{"a": 1}[null]"#, "unusable as hash key: NULL"),
+            (r#"{"a": 1}?[null]"#, "unusable as hash key: NULL"),
+            ("5 / 0", "division by zero in '(5 / 0)'"),
+            (
+                "let half = fn(x) { x / 2 }; half(1 / 0)",
+                "division by zero in '(1 / 0)'",
+            ),
+            (
+                r#"{"ratio": 10 / (5 - 5)}"#,
+                "division by zero in '(10 / (5 - 5))'",
+            ),
+            (
+                "100000000000000000000 / 0",
+                "division by zero in '(100000000000000000000 / 0)'",
+            ),
+            (
+                "[1, 2][9223372036854775808]",
+                "integer 9223372036854775808 out of range for an index",
+            ),
+            (
+                r#""ab"[-9223372036854775809]"#,
+                "integer -9223372036854775809 out of range for an index",
+            ),
+            (
+                "0..9223372036854775807 + 1",
+                "integer 9223372036854775808 out of range for a range bound",
+            ),
         ];
 
-        for (index, (input, expected)) in tests.into_iter().cloned().enumerate() {
+        for (index, (input, expected)) in tests.iter().cloned().enumerate() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
-            let mut environment = Environment::new();
+            let environment = Environment::shared();
 
             assert_eq!(
-                program.eval(&mut environment),
-                Some(Object::Error(expected.into())),
+                program
+                    .eval(&environment)
+                    .map_err(|error| error.cause().clone()),
+                Err(expected.into()),
                 "test {}",
                 index
             );
@@ -523,39 +1343,412 @@ mod tests {
     }
 
     #[test]
-    fn test_let_statements() {
-        let tests: &[(&str, Object)] = &[
-            ("let a = 5; a;", 5.into()),
-            ("let a = 5 * 5; a;", 25.into()),
-            ("let a = 5; let b = a; b;", 5.into()),
-            ("let a = 5; let b = a; let c = a + b + 5; c;", 15.into()),
-        ];
-
-        for (index, (input, expected)) in tests.into_iter().cloned().enumerate() {
-            let mut parser = Parser::new(Lexer::new(input.into()));
-            let program = parser.parse_program().expect("Failed to parse program");
-            let mut environment = Environment::new();
-
-            assert_eq!(
-                program.eval(&mut environment),
-                Some(expected.clone()),
-                "test {}",
-                index
+    fn test_throw_statements() {
+        let thrown = |value: Object| Err(EvalError::Thrown(Box::new(value)));
+        let tests: &[(&str, Result<Option<Object>, EvalError>)] = &[
+            (
+                r#"throw "invalid input"; 1"#,
+                thrown("invalid input".into()),
+            ),
+            (
+                r#"throw {"code": 400, "msg": "bad"};"#,
+                thrown(
+                    HashMap::from([("code".into(), 400.into()), ("msg".into(), "bad".into())])
+                        .into(),
+                ),
+            ),
+            (
+                r#"let check = fn(x) { if (x > 2) { throw "too deep"; } check(x + 1) };
+                let outer = fn() { let result = check(0); "unreachable" };
+                outer(); 1"#,
+                thrown("too deep".into()),
+            ),
+            (
+                "for (i in 0..10) { if (i == 3) { throw i; } }",
+                thrown(3.into()),
+            ),
+            (r#"len(fn() { throw "inner" }())"#, thrown("inner".into())),
+            (
+                "throw missing;",
+                Err("identifier not found: missing".into()),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+            assert_eq!(
+                program
+                    .eval(&environment)
+                    .map_err(|error| error.cause().clone()),
+                *expected,
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_trace() {
+        let input = r#"let add = fn(a, b) { a + b };
+let twice = fn(x) { add(x, true) };
+let map = fn(arr, f) { [f(first(arr))] };
+map([1], twice)"#;
+
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+        let error = program.eval(&Environment::shared()).unwrap_err();
+
+        assert_eq!(
+            error.cause(),
+            &EvalError::from("type mismatch: INTEGER + BOOLEAN in '(a + b)'")
+        );
+        assert_eq!(
+            error
+                .trace()
+                .iter()
+                .map(|frame| (
+                    frame.function.as_str(),
+                    &input[frame.span.start..frame.span.end]
+                ))
+                .collect::<Vec<_>>(),
+            vec![("add", "add"), ("f", "f"), ("map", "map")]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Error: type mismatch: INTEGER + BOOLEAN in '(a + b)'\nin add\nin f\nin map\nin <program>"
+        );
+        assert_eq!(
+            error.trace()[2].span.start,
+            input.rfind("map").unwrap(),
+            "the span is the one of the call site"
+        );
+    }
+
+    #[test]
+    fn test_error_trace_through_tail_calls() {
+        let tests = [
+            (
+                "let add = fn(a, b) { a + b }; let g = fn(x) { add(x, true) }; let h = fn(x) { g(x) }; h(1)",
+                "in add\nin g\nin h\nin <program>",
+            ),
+            (
+                "let f = fn(x) { if (x) { len(x) } }; f(1)",
+                "in f\nin <program>",
+            ),
+            (
+                "map([1], fn(x) { x + true })",
+                "in <anonymous>\nin map\nin <program>",
+            ),
+            (
+                "let loop = fn(n) { if (n == 0) { n + true } else { loop(n - 1) } }; loop(100)",
+                "in loop\nin <36 frames elided by tail calls>\nin loop (64 times)\nin <program>",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.into()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let error = program.eval(&Environment::shared()).unwrap_err();
+            let trace = error.to_string();
+
+            assert_eq!(
+                trace.split_once('\n').map(|(_, trace)| trace),
+                Some(expected),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_let_statements() {
+        let tests: &[(&str, Object)] = &[
+            ("let a = 5; a;", 5.into()),
+            ("let a = 5 * 5; a;", 25.into()),
+            ("let a = 5; let b = a; b;", 5.into()),
+            ("let a = 5; let b = a; let c = a + b + 5; c;", 15.into()),
+            ("let a = 5;", 5.into()),
+            ("let a = 5; let b = a * 2", 10.into()),
+            ("const PI = 3;", 3.into()),
+            ("let f = fn() { let a = 1; }; f()", 1.into()),
+        ];
+
+        for (index, (input, expected)) in tests.iter().cloned().enumerate() {
+            let mut parser = Parser::new(Lexer::new(input.into()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(
+                program.eval(&environment),
+                Ok(Some(expected.clone())),
+                "test {}",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn test_if_blocks_have_their_own_scope() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            (
+                "if (true) { let block_local = 1; } block_local",
+                Err("identifier not found: block_local".into()),
+            ),
+            (
+                "if (false) {} else { let block_local = 1; } block_local",
+                Err("identifier not found: block_local".into()),
+            ),
+            ("let x = 1; if (true) { let x = 2; x }", Ok(2.into())),
+            ("let x = 1; if (true) { let x = 2; } x", Ok(1.into())),
+            (
+                "let x = 1; if (true) { let x = 2; if (true) { let x = 3; } x }",
+                Ok(2.into()),
+            ),
+            (
+                "let x = 0; let y = if (true) { let x = 1; if (true) { let x = 2; x } }; [x, y]",
+                Ok(vec![0.into(), 2.into()].into()),
+            ),
+            ("let a = 1; if (true) { let b = a + 1; b }", Ok(2.into())),
+            ("let i = 0; if (true) { i++; } i", Ok(1.into())),
+            ("const c = 1; if (true) { const c = 2; c }", Ok(2.into())),
+            (
+                "let f = if (true) { let n = 5; fn() { n } }; f()",
+                Ok(5.into()),
+            ),
+            (
+                "let f = fn(x) { if (x) { let y = 1; y } else { y } }; f(false)",
+                Err("identifier not found: y".into()),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = crate::parse(input).expect("Failed to parse program");
+            let result = program
+                .eval(&Environment::shared())
+                .map(Option::unwrap_or_default)
+                .map_err(|error| error.cause().clone());
+
+            assert_eq!(&result, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_const_statements() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            ("const PI = 3; PI * 2;", Ok(6.into())),
+            ("let a = 1; const a = 2; a;", Ok(2.into())),
+            (
+                "const a = 1; let a = 2;",
+                Err("cannot reassign constant a".into()),
+            ),
+            (
+                "const a = 1; const a = 2;",
+                Err("cannot reassign constant a".into()),
+            ),
+            (
+                r#"const a = 1; let {a} = {"a": 2};"#,
+                Err("cannot reassign constant a".into()),
+            ),
+            (
+                "let f = fn() { const a = 1; let a = 2; a }; f();",
+                Err("cannot reassign constant a".into()),
+            ),
+            (
+                "const a = 1; let f = fn() { let a = 2; a }; f();",
+                Ok(2.into()),
+            ),
+            ("const a = 1; let f = fn(a) { a }; f(3);", Ok(3.into())),
+            (
+                "const a = 1; let f = fn() { let a = 2; a }; f(); a;",
+                Ok(1.into()),
+            ),
+        ];
+
+        for (index, (input, expected)) in tests.iter().enumerate() {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(
+                program
+                    .eval(&environment)
+                    .map_err(|error| error.cause().clone()),
+                expected.clone().map(Some),
+                "test {index}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_array_destructuring() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            (
+                r#"let divmod = fn(a, b) { return [a / b, a - a / b * b]; };
+                let [quotient, remainder] = divmod(7, 2);
+                [quotient, remainder]"#,
+                Ok(vec![3.into(), 1.into()].into()),
+            ),
+            ("let [] = []; 1", Ok(1.into())),
+            (
+                "let [a, b] = [1, 2, 3];",
+                Err("cannot destructure an array of 3 elements into 2 names".into()),
+            ),
+            (
+                r#"let [a] = {"a": 1};"#,
+                Err("cannot destructure HASH as an array".into()),
+            ),
+            (
+                "const a = 1; let [a] = [2];",
+                Err("cannot reassign constant a".into()),
+            ),
+        ];
+
+        for (index, (input, expected)) in tests.iter().cloned().enumerate() {
+            let mut parser = Parser::new(Lexer::new(input.into()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(parser.errors.len(), 0, "{:?}", parser.errors);
+            assert_eq!(
+                program.eval(&environment),
+                expected.clone().map(Some),
+                "test {}",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_destructuring() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            (
+                r#"let {name} = {"name": "monkey"}; name"#,
+                Ok("monkey".into()),
+            ),
+            (
+                r#"let {name: title, age} = {"name": "monkey", "age": 3}; [title, age]"#,
+                Ok(vec!["monkey".into(), 3.into()].into()),
+            ),
+            (r#"let {missing} = {"name": "monkey"}; missing"#, Ok(NULL)),
+            (
+                r#"let describe = fn(person) {
+                    let {name, age: years} = person;
+                    name + " is " + years;
+                };
+                describe({"name": "monkey", "age": "three"})"#,
+                Ok("monkey is three".into()),
+            ),
+            (
+                "let {name} = [1, 2];",
+                Err("cannot destructure ARRAY as a hash".into()),
+            ),
+            (
+                "let {name} = foobar;",
+                Err("identifier not found: foobar".into()),
+            ),
+        ];
+
+        for (index, (input, expected)) in tests.iter().cloned().enumerate() {
+            let mut parser = Parser::new(Lexer::new(input.into()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(parser.errors.len(), 0, "{:?}", parser.errors);
+            assert_eq!(
+                program.eval(&environment),
+                expected.clone().map(Some),
+                "test {}",
+                index
             );
         }
     }
 
+    #[test]
+    fn test_eval_statement_by_statement() {
+        let mut parser = Parser::new(Lexer::new("let a = 5; a * 2; return a; a + 1;".into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+        let environment = Environment::shared();
+
+        let results = program
+            .eval_statement_by_statement(&environment, &mut EvalContext::default())
+            .map(|(statement, result)| (statement.to_string(), result))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            results,
+            vec![
+                ("let a = 5;".to_string(), Ok(Some(5.into()))),
+                ("(a * 2)".to_string(), Ok(Some(10.into()))),
+                ("return a;".to_string(), Ok(Some(5.into()))),
+            ]
+        );
+
+        let mut parser = Parser::new(Lexer::new("let a = b; 5;".into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+
+        assert_eq!(
+            program
+                .eval_statement_by_statement(&environment, &mut EvalContext::default())
+                .map(|(_, result)| result)
+                .collect::<Vec<_>>(),
+            vec![Err("identifier not found: b".into())]
+        );
+    }
+
     #[test]
     fn test_function_object() {
         let input = "fn(x) { x + 2; }";
 
         let mut parser = Parser::new(Lexer::new(input.into()));
         let program = parser.parse_program().expect("Failed to parse program");
-        let mut environment = Environment::new();
+        let environment = Environment::shared();
 
-        let results = program.eval(&mut environment).unwrap();
+        let results = program.eval(&environment);
 
-        assert!(matches!(results, Object::Function { .. }));
+        assert!(matches!(results, Ok(Some(Object::Function { .. }))));
+    }
+
+    #[test]
+    fn test_call_any_callee_expression() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            (
+                "let newAdder = fn(x) { fn(y) { x + y } }; newAdder(2)(3)",
+                Ok(5.into()),
+            ),
+            (
+                r#"let handlers = {"click": fn(event) { event + 1 }}; handlers["click"](41)"#,
+                Ok(42.into()),
+            ),
+            ("let arr = [fn(x) { x * 2 }]; arr[0](5)", Ok(10.into())),
+            (
+                "let first = fn(fs, x) { fs[0](x) }; first([fn(x) { -x }], 5)",
+                Ok((-5).into()),
+            ),
+            (
+                "if (true) { fn(x) { x + 1 } } else { fn(x) { x - 1 } }(1)",
+                Ok(2.into()),
+            ),
+            (
+                "(if (false) { fn(x) { x + 1 } } else { fn(x) { x - 1 } })(1)",
+                Ok(0.into()),
+            ),
+            ("[1, 2](1)", Err("not a function: ARRAY".into())),
+            (r#"{"a": 1}["a"](1)"#, Err("not a function: INTEGER".into())),
+            ("if (false) { len }(1)", Err("not a function: NULL".into())),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+
+            assert_eq!(
+                program.eval(&Environment::shared()),
+                expected.clone().map(Some),
+                "{input}"
+            );
+        }
     }
 
     #[test]
@@ -572,14 +1765,14 @@ mod tests {
             ("fn(x) { x; }(5)", 5),
         ];
 
-        for (index, (input, expected)) in tests.into_iter().cloned().enumerate() {
+        for (index, (input, expected)) in tests.iter().cloned().enumerate() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
-            let mut environment = Environment::new();
+            let environment = Environment::shared();
 
             assert_eq!(
-                program.eval(&mut environment),
-                Some(expected.into()),
+                program.eval(&environment),
+                Ok(Some(expected.into())),
                 "test {}",
                 index
             );
@@ -597,9 +1790,9 @@ mod tests {
 
         let mut parser = Parser::new(Lexer::new(input.into()));
         let program = parser.parse_program().expect("Failed to parse program");
-        let mut environment = Environment::new();
+        let environment = Environment::shared();
 
-        assert_eq!(program.eval(&mut environment), Some(4.into()),);
+        assert_eq!(program.eval(&environment), Ok(Some(4.into())),);
     }
 
     #[test]
@@ -622,30 +1815,111 @@ fib(10);
 
         let mut parser = Parser::new(Lexer::new(input.into()));
         let program = parser.parse_program().expect("Failed to parse program");
-        let mut environment = Environment::new();
+        let environment = Environment::shared();
 
-        assert_eq!(program.eval(&mut environment), Some(55.into()),);
+        assert_eq!(program.eval(&environment), Ok(Some(55.into())),);
     }
 
     #[test]
-    fn test_function_doesnt_capture_global_future_environment() {
-        let input = r#"
-let test = fn(x) {
-    return data + x;
+    fn test_tail_calls() {
+        let tests = &[
+            (
+                "let count = fn(n, total) { if (n == 0) { total } else { count(n - 1, total + 1) } };
+                count(10000, 0)",
+                10_000,
+            ),
+            (
+                "let count = fn(n) { if (n == 0) { return 0; } return count(n - 1); }; count(10000)",
+                0,
+            ),
+            (
+                "let count = fn(n) { if (n > 0) { count(n - 1) } else { n } }; count(10000)",
+                0,
+            ),
+            (
+                r#"
+let map = fn(arr, f) {
+    let iter = fn(arr, accumulated) {
+        if (len(arr) == 0) {
+            accumulated
+        } else {
+            iter(rest(arr), push(accumulated, f(first(arr))));
+        }
+    };
+    iter(arr, []);
 };
+let range = fn(n, accumulated) {
+    if (len(accumulated) == n) {
+        return accumulated;
+    }
+    range(n, push(accumulated, len(accumulated)))
+};
+let doubled = map(range(1000, []), fn(x) { x * 2 });
+doubled[999] + len(doubled)
+"#,
+                2998,
+            ),
+        ];
 
-let data = 5;
+        for (input, expected) in tests.iter().cloned() {
+            let program = crate::parse(input).expect("Failed to parse program");
 
-test(5);
-"#;
+            assert_eq!(
+                program.eval(&Environment::shared()),
+                Ok(Some(expected.into())),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_function_sees_later_bindings_of_its_scope() {
+        let tests = &[
+            (
+                "let test = fn(x) { return data + x; }; let data = 5; test(5);",
+                10,
+            ),
+            ("let x = 1; let f = fn() { x }; x++; f()", 2),
+            (
+                "let isEven = fn(n) { if (n == 0) { 1 } else { isOdd(n - 1) } };
+                let isOdd = fn(n) { if (n == 0) { 0 } else { isEven(n - 1) } };
+                isEven(10) + isOdd(7)",
+                2,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+
+            assert_eq!(
+                program.eval(&Environment::shared()),
+                Ok(Some((*expected).into())),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_closure_mutates_captured_binding() {
+        let input = r#"
+let newCounter = fn() {
+    let count = 0;
+    fn() { count++; count }
+};
+let counter = newCounter();
+let other = newCounter();
+counter();
+counter();
+other();
+[counter(), other()]"#;
 
         let mut parser = Parser::new(Lexer::new(input.into()));
         let program = parser.parse_program().expect("Failed to parse program");
-        let mut environment = Environment::new();
 
         assert_eq!(
-            program.eval(&mut environment),
-            Some(Object::Error("identifier not found: data".into())),
+            program.eval(&Environment::shared()),
+            Ok(Some(vec![3.into(), 2.into()].into()))
         );
     }
 
@@ -655,46 +1929,123 @@ test(5);
 
         let mut parser = Parser::new(Lexer::new(input.into()));
         let program = parser.parse_program().expect("Failed to parse program");
-        let mut environment = Environment::new();
+        let environment = Environment::shared();
+
+        assert_eq!(program.eval(&environment), Ok(Some("Hello World!".into())),);
+    }
+
+    #[test]
+    fn test_string_comparison() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            (r#""a" == "a""#, Ok(TRUE)),
+            (r#""a" == "b""#, Ok(FALSE)),
+            (r#""a" != "b""#, Ok(TRUE)),
+            (r#""" == """#, Ok(TRUE)),
+            (r#""" != "a""#, Ok(TRUE)),
+            (r#""a" == "A""#, Ok(FALSE)),
+            (r#""apple" < "banana""#, Ok(TRUE)),
+            (r#""apple" > "app""#, Ok(TRUE)),
+            (r#""" < "a""#, Ok(TRUE)),
+            (r#""Z" < "a""#, Ok(TRUE)),
+            (r#""b" < "b""#, Ok(FALSE)),
+            (
+                r#"let name = "monkey"; if (name == "monkey") { "found" } else { "missing" }"#,
+                Ok("found".into()),
+            ),
+            (
+                r#"let ages = {"monkey": 3}; let name = "mon" + "key";
+                if (name != "monkey") { 0 } else { ages[name] }"#,
+                Ok(3.into()),
+            ),
+            (
+                r#""1" == 1"#,
+                Err(r#"type mismatch: STRING == INTEGER in '("1" == 1)'"#.into()),
+            ),
+            (
+                r#"true < "a""#,
+                Err(r#"type mismatch: BOOLEAN < STRING in '(true < "a")'"#.into()),
+            ),
+        ];
+
+        for (input, expected) in tests.iter().cloned() {
+            let mut parser = Parser::new(Lexer::new(input.into()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
 
-        assert_eq!(program.eval(&mut environment), Some("Hello World!".into()),);
+            assert_eq!(program.eval(&environment), expected.map(Some), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_version_builtin() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            (
+                r#"version()["version"]"#,
+                Ok(env!("CARGO_PKG_VERSION").into()),
+            ),
+            (
+                r#"version()["features"]"#,
+                Ok(crate::capabilities::capabilities()
+                    .features
+                    .into_iter()
+                    .map(Object::from)
+                    .collect::<Vec<_>>()
+                    .into()),
+            ),
+            (
+                "version(1)",
+                Err("wrong number of arguments. Got 1, expected 0".into()),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(
+                program.eval(&environment),
+                expected.clone().map(Some),
+                "{input}"
+            );
+        }
     }
 
     #[test]
     fn test_builtin_functions() {
-        let tests = &[
-            (r#"len("")"#, 0.into()),
-            (r#"len("four")"#, 4.into()),
-            (r#"len("hello world")"#, 11.into()),
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            (r#"len("")"#, Ok(0.into())),
+            (r#"len("four")"#, Ok(4.into())),
+            (r#"len("hello world")"#, Ok(11.into())),
             (
                 "len(1)",
-                Object::Error(r#"argument to "len" not supported, got INTEGER"#.into()),
+                Err(r#"argument to "len" not supported, got INTEGER"#.into()),
             ),
             (
                 r#"len("one", "two")"#,
-                Object::Error(r#"wrong number of arguments. Got 2, expected 1"#.into()),
+                Err(r#"wrong number of arguments. Got 2, expected 1"#.into()),
             ),
-            ("first([1, 2, 3])", 1.into()),
-            ("first([])", NULL),
-            ("last([1, 2, 3])", 3.into()),
-            ("last([])", NULL),
-            ("rest([1, 2, 3])", vec![2.into(), 3.into()].into()),
-            ("rest([1])", vec![].into()),
-            ("rest([])", NULL),
+            ("first([1, 2, 3])", Ok(1.into())),
+            ("first([])", Ok(NULL)),
+            ("last([1, 2, 3])", Ok(3.into())),
+            ("last([])", Ok(NULL)),
+            ("rest([1, 2, 3])", Ok(vec![2.into(), 3.into()].into())),
+            ("rest([1])", Ok(vec![].into())),
+            ("rest([])", Ok(NULL)),
             (
                 "push([1, 2, 3], true)",
-                vec![1.into(), 2.into(), 3.into(), true.into()].into(),
+                Ok(vec![1.into(), 2.into(), 3.into(), true.into()].into()),
             ),
         ];
 
-        for (index, (input, expected)) in tests.into_iter().cloned().enumerate() {
+        for (index, (input, expected)) in tests.iter().cloned().enumerate() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
-            let mut environment = Environment::new();
+            let environment = Environment::shared();
 
             assert_eq!(
-                program.eval(&mut environment),
-                Some(expected.clone()),
+                program.eval(&environment),
+                expected.clone().map(Some),
                 "test {}",
                 index
             );
@@ -707,11 +2058,11 @@ test(5);
 
         let mut parser = Parser::new(Lexer::new(input.into()));
         let program = parser.parse_program().expect("Failed to parse program");
-        let mut environment = Environment::new();
+        let environment = Environment::shared();
 
         assert_eq!(
-            program.eval(&mut environment),
-            Some(vec![1.into(), 4.into(), 6.into()].into()),
+            program.eval(&environment),
+            Ok(Some(vec![1.into(), 4.into(), 6.into()].into())),
         );
     }
 
@@ -733,23 +2084,61 @@ test(5);
                 2.into(),
             ),
             ("[1, 2, 3][3]", NULL),
-            ("[1, 2, 3][-1]", NULL),
+            ("[1, 2, 3][-1]", 3.into()),
+            ("[1, 2, 3][-3]", 1.into()),
+            ("let a = [1, 2, 3]; a[-len(a)]", 1.into()),
+            ("let a = [1, 2, 3]; a[-(len(a) + 1)]", NULL),
+            ("[1, 2, 3][0 - 1]", 3.into()),
+            ("[][-1]", NULL),
         ];
 
-        for (index, (input, expected)) in tests.into_iter().cloned().enumerate() {
+        for (index, (input, expected)) in tests.iter().cloned().enumerate() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
-            let mut environment = Environment::new();
+            let environment = Environment::shared();
 
             assert_eq!(
-                program.eval(&mut environment),
-                Some(expected.clone()),
+                program.eval(&environment),
+                Ok(Some(expected.clone())),
                 "test {}",
                 index
             );
         }
     }
 
+    #[test]
+    fn test_string_index_expressions() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            (r#""hello"[0]"#, Ok("h".into())),
+            (r#""hello"[4]"#, Ok("o".into())),
+            (r#"let s = "hello"; s[len(s) - 1]"#, Ok("o".into())),
+            (r#""héllo"[1]"#, Ok("é".into())),
+            (r#""héllo"[2]"#, Ok("l".into())),
+            (r#""日本語"[2]"#, Ok("語".into())),
+            (r#"len("héllo")"#, Ok(5.into())),
+            (r#"len("日本語")"#, Ok(3.into())),
+            (r#""hello"[5]"#, Ok(NULL)),
+            (r#""héllo"[len("héllo")]"#, Ok(NULL)),
+            (r#""héllo"[-1]"#, Ok("o".into())),
+            (r#""héllo"[-4]"#, Ok("é".into())),
+            (r#"let s = "héllo"; s[-len(s)]"#, Ok("h".into())),
+            (r#"let s = "héllo"; s[-(len(s) + 1)]"#, Ok(NULL)),
+            (r#"""[0]"#, Ok(NULL)),
+            (
+                r#""hello"["h"]"#,
+                Err("index operator not supported: STRING With index of: STRING".into()),
+            ),
+        ];
+
+        for (input, expected) in tests.iter().cloned() {
+            let mut parser = Parser::new(Lexer::new(input.into()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(program.eval(&environment), expected.map(Some), "{input}");
+        }
+    }
+
     #[test]
     fn test_custom_function_map() {
         let input = r#"
@@ -772,11 +2161,11 @@ map(data, squared);
 
         let mut parser = Parser::new(Lexer::new(input.into()));
         let program = parser.parse_program().expect("Failed to parse program");
-        let mut environment = Environment::new();
+        let environment = Environment::shared();
 
         assert_eq!(
-            program.eval(&mut environment),
-            Some(vec![1.into(), 4.into(), 9.into()].into()),
+            program.eval(&environment),
+            Ok(Some(vec![1.into(), 4.into(), 9.into()].into())),
         );
     }
 
@@ -803,9 +2192,43 @@ sum([1, 2, 3, 4, 5]);
 
         let mut parser = Parser::new(Lexer::new(input.into()));
         let program = parser.parse_program().expect("Failed to parse program");
-        let mut environment = Environment::new();
+        let environment = Environment::shared();
+
+        assert_eq!(program.eval(&environment), Ok(Some(15.into())),);
+    }
+
+    #[test]
+    fn test_stack_with_push_and_pop() {
+        let input = r#"
+let apply = fn(operator, left, right) {
+    if (operator == "+") { return left + right; }
+    if (operator == "-") { return left - right; }
+    left * right
+};
+
+let rpn = fn(tokens, stack) {
+    if (len(tokens) == 0) {
+        return last(stack);
+    }
+
+    let token = first(tokens);
+    if (contains(["+", "-", "*"], token)) {
+        let right = last(stack);
+        let left = last(pop(stack));
+        rpn(rest(tokens), push(pop(pop(stack)), apply(token, left, right)))
+    } else {
+        rpn(rest(tokens), push(stack, int(token)))
+    }
+};
+
+rpn(split("3 4 + 2 * 7 -", " "), []);
+"#;
+
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+        let environment = Environment::shared();
 
-        assert_eq!(program.eval(&mut environment), Some(15.into()),);
+        assert_eq!(program.eval(&environment), Ok(Some(7.into())));
     }
 
     #[test]
@@ -822,24 +2245,340 @@ sum([1, 2, 3, 4, 5]);
 
         let mut parser = Parser::new(Lexer::new(input.into()));
         let program = parser.parse_program().expect("Failed to parse program");
-        let mut environment = Environment::new();
+        let environment = Environment::shared();
 
         assert_eq!(
-            program.eval(&mut environment),
-            Some(
-                BTreeMap::from([
+            program.eval(&environment),
+            Ok(Some(
+                HashMap::from([
                     ("one".into(), 1.into()),
                     ("two".into(), 2.into()),
                     ("three".into(), 3.into()),
                     (4.into(), 4.into()),
-                    (TRUE, 5.into()),
-                    (FALSE, 6.into())
+                    (true.into(), 5.into()),
+                    (false.into(), 6.into())
                 ])
                 .into()
-            ),
+            )),
         );
     }
 
+    #[test]
+    fn test_hash_literal_trailing_comma() {
+        let input = r#"let config = {
+    "name": "monkey",
+    "tags": [1, 2,],
+};
+[config["name"], len(config["tags"]),]"#;
+
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+        let environment = Environment::shared();
+
+        assert_eq!(parser.errors.len(), 0, "{:?}", parser.errors);
+        assert_eq!(
+            program.eval(&environment),
+            Ok(Some(Object::from(vec!["monkey".into(), 2.into()])))
+        );
+    }
+
+    #[test]
+    fn test_method_call_sugar() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            ("[1, 2, 3].len()", Ok(3.into())),
+            (r#""hello".len()"#, Ok(5.into())),
+            ("[1, 2, 3].rest().first()", Ok(2.into())),
+            ("[1, 2].push(3).push(4).last()", Ok(4.into())),
+            ("let double = fn(x) { x * 2 }; 5.double()", Ok(10.into())),
+            (
+                "let add = fn(x, y) { x + y }; [1, 2].first().add(10).double()",
+                Err("function not found: double".into()),
+            ),
+            (
+                "let add = fn(x, y) { x + y }; [1, 2].rest().first().add(10)",
+                Ok(12.into()),
+            ),
+            (
+                r#"let person = {"name": "monkey"}; person.name"#,
+                Ok("monkey".into()),
+            ),
+            (
+                r#"let person = {"name": "monkey"}; person.name.len()"#,
+                Ok(6.into()),
+            ),
+            (r#"{"name": "monkey"}.age"#, Ok(NULL)),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+            assert_eq!(
+                program.eval(&environment),
+                expected.clone().map(Some),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_optional_index_expressions() {
+        let config = r#"let config = {"server": {"tls": {"cert": "monkey.pem"}}, "ports": [80]};"#;
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            ("config?.server?.tls?.cert", Ok("monkey.pem".into())),
+            (
+                r#"config?["server"]?["tls"]?["cert"]"#,
+                Ok("monkey.pem".into()),
+            ),
+            ("config?.client?.tls?.cert", Ok(NULL)),
+            ("config?.server?.proxy?.host?.name", Ok(NULL)),
+            ("config.ports?[0]", Ok(80.into())),
+            ("config.missing?[puts(1) + true]", Ok(NULL)),
+            (
+                "config.client.tls",
+                Err("index operator not supported: NULL With index of: STRING".into()),
+            ),
+            (
+                "config?.client.tls",
+                Err("index operator not supported: NULL With index of: STRING".into()),
+            ),
+            (
+                "5?.value",
+                Err("index operator not supported: INTEGER With index of: STRING".into()),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(format!("{config}\n{input}")));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+            assert_eq!(
+                program.eval(&environment),
+                expected.clone().map(Some),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_null_coalescing() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            ("null_value ?? 5", Ok(5.into())),
+            ("1 ?? 5", Ok(1.into())),
+            ("false ?? 5", Ok(FALSE)),
+            ("0 ?? 5", Ok(0.into())),
+            ("null_value ?? null_value ?? 3", Ok(3.into())),
+            ("null_value ?? null_value", Ok(NULL)),
+            (r#"{"a": 1}["b"] ?? {"a": 1}["a"]"#, Ok(1.into())),
+            (r#"{"a": {}}?.a?.b ?? "default""#, Ok("default".into())),
+            ("1 ?? missing", Ok(1.into())),
+            (
+                "null_value ?? missing",
+                Err("identifier not found: missing".into()),
+            ),
+            ("1 ?? puts(1 + true)", Ok(1.into())),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(format!(
+                "let null_value = if (false) {{ 1 }};\n{input}"
+            )));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+            assert_eq!(
+                program.eval(&environment),
+                expected.clone().map(Some),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_ranges() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            ("1..5", Ok(Object::Range { start: 1, end: 5 })),
+            ("1..=5", Ok(Object::Range { start: 1, end: 6 })),
+            (
+                "let n = 3; n - 4..n * 2",
+                Ok(Object::Range { start: -1, end: 6 }),
+            ),
+            ("len(0..5)", Ok(5.into())),
+            ("len(0..=5)", Ok(6.into())),
+            ("len(5..0)", Ok(0.into())),
+            ("len(-2..2)", Ok(4.into())),
+            (
+                "len(-9223372036854775807..9223372036854775807)",
+                Ok(Object::BigInt(
+                    BigInt::parse("18446744073709551614").unwrap(),
+                )),
+            ),
+            (
+                "len(9223372036854775807..-9223372036854775807)",
+                Ok(0.into()),
+            ),
+            ("len(0..=9223372036854775806)", Ok(i64::MAX.into())),
+            (
+                "0..=9223372036854775807",
+                Err("integer 9223372036854775807 out of range for an inclusive range end".into()),
+            ),
+            ("[1, 2, 3, 4][1..3]", Ok(vec![2.into(), 3.into()].into())),
+            (
+                "[1, 2, 3, 4][1..=3]",
+                Ok(vec![2.into(), 3.into(), 4.into()].into()),
+            ),
+            ("[1, 2, 3][2..10]", Ok(vec![3.into()].into())),
+            ("[1, 2, 3][-5..1]", Ok(vec![1.into()].into())),
+            ("[1, 2, 3][-2..3]", Ok(vec![2.into(), 3.into()].into())),
+            ("[1, 2, 3, 4][1..-1]", Ok(vec![2.into(), 3.into()].into())),
+            ("[1, 2, 3][2..1]", Ok(vec![].into())),
+            ("[1, 2, 3][5..9]", Ok(vec![].into())),
+            (
+                r#""a"..2"#,
+                Err("range bounds must be integers, got STRING..INTEGER".into()),
+            ),
+            (
+                "1..true",
+                Err("range bounds must be integers, got INTEGER..BOOLEAN".into()),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(
+                program.eval(&environment),
+                expected.clone().map(Some),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_for_expressions() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            (
+                "let total = 0; for (i in 0..5) { for (j in 0..i) { total++ } }; total",
+                Ok(10.into()),
+            ),
+            (
+                "let total = 0; for (i in 1..=5) { for (j in 0..i) { total++ } }; total",
+                Ok(15.into()),
+            ),
+            (
+                "let total = 0; for (i in 5..0) { for (j in 0..i) { total++ } }; total",
+                Ok(0.into()),
+            ),
+            (
+                "let total = 0; for (i in [10, 20]) { for (j in 0..i) { total++ } }; total",
+                Ok(30.into()),
+            ),
+            ("for (i in 0..3) { i }", Ok(NULL)),
+            (
+                "for (i in 0..3) { i }; i",
+                Err("identifier not found: i".into()),
+            ),
+            ("let i = 10; for (i in 0..3) { i }; i", Ok(10.into())),
+            (
+                "let find = fn(n) { for (i in 0..10) { if (i * i > n) { return i; } } }; find(20)",
+                Ok(5.into()),
+            ),
+            (
+                "for (i in 0..3) { i + true }",
+                Err("type mismatch: INTEGER + BOOLEAN in '(i + true)'".into()),
+            ),
+            (
+                "for (i in 5) { i }",
+                Err("cannot iterate over INTEGER".into()),
+            ),
+            ("const i = 1; for (i in 0..3) { i }; i", Ok(1.into())),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+            assert_eq!(
+                program.eval(&environment),
+                expected.clone().map(Some),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_while_expressions() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[("let i = 0; while (i < 5) { i++; }; i", Ok(5.into())),
+("let i = 3; while (i > 0) { i--; }; i", Ok(0.into())),
+("while (false) { 1 }", Ok(NULL)),
+("let i = 0; let total = 0; while (i < 4) { for (j in 0..i) { total++ }; i++ }; total", Ok(6.into())),
+("let i = 0; while (i < 2) { let doubled = i * 2; i++ }; doubled", Err("identifier not found: doubled".into())),
+("let find = fn() { let i = 0; while (true) { if (i * i > 20) { return i; } i++; } }; find()", Ok(5.into())),
+("while (1 + true) { 1 }", Err("type mismatch: INTEGER + BOOLEAN in '(1 + true)'".into())),
+];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+            assert_eq!(
+                program.eval(&environment),
+                expected.clone().map(Some),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_postfix_expressions() {
+        let tests: &[(&str, Result<Object, EvalError>)] = &[
+            // Postfix, the previous value is returned
+            ("let i = 1; i++", Ok(1.into())),
+            ("let i = 1; i--", Ok(1.into())),
+            ("let i = 1; i++; i", Ok(2.into())),
+            ("let i = 1; i--; i", Ok(0.into())),
+            ("let i = 1; i++ + i", Ok(3.into())),
+            ("let i = 1; -i++", Ok((-1).into())),
+            (
+                r#"let s = "a"; s++"#,
+                Err("unknown operator: STRING++ in '(s++)'".into()),
+            ),
+            ("i++", Err("identifier not found: i".into())),
+            ("const i = 1; i++", Err("cannot reassign constant i".into())),
+            (
+                &format!("let i = {}; i++; i", i64::MAX),
+                Ok(Object::BigInt(
+                    BigInt::parse("9223372036854775808").unwrap(),
+                )),
+            ),
+            ("let i = 9223372036854775808; i--; i", Ok(i64::MAX.into())),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            let environment = Environment::shared();
+
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+            assert_eq!(
+                program.eval(&environment),
+                expected.clone().map(Some),
+                "{input}"
+            );
+        }
+    }
+
     #[test]
     fn test_hash_index_expressions() {
         let tests = &[
@@ -852,14 +2591,14 @@ sum([1, 2, 3, 4, 5]);
             (r#"{false: 5}[false]"#, 5.into()),
         ];
 
-        for (input, expected) in tests.into_iter().cloned() {
+        for (input, expected) in tests.iter().cloned() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
-            let mut environment = Environment::new();
+            let environment = Environment::shared();
 
             assert_eq!(
-                program.eval(&mut environment),
-                Some(expected.clone()),
+                program.eval(&environment),
+                Ok(Some(expected.clone())),
                 "test {}",
                 input
             );
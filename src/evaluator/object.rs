@@ -1,36 +1,245 @@
-use std::collections::BTreeMap;
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap, fmt, rc::Rc};
 
-use crate::{ast::BlockStatement, evaluator::environment::Environment};
+use crate::{
+    ast::{BlockStatement, Expression},
+    bigint::BigInt,
+    evaluator::{context::EvalContext, environment::Environment, error::EvalError},
+    lexer::quote,
+};
+
+/// Signature of the builtins, and of the host functions embedders bind as [`Object::Builtin`].
+pub type BuiltinFunction = fn(&[Object], &mut EvalContext) -> Result<Object, EvalError>;
 
 pub const NULL: Object = Object::Null;
 pub const TRUE: Object = Object::Boolean(true);
 pub const FALSE: Object = Object::Boolean(false);
 
-#[derive(PartialEq, Eq, Debug, Clone, Default, Ord, PartialOrd)]
+#[derive(Debug, Clone, Default)]
 #[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
 #[cfg_attr(
     target_family = "wasm",
     serde(tag = "kind", content = "value", rename_all = "snake_case")
 )]
 pub enum Object {
-    Integer(isize),
+    Integer(
+        #[cfg_attr(
+            target_family = "wasm",
+            serde(serialize_with = "crate::wasm::serialization::integer")
+        )]
+        i64,
+    ),
+    /// Integer too large for an `i64`, which integers are promoted to rather than overflowing
+    #[cfg_attr(target_family = "wasm", serde(rename = "integer"))]
+    BigInt(BigInt),
     Boolean(bool),
-    String(String),
-    Return(Box<Self>),
-    Error(String),
+    /// Strings, arrays and hashes are immutable, and shared between the bindings holding them
+    /// so that copying them around is cheap
+    String(Rc<str>),
+    /// Parameters and body are shared between the copies of the function, which are made on
+    /// every call by looking it up
     Function {
-        parameters: Vec<String>,
-        environment: Environment,
-        body: BlockStatement,
+        parameters: Rc<[Rc<str>]>,
+        /// Scope the function was defined in, shared with the code that defined it
+        #[cfg_attr(target_family = "wasm", serde(skip))]
+        environment: Rc<RefCell<Environment>>,
+        body: Rc<BlockStatement>,
     },
     #[cfg_attr(target_family = "wasm", serde(skip))]
-    Builtin(fn(&[Self]) -> Option<Self>),
-    Array(Vec<Object>),
-    Hash(BTreeMap<Object, Object>),
+    Builtin(BuiltinFunction),
+    /// Unevaluated expression returned by `quote`
+    Quote(Box<Expression>),
+    /// Value of a `macro` literal, called with its arguments quoted to expand into the
+    /// expression it returns
+    Macro {
+        parameters: Vec<Rc<str>>,
+        /// Scope the macro was defined in
+        #[cfg_attr(target_family = "wasm", serde(skip))]
+        environment: Rc<RefCell<Environment>>,
+        body: BlockStatement,
+    },
+    Array(Rc<[Object]>),
+    #[cfg_attr(
+        target_family = "wasm",
+        serde(serialize_with = "crate::wasm::serialization::shared")
+    )]
+    Hash(Rc<HashMap<HashKey, Object>>),
+    /// Integers from `start` up to, but excluding, `end`
+    Range {
+        #[cfg_attr(
+            target_family = "wasm",
+            serde(serialize_with = "crate::wasm::serialization::integer")
+        )]
+        start: i64,
+        #[cfg_attr(
+            target_family = "wasm",
+            serde(serialize_with = "crate::wasm::serialization::integer")
+        )]
+        end: i64,
+    },
     #[default]
     Null,
 }
 
+/// Functions, and macros, are equal when they are the same closure: same code, captured in the
+/// very same scope. Comparing scopes by their bindings would never end for a function bound in
+/// the scope it captured.
+impl PartialEq for Object {
+    #[allow(unpredictable_function_pointer_comparisons)]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Integer(left), Self::Integer(right)) => left == right,
+            (Self::BigInt(left), Self::BigInt(right)) => left == right,
+            (Self::Boolean(left), Self::Boolean(right)) => left == right,
+            (Self::String(left), Self::String(right)) => left == right,
+            (
+                Self::Function {
+                    parameters,
+                    environment,
+                    body,
+                },
+                Self::Function {
+                    parameters: other_parameters,
+                    environment: other_environment,
+                    body: other_body,
+                },
+            ) => {
+                Rc::ptr_eq(environment, other_environment)
+                    && parameters == other_parameters
+                    && body == other_body
+            }
+            (
+                Self::Macro {
+                    parameters,
+                    environment,
+                    body,
+                },
+                Self::Macro {
+                    parameters: other_parameters,
+                    environment: other_environment,
+                    body: other_body,
+                },
+            ) => {
+                Rc::ptr_eq(environment, other_environment)
+                    && parameters == other_parameters
+                    && body == other_body
+            }
+            (Self::Builtin(left), Self::Builtin(right)) => left == right,
+            (Self::Quote(left), Self::Quote(right)) => left == right,
+            (Self::Array(left), Self::Array(right)) => left == right,
+            (Self::Hash(left), Self::Hash(right)) => left == right,
+            (
+                Self::Range { start, end },
+                Self::Range {
+                    start: other_start,
+                    end: other_end,
+                },
+            ) => start == other_start && end == other_end,
+            (Self::Null, Self::Null) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Object {}
+
+/// Key of a hash, one of the objects with a sensible notion of equality, e.g. unlike functions
+/// which would compare by the environment they captured.
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
+#[cfg_attr(
+    target_family = "wasm",
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
+pub enum HashKey {
+    Integer(
+        #[cfg_attr(
+            target_family = "wasm",
+            serde(serialize_with = "crate::wasm::serialization::integer")
+        )]
+        i64,
+    ),
+    #[cfg_attr(target_family = "wasm", serde(rename = "integer"))]
+    BigInt(BigInt),
+    Boolean(bool),
+    String(Rc<str>),
+}
+
+/// Integers first, in numeric order whatever their size, then booleans, then strings.
+impl Ord for HashKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Integer(left), Self::Integer(right)) => left.cmp(right),
+            (Self::Integer(left), Self::BigInt(right)) => BigInt::from(*left).cmp(right),
+            (Self::BigInt(left), Self::Integer(right)) => left.cmp(&BigInt::from(*right)),
+            (Self::BigInt(left), Self::BigInt(right)) => left.cmp(right),
+            (Self::Boolean(left), Self::Boolean(right)) => left.cmp(right),
+            (Self::String(left), Self::String(right)) => left.cmp(right),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for HashKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The error of an object that can't be a hash key.
+impl TryFrom<&Object> for HashKey {
+    type Error = EvalError;
+
+    fn try_from(value: &Object) -> Result<Self, EvalError> {
+        match value {
+            Object::Integer(value) => Ok(Self::Integer(*value)),
+            Object::BigInt(value) => Ok(Self::BigInt(value.clone())),
+            Object::Boolean(value) => Ok(Self::Boolean(*value)),
+            Object::String(value) => Ok(Self::String(value.clone())),
+            value => Err(format!("unusable as hash key: {}", value.kind()).into()),
+        }
+    }
+}
+
+impl From<HashKey> for Object {
+    fn from(value: HashKey) -> Self {
+        match value {
+            HashKey::Integer(value) => Self::Integer(value),
+            HashKey::BigInt(value) => Self::BigInt(value),
+            HashKey::Boolean(value) => Self::Boolean(value),
+            HashKey::String(value) => Self::String(value),
+        }
+    }
+}
+
+impl From<i64> for HashKey {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<bool> for HashKey {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+}
+
+impl From<&str> for HashKey {
+    fn from(value: &str) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl HashKey {
+    /// Position of the kind of the key in the order of keys.
+    fn rank(&self) -> u8 {
+        match self {
+            HashKey::Integer(_) | HashKey::BigInt(_) => 0,
+            HashKey::Boolean(_) => 1,
+            HashKey::String(_) => 2,
+        }
+    }
+}
+
 impl Object {
     pub fn is_truthy(&self) -> bool {
         self != &NULL && self != &FALSE
@@ -38,63 +247,142 @@ impl Object {
 
     pub fn kind(&self) -> &'static str {
         match self {
-            Object::Integer(_) => "INTEGER",
+            Object::Integer(_) | Object::BigInt(_) => "INTEGER",
             Object::Boolean(_) => "BOOLEAN",
             Object::String(_) => "STRING",
-            Object::Return(_) => "RETURN",
-            Object::Error(_) => "ERROR",
             Object::Function { .. } => "FUNCTION",
             Object::Builtin(_) => "BUILTIN",
+            Object::Quote(_) => "QUOTE",
+            Object::Macro { .. } => "MACRO",
             Object::Array(_) => "ARRAY",
             Object::Hash(_) => "HASH",
+            Object::Range { .. } => "RANGE",
             Object::Null => "NULL",
         }
     }
 
+    /// Rough estimate of the bytes taken by the object, including what it owns on the heap but
+    /// not what its elements do: they are shared, and were counted when they were built.
+    /// Functions don't count their captured environment nor their body.
+    pub fn estimated_size(&self) -> usize {
+        let heap = match self {
+            Object::String(value) => value.len(),
+            Object::BigInt(value) => value.heap_size(),
+            Object::Array(elements) => elements.len() * std::mem::size_of::<Object>(),
+            Object::Hash(elements) => {
+                elements.len() * (std::mem::size_of::<HashKey>() + std::mem::size_of::<Object>())
+            }
+            Object::Integer(_)
+            | Object::Range { .. }
+            | Object::Boolean(_)
+            | Object::Function { .. }
+            | Object::Builtin(_)
+            | Object::Quote(_)
+            | Object::Macro { .. }
+            | Object::Null => 0,
+        };
+
+        std::mem::size_of::<Object>() + heap
+    }
+
+    /// The [`Display`](fmt::Display) representation of the object, shown to users.
     pub fn inspect(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Representation of the object shown to users, e.g. by `puts` and the REPL, which is stable:
+/// golden tests and transcripts rely on it, so any change must update the snapshot in the tests.
+/// It doesn't depend on the locale, and evaluating it as source gives back an equal object,
+/// except for the kinds marked otherwise:
+///
+/// - integer: decimal digits, with a leading `-` when negative, e.g. `-12`
+/// - boolean: `true` or `false`
+/// - null: `null`
+/// - string: its content as is at the top level, and as a literal with `"`, `\`, newlines,
+///   carriage returns and tabs escaped within arrays and hashes, e.g. `["a \"b\""]`
+/// - range: `start..end`, e.g. `1..5`
+/// - array: `[` elements separated by `, ` `]`
+/// - hash: `{` `key: value` pairs in the order of the keys, separated by `, ` `}`
+/// - function (not re-parseable, as its environment isn't shown): `fn(parameters) { body }`
+/// - builtin (not re-parseable): `builtin function`
+/// - quote (not re-parseable): `QUOTE(expression)`
+/// - macro (not re-parseable): `macro(parameters) { body }`
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Object::Integer(value) => value.to_string(),
-            Object::Boolean(value) => value.to_string(),
-            Object::String(value) => value.to_string(),
-            Object::Return(value) => value.inspect(),
-            Object::Error(value) => format!("Error: {}", value),
+            Object::Integer(value) => write!(f, "{value}"),
+            Object::BigInt(value) => write!(f, "{value}"),
+            Object::Boolean(value) => write!(f, "{value}"),
+            Object::String(value) => f.write_str(value),
             Object::Function {
                 parameters, body, ..
-            } => {
-                format!("fn({}) {{ {body} }}", parameters.join(", "))
-            }
-            Object::Builtin(_) => "builtin function".into(),
+            } => write!(f, "fn({}) {{ {body} }}", parameters.join(", ")),
+            Object::Builtin(_) => f.write_str("builtin function"),
+            Object::Quote(expression) => write!(f, "QUOTE({expression})"),
+            Object::Macro {
+                parameters, body, ..
+            } => write!(f, "macro({}) {{ {body} }}", parameters.join(", ")),
             Object::Array(elements) => {
-                format!(
-                    "[{}]",
-                    elements
-                        .iter()
-                        .map(|element| element.inspect())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                )
+                f.write_str("[")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", Nested(element))?;
+                }
+                f.write_str("]")
             }
             Object::Hash(elements) => {
-                format!(
-                    "{{{}}}",
-                    elements
-                        .iter()
-                        .map(|(key, value)| format!("{}: {}", key.inspect(), value.inspect()))
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                )
+                let mut elements = elements.iter().collect::<Vec<_>>();
+                elements.sort_by_key(|(key, _)| *key);
+
+                f.write_str("{")?;
+                for (index, (key, value)) in elements.into_iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    let key = Object::from(key.clone());
+                    write!(f, "{}: {}", Nested(&key), Nested(value))?;
+                }
+                f.write_str("}")
             }
-            Object::Null => "null".into(),
+            Object::Range { start, end } => write!(f, "{start}..{end}"),
+            Object::Null => f.write_str("null"),
         }
     }
 }
 
-impl From<isize> for Object {
-    fn from(value: isize) -> Self {
+/// An element of an array or hash, shown with strings quoted so that they can't be confused
+/// with the surrounding syntax.
+struct Nested<'a>(&'a Object);
+
+impl fmt::Display for Nested<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Object::String(value) => f.write_str(&quote(value)),
+            value => write!(f, "{value}"),
+        }
+    }
+}
+
+impl From<i64> for Object {
+    fn from(value: i64) -> Self {
         Self::Integer(value)
     }
 }
 
+/// An [`Object::Integer`] when the integer fits in an `i64`, so that each integer has a single
+/// representation.
+impl From<BigInt> for Object {
+    fn from(value: BigInt) -> Self {
+        match value.to_i64() {
+            Some(value) => Self::Integer(value),
+            None => Self::BigInt(value),
+        }
+    }
+}
+
 impl From<bool> for Object {
     fn from(value: bool) -> Self {
         if value {
@@ -107,25 +395,37 @@ impl From<bool> for Object {
 
 impl From<String> for Object {
     fn from(value: String) -> Self {
-        Self::String(value)
+        Self::String(value.into())
     }
 }
 
 impl From<&str> for Object {
     fn from(value: &str) -> Self {
-        Self::String(value.to_string())
+        Self::String(value.into())
+    }
+}
+
+impl From<Rc<str>> for Object {
+    fn from(value: Rc<str>) -> Self {
+        Self::String(value)
     }
 }
 
 impl From<Vec<Object>> for Object {
     fn from(value: Vec<Object>) -> Self {
-        Self::Array(value)
+        Self::Array(value.into())
     }
 }
 
-impl From<BTreeMap<Object, Object>> for Object {
-    fn from(value: BTreeMap<Object, Object>) -> Self {
-        Self::Hash(value)
+impl From<&[Object]> for Object {
+    fn from(value: &[Object]) -> Self {
+        Self::Array(value.into())
+    }
+}
+
+impl From<HashMap<HashKey, Object>> for Object {
+    fn from(value: HashMap<HashKey, Object>) -> Self {
+        Self::Hash(Rc::new(value))
     }
 }
 
@@ -158,26 +458,27 @@ mod tests {
             (FALSE, "BOOLEAN"),
             (NULL, "NULL"),
             ("foobar".into(), "STRING"),
-            (Object::Return(Box::new(5.into())), "RETURN"),
-            (Object::Error("foobar".into()), "ERROR"),
             (
                 Object::Function {
-                    parameters: vec![],
-                    environment: Environment::new(),
-                    body: BlockStatement::default(),
+                    parameters: Rc::from([]),
+                    environment: Environment::shared(),
+                    body: Rc::default(),
                 },
                 "FUNCTION",
             ),
-            (Object::Builtin(|_| Some(TRUE)), "BUILTIN"),
+            (Object::Builtin(|_, _| Ok(TRUE)), "BUILTIN"),
+            (Object::Range { start: 1, end: 5 }, "RANGE"),
             (vec![TRUE, FALSE, NULL].into(), "ARRAY"),
-            (BTreeMap::from([(TRUE, "true".into())]).into(), "HASH"),
+            (HashMap::from([(true.into(), "true".into())]).into(), "HASH"),
         ];
 
-        for (input, expected) in tests.into_iter().cloned() {
-            assert_eq!(input.kind(), expected);
+        for (input, expected) in tests {
+            assert_eq!(input.kind(), *expected);
         }
     }
 
+    /// Snapshot of the grammar documented on the `Display` of [`Object`], one value per kind and
+    /// nested composites. Changing it changes what users see, so it must be deliberate.
     #[test]
     fn test_object_inspect() {
         let tests = &[
@@ -185,42 +486,207 @@ mod tests {
             (TRUE, "true"),
             (FALSE, "false"),
             ("foobar".into(), "foobar"),
-            (Object::Return(Box::new(Object::Integer(5))), "5"),
-            (Object::Error("foobar".into()), "Error: foobar"),
             (
                 Object::Function {
-                    parameters: vec!["x".into(), "y".into()],
-                    environment: Environment::new(),
-                    body: BlockStatement {
+                    parameters: Rc::from(["x".into(), "y".into()]),
+                    environment: Environment::shared(),
+                    body: Rc::new(BlockStatement {
                         statements: vec![Statement::Expression {
                             value: Expression::InfixOperator {
                                 operator: Token::PlusSign,
-                                lh_expression: Box::new(Expression::Identifier("x".into())),
-                                rh_expression: Box::new(Expression::Identifier("y".into())),
-                            },
-                        }],
-                    },
+                                lh_expression: Box::new(Expression::Identifier("x".into()).into()),
+                                rh_expression: Box::new(Expression::Identifier("y".into()).into()),
+                            }
+                            .into(),
+                        }
+                        .into()],
+                        ..Default::default()
+                    }),
                 },
                 "fn(x, y) { (x + y) }",
             ),
-            (Object::Builtin(|_| Some(TRUE)), "builtin function"),
+            (Object::Builtin(|_, _| Ok(TRUE)), "builtin function"),
+            (Object::Range { start: 1, end: 5 }, "1..5"),
+            (Object::Range { start: -3, end: -5 }, "-3..-5"),
+            (Object::from(vec![TRUE, FALSE, NULL]), "[true, false, null]"),
+            (
+                Object::from(HashMap::from([
+                    (true.into(), "true".into()),
+                    (false.into(), "false".into()),
+                    (1.into(), NULL),
+                ])),
+                r#"{1: null, false: "false", true: "true"}"#,
+            ),
+            (NULL, "null"),
+            (Object::Integer(-12), "-12"),
+            ("say \"hi\"\n".into(), "say \"hi\"\n"),
             (
-                Object::Array(vec![TRUE, FALSE, NULL]),
-                "[true, false, null]",
+                Object::from(vec![
+                    "a, b".into(),
+                    "c".into(),
+                    Object::from(vec![1.into(), Object::Range { start: 0, end: 2 }]),
+                    Object::from(HashMap::from([("k\"ey".into(), vec![].into())])),
+                ]),
+                r#"["a, b", "c", [1, 0..2], {"k\"ey": []}]"#,
             ),
             (
-                Object::Hash(BTreeMap::from([
-                    (TRUE, "true".into()),
-                    (FALSE, "false".into()),
-                    (NULL, "null".into()),
+                Object::from(HashMap::from([
+                    (1.into(), Object::from(HashMap::new())),
+                    ("tab\there".into(), Object::from(vec!["\\".into()])),
                 ])),
-                r#"{false: false, true: true, null: null}"#,
+                r#"{1: {}, "tab\there": ["\\"]}"#,
+            ),
+            (
+                Object::from(vec![r#"say "hi", then"#.into(), "c".into()]),
+                r#"["say \"hi\", then", "c"]"#,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(input.to_string(), *expected);
+        }
+    }
+
+    #[test]
+    fn test_object_display() {
+        let function = |body: &str| {
+            let program = crate::parse(body).unwrap();
+            BlockStatement {
+                statements: program.statements,
+                ..Default::default()
+            }
+        };
+        let tests: Vec<(Object, &str)> = vec![
+            (5.into(), "5"),
+            ((-12).into(), "-12"),
+            (
+                BigInt::parse("92233720368547758070").unwrap().into(),
+                "92233720368547758070",
             ),
+            (TRUE, "true"),
             (NULL, "null"),
+            ("top \"level\"".into(), r#"top "level""#),
+            (
+                Object::Function {
+                    parameters: Rc::from(["x".into(), "y".into()]),
+                    environment: Environment::shared(),
+                    body: Rc::new(function("x * y")),
+                },
+                "fn(x, y) { (x * y) }",
+            ),
+            (Object::Builtin(|_, _| Ok(NULL)), "builtin function"),
+            (
+                Object::Quote(Box::new(Expression::Identifier("a".into()))),
+                "QUOTE(a)",
+            ),
+            (
+                Object::Macro {
+                    parameters: vec!["x".into()],
+                    environment: Environment::shared(),
+                    body: function("quote(unquote(x))"),
+                },
+                "macro(x) { quote(unquote(x)) }",
+            ),
+            (Object::Range { start: -1, end: 3 }, "-1..3"),
+            (
+                Object::from(HashMap::from([(
+                    "outer".into(),
+                    Object::from(HashMap::from([
+                        (1.into(), Object::from(vec!["a, b".into(), NULL])),
+                        (
+                            true.into(),
+                            HashMap::from([("c".into(), "d".into())]).into(),
+                        ),
+                    ])),
+                )])),
+                r#"{"outer": {1: ["a, b", null], true: {"c": "d"}}}"#,
+            ),
         ];
 
-        for (input, expected) in tests.into_iter().cloned() {
-            assert_eq!(input.inspect(), expected);
+        for (object, expected) in tests {
+            assert_eq!(object.to_string(), expected);
+        }
+    }
+
+    /// Pseudo-random objects of every kind that has a literal, nested up to `depth` levels.
+    struct Objects {
+        state: u64,
+    }
+
+    impl Objects {
+        fn next(&mut self, bound: u64) -> u64 {
+            // xorshift64
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            self.state % bound
+        }
+
+        fn scalar(&mut self) -> Object {
+            const CHARACTERS: &[char] = &[
+                'a', 'Z', ' ', ',', ':', '"', '\\', '\n', '\t', 'é', '[', '}',
+            ];
+
+            match self.next(5) {
+                0 => (self.next(2_000) as i64 - 1_000).into(),
+                1 => (self.next(2) == 0).into(),
+                2 => NULL,
+                _ => (0..self.next(6))
+                    .map(|_| CHARACTERS[self.next(CHARACTERS.len() as u64) as usize])
+                    .collect::<String>()
+                    .into(),
+            }
+        }
+
+        fn key(&mut self) -> HashKey {
+            loop {
+                if let Ok(key) = HashKey::try_from(&self.scalar()) {
+                    return key;
+                }
+            }
+        }
+
+        fn object(&mut self, depth: usize) -> Object {
+            match self.next(if depth == 0 { 2 } else { 5 }) {
+                0 | 1 => self.scalar(),
+                2 => {
+                    let start = self.next(20) as i64 - 10;
+                    Object::Range {
+                        start,
+                        end: start + self.next(10) as i64,
+                    }
+                }
+                3 => (0..self.next(4))
+                    .map(|_| self.object(depth - 1))
+                    .collect::<Vec<_>>()
+                    .into(),
+                _ => (0..self.next(4))
+                    .map(|_| (self.key(), self.object(depth - 1)))
+                    .collect::<HashMap<_, _>>()
+                    .into(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_object_inspect_round_trips() {
+        let mut objects = Objects {
+            state: 0x2545_f491_4f6c_dd1d,
+        };
+
+        for _ in 0..500 {
+            let object = match objects.object(3) {
+                // A top-level string is shown as is, its literal is what's within composites
+                Object::String(value) => vec![value.into()].into(),
+                object => object,
+            };
+            let source = object.inspect();
+
+            let program =
+                crate::parse(&source).unwrap_or_else(|errors| panic!("{source}: {errors:?}"));
+            let evaluated = crate::evaluator::Evaluator::eval(&program, &Environment::shared());
+
+            assert_eq!(evaluated, Ok(Some(object)), "{source}");
         }
     }
 }
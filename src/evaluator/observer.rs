@@ -0,0 +1,212 @@
+use std::io::Write;
+
+use crate::{
+    ast::{Expression, Spanned, Statement},
+    evaluator::{environment::Environment, error::Frame, object::Object},
+};
+
+/// Hooks called as a program is evaluated, once installed as the
+/// [`observer`](crate::evaluator::context::EvalContext::observer) of its context. Every hook does
+/// nothing unless overridden, and nothing is called without an observer.
+pub trait EvalObserver {
+    /// Before `statement` is evaluated in `environment`.
+    fn on_statement(&mut self, _statement: &Spanned<Statement>, _environment: &Environment) {}
+
+    /// Once `expression` evaluated to `value`. The `if`s and calls ending a function body have
+    /// no result of their own, their value being the one of the call they end.
+    fn on_expression_result(&mut self, _expression: &Expression, _value: &Object) {}
+
+    /// Before the function of `frame`, one written in Monkey rather than a builtin, is applied to
+    /// `arguments`.
+    fn on_call(&mut self, _frame: &Frame, _arguments: &[Object]) {}
+
+    /// Once the call of `frame` ended, returning, failing or handing over to the call it ends
+    /// with, which is then reported at the same depth.
+    fn on_return(&mut self, _frame: &Frame) {}
+}
+
+/// Writes each statement, call and expression result as it is evaluated, one per line, indented
+/// by the depth of the calls in progress.
+pub struct TraceObserver<W: Write> {
+    output: W,
+    depth: usize,
+}
+
+impl<W: Write> TraceObserver<W> {
+    pub fn new(output: W) -> Self {
+        Self { output, depth: 0 }
+    }
+
+    /// Writes `line` at the current depth. The trace is best effort, failing to write it doesn't
+    /// stop the evaluation.
+    fn line(&mut self, line: std::fmt::Arguments) {
+        let _ = writeln!(self.output, "{:width$}{line}", "", width = self.depth * 2);
+    }
+}
+
+impl<W: Write> EvalObserver for TraceObserver<W> {
+    fn on_statement(&mut self, statement: &Spanned<Statement>, _environment: &Environment) {
+        self.line(format_args!("{statement}"));
+    }
+
+    fn on_expression_result(&mut self, expression: &Expression, value: &Object) {
+        self.line(format_args!("{expression} => {value}"));
+    }
+
+    fn on_call(&mut self, frame: &Frame, arguments: &[Object]) {
+        let arguments = arguments
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        self.line(format_args!(
+            "call {}({})",
+            frame.function,
+            arguments.join(", ")
+        ));
+        self.depth += 1;
+    }
+
+    fn on_return(&mut self, _frame: &Frame) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        evaluator::{context::EvalContext, Evaluator},
+        parse,
+    };
+
+    use super::*;
+
+    /// Every event, as a line, shared with the test once the observer is installed.
+    #[derive(Clone, Default)]
+    struct Recorder(Rc<RefCell<Vec<String>>>);
+
+    impl EvalObserver for Recorder {
+        fn on_statement(&mut self, statement: &Spanned<Statement>, environment: &Environment) {
+            let bound = environment
+                .names(usize::MAX)
+                .into_iter()
+                .collect::<Vec<_>>();
+            self.0
+                .borrow_mut()
+                .push(format!("statement {statement} with [{}]", bound.join(", ")));
+        }
+
+        fn on_expression_result(&mut self, expression: &Expression, value: &Object) {
+            self.0
+                .borrow_mut()
+                .push(format!("result {expression} => {value}"));
+        }
+
+        fn on_call(&mut self, frame: &Frame, arguments: &[Object]) {
+            self.0
+                .borrow_mut()
+                .push(format!("call {} with {}", frame.function, arguments.len()));
+        }
+
+        fn on_return(&mut self, frame: &Frame) {
+            self.0
+                .borrow_mut()
+                .push(format!("return {}", frame.function));
+        }
+    }
+
+    fn eval(input: &str, observer: impl EvalObserver + 'static) -> Result<Option<Object>, String> {
+        let mut context = EvalContext::default();
+        context.observer = Some(Box::new(observer));
+        parse(input)
+            .unwrap()
+            .eval_with_context(&Environment::shared(), &mut context)
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn test_observer_events() {
+        let recorder = Recorder::default();
+        let input = "let double = fn(x) { let y = x * 2; y }; if (double(2) > 3) { 1 } else { 0 }";
+
+        assert_eq!(eval(input, recorder.clone()), Ok(Some(1.into())));
+        assert_eq!(
+            *recorder.0.borrow(),
+            [
+                "statement let double = fn(x) {let y = (x * 2);y}; with []",
+                "result fn(x) {let y = (x * 2);y} => fn(x) { let y = (x * 2);y }",
+                "statement if ((double(2) > 3)) {1}else {0} with [double]",
+                "result 2 => 2",
+                "call double with 1",
+                "statement let y = (x * 2); with [double, x]",
+                "result x => 2",
+                "result 2 => 2",
+                "result (x * 2) => 4",
+                "statement y with [double, x, y]",
+                "result y => 4",
+                "return double",
+                "result double(2) => 4",
+                "result 3 => 3",
+                "result (double(2) > 3) => true",
+                "statement 1 with [double]",
+                "result 1 => 1",
+                "result if ((double(2) > 3)) {1}else {0} => 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trace_observer() {
+        #[derive(Clone, Default)]
+        struct Output(Rc<RefCell<Vec<u8>>>);
+
+        impl Write for Output {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.borrow_mut().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let output = Output::default();
+        let input = "let f = fn(n) { if (n > 0) { 1 + f(n - 1) } else { 0 } }; f(1)";
+
+        assert_eq!(
+            eval(input, TraceObserver::new(output.clone())),
+            Ok(Some(1.into()))
+        );
+        let expected = [
+            "let f = fn(n) {if ((n > 0)) {(1 + f((n - 1)))}else {0}};",
+            "fn(n) {if ((n > 0)) {(1 + f((n - 1)))}else {0}} => fn(n) { if ((n > 0)) {(1 + f((n - 1)))}else {0} }",
+            "f(1)",
+            "1 => 1",
+            "call f(1)",
+            "  if ((n > 0)) {(1 + f((n - 1)))}else {0}",
+            "  n => 1",
+            "  0 => 0",
+            "  (n > 0) => true",
+            "  (1 + f((n - 1)))",
+            "  1 => 1",
+            "  n => 1",
+            "  1 => 1",
+            "  (n - 1) => 0",
+            "  call f(0)",
+            "    if ((n > 0)) {(1 + f((n - 1)))}else {0}",
+            "    n => 0",
+            "    0 => 0",
+            "    (n > 0) => false",
+            "    0",
+            "    0 => 0",
+            "  f((n - 1)) => 0",
+            "  (1 + f((n - 1))) => 1",
+            "f(1) => 1",
+        ];
+        assert_eq!(
+            String::from_utf8(output.0.take()).unwrap(),
+            expected.map(|line| format!("{line}\n")).concat()
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::{collections::HashMap, time::Duration};
+
+/// Counts the evaluations and accumulates the wall time spent per function, builtin and kind of
+/// expression, once installed as the [`profiler`](crate::evaluator::context::EvalContext::profiler)
+/// of a context.
+///
+/// Times are inclusive: the time of a call or expression includes the one of everything
+/// evaluated within it. Profiling reads the clock, which isn't available on WebAssembly.
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    functions: HashMap<String, Hits>,
+    builtins: HashMap<String, Hits>,
+    expressions: HashMap<&'static str, Hits>,
+}
+
+/// How many times something was evaluated, and how long it took in total.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Hits {
+    pub count: usize,
+    pub time: Duration,
+}
+
+/// Line of a [`ProfileReport`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ProfileEntry {
+    pub name: String,
+    pub hits: Hits,
+}
+
+/// What a [`Profiler`] gathered, each list sorted from the most time spent to the least.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct ProfileReport {
+    /// Functions written in Monkey, by the name they were called by
+    pub functions: Vec<ProfileEntry>,
+    pub builtins: Vec<ProfileEntry>,
+    /// Expressions, by kind as named by [`Expression::kind`](crate::ast::Expression::kind)
+    pub expressions: Vec<ProfileEntry>,
+}
+
+impl Hits {
+    fn add(&mut self, time: Duration) {
+        self.count += 1;
+        self.time += time;
+    }
+}
+
+impl Profiler {
+    /// Records a call of the Monkey function called by `name` which took `time`.
+    pub(crate) fn function(&mut self, name: &str, time: Duration) {
+        record(&mut self.functions, name, time);
+    }
+
+    /// Records a call of the builtin called by `name` which took `time`.
+    pub(crate) fn builtin(&mut self, name: &str, time: Duration) {
+        record(&mut self.builtins, name, time);
+    }
+
+    /// Records the evaluation of an expression of `kind` which took `time`.
+    pub(crate) fn expression(&mut self, kind: &'static str, time: Duration) {
+        self.expressions.entry(kind).or_default().add(time);
+    }
+
+    pub fn report(&self) -> ProfileReport {
+        ProfileReport {
+            functions: entries(&self.functions),
+            builtins: entries(&self.builtins),
+            expressions: entries(&self.expressions),
+        }
+    }
+}
+
+/// Records in `hits` a call by `name`, only allocating the name the first time it is called.
+fn record(hits: &mut HashMap<String, Hits>, name: &str, time: Duration) {
+    match hits.get_mut(name) {
+        Some(hits) => hits.add(time),
+        None => {
+            let mut first = Hits::default();
+            first.add(time);
+            hits.insert(name.to_string(), first);
+        }
+    }
+}
+
+/// Entries of `hits` from the most time spent to the least, then by name.
+fn entries<K: AsRef<str>>(hits: &HashMap<K, Hits>) -> Vec<ProfileEntry> {
+    let mut entries = hits
+        .iter()
+        .map(|(name, hits)| ProfileEntry {
+            name: name.as_ref().to_string(),
+            hits: *hits,
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| b.hits.time.cmp(&a.hits.time).then(a.name.cmp(&b.name)));
+    entries
+}
+
+/// One table per list, leaving out the empty ones, e.g.
+///
+/// ```text
+/// functions
+///   fib        177 calls             1.204ms
+/// ```
+impl std::fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tables = [
+            ("functions", "calls", &self.functions),
+            ("builtins", "calls", &self.builtins),
+            ("expressions", "evaluations", &self.expressions),
+        ];
+        let width = tables
+            .iter()
+            .flat_map(|(_, _, entries)| entries.iter().map(|entry| entry.name.chars().count()))
+            .max()
+            .unwrap_or_default();
+
+        for (title, unit, entries) in tables {
+            if entries.is_empty() {
+                continue;
+            }
+
+            writeln!(f, "{title}")?;
+            for ProfileEntry { name, hits } in entries {
+                writeln!(
+                    f,
+                    "  {name:width$}  {:>9} {unit:11}  {:>12}",
+                    hits.count,
+                    format!("{:.3?}", hits.time),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluator::{context::EvalContext, environment::Environment, Evaluator},
+        parse,
+    };
+
+    use super::*;
+
+    fn profile(input: &str) -> ProfileReport {
+        let mut context = EvalContext::default();
+        context.profiler = Some(Profiler::default());
+        parse(input)
+            .unwrap()
+            .eval_with_context(&Environment::shared(), &mut context)
+            .unwrap();
+        context.profiler.unwrap().report()
+    }
+
+    fn count(entries: &[ProfileEntry], name: &str) -> Option<usize> {
+        entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.hits.count)
+    }
+
+    #[test]
+    fn test_hot_helper() {
+        let report = profile(
+            "let square = fn(x) { x * x + len([]) };
+            let sum = fn(n, total) { if (n == 0) { total } else { sum(n - 1, total + square(n)) } };
+            sum(100, 0)",
+        );
+
+        assert_eq!(count(&report.functions, "square"), Some(100));
+        assert_eq!(count(&report.functions, "sum"), Some(101));
+        assert_eq!(report.functions.len(), 2);
+        assert_eq!(count(&report.builtins, "len"), Some(100));
+        assert_eq!(count(&report.expressions, "array"), Some(100));
+        assert_eq!(count(&report.expressions, "function"), Some(2));
+        assert_eq!(count(&report.expressions, "function_call"), Some(301));
+        assert_eq!(count(&report.expressions, "if"), Some(101));
+        assert!(count(&report.expressions, "infix_operator").unwrap() > 400);
+        assert!(report
+            .functions
+            .windows(2)
+            .all(|pair| pair[0].hits.time >= pair[1].hits.time));
+    }
+
+    #[test]
+    fn test_report_display() {
+        let entry = |name: &str, count, millis| ProfileEntry {
+            name: name.into(),
+            hits: Hits {
+                count,
+                time: Duration::from_millis(millis),
+            },
+        };
+        let report = ProfileReport {
+            functions: vec![entry("fib", 177, 12)],
+            builtins: vec![],
+            expressions: vec![entry("function_call", 177, 12), entry("integer", 530, 1)],
+        };
+
+        assert_eq!(
+            report.to_string(),
+            "functions
+  fib                  177 calls            12.000ms
+expressions
+  function_call        177 evaluations      12.000ms
+  integer              530 evaluations       1.000ms
+"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use std::io::{self, IsTerminal, Write};
+
+/// When the output of a program is handed to the underlying writer.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Buffering {
+    /// Kept until a newline is written or the sink is flushed
+    Line,
+    /// Written right away
+    Unbuffered,
+}
+
+/// Destination of what programs print, such as through `puts`. Anything still buffered is
+/// written out when the sink is dropped.
+pub struct Sink {
+    writer: Box<dyn Write>,
+    buffering: Buffering,
+    buffer: Vec<u8>,
+}
+
+impl Sink {
+    pub fn new(writer: impl Write + 'static, buffering: Buffering) -> Self {
+        Self {
+            writer: Box::new(writer),
+            buffering,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Standard output, unbuffered when it is a terminal so that prompts show up as they are
+    /// printed, and line-buffered otherwise.
+    pub fn stdout() -> Self {
+        let buffering = if io::stdout().is_terminal() {
+            Buffering::Unbuffered
+        } else {
+            Buffering::Line
+        };
+
+        Self::new(io::stdout(), buffering)
+    }
+
+    pub fn buffering(&self) -> Buffering {
+        self.buffering
+    }
+
+    pub fn write_str(&mut self, text: &str) -> io::Result<()> {
+        match self.buffering {
+            Buffering::Unbuffered => {
+                self.writer.write_all(text.as_bytes())?;
+                self.writer.flush()
+            }
+            Buffering::Line => {
+                self.buffer.extend_from_slice(text.as_bytes());
+
+                let Some(last_newline) = self.buffer.iter().rposition(|byte| *byte == b'\n') else {
+                    return Ok(());
+                };
+                let lines = self.buffer.drain(..=last_newline).collect::<Vec<_>>();
+                self.writer.write_all(&lines)?;
+                self.writer.flush()
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.writer.flush()
+    }
+}
+
+impl Default for Sink {
+    fn default() -> Self {
+        Self::stdout()
+    }
+}
+
+impl std::fmt::Debug for Sink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sink")
+            .field("buffering", &self.buffering)
+            .field("buffered", &self.buffer.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Sink {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedWriter(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_line_buffering() {
+        let written = SharedWriter::default();
+        let mut sink = Sink::new(written.clone(), Buffering::Line);
+
+        sink.write_str("a").unwrap();
+        assert_eq!(*written.0.borrow(), b"");
+        sink.write_str("b\nc").unwrap();
+        assert_eq!(*written.0.borrow(), b"ab\n");
+        sink.flush().unwrap();
+        assert_eq!(*written.0.borrow(), b"ab\nc");
+
+        sink.write_str("d").unwrap();
+        drop(sink);
+        assert_eq!(*written.0.borrow(), b"ab\ncd");
+    }
+
+    #[test]
+    fn test_unbuffered() {
+        let written = SharedWriter::default();
+        let mut sink = Sink::new(written.clone(), Buffering::Unbuffered);
+
+        sink.write_str("a").unwrap();
+        assert_eq!(*written.0.borrow(), b"a");
+    }
+}
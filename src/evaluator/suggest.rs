@@ -0,0 +1,126 @@
+//! "Did you mean" hints for names that aren't bound, picked among the bindings in scope and the
+//! builtins.
+
+use crate::evaluator::{builtins::Builtin, environment::Environment};
+
+/// Most edits a name can be away from the one looked up to be suggested.
+const MAX_DISTANCE: usize = 2;
+
+/// Bindings compared against the name looked up, so that a huge environment doesn't make an
+/// error expensive to build.
+const MAX_CANDIDATES: usize = 1_000;
+
+/// Error for the unbound `name`, e.g. `identifier not found: fibb, did you mean "fib"?`, where
+/// `kind` is what was looked up.
+pub(crate) fn not_found(kind: &str, name: &str, environment: &Environment) -> String {
+    match suggestion(name, environment) {
+        Some(suggestion) => format!("{kind} not found: {name}, did you mean \"{suggestion}\"?"),
+        None => format!("{kind} not found: {name}"),
+    }
+}
+
+/// Closest name in scope to `name`, the first alphabetically among the closest ones. Names
+/// differing by as many edits as they have characters aren't considered close, e.g. `b` for `a`,
+/// and neither is `name` itself, e.g. a builtin looked up where only bindings are.
+fn suggestion(name: &str, environment: &Environment) -> Option<String> {
+    let length = name.chars().count();
+    let mut candidates = environment.names(MAX_CANDIDATES);
+    candidates.extend(Builtin::NAMES.iter().map(ToString::to_string));
+
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.chars().count().abs_diff(length) <= MAX_DISTANCE)
+        .map(|candidate| (distance(name, &candidate), candidate))
+        .filter(|(distance, _)| (1..=MAX_DISTANCE).contains(distance) && *distance < length)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Edits turning `from` into `to`, counting the insertion, deletion or substitution of a
+/// character, or the swap of two adjacent ones, as one.
+fn distance(from: &str, to: &str) -> usize {
+    let from = from.chars().collect::<Vec<_>>();
+    let to = to.chars().collect::<Vec<_>>();
+
+    // Distances from the prefixes of `from` to the prefixes of `to`, two rows back and one back
+    let mut before_previous = vec![0; to.len() + 1];
+    let mut previous = (0..=to.len()).collect::<Vec<_>>();
+
+    for i in 1..=from.len() {
+        let mut current = vec![i; to.len() + 1];
+        for j in 1..=to.len() {
+            let substitution = usize::from(from[i - 1] != to[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + substitution);
+
+            if i > 1 && j > 1 && from[i - 1] == to[j - 2] && from[i - 2] == to[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1);
+            }
+        }
+        before_previous = std::mem::replace(&mut previous, current);
+    }
+
+    previous[to.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{evaluator::Evaluator, parse};
+
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        let tests = &[
+            ("fib", "fib", 0),
+            ("fibb", "fib", 1),
+            ("pust", "puts", 1),
+            ("lenght", "length", 1),
+            ("lenght", "len", 3),
+            ("", "abc", 3),
+            ("héllo", "hello", 1),
+        ];
+
+        for (from, to, expected) in tests {
+            assert_eq!(distance(from, to), *expected, "{from} -> {to}");
+            assert_eq!(distance(to, from), *expected, "{to} -> {from}");
+        }
+    }
+
+    #[test]
+    fn test_not_found_suggestions() {
+        let tests = &[
+            (
+                "lne([1])",
+                r#"function not found: lne, did you mean "len"?"#,
+            ),
+            (
+                "let fib = fn(n) { n }; fibb(10)",
+                r#"function not found: fibb, did you mean "fib"?"#,
+            ),
+            (
+                "let counter = 1; countr + 1",
+                r#"identifier not found: countr, did you mean "counter"?"#,
+            ),
+            (
+                "let f = fn(total) { totl++ }; f(1)",
+                r#"identifier not found: totl, did you mean "total"?"#,
+            ),
+            ("let a = 1; b", "identifier not found: b"),
+            ("foobar", "identifier not found: foobar"),
+            ("len++", "identifier not found: len"),
+        ];
+
+        for (input, expected) in tests {
+            let program = parse(input).expect("Failed to parse program");
+            assert_eq!(
+                program
+                    .eval(&Environment::shared())
+                    .map_err(|error| error.cause().clone()),
+                Err((*expected).into()),
+                "{input}"
+            );
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    ast::{BlockStatement, Expression, Spanned, Statement},
+    lexer::{quote, Lexer, Token},
+    parser::{ExpressionPrecedence, Program},
+};
+
+const INDENT: &str = "    ";
+
+/// Width past which a hash literal gets one entry per line rather than all on one.
+const MAX_INLINE_HASH_WIDTH: usize = 60;
+
+/// Source of `program` laid out the way Monkey is usually written: one statement per line,
+/// blocks indented by four spaces, and only the parentheses and semicolons it needs to parse
+/// back to the same program. Comments are kept before the statement they were attached to.
+pub fn format(program: &Program) -> String {
+    let mut source = String::new();
+    lines(&mut source, 0, &program.statements, &program.comments);
+    source
+}
+
+/// Writes `statements` on their own lines, indented to `depth`.
+fn lines(
+    source: &mut String,
+    depth: usize,
+    statements: &[Spanned<Statement>],
+    comments: &BTreeMap<usize, Vec<String>>,
+) {
+    let indent = INDENT.repeat(depth);
+    let write_comments = |source: &mut String, index| {
+        for comment in comments.get(&index).into_iter().flatten() {
+            source.push_str(&format!("{indent}//{comment}\n"));
+        }
+    };
+
+    let statements = statements
+        .iter()
+        .map(|statement| (statement, self::statement(depth, statement)))
+        .collect::<Vec<_>>();
+
+    for (index, (statement, line)) in statements.iter().enumerate() {
+        write_comments(source, index);
+        source.push_str(&indent);
+        source.push_str(line);
+
+        let next = statements.get(index + 1).map(|(_, line)| line.as_str());
+        if needs_semicolon(statement, next) {
+            source.push(';');
+        }
+        source.push('\n');
+    }
+    write_comments(source, statements.len());
+}
+
+/// Whether `statement` has to be ended by a `;`, either by the grammar or so that it doesn't run
+/// into the `next` one. Expressions ending with a block are only continued by a `(`, `[`, `-` or
+/// `+` on the next line, as a call, an index or an infix operator.
+fn needs_semicolon(statement: &Statement, next: Option<&str>) -> bool {
+    let Statement::Expression { value } = statement else {
+        return true;
+    };
+
+    match next {
+        None => false,
+        Some(next) => {
+            !matches!(
+                value.node,
+                Expression::If { .. }
+                    | Expression::For { .. }
+                    | Expression::While { .. }
+                    | Expression::Function { .. }
+                    | Expression::Macro { .. }
+            ) || next.starts_with(['(', '[', '-', '+'])
+        }
+    }
+}
+
+fn statement(depth: usize, statement: &Statement) -> String {
+    match statement {
+        Statement::Let { name, value } => format!("let {name} = {}", expression(depth, value)),
+        Statement::Const { name, value } => format!("const {name} = {}", expression(depth, value)),
+        Statement::Destructure { pattern, value } => {
+            format!("let {pattern} = {}", expression(depth, value))
+        }
+        Statement::Return { value } => format!("return {}", expression(depth, value)),
+        Statement::Throw { value } => format!("throw {}", expression(depth, value)),
+        Statement::Expression { value } => expression(depth, value),
+    }
+}
+
+fn block(depth: usize, block: &BlockStatement) -> String {
+    if block.statements.is_empty() && block.comments.is_empty() {
+        return "{}".into();
+    }
+
+    let mut source = "{\n".to_string();
+    lines(&mut source, depth + 1, &block.statements, &block.comments);
+    source.push_str(&INDENT.repeat(depth));
+    source.push('}');
+    source
+}
+
+/// Precedence `expression` binds with, the one of the operator it was parsed from. Literals and
+/// the expressions delimited by their own brackets never need to be parenthesized.
+fn precedence(expression: &Expression) -> ExpressionPrecedence {
+    match expression {
+        Expression::InfixOperator { operator, .. } => operator.precedence(),
+        Expression::Range { .. } => ExpressionPrecedence::Range,
+        Expression::PrefixOperator { .. } => ExpressionPrecedence::Prefix,
+        Expression::Postfix { .. } => ExpressionPrecedence::Postfix,
+        Expression::FunctionCall { .. } => ExpressionPrecedence::Call,
+        _ => ExpressionPrecedence::Index,
+    }
+}
+
+/// `operand` of an operator, parenthesized when it binds looser than `minimum`, or as loose when
+/// `strict`, e.g. for the right-hand side of a left-associative operator.
+fn operand(
+    depth: usize,
+    operand: &Expression,
+    minimum: ExpressionPrecedence,
+    strict: bool,
+) -> String {
+    let precedence = precedence(operand);
+    let source = expression(depth, operand);
+
+    if precedence < minimum || (strict && precedence == minimum) {
+        format!("({source})")
+    } else {
+        source
+    }
+}
+
+/// `index` as the name of a member, when it can be written as `.name`.
+fn member(index: &Expression) -> Option<&str> {
+    let Expression::String(name) = index else {
+        return None;
+    };
+
+    let tokens = Lexer::new(name.clone()).collect::<Vec<_>>();
+    (tokens == [Token::Identifier(name.as_str().into()), Token::Eof]).then_some(name)
+}
+
+fn list(depth: usize, expressions: &[Spanned<Expression>]) -> String {
+    expressions
+        .iter()
+        .map(|expression| self::expression(depth, expression))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn hash(depth: usize, entries: &BTreeMap<Spanned<Expression>, Spanned<Expression>>) -> String {
+    let entries = entries
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}: {}",
+                expression(depth + 1, key),
+                expression(depth + 1, value)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let inline = format!("{{{}}}", entries.join(", "));
+    if inline.len() <= MAX_INLINE_HASH_WIDTH && !inline.contains('\n') {
+        return inline;
+    }
+
+    let indent = INDENT.repeat(depth + 1);
+    let mut source = "{\n".to_string();
+    for entry in entries {
+        source.push_str(&format!("{indent}{entry},\n"));
+    }
+    source.push_str(&INDENT.repeat(depth));
+    source.push('}');
+    source
+}
+
+/// `expression` written at `depth`, the indentation of the line it starts on.
+fn expression(depth: usize, expression: &Expression) -> String {
+    match expression {
+        Expression::Integer(value) => value.to_string(),
+        Expression::BigInt(value) => value.to_string(),
+        Expression::Identifier(name) => name.to_string(),
+        Expression::Boolean(value) => value.to_string(),
+        Expression::Null => "null".into(),
+        Expression::String(value) => quote(value),
+        Expression::PrefixOperator {
+            operator,
+            expression: inner,
+        } => {
+            // Nested prefix operators don't need parentheses, unless they would be read as a
+            // single `--` or `++` token
+            let source = match &inner.node {
+                Expression::PrefixOperator {
+                    operator: nested, ..
+                } if nested != operator || *operator == Token::ExclamationMark => {
+                    self::expression(depth, inner)
+                }
+                _ => self::operand(depth, inner, ExpressionPrecedence::Prefix, true),
+            };
+            format!("{operator}{source}")
+        }
+        Expression::InfixOperator {
+            operator,
+            lh_expression,
+            rh_expression,
+        } => {
+            let precedence = operator.precedence();
+            // `??` groups to the right, every other operator to the left
+            let right_associative = precedence == ExpressionPrecedence::Coalesce;
+            format!(
+                "{} {operator} {}",
+                operand(depth, lh_expression, precedence, right_associative),
+                operand(depth, rh_expression, precedence, !right_associative),
+            )
+        }
+        Expression::FunctionCall { name, arguments } => format!(
+            "{}({})",
+            operand(depth, name, ExpressionPrecedence::Call, false),
+            list(depth, arguments)
+        ),
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            let mut source = format!(
+                "if ({}) {}",
+                self::expression(depth, condition),
+                block(depth, consequence)
+            );
+            if let Some(alternative) = alternative {
+                source.push_str(&format!(" else {}", block(depth, alternative)));
+            }
+            source
+        }
+        Expression::For {
+            variable,
+            iterable,
+            body,
+        } => format!(
+            "for ({variable} in {}) {}",
+            self::expression(depth, iterable),
+            block(depth, body)
+        ),
+        Expression::While { condition, body } => format!(
+            "while ({}) {}",
+            self::expression(depth, condition),
+            block(depth, body)
+        ),
+        Expression::Postfix { operator, name } => format!("{name}{operator}"),
+        Expression::Function { arguments, body } => {
+            format!("fn({}) {}", arguments.join(", "), block(depth, body))
+        }
+        Expression::Macro { arguments, body } => {
+            format!("macro({}) {}", arguments.join(", "), block(depth, body))
+        }
+        Expression::Array(elements) => format!("[{}]", list(depth, elements)),
+        Expression::Index { left, index } | Expression::OptionalIndex { left, index } => {
+            let left = operand(depth, left, ExpressionPrecedence::Call, false);
+            let optional = if matches!(expression, Expression::OptionalIndex { .. }) {
+                "?"
+            } else {
+                ""
+            };
+            match member(index) {
+                Some(name) => format!("{left}{optional}.{name}"),
+                None => format!("{left}{optional}[{}]", self::expression(depth, index)),
+            }
+        }
+        Expression::Range {
+            start,
+            end,
+            inclusive,
+        } => format!(
+            "{}{}{}",
+            operand(depth, start, ExpressionPrecedence::Range, false),
+            if *inclusive { "..=" } else { ".." },
+            operand(depth, end, ExpressionPrecedence::Range, true),
+        ),
+        Expression::HashLiteral(entries) => hash(depth, entries),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse,
+        parser::{corpus, Parser},
+    };
+
+    use super::*;
+
+    fn assert_round_trips(input: &str) {
+        let program = parse(input).unwrap_or_else(|errors| panic!("{input}: {errors:?}"));
+        let formatted = format(&program);
+        let reparsed = parse(&formatted).unwrap_or_else(|errors| panic!("{formatted}: {errors:?}"));
+
+        assert_eq!(reparsed.statements, program.statements, "{formatted}");
+        assert_eq!(format(&reparsed), formatted);
+    }
+
+    #[test]
+    fn test_format_round_trips() {
+        for input in corpus::PROGRAMS {
+            assert_round_trips(input);
+        }
+        for (input, _) in corpus::PRECEDENCE {
+            assert_round_trips(input);
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let input = r#"let add=fn(a,b){return a+b;};// Sums
+let person={"name":"Monkey","age":7,"languages":["en","fr"],"greet":fn(){"hi"}};
+if(person.age>(1+2)*3){puts(person["name"])}else{
+// Too young
+null;}
+let i=0;while(i<3){i++;};
+-(-i);(a-b)-(c-d);a??(b??c);(a??b)??c;person?.address?["zip code"]"#;
+
+        let program = Parser::new(Lexer::new(input.into()))
+            .with_comments()
+            .parse_program()
+            .expect("Failed to parse program");
+
+        assert_eq!(
+            format(&program),
+            r#"let add = fn(a, b) {
+    return a + b;
+};
+// Sums
+let person = {
+    "age": 7,
+    "greet": fn() {
+        "hi"
+    },
+    "languages": ["en", "fr"],
+    "name": "Monkey",
+};
+if (person.age > (1 + 2) * 3) {
+    puts(person.name)
+} else {
+    // Too young
+    null
+}
+let i = 0;
+while (i < 3) {
+    i++
+};
+-(-i);
+a - b - (c - d);
+a ?? b ?? c;
+(a ?? b) ?? c;
+person?.address?["zip code"]
+"#
+        );
+    }
+
+    #[test]
+    fn test_format_short_hashes_inline() {
+        assert_eq!(
+            format(&parse(r#"let a={"one":1,"two":{"three":3}};{}"#).unwrap()),
+            "let a = {\"one\": 1, \"two\": {\"three\": 3}};\n{}\n"
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    cell::{Ref, RefCell},
+    rc::Rc,
+};
+
+use crate::{
+    capabilities::{capabilities, Capabilities},
+    evaluator::{
+        context::{EvalContext, EvalOptions, EvalStats},
+        environment::Environment,
+        error::EvalError,
+        macros::define_and_expand_macros,
+        object::{BuiltinFunction, Object},
+        profiler::{ProfileReport, Profiler},
+        Evaluator,
+    },
+    parse,
+    parser::Diagnostic,
+};
+
+/// Entry point for embedders, keeping the bindings of every evaluated source like the REPL.
+#[derive(Default)]
+pub struct Interpreter {
+    environment: Rc<RefCell<Environment>>,
+    context: EvalContext,
+}
+
+impl Interpreter {
+    pub fn new(options: EvalOptions) -> Self {
+        Self {
+            environment: Environment::shared(),
+            context: EvalContext::new(options),
+        }
+    }
+
+    /// Parses and evaluates `source`, returning the parser errors if it isn't valid or the
+    /// `source-too-large` one if it is over [`EvalOptions::max_source_bytes`], or else the result
+    /// of the evaluation. What it printed is flushed before returning.
+    pub fn eval(
+        &mut self,
+        source: &str,
+    ) -> Result<Result<Option<Object>, EvalError>, Vec<Diagnostic>> {
+        self.context
+            .options
+            .check_source_size(source)
+            .map_err(|diagnostic| vec![diagnostic])?;
+        let program = parse(source)?;
+        let result = define_and_expand_macros(program, &self.environment, &mut self.context)
+            .and_then(|program| program.eval_with_context(&self.environment, &mut self.context));
+        let _ = self.context.output.flush();
+        Ok(result)
+    }
+
+    pub fn environment(&self) -> Ref<'_, Environment> {
+        self.environment.borrow()
+    }
+
+    pub fn stats(&self) -> &EvalStats {
+        &self.context.stats
+    }
+
+    /// Counts and times the evaluation of each function and expression from now on, see
+    /// [`Self::profile`].
+    pub fn enable_profiling(&mut self) {
+        self.context.profiler.get_or_insert_with(Profiler::default);
+    }
+
+    /// What was profiled since [`Self::enable_profiling`], `None` when it wasn't called.
+    pub fn profile(&self) -> Option<ProfileReport> {
+        self.context.profiler.as_ref().map(Profiler::report)
+    }
+
+    /// Version and features of the interpreter, as returned by the `version()` builtin.
+    pub fn capabilities() -> Capabilities {
+        capabilities()
+    }
+}
+
+/// Why a prelude couldn't be turned into an [`InterpreterTemplate`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum PreludeError {
+    Parse(Vec<Diagnostic>),
+    /// The error the prelude evaluated to
+    Eval(EvalError),
+}
+
+/// Prelude and host functions set up once, to quickly create interpreters sharing them.
+pub struct InterpreterTemplate {
+    base: Rc<RefCell<Environment>>,
+    options: EvalOptions,
+}
+
+impl InterpreterTemplate {
+    /// Binds the host `functions`, callable like builtins, then evaluates the `prelude` with
+    /// them in scope.
+    pub fn new(
+        prelude: &str,
+        functions: &[(&str, BuiltinFunction)],
+        options: EvalOptions,
+    ) -> Result<Self, PreludeError> {
+        let base = Environment::shared();
+        for (name, function) in functions {
+            base.borrow_mut().set_ref(name, Object::Builtin(*function));
+        }
+
+        options
+            .check_source_size(prelude)
+            .map_err(|diagnostic| PreludeError::Parse(vec![diagnostic]))?;
+        let program = parse(prelude).map_err(PreludeError::Parse)?;
+        let mut context = EvalContext::new(options.clone());
+        define_and_expand_macros(program, &base, &mut context)
+            .and_then(|program| program.eval_with_context(&base, &mut context))
+            .map_err(PreludeError::Eval)?;
+
+        Ok(Self { base, options })
+    }
+
+    /// Interpreter with its own scope over the shared prelude, which is neither re-evaluated nor
+    /// cloned. Rebinding a prelude name only affects the returned interpreter, though functions
+    /// of the prelude keep updating the bindings of the prelude they captured, for every
+    /// interpreter.
+    pub fn instantiate(&self) -> Interpreter {
+        Interpreter {
+            environment: Rc::new(RefCell::new(Environment::with_base(self.base.clone()))),
+            context: EvalContext::new(self.options.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRELUDE: &str = r#"let greet = fn(name) { "hello " + name };
+let counter = 0;"#;
+
+    fn host_double(arguments: &[Object], _: &mut EvalContext) -> Result<Object, EvalError> {
+        match arguments {
+            [Object::Integer(value)] => Ok((value * 2).into()),
+            _ => Err("double expects an integer".into()),
+        }
+    }
+
+    fn template() -> InterpreterTemplate {
+        InterpreterTemplate::new(PRELUDE, &[("double", host_double)], EvalOptions::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_template_instances_are_isolated() {
+        let template = template();
+        let mut first = template.instantiate();
+        let mut second = template.instantiate();
+
+        assert_eq!(
+            first.eval(r#"greet("first")"#),
+            Ok(Ok(Some("hello first".into())))
+        );
+        assert_eq!(
+            second.eval(r#"greet("second")"#),
+            Ok(Ok(Some("hello second".into())))
+        );
+
+        assert_eq!(
+            first.eval("let counter = 1; let greet = 2; counter"),
+            Ok(Ok(Some(1.into())))
+        );
+        assert_eq!(second.eval("counter"), Ok(Ok(Some(0.into()))));
+        assert_eq!(
+            second.eval(r#"greet("again")"#),
+            Ok(Ok(Some("hello again".into())))
+        );
+        assert_eq!(first.eval("let first_only = 1;"), Ok(Ok(Some(1.into()))));
+        assert_eq!(
+            second.eval("first_only"),
+            Ok(Err("identifier not found: first_only".into()))
+        );
+    }
+
+    #[test]
+    fn test_template_host_functions() {
+        let mut interpreter = template().instantiate();
+
+        assert_eq!(interpreter.eval("double(21)"), Ok(Ok(Some(42.into()))));
+        assert_eq!(interpreter.eval("4.double()"), Ok(Ok(Some(8.into()))));
+        assert_eq!(
+            InterpreterTemplate::new(
+                "let a = double(2);",
+                &[("double", host_double)],
+                EvalOptions::default()
+            )
+            .unwrap()
+            .instantiate()
+            .eval("a"),
+            Ok(Ok(Some(4.into())))
+        );
+    }
+
+    #[test]
+    fn test_template_instantiation_shares_the_prelude() {
+        let template = template();
+        let interpreters = (0..100).map(|_| template.instantiate()).collect::<Vec<_>>();
+
+        // One for the template, one for the `greet` it defines, one per interpreter
+        assert_eq!(Rc::strong_count(&template.base), 102);
+        assert!(interpreters
+            .iter()
+            .all(|interpreter| interpreter.environment().get("greet").is_some()));
+    }
+
+    #[test]
+    fn test_template_prelude_errors() {
+        assert!(matches!(
+            InterpreterTemplate::new("let = 1;", &[], EvalOptions::default()),
+            Err(PreludeError::Parse(_))
+        ));
+        assert_eq!(
+            InterpreterTemplate::new("let a = b;", &[], EvalOptions::default()).err(),
+            Some(PreludeError::Eval("identifier not found: b".into()))
+        );
+    }
+
+    #[test]
+    fn test_interpreter_keeps_bindings() {
+        let mut interpreter = Interpreter::default();
+
+        assert_eq!(interpreter.eval("let a = 5;"), Ok(Ok(Some(5.into()))));
+        assert_eq!(interpreter.eval("a * 2"), Ok(Ok(Some(10.into()))));
+        assert_eq!(interpreter.environment().get("a"), Some(5.into()));
+        assert!(interpreter.eval("let = 1;").is_err());
+    }
+
+    #[test]
+    fn test_interpreter_expands_macros() {
+        let mut interpreter = Interpreter::default();
+        let unless = r#"
+            let unless = macro(condition, consequence, alternative) {
+                quote(if (!(unquote(condition))) {
+                    unquote(consequence);
+                } else {
+                    unquote(alternative);
+                });
+            };
+        "#;
+
+        assert_eq!(interpreter.eval(unless), Ok(Ok(None)));
+        assert_eq!(
+            interpreter.eval(r#"unless(10 > 5, "not greater", "greater")"#),
+            Ok(Ok(Some("greater".into())))
+        );
+        assert_eq!(
+            interpreter.eval(r#"unless(1 > 5, "not greater", oops)"#),
+            Ok(Ok(Some("not greater".into())))
+        );
+    }
+
+    #[test]
+    fn test_interpreter_max_source_size() {
+        let mut interpreter = Interpreter::new(EvalOptions {
+            max_source_bytes: 6,
+            ..Default::default()
+        });
+
+        assert_eq!(interpreter.eval("1 + 23"), Ok(Ok(Some(24.into()))));
+        assert_eq!(interpreter.eval("\"éé\""), Ok(Ok(Some("éé".into()))));
+
+        let Err(diagnostics) = interpreter.eval("1 + 234") else {
+            panic!("expected the source to be rejected");
+        };
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "source-too-large");
+        assert_eq!(
+            diagnostics[0].message,
+            "source too large (7 bytes, limit 6)"
+        );
+        assert!(
+            interpreter.eval("\"ééé\"").is_err(),
+            "the limit is in bytes, not characters"
+        );
+
+        assert!(matches!(
+            InterpreterTemplate::new(
+                "let a = 1;",
+                &[],
+                EvalOptions {
+                    max_source_bytes: 9,
+                    ..Default::default()
+                }
+            ),
+            Err(PreludeError::Parse(diagnostics)) if diagnostics[0].code == "source-too-large"
+        ));
+    }
+
+    #[test]
+    fn test_interpreter_capabilities() {
+        assert_eq!(Interpreter::capabilities(), capabilities());
+    }
+}
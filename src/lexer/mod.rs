@@ -1,15 +1,33 @@
+use std::{collections::HashSet, rc::Rc};
+
+mod span;
 mod token;
 
+pub use span::Span;
 pub use token::Token;
 
+use crate::bigint::BigInt;
+
+/// A `//` comment, skipped by the lexer but kept for the tools working on the source.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Comment {
+    /// Everything following the `//` up to the end of the line
+    pub text: String,
+    pub span: Span,
+}
+
 #[derive(Default)]
 pub struct Lexer {
-    input: String,
+    /// The only copy of the source kept around, indexed by character
     chars: Vec<char>,
     position: usize,
     read_position: usize,
     ch: Option<char>,
     reached_eof: bool,
+    token_start: usize,
+    comments: Vec<Comment>,
+    /// Identifiers read so far, so that each name is allocated once and then shared
+    names: HashSet<Rc<str>>,
 }
 
 impl Iterator for Lexer {
@@ -30,42 +48,77 @@ impl Iterator for Lexer {
 impl Lexer {
     pub fn new(input: String) -> Self {
         let mut lexer = Self {
-            chars: input.clone().chars().collect(),
-            input,
+            chars: input.chars().collect(),
             ..Default::default()
         };
         lexer.read_char();
+        lexer.skip_shebang();
         lexer
     }
 
-    pub fn read_char(&mut self) {
-        if self.read_position >= self.input.len() {
-            self.ch = Default::default();
-        } else {
-            self.ch = self.chars.get(self.read_position).copied();
+    /// Skips a `#!` line at the very start of the input, so that scripts can be executable.
+    fn skip_shebang(&mut self) {
+        if self.ch != Some('#') || self.peek_char() != Some('!') {
+            return;
+        }
+
+        while self.ch.is_some() && self.ch != Some('\n') {
+            self.read_char();
         }
+    }
+
+    pub fn read_char(&mut self) {
+        self.ch = self.chars.get(self.read_position).copied();
         self.position = self.read_position;
         self.read_position += 1;
     }
 
     pub fn peek_char(&mut self) -> Option<char> {
-        if self.read_position >= self.input.len() {
-            None
-        } else {
-            self.chars.get(self.read_position).copied()
-        }
+        self.chars.get(self.read_position).copied()
+    }
+
+    /// Comments skipped so far, in order.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    /// Span of the token last returned by [`Lexer::next_token`].
+    pub fn span(&self) -> Span {
+        Span::new(self.token_start, self.position)
     }
 
     pub fn next_token(&mut self) -> Token {
+        self.token_start = self.position;
+
         let Some(ch) = self.ch else {
             return Token::Eof;
         };
 
         let token = match ch {
-            '+' => Token::PlusSign,
-            '-' => Token::MinusSign,
+            '+' => {
+                if self.peek_char() == Some('+') {
+                    self.read_char();
+                    Token::Increment
+                } else {
+                    Token::PlusSign
+                }
+            }
+            '-' => {
+                if self.peek_char() == Some('-') {
+                    self.read_char();
+                    Token::Decrement
+                } else {
+                    Token::MinusSign
+                }
+            }
             '*' => Token::Asterisk,
-            '/' => Token::Slash,
+            '/' => {
+                if self.peek_char() == Some('/') {
+                    self.read_comment();
+                    return self.next_token();
+                }
+                Token::Slash
+            }
             '<' => Token::LessThan,
             '>' => Token::GreaterThan,
             '(' => Token::LeftParen,
@@ -77,6 +130,19 @@ impl Lexer {
             ',' => Token::Comma,
             ';' => Token::Semicolon,
             ':' => Token::Colon,
+            '.' => {
+                if self.peek_char() != Some('.') {
+                    Token::Dot
+                } else {
+                    self.read_char();
+                    if self.peek_char() == Some('=') {
+                        self.read_char();
+                        Token::DotDotEqual
+                    } else {
+                        Token::DotDot
+                    }
+                }
+            }
             '"' => Token::String(self.read_string()),
             '=' => {
                 if self.peek_char() == Some('=') {
@@ -86,6 +152,21 @@ impl Lexer {
                     Token::EqualSign
                 }
             }
+            '?' => match self.peek_char() {
+                Some('.') => {
+                    self.read_char();
+                    Token::QuestionDot
+                }
+                Some('[') => {
+                    self.read_char();
+                    Token::QuestionBracket
+                }
+                Some('?') => {
+                    self.read_char();
+                    Token::DoubleQuestion
+                }
+                _ => Token::Illegal(ch),
+            },
             '!' => {
                 if self.peek_char() == Some('=') {
                     self.read_char();
@@ -102,17 +183,24 @@ impl Lexer {
                 let identifier = self.read_identifier();
                 return match identifier.as_str() {
                     "let" => Token::Let,
+                    "const" => Token::Const,
                     "fn" => Token::Function,
+                    "macro" => Token::Macro,
                     "if" => Token::If,
+                    "for" => Token::For,
+                    "in" => Token::In,
+                    "while" => Token::While,
                     "else" => Token::Else,
                     "return" => Token::Return,
+                    "throw" => Token::Throw,
                     "true" => Token::True,
+                    "null" => Token::Null,
                     "false" => Token::False,
-                    _ => Token::Identifier(identifier),
+                    _ => Token::Identifier(self.intern(identifier)),
                 };
             }
             '0'..='9' => {
-                return Token::Integer(self.read_integer());
+                return self.read_integer();
             }
             _ => Token::Illegal(ch),
         };
@@ -122,32 +210,100 @@ impl Lexer {
         token
     }
 
+    fn read_comment(&mut self) {
+        let start = self.position;
+        while self.ch.is_some() && self.ch != Some('\n') {
+            self.read_char();
+        }
+
+        self.comments.push(Comment {
+            text: self.chars[start + 2..self.position].iter().collect(),
+            span: Span::new(start, self.position),
+        });
+    }
+
+    fn intern(&mut self, identifier: String) -> Rc<str> {
+        if let Some(name) = self.names.get(identifier.as_str()) {
+            return name.clone();
+        }
+
+        let name = Rc::<str>::from(identifier);
+        self.names.insert(name.clone());
+        name
+    }
+
     fn read_identifier(&mut self) -> String {
         let position = self.position;
         while self.ch.is_some() && matches!(self.ch.unwrap(), 'a'..='z' | 'A'..='Z' | '_') {
             self.read_char();
         }
-        self.input[position..self.position].to_string()
+        self.chars[position..self.position].iter().collect()
     }
 
-    fn read_integer(&mut self) -> isize {
+    /// Reads an integer literal, as a [`Token::BigInt`] when it doesn't fit in an `i64`.
+    fn read_integer(&mut self) -> Token {
         let position = self.position;
         while self.ch.is_some() && self.ch.unwrap().is_ascii_digit() {
             self.read_char();
         }
-        self.input[position..self.position].parse().unwrap()
+        let digits = self.chars[position..self.position]
+            .iter()
+            .collect::<String>();
+
+        match digits.parse() {
+            Ok(value) => Token::Integer(value),
+            Err(_) => Token::BigInt(BigInt::parse(&digits).expect("integer literals are digits")),
+        }
     }
 
+    /// Reads a string literal, in which `\"`, `\\`, `\n`, `\r` and `\t` are escapes. A backslash
+    /// followed by anything else is kept as is.
     fn read_string(&mut self) -> String {
-        let position = self.position + 1;
+        let mut value = String::new();
+
         loop {
             self.read_char();
-            if self.ch == Some('"') || self.ch == Some(0.into()) {
-                break;
+            match self.ch {
+                None | Some('"') => break,
+                Some('\\') => {
+                    let escaped = match self.peek_char() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        _ => {
+                            value.push('\\');
+                            continue;
+                        }
+                    };
+                    self.read_char();
+                    value.push(escaped);
+                }
+                Some(ch) => value.push(ch),
             }
         }
-        self.input[position..self.position].to_string()
+
+        value
+    }
+}
+
+/// String literal which [`Lexer`] reads back as `value`.
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch => quoted.push(ch),
+        }
     }
+    quoted.push('"');
+    quoted
 }
 
 #[cfg(test)]
@@ -273,7 +429,7 @@ if (5 < 10) {
 
         let mut lexer = Lexer::new(input.into());
 
-        for (index, test) in tests.into_iter().enumerate() {
+        for (index, test) in tests.iter().enumerate() {
             let current_token = lexer.next_token();
 
             assert_eq!(
@@ -283,4 +439,217 @@ if (5 < 10) {
             );
         }
     }
+
+    #[test]
+    fn test_lexer_spans() {
+        let mut lexer = Lexer::new("let five =\n  \"five\";".into());
+        let tests = &[
+            (Token::Let, Span::new(0, 3)),
+            (Token::Identifier("five".into()), Span::new(4, 8)),
+            (Token::EqualSign, Span::new(9, 10)),
+            (Token::String("five".into()), Span::new(13, 19)),
+            (Token::Semicolon, Span::new(19, 20)),
+            (Token::Eof, Span::new(20, 20)),
+        ];
+
+        for (token, span) in tests {
+            assert_eq!(lexer.next_token(), *token);
+            assert_eq!(lexer.span(), *span, "{token:?}");
+        }
+    }
+
+    #[test]
+    fn test_lexer_comments() {
+        let mut lexer = Lexer::new("// add\nlet a = 1 / 2; // half\n//".into());
+        let tests = &[
+            (Token::Let, Span::new(7, 10)),
+            (Token::Identifier("a".into()), Span::new(11, 12)),
+            (Token::EqualSign, Span::new(13, 14)),
+            (Token::Integer(1), Span::new(15, 16)),
+            (Token::Slash, Span::new(17, 18)),
+            (Token::Integer(2), Span::new(19, 20)),
+            (Token::Semicolon, Span::new(20, 21)),
+            (Token::Eof, Span::new(32, 32)),
+        ];
+
+        for (token, span) in tests {
+            assert_eq!(lexer.next_token(), *token);
+            assert_eq!(lexer.span(), *span, "{token:?}");
+        }
+
+        assert_eq!(
+            lexer.comments(),
+            &[
+                Comment {
+                    text: " add".into(),
+                    span: Span::new(0, 6)
+                },
+                Comment {
+                    text: " half".into(),
+                    span: Span::new(22, 29)
+                },
+                Comment {
+                    text: "".into(),
+                    span: Span::new(30, 32)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_shebang() {
+        let input = "#!/usr/bin/env monkey\nlet x = 5;";
+        let mut lexer = Lexer::new(input.into());
+
+        assert_eq!(lexer.next_token(), Token::Let);
+        assert_eq!(lexer.span(), Span::new(22, 25));
+        assert_eq!(lexer.span().line_column(input), (2, 1));
+        assert_eq!(
+            lexer.collect::<Vec<_>>(),
+            vec![
+                Token::Identifier("x".into()),
+                Token::EqualSign,
+                Token::Integer(5),
+                Token::Semicolon,
+                Token::Eof,
+            ]
+        );
+
+        assert_eq!(
+            Lexer::new(" #!x".into()).collect::<Vec<_>>(),
+            vec![
+                Token::Illegal('#'),
+                Token::ExclamationMark,
+                Token::Identifier("x".into()),
+                Token::Eof,
+            ]
+        );
+        assert_eq!(
+            Lexer::new("5;\n#!x".into()).nth(2),
+            Some(Token::Illegal('#'))
+        );
+    }
+
+    #[test]
+    fn test_lexer_unterminated_string() {
+        let mut lexer = Lexer::new(r#"let s = "abc"#.into());
+
+        assert_eq!(
+            lexer.by_ref().take(3).collect::<Vec<_>>(),
+            vec![Token::Let, Token::Identifier("s".into()), Token::EqualSign]
+        );
+        assert_eq!(lexer.next_token(), Token::String("abc".into()));
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_lexer_string_escapes() {
+        let input = r#""a \"b\" \\ \n\t\r \d" "\""#;
+        let mut lexer = Lexer::new(input.into());
+
+        assert_eq!(
+            lexer.next_token(),
+            Token::String("a \"b\" \\ \n\t\r \\d".into())
+        );
+        assert_eq!(lexer.span(), Span::new(0, 22));
+        assert_eq!(lexer.next_token(), Token::String("\"".into()));
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_quote() {
+        for value in ["", "plain", "a \"b\" \\ \n\t\r \\d", "é, 世界"] {
+            let mut lexer = Lexer::new(quote(value));
+            assert_eq!(lexer.next_token(), Token::String(value.into()));
+            assert_eq!(lexer.next_token(), Token::Eof);
+        }
+
+        assert_eq!(quote("say \"hi\"\n"), r#""say \"hi\"\n""#);
+    }
+
+    #[test]
+    fn test_lexer_multibyte_characters() {
+        let mut lexer = Lexer::new(r#""héllo, 世界" + "🐒";"#.into());
+
+        assert_eq!(lexer.next_token(), Token::String("héllo, 世界".into()));
+        assert_eq!(lexer.span(), Span::new(0, 11));
+        assert_eq!(lexer.next_token(), Token::PlusSign);
+        assert_eq!(lexer.next_token(), Token::String("🐒".into()));
+        assert_eq!(lexer.span(), Span::new(14, 17));
+        assert_eq!(lexer.next_token(), Token::Semicolon);
+    }
+
+    #[test]
+    fn test_lexer_increment_decrement() {
+        assert_eq!(
+            Lexer::new("i++ - -j--+1".into()).collect::<Vec<_>>(),
+            vec![
+                Token::Identifier("i".into()),
+                Token::Increment,
+                Token::MinusSign,
+                Token::MinusSign,
+                Token::Identifier("j".into()),
+                Token::Decrement,
+                Token::PlusSign,
+                Token::Integer(1),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_big_integers() {
+        assert_eq!(
+            Lexer::new("9223372036854775807 9223372036854775808".into()).collect::<Vec<_>>(),
+            vec![
+                Token::Integer(i64::MAX),
+                Token::BigInt(BigInt::parse("9223372036854775808").unwrap()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_optional_index() {
+        assert_eq!(
+            Lexer::new("a?.b?[0] ?".into()).collect::<Vec<_>>(),
+            vec![
+                Token::Identifier("a".into()),
+                Token::QuestionDot,
+                Token::Identifier("b".into()),
+                Token::QuestionBracket,
+                Token::Integer(0),
+                Token::RightBracket,
+                Token::Illegal('?'),
+                Token::Eof,
+            ]
+        );
+        assert_eq!(
+            Lexer::new("a ?? b".into()).collect::<Vec<_>>(),
+            vec![
+                Token::Identifier("a".into()),
+                Token::DoubleQuestion,
+                Token::Identifier("b".into()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_interns_identifiers() {
+        let names = Lexer::new("let x = y; x + y + xy".into())
+            .filter_map(|token| match token {
+                Token::Identifier(name) => Some(name),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            ["x".into(), "y".into(), "x".into(), "y".into(), "xy".into()]
+        );
+        assert!(Rc::ptr_eq(&names[0], &names[2]));
+        assert!(Rc::ptr_eq(&names[1], &names[3]));
+        assert!(!Rc::ptr_eq(&names[0], &names[1]));
+    }
 }
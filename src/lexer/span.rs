@@ -0,0 +1,51 @@
+/// Character offsets of a piece of source code, `end` being exclusive.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Ord, PartialOrd)]
+#[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// 1-based line and column of the start of the span within `source`.
+    pub fn line_column(&self, source: &str) -> (usize, usize) {
+        let mut line = 1;
+        let mut column = 1;
+
+        for ch in source.chars().take(self.start) {
+            if ch == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+
+        (line, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_span_line_column() {
+        let source = "let a = 1;\nlet b = 2;\n\nb";
+        let tests = &[
+            (Span::new(0, 3), (1, 1)),
+            (Span::new(4, 5), (1, 5)),
+            (Span::new(11, 14), (2, 1)),
+            (Span::new(15, 16), (2, 5)),
+            (Span::new(23, 24), (4, 1)),
+        ];
+
+        for (span, expected) in tests {
+            assert_eq!(span.line_column(source), *expected, "{span:?}");
+        }
+    }
+}
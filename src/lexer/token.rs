@@ -1,4 +1,6 @@
-use crate::parser::ExpressionPrecedence;
+use std::rc::Rc;
+
+use crate::{bigint::BigInt, parser::ExpressionPrecedence};
 
 #[derive(PartialEq, Eq, Debug, Clone, Ord, PartialOrd)]
 #[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
@@ -8,11 +10,21 @@ use crate::parser::ExpressionPrecedence;
 )]
 pub enum Token {
     // Literals
-    Integer(isize),
+    Integer(
+        #[cfg_attr(
+            target_family = "wasm",
+            serde(serialize_with = "crate::wasm::serialization::integer")
+        )]
+        i64,
+    ),
+    /// Integer literal too large for an `i64`
+    #[cfg_attr(target_family = "wasm", serde(rename = "integer"))]
+    BigInt(BigInt),
     String(String),
 
     // Identifiers
-    Identifier(String),
+    /// Shared by all the tokens of a source spelling the same name
+    Identifier(Rc<str>),
 
     // Operators
     EqualSign,
@@ -26,6 +38,13 @@ pub enum Token {
     GreaterThan,
     Equal,
     NotEqual,
+    DoubleQuestion,
+    DotDot,
+    DotDotEqual,
+    Dot,
+    QuestionDot,
+    Increment,
+    Decrement,
 
     // Delimiters
     Comma,
@@ -37,17 +56,25 @@ pub enum Token {
     LeftBracket,
     RightBracket,
     Colon,
+    QuestionBracket,
 
     // Keywords
     Function,
+    Macro,
     True,
     False,
+    Null,
     If,
     Else,
+    For,
+    In,
+    While,
 
     // Statements
     Let,
+    Const,
     Return,
+    Throw,
 
     // Other
     Eof,
@@ -55,19 +82,314 @@ pub enum Token {
 }
 
 impl Token {
+    /// How tightly the token binds as an infix operator in the built-in grammar of the parser.
     pub fn precedence(&self) -> ExpressionPrecedence {
         match self {
-            Token::PlusSign => ExpressionPrecedence::Sum,
-            Token::MinusSign => ExpressionPrecedence::Sum,
-            Token::Asterisk => ExpressionPrecedence::Product,
-            Token::Slash => ExpressionPrecedence::Product,
-            Token::LessThan => ExpressionPrecedence::LessGreater,
-            Token::GreaterThan => ExpressionPrecedence::LessGreater,
-            Token::Equal => ExpressionPrecedence::Equals,
-            Token::NotEqual => ExpressionPrecedence::Equals,
+            Token::PlusSign | Token::MinusSign => ExpressionPrecedence::Sum,
+            Token::Asterisk | Token::Slash => ExpressionPrecedence::Product,
+            Token::LessThan | Token::GreaterThan => ExpressionPrecedence::LessGreater,
+            Token::Equal | Token::NotEqual => ExpressionPrecedence::Equals,
+            Token::DoubleQuestion => ExpressionPrecedence::Coalesce,
+            Token::DotDot | Token::DotDotEqual => ExpressionPrecedence::Range,
+            Token::Increment | Token::Decrement => ExpressionPrecedence::Postfix,
             Token::LeftParen => ExpressionPrecedence::Call,
-            Token::LeftBracket => ExpressionPrecedence::Index,
-            _ => ExpressionPrecedence::Lowest,
+            Token::LeftBracket | Token::Dot | Token::QuestionDot | Token::QuestionBracket => {
+                ExpressionPrecedence::Index
+            }
+            Token::Integer(_)
+            | Token::BigInt(_)
+            | Token::String(_)
+            | Token::Identifier(_)
+            | Token::EqualSign
+            | Token::ExclamationMark
+            | Token::Comma
+            | Token::Semicolon
+            | Token::RightParen
+            | Token::LeftBrace
+            | Token::RightBrace
+            | Token::RightBracket
+            | Token::Colon
+            | Token::Function
+            | Token::Macro
+            | Token::True
+            | Token::False
+            | Token::Null
+            | Token::If
+            | Token::Else
+            | Token::For
+            | Token::In
+            | Token::While
+            | Token::Let
+            | Token::Const
+            | Token::Return
+            | Token::Throw
+            | Token::Eof
+            | Token::Illegal(_) => ExpressionPrecedence::Lowest,
+        }
+    }
+
+    /// Reserved words, which can't be used as identifiers. `true`, `false` and `null` are also
+    /// literals.
+    pub fn is_keyword(&self) -> bool {
+        match self {
+            Token::Function
+            | Token::Macro
+            | Token::True
+            | Token::False
+            | Token::Null
+            | Token::If
+            | Token::Else
+            | Token::For
+            | Token::In
+            | Token::While
+            | Token::Let
+            | Token::Const
+            | Token::Return
+            | Token::Throw => true,
+            Token::Integer(_)
+            | Token::BigInt(_)
+            | Token::String(_)
+            | Token::Identifier(_)
+            | Token::EqualSign
+            | Token::PlusSign
+            | Token::MinusSign
+            | Token::ExclamationMark
+            | Token::Asterisk
+            | Token::Slash
+            | Token::LessThan
+            | Token::GreaterThan
+            | Token::Equal
+            | Token::NotEqual
+            | Token::DoubleQuestion
+            | Token::DotDot
+            | Token::DotDotEqual
+            | Token::Dot
+            | Token::QuestionDot
+            | Token::Increment
+            | Token::Decrement
+            | Token::Comma
+            | Token::Semicolon
+            | Token::LeftParen
+            | Token::RightParen
+            | Token::LeftBrace
+            | Token::RightBrace
+            | Token::LeftBracket
+            | Token::RightBracket
+            | Token::Colon
+            | Token::QuestionBracket
+            | Token::Eof
+            | Token::Illegal(_) => false,
+        }
+    }
+
+    /// Tokens combining or accessing values, including assignment and member access.
+    pub fn is_operator(&self) -> bool {
+        match self {
+            Token::EqualSign
+            | Token::PlusSign
+            | Token::MinusSign
+            | Token::ExclamationMark
+            | Token::Asterisk
+            | Token::Slash
+            | Token::LessThan
+            | Token::GreaterThan
+            | Token::Equal
+            | Token::NotEqual
+            | Token::DoubleQuestion
+            | Token::DotDot
+            | Token::DotDotEqual
+            | Token::Dot
+            | Token::QuestionDot
+            | Token::Increment
+            | Token::Decrement => true,
+            Token::Integer(_)
+            | Token::BigInt(_)
+            | Token::String(_)
+            | Token::Identifier(_)
+            | Token::Comma
+            | Token::Semicolon
+            | Token::LeftParen
+            | Token::RightParen
+            | Token::LeftBrace
+            | Token::RightBrace
+            | Token::LeftBracket
+            | Token::RightBracket
+            | Token::Colon
+            | Token::QuestionBracket
+            | Token::Function
+            | Token::Macro
+            | Token::True
+            | Token::False
+            | Token::Null
+            | Token::If
+            | Token::Else
+            | Token::For
+            | Token::In
+            | Token::While
+            | Token::Let
+            | Token::Const
+            | Token::Return
+            | Token::Throw
+            | Token::Eof
+            | Token::Illegal(_) => false,
+        }
+    }
+
+    pub fn is_literal(&self) -> bool {
+        match self {
+            Token::Integer(_)
+            | Token::BigInt(_)
+            | Token::String(_)
+            | Token::True
+            | Token::False
+            | Token::Null => true,
+            Token::Identifier(_)
+            | Token::EqualSign
+            | Token::PlusSign
+            | Token::MinusSign
+            | Token::ExclamationMark
+            | Token::Asterisk
+            | Token::Slash
+            | Token::LessThan
+            | Token::GreaterThan
+            | Token::Equal
+            | Token::NotEqual
+            | Token::DoubleQuestion
+            | Token::DotDot
+            | Token::DotDotEqual
+            | Token::Dot
+            | Token::QuestionDot
+            | Token::Increment
+            | Token::Decrement
+            | Token::Comma
+            | Token::Semicolon
+            | Token::LeftParen
+            | Token::RightParen
+            | Token::LeftBrace
+            | Token::RightBrace
+            | Token::LeftBracket
+            | Token::RightBracket
+            | Token::Colon
+            | Token::QuestionBracket
+            | Token::Function
+            | Token::Macro
+            | Token::If
+            | Token::Else
+            | Token::For
+            | Token::In
+            | Token::While
+            | Token::Let
+            | Token::Const
+            | Token::Return
+            | Token::Throw
+            | Token::Eof
+            | Token::Illegal(_) => false,
+        }
+    }
+
+    /// Punctuation separating or enclosing parts of the program, such as brackets.
+    pub fn is_delimiter(&self) -> bool {
+        match self {
+            Token::Comma
+            | Token::Semicolon
+            | Token::LeftParen
+            | Token::RightParen
+            | Token::LeftBrace
+            | Token::RightBrace
+            | Token::LeftBracket
+            | Token::RightBracket
+            | Token::Colon
+            | Token::QuestionBracket => true,
+            Token::Integer(_)
+            | Token::BigInt(_)
+            | Token::String(_)
+            | Token::Identifier(_)
+            | Token::EqualSign
+            | Token::PlusSign
+            | Token::MinusSign
+            | Token::ExclamationMark
+            | Token::Asterisk
+            | Token::Slash
+            | Token::LessThan
+            | Token::GreaterThan
+            | Token::Equal
+            | Token::NotEqual
+            | Token::DoubleQuestion
+            | Token::DotDot
+            | Token::DotDotEqual
+            | Token::Dot
+            | Token::QuestionDot
+            | Token::Increment
+            | Token::Decrement
+            | Token::Function
+            | Token::Macro
+            | Token::True
+            | Token::False
+            | Token::Null
+            | Token::If
+            | Token::Else
+            | Token::For
+            | Token::In
+            | Token::While
+            | Token::Let
+            | Token::Const
+            | Token::Return
+            | Token::Throw
+            | Token::Eof
+            | Token::Illegal(_) => false,
+        }
+    }
+
+    /// Whether the parser has a prefix parser for the token, i.e. an expression can begin with it.
+    pub fn can_start_expression(&self) -> bool {
+        match self {
+            Token::Integer(_)
+            | Token::BigInt(_)
+            | Token::String(_)
+            | Token::Identifier(_)
+            | Token::PlusSign
+            | Token::MinusSign
+            | Token::ExclamationMark
+            | Token::LeftParen
+            | Token::LeftBrace
+            | Token::LeftBracket
+            | Token::Function
+            | Token::Macro
+            | Token::True
+            | Token::False
+            | Token::Null
+            | Token::If
+            | Token::For
+            | Token::While => true,
+            Token::EqualSign
+            | Token::Asterisk
+            | Token::Slash
+            | Token::LessThan
+            | Token::GreaterThan
+            | Token::Equal
+            | Token::NotEqual
+            | Token::DoubleQuestion
+            | Token::DotDot
+            | Token::DotDotEqual
+            | Token::Dot
+            | Token::QuestionDot
+            | Token::Increment
+            | Token::Decrement
+            | Token::Comma
+            | Token::Semicolon
+            | Token::RightParen
+            | Token::RightBrace
+            | Token::RightBracket
+            | Token::Colon
+            | Token::QuestionBracket
+            | Token::Else
+            | Token::In
+            | Token::Let
+            | Token::Const
+            | Token::Return
+            | Token::Throw
+            | Token::Eof
+            | Token::Illegal(_) => false,
         }
     }
 }
@@ -76,6 +398,7 @@ impl std::fmt::Display for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Token::Integer(i) => write!(f, "{i}"),
+            Token::BigInt(i) => write!(f, "{i}"),
             Token::String(s) => write!(f, "\"{s}\""),
             Token::Identifier(i) => write!(f, "{i}"),
             Token::EqualSign => write!(f, "="),
@@ -97,15 +420,144 @@ impl std::fmt::Display for Token {
             Token::LeftBracket => write!(f, "["),
             Token::RightBracket => write!(f, "]"),
             Token::Colon => write!(f, ":"),
+            Token::Dot => write!(f, "."),
+            Token::QuestionDot => write!(f, "?."),
+            Token::QuestionBracket => write!(f, "?["),
+            Token::DoubleQuestion => write!(f, "??"),
+            Token::DotDot => write!(f, ".."),
+            Token::DotDotEqual => write!(f, "..="),
+            Token::Increment => write!(f, "++"),
+            Token::Decrement => write!(f, "--"),
             Token::Function => write!(f, "fn"),
+            Token::Macro => write!(f, "macro"),
             Token::True => write!(f, "true"),
             Token::False => write!(f, "false"),
+            Token::Null => write!(f, "null"),
             Token::If => write!(f, "if"),
             Token::Else => write!(f, "else"),
+            Token::For => write!(f, "for"),
+            Token::In => write!(f, "in"),
+            Token::While => write!(f, "while"),
             Token::Let => write!(f, "let"),
+            Token::Const => write!(f, "const"),
             Token::Return => write!(f, "return"),
+            Token::Throw => write!(f, "throw"),
             Token::Eof => write!(f, ""),
             Token::Illegal(c) => write!(f, "{c}"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lexer::Lexer,
+        parser::{parse_with_diagnostics, Parser},
+    };
+
+    use super::*;
+
+    /// Every variant with whether it is a keyword, an operator, a literal, a delimiter, and can
+    /// start an expression.
+    fn classifications() -> Vec<(Token, [bool; 5])> {
+        vec![
+            (Token::Integer(1), [false, false, true, false, true]),
+            (Token::String("a".into()), [false, false, true, false, true]),
+            (
+                Token::Identifier("a".into()),
+                [false, false, false, false, true],
+            ),
+            (Token::EqualSign, [false, true, false, false, false]),
+            (Token::PlusSign, [false, true, false, false, true]),
+            (Token::MinusSign, [false, true, false, false, true]),
+            (Token::ExclamationMark, [false, true, false, false, true]),
+            (Token::Asterisk, [false, true, false, false, false]),
+            (Token::Slash, [false, true, false, false, false]),
+            (Token::LessThan, [false, true, false, false, false]),
+            (Token::GreaterThan, [false, true, false, false, false]),
+            (Token::Equal, [false, true, false, false, false]),
+            (Token::NotEqual, [false, true, false, false, false]),
+            (Token::DoubleQuestion, [false, true, false, false, false]),
+            (Token::DotDot, [false, true, false, false, false]),
+            (Token::DotDotEqual, [false, true, false, false, false]),
+            (Token::Dot, [false, true, false, false, false]),
+            (Token::QuestionDot, [false, true, false, false, false]),
+            (Token::Increment, [false, true, false, false, false]),
+            (Token::Decrement, [false, true, false, false, false]),
+            (Token::Comma, [false, false, false, true, false]),
+            (Token::Semicolon, [false, false, false, true, false]),
+            (Token::LeftParen, [false, false, false, true, true]),
+            (Token::RightParen, [false, false, false, true, false]),
+            (Token::LeftBrace, [false, false, false, true, true]),
+            (Token::RightBrace, [false, false, false, true, false]),
+            (Token::LeftBracket, [false, false, false, true, true]),
+            (Token::RightBracket, [false, false, false, true, false]),
+            (Token::Colon, [false, false, false, true, false]),
+            (Token::QuestionBracket, [false, false, false, true, false]),
+            (Token::Function, [true, false, false, false, true]),
+            (Token::Macro, [true, false, false, false, true]),
+            (Token::True, [true, false, true, false, true]),
+            (Token::False, [true, false, true, false, true]),
+            (Token::Null, [true, false, true, false, true]),
+            (Token::If, [true, false, false, false, true]),
+            (Token::Else, [true, false, false, false, false]),
+            (Token::For, [true, false, false, false, true]),
+            (Token::In, [true, false, false, false, false]),
+            (Token::While, [true, false, false, false, true]),
+            (Token::Let, [true, false, false, false, false]),
+            (Token::Const, [true, false, false, false, false]),
+            (Token::Return, [true, false, false, false, false]),
+            (Token::Throw, [true, false, false, false, false]),
+            (Token::Eof, [false, false, false, false, false]),
+            (Token::Illegal('#'), [false, false, false, false, false]),
+        ]
+    }
+
+    #[test]
+    fn test_token_classification() {
+        for (token, expected) in classifications() {
+            assert_eq!(
+                [
+                    token.is_keyword(),
+                    token.is_operator(),
+                    token.is_literal(),
+                    token.is_delimiter(),
+                    token.can_start_expression(),
+                ],
+                expected,
+                "{token:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_can_start_expression_matches_parser() {
+        for (token, _) in classifications() {
+            // Behind a prefix operator so that statement keywords are parsed as expressions
+            let report = parse_with_diagnostics(&format!("- {token}"));
+            let has_prefix_parser = !report.diagnostics.iter().any(|diagnostic| {
+                diagnostic.code == "no-prefix-parser" && diagnostic.span.start == 2
+            });
+
+            assert_eq!(
+                has_prefix_parser,
+                token.can_start_expression(),
+                "{token:?}: {:?}",
+                report.diagnostics
+            );
+        }
+    }
+
+    #[test]
+    fn test_precedence_matches_parser() {
+        let parser = Parser::new(Lexer::new(String::new()));
+
+        for (token, _) in classifications() {
+            assert_eq!(
+                parser.infix_precedence(&token),
+                token.precedence(),
+                "{token:?}"
+            );
+        }
+    }
+}
@@ -1,12 +1,22 @@
 #![allow(dead_code)]
 
-mod ast;
-mod evaluator;
-mod lexer;
-mod parser;
+pub mod analyzer;
+pub mod ast;
+pub mod bigint;
+pub mod capabilities;
+pub mod doc;
+pub mod evaluator;
+pub mod format;
+pub mod interpreter;
+pub mod lexer;
+pub mod optimizer;
+pub mod parser;
 
 #[cfg(target_family = "wasm")]
 mod wasm;
 
 #[cfg(target_family = "wasm")]
 pub use crate::wasm::*;
+
+pub use interpreter::{Interpreter, InterpreterTemplate};
+pub use parser::{parse, parse_with_diagnostics};
@@ -1,44 +1,29 @@
-use evaluator::Evaluator;
-use std::io::Write;
-
-mod ast;
-mod evaluator;
-mod lexer;
-mod parser;
-
-const PROMPT: &str = "λ> ";
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("Hello, this is the Monkey programming language!");
-    println!("Let's get started!\n");
-
-    let mut environment = evaluator::environment::Environment::new();
-
-    loop {
-        print!("{PROMPT}");
-        std::io::stdout().flush()?;
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input)?;
-
-        let lexer = lexer::Lexer::new(input);
-        let mut parser = parser::Parser::new(lexer);
-        let Some(program) = parser.parse_program() else {
-            println!("Error parsing program");
-            continue;
-        };
-
-        if !parser.errors.is_empty() {
-            println!("Woops! We ran into some monkey business here!\n");
-            println!("Parser errors:");
-            for error in parser.errors.iter() {
-                println!("- {error}");
-            }
-            continue;
-        }
-
-        let evaluated = program.eval(&mut environment);
-        if let Some(evaluated) = evaluated {
-            println!("{}", evaluated.inspect());
+use std::{process::ExitCode, thread};
+
+mod cli;
+
+/// Stack of the thread running the interpreter, deep enough for the default `max_call_depth`
+/// and `max_nesting_depth` even in debug builds, where each nested expression being evaluated
+/// takes up to 32 kilobytes.
+const STACK_SIZE: usize = 128 * 1024 * 1024;
+
+/// Stack the evaluation can take on the interpreter thread, well above the default
+/// `max_stack_bytes` which fits any thread, the rest being left to the frames of the CLI.
+const MAX_STACK_BYTES: usize = STACK_SIZE - 16 * 1024 * 1024;
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect();
+    let interpreter = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || cli::run(args).map_err(|error| error.to_string()))
+        .expect("failed to spawn the interpreter thread");
+
+    match interpreter.join() {
+        Ok(Ok(code)) => code,
+        Ok(Err(error)) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
         }
+        Err(_) => ExitCode::FAILURE,
     }
 }
@@ -0,0 +1,224 @@
+use std::convert::Infallible;
+
+use crate::{
+    ast::{modify_statements, BlockStatement, Expression, Spanned, Statement},
+    evaluator::{environment::Environment, object::Object, Evaluator},
+    parser::Program,
+};
+
+/// Simplifies `program` without changing what it evaluates to: operators applied to literals are
+/// folded into the literal they produce, then `if`s whose condition is a literal are replaced by
+/// the branch it selects.
+///
+/// The rewrite goes from the bottom up, so a condition is folded before the `if` holding it is
+/// looked at, and an `if` collapsed into a literal can in turn make the condition of an enclosing
+/// one constant: a single pass reaches the fixpoint.
+pub fn optimize(program: Program) -> Program {
+    let Ok(statements) = modify_statements(program.statements, &mut |expression| {
+        Ok::<_, Infallible>(eliminate_dead_branch(fold_constant(expression)))
+    });
+
+    Program {
+        statements,
+        ..program
+    }
+}
+
+/// Replaces a prefix or infix operator applied to literals by the literal it evaluates to.
+/// Operations failing at runtime, like a division by zero, are left for the evaluation to report.
+fn fold_constant(expression: Spanned<Expression>) -> Spanned<Expression> {
+    let foldable = match &expression.node {
+        Expression::PrefixOperator { expression, .. } => is_literal(expression),
+        Expression::InfixOperator {
+            lh_expression,
+            rh_expression,
+            ..
+        } => is_literal(lh_expression) && is_literal(rh_expression),
+        _ => false,
+    };
+    if !foldable {
+        return expression;
+    }
+
+    match expression.node.eval(&Environment::shared()).map(literal) {
+        Ok(Some(node)) => Spanned::new(node, expression.span),
+        _ => expression,
+    }
+}
+
+/// Replaces an `if` whose condition is a literal by the branch it selects: by the expression of
+/// a branch made of a single one, by `null` when the branch is missing or empty. A branch with
+/// more statements stays within an `if`, only without the other branch.
+fn eliminate_dead_branch(expression: Spanned<Expression>) -> Spanned<Expression> {
+    let Spanned {
+        node:
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            },
+        span,
+    } = expression
+    else {
+        return expression;
+    };
+
+    let Some(truthy) = is_literal(&condition)
+        .then(|| condition.node.eval(&Environment::shared()).ok())
+        .flatten()
+        .map(|condition| condition.is_truthy())
+    else {
+        return Spanned::new(
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            },
+            span,
+        );
+    };
+
+    let branch = if truthy {
+        consequence
+    } else {
+        alternative.unwrap_or_default()
+    };
+
+    let mut statements = branch.statements;
+    match (statements.len(), statements.pop()) {
+        (_, None) => Spanned::new(Expression::Null, span),
+        (
+            1,
+            Some(Spanned {
+                node: Statement::Expression { value },
+                ..
+            }),
+        ) => value,
+        (_, Some(last)) => {
+            statements.push(last);
+            Spanned::new(
+                Expression::If {
+                    condition: Box::new(Spanned::new(Expression::Boolean(true), condition.span)),
+                    consequence: BlockStatement {
+                        statements,
+                        ..branch
+                    },
+                    alternative: None,
+                },
+                span,
+            )
+        }
+    }
+}
+
+fn is_literal(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Integer(_)
+            | Expression::BigInt(_)
+            | Expression::Boolean(_)
+            | Expression::Null
+            | Expression::String(_)
+    )
+}
+
+/// Literal evaluating to `value`, if there is one.
+fn literal(value: Object) -> Option<Expression> {
+    match value {
+        Object::Integer(value) => Some(Expression::Integer(value)),
+        Object::BigInt(value) => Some(Expression::BigInt(value)),
+        Object::Boolean(value) => Some(Expression::Boolean(value)),
+        Object::String(value) => Some(Expression::String(value.to_string())),
+        Object::Null => Some(Expression::Null),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    use super::*;
+
+    fn optimized(input: &str) -> String {
+        optimize(parse(input).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_fold_constants() {
+        let tests = [
+            ("1 + 2 * 3", "7"),
+            ("-(4 - 6)", "2"),
+            ("!true == false", "true"),
+            (r#""foo" + "bar""#, r#""foobar""#),
+            ("9223372036854775807 + 1", "9223372036854775808"),
+            ("null ?? 3", "3"),
+            ("a + 1 * 2", "(a + 2)"),
+            ("1 / 0", "(1 / 0)"),
+            ("true + 1", "(true + 1)"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(optimized(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_eliminate_dead_branches() {
+        let tests = [
+            ("if (true) { a } else { b }", "a"),
+            ("if (false) { a } else { b }", "b"),
+            ("if (1 > 2) { a }", "null"),
+            ("if (null) { a } else {}", "null"),
+            ("if (0) { a }", "a"),
+            (r#"if ("") { a } else { b }"#, "a"),
+            (
+                "if (1 < 2) { if (2 * 2 == 4) { if (false) { a } else { b } } else { c } }",
+                "b",
+            ),
+            (
+                "if (if (false) { true } else { false }) { a } else { b }",
+                "b",
+            ),
+            ("if (if (true) { 1 }) { a }", "a"),
+            (
+                "if (1 > 2) { a } else { let b = 1; b }",
+                "if (true) {let b = 1;b}",
+            ),
+            (
+                "fn() { if (true) { return 1; } }",
+                "fn() {if (true) {return 1;}}",
+            ),
+            ("if (x) { a } else { b }", "if (x) {a}else {b}"),
+            ("if (x == 1) { a }", "if ((x == 1)) {a}"),
+            ("if (f()) { a } else { b }", "if (f()) {a}else {b}"),
+            (
+                "if (x) { if (true) { a } } else { if (1 == 2) { b } }",
+                "if (x) {a}else {null}",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(optimized(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_optimize_keeps_results() {
+        let inputs = [
+            "let a = 1; let b = 2; if (1 < 2) { a } else { b }",
+            "if (1 > 2) { 1 } else { let c = 3; c * 2 }",
+            "let f = fn(x) { if (true) { x + 1 } }; f(2) + (2 * 3)",
+            "if (false) { 1 }",
+        ];
+
+        for input in inputs {
+            let environment = Environment::shared();
+            let expected = parse(input).unwrap().eval(&environment).unwrap();
+            let environment = Environment::shared();
+            let result = optimize(parse(input).unwrap()).eval(&environment).unwrap();
+
+            assert_eq!(result, expected, "{input}");
+        }
+    }
+}
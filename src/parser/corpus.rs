@@ -0,0 +1,94 @@
+//! Valid programs shared by the tests of the parser and of the tools built on top of it, so
+//! that they all exercise the same grammar.
+
+/// Inputs along with the fully parenthesized expressions they parse to.
+pub(crate) const PRECEDENCE: &[(&str, &str)] = &[
+    ("-a * b", "((-a) * b)"),
+    ("!-a", "(!(-a))"),
+    ("a + b + c", "((a + b) + c)"),
+    ("a + b - c", "((a + b) - c)"),
+    ("a * b * c", "((a * b) * c)"),
+    ("a * b / c", "((a * b) / c)"),
+    ("a + b / c", "(a + (b / c))"),
+    ("a + b * c + d / e - f", "(((a + (b * c)) + (d / e)) - f)"),
+    ("3 + 4; -5 * 5", "(3 + 4);((-5) * 5)"),
+    ("5 > 4 == 3 < 4", "((5 > 4) == (3 < 4))"),
+    ("5 < 4 != 3 > 4", "((5 < 4) != (3 > 4))"),
+    (
+        "3 + 4 * 5 == 3 * 1 + 4 * 5",
+        "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))",
+    ),
+    ("true", "true"),
+    ("false", "false"),
+    ("3 > 5 == false", "((3 > 5) == false)"),
+    ("3 < 5 == true", "((3 < 5) == true)"),
+    ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4)"),
+    ("(5 + 5) * 2", "((5 + 5) * 2)"),
+    ("2 / (5 + 5)", "(2 / (5 + 5))"),
+    ("-(5 + 5)", "(-(5 + 5))"),
+    ("!(true == true)", "(!(true == true))"),
+    ("a + add(b * c) + d", "((a + add((b * c))) + d)"),
+    (
+        "add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))",
+        "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)))",
+    ),
+    (
+        "add(a + b + c * d / f + g)",
+        "add((((a + b) + ((c * d) / f)) + g))",
+    ),
+    (
+        "a * [1, 2, 3, 4][b * c] * d",
+        "((a * ([1, 2, 3, 4][(b * c)])) * d)",
+    ),
+    (
+        "add(a * b[2], b[1], 2 * [1, 2][1])",
+        "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
+    ),
+    ("a ?? b ?? c", "(a ?? (b ?? c))"),
+    ("a ?? b == c", "(a ?? (b == c))"),
+    ("a == b ?? c + d", "((a == b) ?? (c + d))"),
+    ("a?.b ?? -c", "((a?[\"b\"]) ?? (-c))"),
+];
+
+/// Programs covering every statement and expression of the grammar.
+pub(crate) const PROGRAMS: &[&str] = &[
+    "let x = 5;\nlet y = true;\nlet foobar = y;",
+    "const PI = 3; const greeting = \"hi\";",
+    "let {name, age: years} = person;",
+    "let [quotient, remainder] = divmod(7, 2);",
+    "return 5;\nreturn true;\nreturn y;",
+    "throw \"invalid input\";",
+    "let a = -(1 + b);\nif (a) { a.len() }",
+    "if (x < y) { x }",
+    "if (x < y) { x } else { y }",
+    "if (a) { if (b) { 1 } else { 2 } } else { if (c) { 3 } }",
+    "fn(x, y) { x + y }",
+    "fn() {}; fn(x) {}; fn(x, y, z) {}",
+    "let add = fn(a, b) { return a + b; };\nadd(1, 2 * 3, 4 + 5)",
+    "let f = fn(x) { fn(y) { x + y } }; f(1)(2)",
+    "fn(x) { x }(1)",
+    "let unless = macro(c, a, b) { quote(if (!(unquote(c))) { unquote(a) } else { unquote(b) }) };",
+    "\"hello world\"; \"say \\\"hi\\\"\\n\"",
+    "[1, 2 * 2, 3 + 3]",
+    "myArray[1 + 1]",
+    r#"{"one": 1, "two": 2, "three": 3}"#,
+    r#"{1: "one", 2: "two", 3: "three"}"#,
+    r#"{true: "true, that's right", false: "nope"}"#,
+    "{}",
+    r#"{"one": 0 + 1, "two": 10 - 8, "three": 15 / 5}"#,
+    r#"{"name": "Monkey", "languages": ["en", "fr"], "add": fn(a, b) { a + b }}"#,
+    "add(\n  [1,],\n  {1: 2,},\n  fn(x,) { x },\n)",
+    "arr.push(x); s.len(); arr.rest().first()",
+    "person.address.city; people[0].name.len(); person[\"first name\"]; person[\"if\"]",
+    "-a.b; a.add(1, 2 * 3) + 1; [1, 2].len()",
+    "a?.b; a?[0]; a?.b?[1 + 1].c; -a?.b",
+    "1..5; a..=b; 0..n - 1; -1..len(a) * 2; a[1..3]; 0..5 == r; (0..5)..(1..2)",
+    "for (i in 0..10) { puts(i); }",
+    "let i = 0; while (i < 3) { i++ }; i",
+    "-i++; a - -b; i++ * j--; - -a; -(-a); !!a; -!a",
+    "+5; +-a; -+a; +(+a); a + +b",
+    "if (a) { 1 }\n-1",
+    "while (a) { a-- }\n[1]",
+    "fn() { 1 }\n(2)",
+    "(-a).b; (a + b)(c); (a ?? b) ?? c; a - (b - c); (a * b) - c",
+];
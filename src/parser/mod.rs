@@ -1,41 +1,101 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, rc::Rc};
 
 use crate::{
-    ast::{BlockStatement, Expression, Statement},
-    lexer::{Lexer, Token},
+    ast::{BlockStatement, Expression, Pattern, Spanned, Statement},
+    lexer::{Lexer, Span, Token},
 };
 
+#[cfg(test)]
+pub(crate) mod corpus;
 mod program;
+mod report;
 
 pub use program::Program;
+pub(crate) use report::escape_json;
+pub use report::{Diagnostic, ParseReport, Severity};
 
 #[derive(Default)]
 pub struct Parser {
     lexer: Lexer,
     current_token: Option<Token>,
     peek_token: Option<Token>,
-    pub errors: Vec<String>,
+    current_span: Span,
+    peek_span: Span,
+    pub errors: Vec<Diagnostic>,
+    pub warnings: Vec<Diagnostic>,
+    /// Brackets opened and not closed yet by the tokens before the current one
+    brackets: usize,
+    /// Expressions being parsed within one another
+    depth: usize,
+    max_nesting_depth: usize,
+    /// Consulted in order, the first matching one parsing the expression
+    prefix_parsers: Vec<(TokenPredicate, PrefixParseFn)>,
+    infix_parsers: Vec<(TokenPredicate, ExpressionPrecedence, InfixParseFn)>,
+    /// Comments of the lexer attached to a statement so far, when keeping them
+    attached_comments: Option<usize>,
+}
+
+/// Whether a parse function applies to a token.
+pub type TokenPredicate = fn(&Token) -> bool;
+/// Parses an expression from its first token, which is the current one.
+pub type PrefixParseFn = fn(&mut Parser) -> Option<Expression>;
+/// Parses an operator, the current token, applied to the expression before it.
+pub type InfixParseFn = fn(&mut Parser, Spanned<Expression>) -> Option<Expression>;
+
+/// How deeply expressions and blocks can be nested by default, far more than any program written
+/// by hand while keeping the parser well within the stack, including the smaller one of
+/// WebAssembly.
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 512;
+
+/// Parses `source`, collecting every error and warning along the way.
+pub fn parse_with_diagnostics(source: &str) -> ParseReport {
+    let mut parser = Parser::new(Lexer::new(source.into()));
+    let program = parser.parse_program();
+
+    let mut diagnostics = parser.warnings;
+    diagnostics.append(&mut parser.errors);
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span);
+
+    let has_errors = diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error);
+    let program = program.filter(|program| !program.statements.is_empty() || !has_errors);
+
+    ParseReport {
+        diagnostics,
+        program,
+    }
 }
 
-#[derive(PartialEq, Eq, Default, PartialOrd, Ord, Debug)]
+/// Parses `source`, ignoring warnings.
+pub fn parse(source: &str) -> Result<Program, Vec<Diagnostic>> {
+    parse_with_diagnostics(source).into_result()
+}
+
+#[derive(PartialEq, Eq, Default, PartialOrd, Ord, Debug, Clone, Copy)]
 pub enum ExpressionPrecedence {
     #[default]
     Lowest,
+    Coalesce,    // ??
     Equals,      // ==
     LessGreater, // > or <
+    Range,       // start..end
     Sum,         //+
     Product,     //*
     Prefix,      //-Xor!X
+    Postfix,     // X++ or X--
     Call,        // myFunction(X)
-    Index,       // array[index]
+    Index,       // array[index] or value.member
 }
 
 impl Parser {
     pub fn new(lexer: Lexer) -> Self {
         let mut parser = Self {
             lexer,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
             ..Default::default()
         };
+        parser.register_builtin_grammar();
 
         parser.next_token();
         parser.next_token();
@@ -43,49 +103,186 @@ impl Parser {
         parser
     }
 
+    /// Rejects expressions nested more than `depth` levels deep, such as parenthesized ones or
+    /// blocks, instead of [`DEFAULT_MAX_NESTING_DEPTH`].
+    pub fn with_max_nesting_depth(mut self, depth: usize) -> Self {
+        self.max_nesting_depth = depth;
+        self
+    }
+
+    /// Keeps the `//` comments, attaching each one to the statement following it, or to the end
+    /// of its block, for the pretty mode of [`Program`] to write them back.
+    pub fn with_comments(mut self) -> Self {
+        self.attached_comments = Some(0);
+        self
+    }
+
+    pub fn current_token(&self) -> Option<&Token> {
+        self.current_token.as_ref()
+    }
+
+    pub fn peek_token(&self) -> Option<&Token> {
+        self.peek_token.as_ref()
+    }
+
     pub fn next_token(&mut self) {
+        self.brackets = self.brackets_after_current();
         self.current_token = self.peek_token.take();
+        self.current_span = self.peek_span;
         self.peek_token = if self.current_token == Some(Token::Eof) {
             None
         } else {
-            Some(self.lexer.next_token())
+            let token = self.lexer.next_token();
+            self.peek_span = self.lexer.span();
+            Some(token)
         };
     }
 
+    /// Brackets left open once the current token is consumed.
+    fn brackets_after_current(&self) -> usize {
+        match self.current_token {
+            Some(
+                Token::LeftParen | Token::LeftBrace | Token::LeftBracket | Token::QuestionBracket,
+            ) => self.brackets + 1,
+            Some(Token::RightParen | Token::RightBrace | Token::RightBracket) => {
+                self.brackets.saturating_sub(1)
+            }
+            _ => self.brackets,
+        }
+    }
+
     pub fn parse_program(&mut self) -> Option<Program> {
         let mut program = Program::default();
 
         while self.current_token != Some(Token::Eof) {
-            if let Some(statement) = self.parse_statement() {
-                program.statements.push(statement);
+            self.attach_comments(&mut program.comments, program.statements.len());
+            let (brackets, start) = (self.brackets, self.current_span.start);
+            match self.parse_statement() {
+                Some(statement) => program.statements.push(self.spanned(start, statement)),
+                None => self.synchronize(brackets),
             }
             self.next_token();
         }
+        self.attach_comments(&mut program.comments, program.statements.len());
 
         Some(program)
     }
 
+    /// Attaches the comments before the current token, not attached yet, to the statement at
+    /// `index`. Those within the previous statement, such as between the elements of an array,
+    /// end up moved before this one.
+    fn attach_comments(&mut self, comments: &mut BTreeMap<usize, Vec<String>>, index: usize) {
+        let Some(attached) = self.attached_comments else {
+            return;
+        };
+
+        let preceding = self.lexer.comments()[attached..]
+            .iter()
+            .take_while(|comment| comment.span.end <= self.current_span.start)
+            .map(|comment| comment.text.clone())
+            .collect::<Vec<_>>();
+        if !preceding.is_empty() {
+            self.attached_comments = Some(attached + preceding.len());
+            comments.entry(index).or_default().extend(preceding);
+        }
+    }
+
+    /// Skips the rest of a statement that failed to parse, so that its leftover tokens don't
+    /// cascade into more errors. Stops on its `;` or before the keyword starting the next
+    /// statement, ignoring anything nested in brackets opened since the failure, or before the
+    /// `}` closing the block the statement started in with `brackets` open.
+    fn synchronize(&mut self, brackets: usize) {
+        // Brackets the statement failed within are tracked separately, as they may never be
+        // closed, such as with `if (a { b }`
+        let mut depth = 0usize;
+
+        loop {
+            match self.current_token {
+                None | Some(Token::Eof) => return,
+                Some(Token::Semicolon) if depth == 0 => return,
+                Some(
+                    Token::LeftParen
+                    | Token::LeftBrace
+                    | Token::LeftBracket
+                    | Token::QuestionBracket,
+                ) => depth += 1,
+                Some(Token::RightParen | Token::RightBrace | Token::RightBracket) => {
+                    depth = depth.saturating_sub(1)
+                }
+                _ => {}
+            }
+
+            let next_starts_statement = matches!(
+                self.peek_token,
+                None | Some(
+                    Token::Eof
+                        | Token::Let
+                        | Token::Const
+                        | Token::Return
+                        | Token::Throw
+                        | Token::Function
+                        | Token::Macro
+                        | Token::If
+                        | Token::For
+                        | Token::While
+                )
+            );
+            let next_closes_block = self.peek_token == Some(Token::RightBrace)
+                && self.brackets_after_current() <= brackets;
+            if (depth == 0 && next_starts_statement) || next_closes_block {
+                return;
+            }
+
+            self.next_token();
+        }
+    }
+
+    /// `node`, spanning from `start` to the end of the current token, its last one.
+    fn spanned<T>(&self, start: usize, node: T) -> Spanned<T> {
+        Spanned::new(node, Span::new(start, self.current_span.end))
+    }
+
     pub fn parse_statement(&mut self) -> Option<Statement> {
         match self.current_token.as_ref()? {
             Token::Let => self.parse_let_statement(),
+            Token::Const => self.parse_const_statement(),
             Token::Return => self.parse_return_statement(),
+            Token::Throw => self.parse_throw_statement(),
             _ => self.parse_expression_statement(),
         }
     }
 
     pub fn parse_let_statement(&mut self) -> Option<Statement> {
-        let name = match self.peek_token.as_ref() {
-            Some(Token::Identifier(name)) => name.to_string(),
-            token => {
-                self.errors.push(format!(
-                    "expected next token to be Identifier, got {token:?}"
-                ));
-                return None;
-            }
-        };
+        if self.peek_token == Some(Token::LeftBrace) {
+            self.next_token();
+            let pattern = self.parse_hash_pattern()?;
+            let value = self.parse_let_value()?;
 
-        self.next_token();
+            return Some(Statement::Destructure { pattern, value });
+        }
+
+        if self.peek_token == Some(Token::LeftBracket) {
+            self.next_token();
+            let pattern = self.parse_array_pattern()?;
+            let value = self.parse_let_value()?;
+
+            return Some(Statement::Destructure { pattern, value });
+        }
+
+        let name = self.expect_identifier()?;
+        let value = self.parse_let_value()?;
+
+        Some(Statement::Let { name, value })
+    }
 
+    pub fn parse_const_statement(&mut self) -> Option<Statement> {
+        let name = self.expect_identifier()?;
+        let value = self.parse_let_value()?;
+
+        Some(Statement::Const { name, value })
+    }
+
+    fn parse_let_value(&mut self) -> Option<Spanned<Expression>> {
         self.expect_token(Token::EqualSign)?;
         self.next_token();
 
@@ -95,7 +292,48 @@ impl Parser {
             self.next_token();
         };
 
-        Some(Statement::Let { name, value })
+        Some(value)
+    }
+
+    fn parse_hash_pattern(&mut self) -> Option<Pattern> {
+        let mut bindings = Vec::new();
+
+        while self.peek_token != Some(Token::RightBrace) {
+            let key = self.expect_identifier()?;
+
+            let binding = if self.peek_token == Some(Token::Colon) {
+                self.next_token();
+                self.expect_identifier()?
+            } else {
+                key.clone()
+            };
+
+            bindings.push((key.to_string(), binding));
+
+            if self.peek_token != Some(Token::RightBrace) {
+                self.expect_token(Token::Comma)?;
+            }
+        }
+
+        self.expect_token(Token::RightBrace)?;
+
+        Some(Pattern::Hash(bindings))
+    }
+
+    fn parse_array_pattern(&mut self) -> Option<Pattern> {
+        let mut bindings = Vec::new();
+
+        while self.peek_token != Some(Token::RightBracket) {
+            bindings.push(self.expect_identifier()?);
+
+            if self.peek_token != Some(Token::RightBracket) {
+                self.expect_token(Token::Comma)?;
+            }
+        }
+
+        self.expect_token(Token::RightBracket)?;
+
+        Some(Pattern::Array(bindings))
     }
 
     fn parse_return_statement(&mut self) -> Option<Statement> {
@@ -110,6 +348,18 @@ impl Parser {
         Some(Statement::Return { value })
     }
 
+    fn parse_throw_statement(&mut self) -> Option<Statement> {
+        self.next_token();
+
+        let value = self.parse_expression(ExpressionPrecedence::Lowest)?;
+
+        if self.peek_token == Some(Token::Semicolon) {
+            self.next_token();
+        };
+
+        Some(Statement::Throw { value })
+    }
+
     fn parse_expression_statement(&mut self) -> Option<Statement> {
         let value = self.parse_expression(ExpressionPrecedence::Lowest)?;
 
@@ -120,66 +370,233 @@ impl Parser {
         Some(Statement::Expression { value })
     }
 
-    fn parse_expression(&mut self, precendence: ExpressionPrecedence) -> Option<Expression> {
-        let mut left = match self.current_token.clone()? {
+    /// Parses an expression, unless it is nested too deeply within others, as every nested
+    /// expression goes through here.
+    pub fn parse_expression(
+        &mut self,
+        precendence: ExpressionPrecedence,
+    ) -> Option<Spanned<Expression>> {
+        self.check_nesting_depth()?;
+
+        // Restored rather than decremented, as the operators parsed in between nest deeper too
+        let depth = self.depth;
+        self.depth += 1;
+        let expression = self.parse_expression_inner(precendence);
+        self.depth = depth;
+
+        expression
+    }
+
+    fn check_nesting_depth(&mut self) -> Option<()> {
+        if self.depth < self.max_nesting_depth {
+            return Some(());
+        }
+
+        self.error(
+            "nesting-too-deep",
+            self.current_span,
+            format!(
+                "expression nesting too deep (limit {})",
+                self.max_nesting_depth
+            ),
+        );
+        None
+    }
+
+    fn parse_expression_inner(
+        &mut self,
+        precendence: ExpressionPrecedence,
+    ) -> Option<Spanned<Expression>> {
+        let token = self.current_token.clone()?;
+        let Some(prefix) = self.prefix_parser(&token) else {
+            self.error(
+                "no-prefix-parser",
+                self.current_span,
+                format!("no expression statement parser for {token}"),
+            );
+            return None;
+        };
+
+        let start = self.current_span.start;
+        let mut left = prefix(self)?;
+
+        while self.peek_token != Some(Token::Semicolon) {
+            let Some((infix_precedence, infix)) = self
+                .peek_token
+                .as_ref()
+                .and_then(|token| self.infix_parser(token))
+            else {
+                break;
+            };
+            if precendence >= infix_precedence {
+                break;
+            }
+
+            let operand = self.spanned(start, left);
+            self.next_token();
+            // Each operator nests the expression before it one level deeper, so that a long
+            // chain like `a + a + ...` or `f()()...` counts as deeply nested as its tree
+            self.check_nesting_depth()?;
+            self.depth += 1;
+            left = infix(self, operand)?;
+        }
+
+        Some(self.spanned(start, left))
+    }
+
+    /// Registers how to parse an expression starting with a token matching `predicate`, taking
+    /// precedence over the grammar registered so far. `parse` is called with the token as the
+    /// current one, and returns `None` after reporting an error.
+    pub fn register_prefix(&mut self, predicate: TokenPredicate, parse: PrefixParseFn) {
+        self.prefix_parsers.insert(0, (predicate, parse));
+    }
+
+    /// Registers how to parse an operator matching `predicate` following an expression, binding
+    /// with `precedence`, taking precedence over the grammar registered so far. `parse` is called
+    /// with the operator as the current token and the expression on its left.
+    pub fn register_infix(
+        &mut self,
+        predicate: TokenPredicate,
+        precedence: ExpressionPrecedence,
+        parse: InfixParseFn,
+    ) {
+        self.infix_parsers.insert(0, (predicate, precedence, parse));
+    }
+
+    fn prefix_parser(&self, token: &Token) -> Option<PrefixParseFn> {
+        self.prefix_parsers
+            .iter()
+            .find(|(predicate, _)| predicate(token))
+            .map(|(_, parse)| *parse)
+    }
+
+    fn infix_parser(&self, token: &Token) -> Option<(ExpressionPrecedence, InfixParseFn)> {
+        self.infix_parsers
+            .iter()
+            .find(|(predicate, ..)| predicate(token))
+            .map(|(_, precedence, parse)| (*precedence, *parse))
+    }
+
+    fn register_builtin_grammar(&mut self) {
+        use ExpressionPrecedence as P;
+
+        let prefixes: [(TokenPredicate, PrefixParseFn); 10] = [
+            (
+                |token| {
+                    matches!(
+                        token,
+                        Token::Integer(_)
+                            | Token::BigInt(_)
+                            | Token::String(_)
+                            | Token::Identifier(_)
+                            | Token::True
+                            | Token::False
+                            | Token::Null
+                    )
+                },
+                Self::parse_literal,
+            ),
+            (
+                |token| *token == Token::LeftBracket,
+                Self::parse_array_literal,
+            ),
+            (|token| *token == Token::LeftBrace, Self::parse_hash_literal),
+            (
+                |token| {
+                    matches!(
+                        token,
+                        Token::PlusSign | Token::MinusSign | Token::ExclamationMark
+                    )
+                },
+                Self::parse_prefix_expression,
+            ),
+            (
+                |token| *token == Token::LeftParen,
+                Self::parse_grouped_expression,
+            ),
+            (|token| *token == Token::If, Self::parse_if_expression),
+            (|token| *token == Token::For, Self::parse_for_expression),
+            (|token| *token == Token::While, Self::parse_while_expression),
+            (
+                |token| *token == Token::Function,
+                Self::parse_function_literal,
+            ),
+            (|token| *token == Token::Macro, Self::parse_macro_literal),
+        ];
+        let infixes: [(TokenPredicate, ExpressionPrecedence, InfixParseFn); 11] = [
+            (
+                |token| *token == Token::DoubleQuestion,
+                P::Coalesce,
+                Self::parse_infix_expression,
+            ),
+            (
+                |token| matches!(token, Token::Equal | Token::NotEqual),
+                P::Equals,
+                Self::parse_infix_expression,
+            ),
+            (
+                |token| matches!(token, Token::LessThan | Token::GreaterThan),
+                P::LessGreater,
+                Self::parse_infix_expression,
+            ),
+            (
+                |token| matches!(token, Token::DotDot | Token::DotDotEqual),
+                P::Range,
+                Self::parse_range_expression,
+            ),
+            (
+                |token| matches!(token, Token::PlusSign | Token::MinusSign),
+                P::Sum,
+                Self::parse_infix_expression,
+            ),
+            (
+                |token| matches!(token, Token::Asterisk | Token::Slash),
+                P::Product,
+                Self::parse_infix_expression,
+            ),
+            (
+                |token| matches!(token, Token::Increment | Token::Decrement),
+                P::Postfix,
+                Self::parse_postfix_expression,
+            ),
+            (
+                |token| *token == Token::LeftParen,
+                P::Call,
+                Self::parse_call_expression,
+            ),
+            (
+                |token| *token == Token::LeftBracket,
+                P::Index,
+                Self::parse_index_expression,
+            ),
+            (
+                |token| *token == Token::Dot,
+                P::Index,
+                Self::parse_member_expression,
+            ),
+            (
+                |token| matches!(token, Token::QuestionDot | Token::QuestionBracket),
+                P::Index,
+                Self::parse_optional_index_expression,
+            ),
+        ];
+
+        self.prefix_parsers = prefixes.to_vec();
+        self.infix_parsers = infixes.to_vec();
+    }
+
+    /// Integers, strings, identifiers, booleans and `null`.
+    fn parse_literal(&mut self) -> Option<Expression> {
+        match self.current_token.clone()? {
             Token::Integer(integer) => Some(integer.into()),
+            Token::BigInt(integer) => Some(Expression::BigInt(integer)),
             Token::Identifier(identifier) => Some(Expression::Identifier(identifier)),
             Token::String(string) => Some(string.into()),
-            Token::LeftBracket => self.parse_array_literal(),
-            Token::LeftBrace => self.parse_hash_literal(),
             Token::True => Some(true.into()),
             Token::False => Some(false.into()),
-            Token::PlusSign => self.parse_prefix_expression(),
-            Token::MinusSign => self.parse_prefix_expression(),
-            Token::ExclamationMark => self.parse_prefix_expression(),
-            Token::LeftParen => self.parse_grouped_expression(),
-            Token::If => self.parse_if_expression(),
-            Token::Function => self.parse_function_literal(),
-            token => {
-                self.errors
-                    .push(format!("no expression statement parser for {token}"));
-                None
-            }
-        }?;
-
-        while self.peek_token != Some(Token::Semicolon) && precendence < self.peek_precedence() {
-            let infix = match self.peek_token.clone()? {
-                Token::PlusSign
-                | Token::MinusSign
-                | Token::Slash
-                | Token::Asterisk
-                | Token::Equal
-                | Token::NotEqual
-                | Token::LessThan
-                | Token::GreaterThan => {
-                    self.next_token();
-                    self.parse_infix_expression(left.clone())
-                }
-                Token::LeftParen => {
-                    self.next_token();
-                    self.parse_call_expression(left.clone())
-                }
-                Token::LeftBracket => {
-                    self.next_token();
-                    self.parse_index_expression(left.clone())
-                }
-                _ => {
-                    self.errors.push(format!(
-                        "no infix statement parser for {}",
-                        self.peek_token.clone().unwrap()
-                    ));
-                    return Some(left);
-                }
-            };
-
-            if let Some(infix) = infix {
-                left = infix;
-            } else {
-                return Some(left);
-            }
+            Token::Null => Some(Expression::Null),
+            _ => None,
         }
-
-        Some(left)
     }
 
     fn parse_hash_literal(&mut self) -> Option<Expression> {
@@ -194,6 +611,14 @@ impl Parser {
 
             let value = self.parse_expression(ExpressionPrecedence::Lowest)?;
 
+            if map.contains_key(&key) {
+                self.warning(
+                    "duplicate-key",
+                    key.span,
+                    format!("duplicate key {key} in hash literal, the last value wins"),
+                );
+            }
+
             map.insert(key, value);
 
             if self.peek_token != Some(Token::RightBrace) {
@@ -206,16 +631,55 @@ impl Parser {
         Some(Expression::HashLiteral(map))
     }
 
-    fn parse_index_expression(&mut self, left: Expression) -> Option<Expression> {
+    fn parse_index_expression(&mut self, left: Spanned<Expression>) -> Option<Expression> {
         self.next_token();
         let index = self.parse_expression(ExpressionPrecedence::Lowest)?;
+        self.expect_closing(Token::RightBracket, "index expression")?;
 
-        if self.peek_token != Some(Token::RightBracket) {
-            return None;
+        Some(Expression::Index {
+            left: Box::new(left),
+            index: Box::new(index),
+        })
+    }
+
+    /// `value.method(args)` is sugar for `method(value, args)`, while `value.member` is sugar
+    /// for `value["member"]`.
+    fn parse_member_expression(&mut self, left: Spanned<Expression>) -> Option<Expression> {
+        let member = self.expect_identifier()?;
+        let member_span = self.current_span;
+
+        if self.peek_token != Some(Token::LeftParen) {
+            return Some(Expression::Index {
+                left: Box::new(left),
+                index: Box::new(Spanned::new(
+                    Expression::String(member.to_string()),
+                    member_span,
+                )),
+            });
         }
         self.next_token();
 
-        Some(Expression::Index {
+        let mut arguments = vec![left];
+        arguments.extend(self.parse_expression_list(Token::RightParen)?);
+
+        Some(Expression::FunctionCall {
+            name: Box::new(Spanned::new(Expression::Identifier(member), member_span)),
+            arguments,
+        })
+    }
+
+    fn parse_optional_index_expression(&mut self, left: Spanned<Expression>) -> Option<Expression> {
+        let index = if self.current_token == Some(Token::QuestionDot) {
+            let member = self.expect_identifier()?;
+            Spanned::new(Expression::String(member.to_string()), self.current_span)
+        } else {
+            self.next_token();
+            let index = self.parse_expression(ExpressionPrecedence::Lowest)?;
+            self.expect_closing(Token::RightBracket, "index expression")?;
+            index
+        };
+
+        Some(Expression::OptionalIndex {
             left: Box::new(left),
             index: Box::new(index),
         })
@@ -238,9 +702,14 @@ impl Parser {
         })
     }
 
-    fn parse_infix_expression(&mut self, lh_expression: Expression) -> Option<Expression> {
+    fn parse_infix_expression(&mut self, lh_expression: Spanned<Expression>) -> Option<Expression> {
         let operator = self.current_token.clone()?;
-        let precedence = self.current_precedence();
+        let precedence = if operator == Token::DoubleQuestion {
+            // Right-associative, `a ?? b ?? c` is `a ?? (b ?? c)`
+            ExpressionPrecedence::Lowest
+        } else {
+            self.current_precedence()
+        };
 
         self.next_token();
 
@@ -256,9 +725,10 @@ impl Parser {
 
         let expression = self.parse_expression(ExpressionPrecedence::Lowest)?;
 
-        self.expect_token(Token::RightParen)?;
+        self.expect_closing(Token::RightParen, "grouped expression")?;
 
-        Some(expression)
+        // Spans the parentheses once returned
+        Some(expression.node)
     }
 
     fn parse_if_expression(&mut self) -> Option<Expression> {
@@ -287,17 +757,95 @@ impl Parser {
         })
     }
 
+    fn parse_range_expression(&mut self, start: Spanned<Expression>) -> Option<Expression> {
+        let inclusive = self.current_token == Some(Token::DotDotEqual);
+        let precedence = self.current_precedence();
+
+        self.next_token();
+
+        Some(Expression::Range {
+            start: Box::new(start),
+            end: Box::new(self.parse_expression(precedence)?),
+            inclusive,
+        })
+    }
+
+    fn parse_for_expression(&mut self) -> Option<Expression> {
+        self.expect_token(Token::LeftParen)?;
+        let variable = self.expect_identifier()?;
+        self.expect_token(Token::In)?;
+        self.next_token();
+
+        let iterable = self.parse_expression(ExpressionPrecedence::Lowest)?;
+
+        self.expect_token(Token::RightParen)?;
+        self.expect_token(Token::LeftBrace)?;
+
+        let body = self.parse_block_statement()?;
+
+        Some(Expression::For {
+            variable,
+            iterable: Box::new(iterable),
+            body,
+        })
+    }
+
+    fn parse_while_expression(&mut self) -> Option<Expression> {
+        self.expect_token(Token::LeftParen)?;
+        self.next_token();
+
+        let condition = self.parse_expression(ExpressionPrecedence::Lowest)?;
+
+        self.expect_token(Token::RightParen)?;
+        self.expect_token(Token::LeftBrace)?;
+
+        let body = self.parse_block_statement()?;
+
+        Some(Expression::While {
+            condition: Box::new(condition),
+            body,
+        })
+    }
+
+    fn parse_postfix_expression(&mut self, operand: Spanned<Expression>) -> Option<Expression> {
+        let operator = self.current_token.clone()?;
+
+        let Expression::Identifier(name) = operand.node else {
+            self.error(
+                "invalid-postfix-operand",
+                self.current_span,
+                format!("{operator} can only be applied to an identifier, got {operand}"),
+            );
+            return None;
+        };
+
+        Some(Expression::Postfix { operator, name })
+    }
+
     fn parse_function_literal(&mut self) -> Option<Expression> {
+        let (arguments, body) = self.parse_parameters_and_body()?;
+
+        Some(Expression::Function { arguments, body })
+    }
+
+    fn parse_macro_literal(&mut self) -> Option<Expression> {
+        let (arguments, body) = self.parse_parameters_and_body()?;
+
+        Some(Expression::Macro { arguments, body })
+    }
+
+    /// `(parameters) { body }` following `fn` or `macro`.
+    fn parse_parameters_and_body(&mut self) -> Option<(Vec<Rc<str>>, BlockStatement)> {
         self.expect_token(Token::LeftParen)?;
         let arguments = self.parse_function_parameters()?;
 
         self.expect_token(Token::LeftBrace)?;
         let body = self.parse_block_statement()?;
 
-        Some(Expression::Function { arguments, body })
+        Some((arguments, body))
     }
 
-    fn parse_function_parameters(&mut self) -> Option<Vec<String>> {
+    fn parse_function_parameters(&mut self) -> Option<Vec<Rc<str>>> {
         let mut parameters = Vec::new();
 
         if self.peek_token == Some(Token::RightParen) {
@@ -310,23 +858,34 @@ impl Parser {
         if let Some(Token::Identifier(name)) = self.current_token.clone() {
             parameters.push(name);
         } else {
-            self.errors.push(format!(
-                "expected next token to be Identifier, got {:?}",
-                self.current_token
-            ));
+            self.error(
+                "unexpected-token",
+                self.current_span,
+                format!(
+                    "expected next token to be Identifier, got {:?}",
+                    self.current_token
+                ),
+            );
             return None;
         };
 
         while self.peek_token == Some(Token::Comma) {
             self.next_token();
+            if self.peek_token == Some(Token::RightParen) {
+                break;
+            }
             self.next_token();
             if let Some(Token::Identifier(name)) = self.current_token.clone() {
                 parameters.push(name);
             } else {
-                self.errors.push(format!(
-                    "expected next token to be Identifier, got {:?}",
-                    self.current_token
-                ));
+                self.error(
+                    "unexpected-token",
+                    self.current_span,
+                    format!(
+                        "expected next token to be Identifier, got {:?}",
+                        self.current_token
+                    ),
+                );
                 return None;
             };
         }
@@ -336,14 +895,14 @@ impl Parser {
         Some(parameters)
     }
 
-    fn parse_call_expression(&mut self, name: Expression) -> Option<Expression> {
+    fn parse_call_expression(&mut self, name: Spanned<Expression>) -> Option<Expression> {
         Some(Expression::FunctionCall {
             name: Box::new(name),
             arguments: self.parse_expression_list(Token::RightParen)?,
         })
     }
 
-    fn parse_expression_list(&mut self, end_token: Token) -> Option<Vec<Expression>> {
+    fn parse_expression_list(&mut self, end_token: Token) -> Option<Vec<Spanned<Expression>>> {
         let mut arguments = Vec::new();
 
         if self.peek_token.as_ref() == Some(&end_token) {
@@ -357,6 +916,9 @@ impl Parser {
 
         while self.peek_token == Some(Token::Comma) {
             self.next_token();
+            if self.peek_token.as_ref() == Some(&end_token) {
+                break;
+            }
             self.next_token();
             arguments.push(self.parse_expression(ExpressionPrecedence::Lowest)?);
         }
@@ -366,87 +928,640 @@ impl Parser {
         Some(arguments)
     }
 
-    fn parse_block_statement(&mut self) -> Option<BlockStatement> {
-        let mut statements = Vec::new();
-        self.next_token();
+    fn parse_block_statement(&mut self) -> Option<BlockStatement> {
+        let mut block = BlockStatement::default();
+        self.next_token();
+        // Counted like an expression, as it nests the ones within it one level further
+        self.depth += 1;
+
+        while self.current_token != Some(Token::RightBrace)
+            && self.current_token != Some(Token::Eof)
+        {
+            self.attach_comments(&mut block.comments, block.statements.len());
+            let (brackets, start) = (self.brackets, self.current_span.start);
+            match self.parse_statement() {
+                Some(statement) => block.statements.push(self.spanned(start, statement)),
+                None => self.synchronize(brackets),
+            }
+            self.next_token();
+        }
+        self.attach_comments(&mut block.comments, block.statements.len());
+        self.depth -= 1;
+
+        Some(block)
+    }
+
+    /// Precedence of the current token as an infix operator, for parsing its right-hand side.
+    pub fn current_precedence(&self) -> ExpressionPrecedence {
+        self.current_token
+            .as_ref()
+            .map(|token| self.infix_precedence(token))
+            .unwrap_or_default()
+    }
+
+    /// How tightly `token` binds as an infix operator, the lowest precedence if it isn't one.
+    pub fn infix_precedence(&self, token: &Token) -> ExpressionPrecedence {
+        self.infix_parser(token)
+            .map(|(precedence, _)| precedence)
+            .unwrap_or_default()
+    }
+
+    fn expect_identifier(&mut self) -> Option<Rc<str>> {
+        let name = match self.peek_token.as_ref() {
+            Some(Token::Identifier(name)) => name.clone(),
+            token => {
+                let message = format!("expected next token to be Identifier, got {token:?}");
+                self.error("unexpected-token", self.peek_span, message);
+                return None;
+            }
+        };
+
+        self.next_token();
+
+        Some(name)
+    }
+
+    pub fn expect_token(&mut self, token: Token) -> Option<()> {
+        if self.peek_token.as_ref() != Some(&token) {
+            self.error(
+                "unexpected-token",
+                self.peek_span,
+                format!(
+                    "expected next token to be {token:?}, got {:?}",
+                    self.peek_token
+                ),
+            );
+            return None;
+        };
+
+        self.next_token();
+
+        Some(())
+    }
+
+    /// Like [`Self::expect_token`], for the `token` closing a `construct` named in the error.
+    fn expect_closing(&mut self, token: Token, construct: &str) -> Option<()> {
+        if self.peek_token.as_ref() != Some(&token) {
+            self.error(
+                "unexpected-token",
+                self.peek_span,
+                format!(
+                    "expected next token to be {token:?}, got {:?}, to close the {construct}",
+                    self.peek_token
+                ),
+            );
+            return None;
+        }
+
+        self.next_token();
+
+        Some(())
+    }
+
+    fn error(&mut self, code: &'static str, span: Span, message: String) {
+        self.errors.push(Diagnostic::error(code, message, span));
+    }
+
+    fn warning(&mut self, code: &'static str, span: Span, message: String) {
+        self.warnings.push(Diagnostic::warning(code, message, span));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluator::{environment::Environment, Evaluator};
+
+    use super::*;
+
+    fn hash_literal<const N: usize>(entries: [(Expression, Expression); N]) -> Expression {
+        Expression::HashLiteral(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_let_statements() {
+        let input = r#"let x = 5;
+let y = true;
+let foobar = y;"#;
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+
+        assert_eq!(parser.errors.len(), 0, "{:?}", parser.errors);
+        assert_eq!(program.statements.len(), 3);
+
+        assert_eq!(
+            program.statements[0],
+            Statement::Let {
+                name: "x".into(),
+                value: 5.into()
+            }
+        );
+        assert_eq!(
+            program.statements[1],
+            Statement::Let {
+                name: "y".into(),
+                value: true.into(),
+            }
+        );
+        assert_eq!(
+            program.statements[2],
+            Statement::Let {
+                name: "foobar".into(),
+                value: Expression::Identifier("y".into()).into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_const_statements() {
+        let input = "const PI = 3;const greeting = \"hi\";";
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+
+        assert_eq!(parser.errors.len(), 0, "{:?}", parser.errors);
+        assert_eq!(
+            program.statements,
+            vec![
+                Statement::Const {
+                    name: "PI".into(),
+                    value: 3.into()
+                },
+                Statement::Const {
+                    name: "greeting".into(),
+                    value: Expression::String("hi".into()).into()
+                },
+            ]
+        );
+        assert_eq!(program.to_string(), input);
+    }
+
+    #[test]
+    fn test_let_hash_destructuring() {
+        let input = "let {name, age: years} = person;";
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+
+        assert_eq!(parser.errors.len(), 0, "{:?}", parser.errors);
+        assert_eq!(program.statements.len(), 1);
+
+        assert_eq!(
+            program.statements[0],
+            Statement::Destructure {
+                pattern: Pattern::Hash(vec![
+                    ("name".into(), "name".into()),
+                    ("age".into(), "years".into()),
+                ]),
+                value: Expression::Identifier("person".into()).into()
+            }
+        );
+        assert_eq!(program.to_string(), input);
+    }
+
+    #[test]
+    fn test_let_hash_destructuring_errors() {
+        let tests = &[
+            (
+                "let {1} = person;",
+                "expected next token to be Identifier, got Some(Integer(1))",
+            ),
+            (
+                "let {name age} = person;",
+                "expected next token to be Comma, got Some(Identifier(\"age\"))",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            parser.parse_program().expect("Failed to parse program");
+
+            assert_eq!(
+                parser.errors.first().map(|error| error.message.as_str()),
+                Some(*expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let input = "let x 5;
+let y = 10;
+if (y > 1 { y }
+let z = ;
+let add = fn(a, b) { a + b };
+return add(y, 2);";
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+
+        assert_eq!(
+            parser
+                .errors
+                .iter()
+                .map(|error| (error.span.line_column(input), error.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    (1, 7),
+                    "expected next token to be EqualSign, got Some(Integer(5))"
+                ),
+                (
+                    (3, 11),
+                    "expected next token to be RightParen, got Some(LeftBrace)"
+                ),
+                ((4, 9), "no expression statement parser for ;"),
+            ]
+        );
+        assert_eq!(
+            program
+                .statements
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "let y = 10;",
+                "let add = fn(a, b) {(a + b)};",
+                "return add(y, 2);"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_brackets() {
+        let tests = &[
+            (
+                "myArray[1 + 1",
+                (1, 14),
+                "expected next token to be RightBracket, got Some(Eof), to close the index expression",
+            ),
+            (
+                "let a = myArray[1 + 1; let b = 2;",
+                (1, 22),
+                "expected next token to be RightBracket, got Some(Semicolon), to close the index expression",
+            ),
+            (
+                "hash?[key let b = 2;",
+                (1, 11),
+                r#"expected next token to be RightBracket, got Some(Let), to close the index expression"#,
+            ),
+            (
+                "(1 + 2",
+                (1, 7),
+                "expected next token to be RightParen, got Some(Eof), to close the grouped expression",
+            ),
+            (
+                "let a = (1 + 2 * 3;\na",
+                (1, 19),
+                "expected next token to be RightParen, got Some(Semicolon), to close the grouped expression",
+            ),
+        ];
+
+        for (input, location, message) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            parser.parse_program().expect("Failed to parse program");
+
+            assert_eq!(
+                parser
+                    .errors
+                    .iter()
+                    .map(|error| (error.span.line_column(input), error.message.as_str()))
+                    .collect::<Vec<_>>(),
+                vec![(*location, *message)],
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_spans() {
+        let input = "let a = -(1 + b);\nif (a) { a.len() }";
+        let program = parse(input).unwrap();
+        let source = |span: Span| {
+            input
+                .chars()
+                .skip(span.start)
+                .take(span.end - span.start)
+                .collect::<String>()
+        };
+
+        let [let_statement, if_statement] = &program.statements[..] else {
+            panic!("expected two statements, got {:?}", program.statements);
+        };
+        assert_eq!(source(let_statement.span), "let a = -(1 + b);");
+        assert_eq!(source(if_statement.span), "if (a) { a.len() }");
+
+        let Statement::Let { value, .. } = &let_statement.node else {
+            panic!("expected a let statement, got {let_statement}");
+        };
+        assert_eq!(source(value.span), "-(1 + b)");
+        let Expression::PrefixOperator { expression, .. } = &value.node else {
+            panic!("expected a prefix operator, got {value}");
+        };
+        assert_eq!(source(expression.span), "(1 + b)");
+        let Expression::InfixOperator {
+            lh_expression,
+            rh_expression,
+            ..
+        } = &expression.node
+        else {
+            panic!("expected an infix operator, got {expression}");
+        };
+        assert_eq!(source(lh_expression.span), "1");
+        assert_eq!(source(rh_expression.span), "b");
+
+        let Statement::Expression { value } = &if_statement.node else {
+            panic!("expected an expression statement, got {if_statement}");
+        };
+        let Expression::If {
+            condition,
+            consequence,
+            ..
+        } = &value.node
+        else {
+            panic!("expected an if expression, got {value}");
+        };
+        assert_eq!(source(condition.span), "a");
+        assert_eq!(source(consequence.statements[0].span), "a.len()");
+        let Statement::Expression { value } = &consequence.statements[0].node else {
+            panic!("expected an expression statement");
+        };
+        let Expression::FunctionCall { name, arguments } = &value.node else {
+            panic!("expected a call, got {value}");
+        };
+        assert_eq!(source(name.span), "len");
+        assert_eq!(source(arguments[0].span), "a");
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = r#"// Doubles
+// its argument
+let double = fn(x) {
+  // Not tripled
+  x * 2 // Trailing
+  // Before the end
+};
+
+if (double(2) > 3) { // Large
+  "large"
+} else {
+  // Small
+}
+// The end"#;
+        let expected = r#"// Doubles
+// its argument
+let double = fn(x) {
+    // Not tripled
+    (x * 2);
+    // Trailing
+    // Before the end
+};
+if ((double(2) > 3)) {
+    // Large
+    "large";
+} else {
+    // Small
+};
+// The end
+"#;
+        let parse = |input: &str| {
+            let mut parser = Parser::new(Lexer::new(input.into())).with_comments();
+            let program = parser.parse_program().expect("Failed to parse program");
+            assert_eq!(parser.errors, vec![], "{input}");
+            program
+        };
+
+        let program = parse(input);
+        assert_eq!(format!("{program:#}"), expected);
+        assert_eq!(format!("{:#}", parse(expected)), expected);
+
+        let without_comments = crate::parse(input).unwrap();
+        assert!(without_comments.comments.is_empty());
+        assert_eq!(program.to_string(), without_comments.to_string());
+        assert_eq!(
+            program.eval(&Environment::shared()),
+            without_comments.eval(&Environment::shared())
+        );
+    }
+
+    /// `a % b`, desugared to `a - (a / b) * b`.
+    fn parse_remainder(parser: &mut Parser, left: Spanned<Expression>) -> Option<Expression> {
+        parser.next_token();
+        let right = parser.parse_expression(ExpressionPrecedence::Product)?;
+
+        let quotient = Expression::InfixOperator {
+            operator: Token::Slash,
+            lh_expression: Box::new(left.clone()),
+            rh_expression: Box::new(right.clone()),
+        };
+        Some(Expression::InfixOperator {
+            operator: Token::MinusSign,
+            lh_expression: Box::new(left),
+            rh_expression: Box::new(
+                Expression::InfixOperator {
+                    operator: Token::Asterisk,
+                    lh_expression: Box::new(quotient.into()),
+                    rh_expression: Box::new(right),
+                }
+                .into(),
+            ),
+        })
+    }
+
+    /// `#value`, desugared to `len(value)`.
+    fn parse_length(parser: &mut Parser) -> Option<Expression> {
+        parser.next_token();
+        let value = parser.parse_expression(ExpressionPrecedence::Prefix)?;
+
+        Some(Expression::FunctionCall {
+            name: Box::new(Expression::Identifier("len".into()).into()),
+            arguments: vec![value],
+        })
+    }
+
+    #[test]
+    fn test_registered_parsers() {
+        let eval = |input: &str| {
+            let mut parser = Parser::new(Lexer::new(input.into()));
+            parser.register_infix(
+                |token| *token == Token::Illegal('%'),
+                ExpressionPrecedence::Product,
+                parse_remainder,
+            );
+            parser.register_prefix(|token| *token == Token::Illegal('#'), parse_length);
+
+            let program = parser.parse_program().expect("Failed to parse program");
+            assert_eq!(parser.errors, vec![], "{input}");
+            program.eval(&Environment::shared())
+        };
 
-        while self.current_token != Some(Token::RightBrace)
-            && self.current_token != Some(Token::Eof)
-        {
-            if let Some(statement) = self.parse_statement() {
-                statements.push(statement);
-            }
-            self.next_token();
-        }
+        assert_eq!(eval("7 % 3"), Ok(Some(1.into())));
+        assert_eq!(eval("1 + 7 % 3 * 2"), Ok(Some(3.into())));
+        assert_eq!(eval("#[1, 2, 3] % 2"), Ok(Some(1.into())));
+        assert_eq!(eval(r#"#"ab" + 1"#), Ok(Some(3.into())));
 
-        Some(BlockStatement { statements })
+        let mut parser = Parser::new(Lexer::new("7 % 3".into()));
+        parser.parse_program();
+        assert_eq!(parser.errors.len(), 1, "unregistered by default");
     }
 
-    fn peek_precedence(&self) -> ExpressionPrecedence {
-        match self.peek_token.as_ref() {
-            Some(token) => token.precedence(),
-            None => Default::default(),
-        }
-    }
+    #[test]
+    fn test_registered_parsers_take_precedence() {
+        let mut parser = Parser::new(Lexer::new("-1 - 2".into()));
+        parser.register_prefix(
+            |token| *token == Token::MinusSign,
+            |parser| {
+                parser.next_token();
+                let operand = parser.parse_expression(ExpressionPrecedence::Prefix)?;
+                Some(operand.node)
+            },
+        );
+        parser.register_infix(
+            |token| *token == Token::MinusSign,
+            ExpressionPrecedence::Sum,
+            |parser, left| {
+                parser.next_token();
+                let right = parser.parse_expression(ExpressionPrecedence::Sum)?;
+                Some(Expression::InfixOperator {
+                    operator: Token::PlusSign,
+                    lh_expression: Box::new(left),
+                    rh_expression: Box::new(right),
+                })
+            },
+        );
 
-    fn current_precedence(&self) -> ExpressionPrecedence {
-        match self.current_token.as_ref() {
-            Some(token) => token.precedence(),
-            None => Default::default(),
-        }
+        let program = parser.parse_program().expect("Failed to parse program");
+        assert_eq!(program.to_string(), "(1 + 2)");
     }
 
-    fn expect_token(&mut self, token: Token) -> Option<()> {
-        if self.peek_token.as_ref() != Some(&token) {
-            self.errors.push(format!(
-                "expected next token to be {token:?}, got {:?}",
-                self.peek_token
-            ));
-            return None;
+    #[test]
+    fn test_nesting_depth() {
+        let nested = |open: &str, close: &str, depth: usize| {
+            format!("{}1{}", open.repeat(depth), close.repeat(depth))
+        };
+        let errors = |input: String| {
+            let mut parser = Parser::new(Lexer::new(input));
+            parser.parse_program().expect("Failed to parse program");
+            parser.errors
         };
 
-        self.next_token();
+        assert_eq!(
+            errors(nested("(", ")", DEFAULT_MAX_NESTING_DEPTH - 1)),
+            vec![]
+        );
+        assert_eq!(
+            errors(nested("[", "]", DEFAULT_MAX_NESTING_DEPTH - 1)),
+            vec![]
+        );
+        assert_eq!(
+            errors(nested("!", "", DEFAULT_MAX_NESTING_DEPTH - 1)),
+            vec![]
+        );
+        assert_eq!(
+            errors(vec!["a"; DEFAULT_MAX_NESTING_DEPTH - 1].join(" + ")),
+            vec![]
+        );
+        assert_eq!(
+            errors(format!("[{}]", vec!["a + a"; 50_000].join(", "))),
+            vec![],
+            "only nesting counts, not length"
+        );
 
-        Some(())
+        for input in [
+            nested("(", ")", 5_000),
+            nested("[", "]", 5_000),
+            nested("!", "", 5_000),
+            nested("{1: ", "}", 5_000),
+            nested("fn() { ", " }", 5_000),
+            vec!["a"; 50_000].join(" + "),
+            vec!["a"; 50_000].join(" ?? "),
+            format!("f{}", "()".repeat(50_000)),
+            format!("a{}", "[0]".repeat(50_000)),
+            format!("a{}", ".b".repeat(50_000)),
+        ] {
+            let errors = errors(input);
+            assert_eq!(errors.len(), 1, "{errors:?}");
+            assert_eq!(errors[0].code, "nesting-too-deep");
+            assert_eq!(errors[0].message, "expression nesting too deep (limit 512)");
+        }
+
+        let mut parser = Parser::new(Lexer::new(nested("(", ")", 3))).with_max_nesting_depth(3);
+        parser.parse_program().expect("Failed to parse program");
+        assert_eq!(parser.errors.len(), 1);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_error_recovery_in_blocks() {
+        let input = "let f = fn() { let = 1; [1, (2 3)]; let a = 2; a };
+let b = f();";
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+
+        assert_eq!(
+            parser
+                .errors
+                .iter()
+                .map(|error| error.message.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "expected next token to be Identifier, got Some(EqualSign)",
+                "expected next token to be RightParen, got Some(Integer(3)), to close the grouped expression",
+            ]
+        );
+        assert_eq!(
+            program
+                .statements
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["let f = fn() {let a = 2;a};", "let b = f();"]
+        );
+    }
 
     #[test]
-    fn test_let_statements() {
-        let input = r#"let x = 5;
-let y = true;
-let foobar = y;"#;
+    fn test_let_array_destructuring() {
+        let input = "let [quotient, remainder] = divmod(7, 2);";
         let mut parser = Parser::new(Lexer::new(input.into()));
         let program = parser.parse_program().expect("Failed to parse program");
 
         assert_eq!(parser.errors.len(), 0, "{:?}", parser.errors);
-        assert_eq!(program.statements.len(), 3);
-
         assert_eq!(
-            program.statements[0],
-            Statement::Let {
-                name: "x".into(),
-                value: 5.into()
-            }
+            program.statements,
+            vec![Statement::Destructure {
+                pattern: Pattern::Array(vec!["quotient".into(), "remainder".into()]),
+                value: Expression::FunctionCall {
+                    name: Box::new(Expression::Identifier("divmod".into()).into()),
+                    arguments: vec![7.into(), 2.into()],
+                }
+                .into()
+            }]
         );
+        assert_eq!(program.to_string(), input);
+
+        let mut parser = Parser::new(Lexer::new("let [a, 1] = b;".into()));
+        parser.parse_program().expect("Failed to parse program");
         assert_eq!(
-            program.statements[1],
-            Statement::Let {
-                name: "y".into(),
-                value: true.into(),
-            }
+            parser.errors.first().map(|error| error.message.as_str()),
+            Some("expected next token to be Identifier, got Some(Integer(1))")
         );
+    }
+
+    #[test]
+    fn test_throw_statements() {
+        let input = r#"throw "invalid input";"#;
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+
+        assert_eq!(parser.errors.len(), 0, "{:?}", parser.errors);
         assert_eq!(
-            program.statements[2],
-            Statement::Let {
-                name: "foobar".into(),
-                value: Expression::Identifier("y".into())
-            }
+            program.statements,
+            vec![Statement::Throw {
+                value: Expression::String("invalid input".into()).into()
+            }]
         );
+        assert_eq!(program.to_string(), input);
     }
 
     #[test]
@@ -464,7 +1579,7 @@ return true;"#;
         assert_eq!(
             program.statements[1],
             Statement::Return {
-                value: Expression::Identifier("y".into())
+                value: Expression::Identifier("y".into()).into()
             }
         );
         assert_eq!(
@@ -479,26 +1594,36 @@ return true;"#;
             statements: vec![
                 Statement::Let {
                     name: "myVar".into(),
-                    value: Expression::Identifier("anotherVar".into()),
-                },
+                    value: Expression::Identifier("anotherVar".into()).into(),
+                }
+                .into(),
                 Statement::Expression {
                     value: Expression::FunctionCall {
-                        name: Box::new(Expression::Identifier("print".into())),
+                        name: Box::new(Expression::Identifier("print".into()).into()),
                         arguments: vec![
                             123.into(),
                             true.into(),
                             Expression::InfixOperator {
                                 operator: Token::MinusSign,
-                                rh_expression: Box::new(Expression::PrefixOperator {
-                                    operator: Token::ExclamationMark,
-                                    expression: Box::new(Expression::Identifier("null".into())),
-                                }),
+                                rh_expression: Box::new(
+                                    Expression::PrefixOperator {
+                                        operator: Token::ExclamationMark,
+                                        expression: Box::new(
+                                            Expression::Identifier("null".into()).into(),
+                                        ),
+                                    }
+                                    .into(),
+                                ),
                                 lh_expression: Box::new(false.into()),
-                            },
+                            }
+                            .into(),
                         ],
-                    },
-                },
+                    }
+                    .into(),
+                }
+                .into(),
             ],
+            ..Default::default()
         };
 
         assert_eq!(
@@ -518,7 +1643,7 @@ return true;"#;
         assert_eq!(
             program.statements[0],
             Statement::Expression {
-                value: Expression::Identifier("foobar".into())
+                value: Expression::Identifier("foobar".into()).into()
             }
         );
     }
@@ -574,7 +1699,7 @@ return true;"#;
             ("-15;", Token::MinusSign, 15),
         ];
 
-        for (input, operator, value) in tests.into_iter().cloned() {
+        for (input, operator, value) in tests.iter().cloned() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
 
@@ -588,6 +1713,7 @@ return true;"#;
                         operator,
                         expression: Box::new(value.into())
                     }
+                    .into()
                 }
             )
         }
@@ -600,7 +1726,7 @@ return true;"#;
             ("!false", false, Token::ExclamationMark),
         ];
 
-        for (input, value, operator) in tests.into_iter().cloned() {
+        for (input, value, operator) in tests.iter().cloned() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
 
@@ -614,6 +1740,7 @@ return true;"#;
                         operator,
                         expression: Box::new(value.into())
                     }
+                    .into()
                 }
             )
         }
@@ -632,7 +1759,7 @@ return true;"#;
             ("5 != 5", 5, Token::NotEqual, 5),
         ];
 
-        for (input, lh_integer, operator, rh_integer) in tests.into_iter().cloned() {
+        for (input, lh_integer, operator, rh_integer) in tests.iter().cloned() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
 
@@ -647,6 +1774,7 @@ return true;"#;
                         lh_expression: Box::new(lh_integer.into()),
                         rh_expression: Box::new(rh_integer.into()),
                     }
+                    .into()
                 }
             )
         }
@@ -660,7 +1788,7 @@ return true;"#;
             ("false == false", false, Token::Equal, false),
         ];
 
-        for (input, lh_boolean, operator, rh_boolean) in tests.into_iter().cloned() {
+        for (input, lh_boolean, operator, rh_boolean) in tests.iter().cloned() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
 
@@ -675,6 +1803,7 @@ return true;"#;
                         lh_expression: Box::new(lh_boolean.into()),
                         rh_expression: Box::new(rh_boolean.into()),
                     }
+                    .into()
                 }
             )
         }
@@ -682,51 +1811,7 @@ return true;"#;
 
     #[test]
     fn test_operator_precedence_parsing() {
-        let tests = &[
-            ("-a * b", "((-a) * b)"),
-            ("!-a", "(!(-a))"),
-            ("a + b + c", "((a + b) + c)"),
-            ("a + b - c", "((a + b) - c)"),
-            ("a * b * c", "((a * b) * c)"),
-            ("a * b / c", "((a * b) / c)"),
-            ("a + b / c", "(a + (b / c))"),
-            ("a + b * c + d / e - f", "(((a + (b * c)) + (d / e)) - f)"),
-            ("3 + 4; -5 * 5", "(3 + 4)((-5) * 5)"),
-            ("5 > 4 == 3 < 4", "((5 > 4) == (3 < 4))"),
-            ("5 < 4 != 3 > 4", "((5 < 4) != (3 > 4))"),
-            (
-                "3 + 4 * 5 == 3 * 1 + 4 * 5",
-                "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))",
-            ),
-            ("true", "true"),
-            ("false", "false"),
-            ("3 > 5 == false", "((3 > 5) == false)"),
-            ("3 < 5 == true", "((3 < 5) == true)"),
-            ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4)"),
-            ("(5 + 5) * 2", "((5 + 5) * 2)"),
-            ("2 / (5 + 5)", "(2 / (5 + 5))"),
-            ("-(5 + 5)", "(-(5 + 5))"),
-            ("!(true == true)", "(!(true == true))"),
-            ("a + add(b * c) + d", "((a + add((b * c))) + d)"),
-            (
-                "add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))",
-                "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)))",
-            ),
-            (
-                "add(a + b + c * d / f + g)",
-                "add((((a + b) + ((c * d) / f)) + g))",
-            ),
-            (
-                "a * [1, 2, 3, 4][b * c] * d",
-                "((a * ([1, 2, 3, 4][(b * c)])) * d)",
-            ),
-            (
-                "add(a * b[2], b[1], 2 * [1, 2][1])",
-                "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
-            ),
-        ];
-
-        for (input, expected) in tests.into_iter().cloned() {
+        for (input, expected) in corpus::PRECEDENCE.iter().cloned() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
 
@@ -736,6 +1821,23 @@ return true;"#;
         }
     }
 
+    #[test]
+    fn test_display_round_trips() {
+        let inputs = corpus::PRECEDENCE
+            .iter()
+            .map(|(input, _)| input)
+            .chain(corpus::PROGRAMS);
+
+        for input in inputs {
+            let program = parse(input).unwrap_or_else(|errors| panic!("{input}: {errors:?}"));
+            let displayed = program.to_string();
+            let reparsed =
+                parse(&displayed).unwrap_or_else(|errors| panic!("{displayed}: {errors:?}"));
+
+            assert_eq!(reparsed.statements, program.statements, "{displayed}");
+        }
+    }
+
     #[test]
     fn test_if_expression() {
         let input = "if (x < y) { x }";
@@ -750,18 +1852,24 @@ return true;"#;
             program.statements[0],
             Statement::Expression {
                 value: Expression::If {
-                    condition: Box::new(Expression::InfixOperator {
-                        operator: Token::LessThan,
-                        lh_expression: Box::new(Expression::Identifier("x".into())),
-                        rh_expression: Box::new(Expression::Identifier("y".into()))
-                    }),
+                    condition: Box::new(
+                        Expression::InfixOperator {
+                            operator: Token::LessThan,
+                            lh_expression: Box::new(Expression::Identifier("x".into()).into()),
+                            rh_expression: Box::new(Expression::Identifier("y".into()).into())
+                        }
+                        .into()
+                    ),
                     consequence: BlockStatement {
                         statements: vec![Statement::Expression {
-                            value: Expression::Identifier("x".into())
-                        }]
+                            value: Expression::Identifier("x".into()).into()
+                        }
+                        .into()],
+                        ..Default::default()
                     },
                     alternative: None
                 }
+                .into()
             }
         )
     }
@@ -780,22 +1888,30 @@ return true;"#;
             program.statements[0],
             Statement::Expression {
                 value: Expression::If {
-                    condition: Box::new(Expression::InfixOperator {
-                        operator: Token::LessThan,
-                        lh_expression: Box::new(Expression::Identifier("x".into())),
-                        rh_expression: Box::new(Expression::Identifier("y".into()))
-                    }),
+                    condition: Box::new(
+                        Expression::InfixOperator {
+                            operator: Token::LessThan,
+                            lh_expression: Box::new(Expression::Identifier("x".into()).into()),
+                            rh_expression: Box::new(Expression::Identifier("y".into()).into())
+                        }
+                        .into()
+                    ),
                     consequence: BlockStatement {
                         statements: vec![Statement::Expression {
-                            value: Expression::Identifier("x".into())
-                        }]
+                            value: Expression::Identifier("x".into()).into()
+                        }
+                        .into()],
+                        ..Default::default()
                     },
                     alternative: Some(BlockStatement {
                         statements: vec![Statement::Expression {
-                            value: Expression::Identifier("y".into())
-                        }]
+                            value: Expression::Identifier("y".into()).into()
+                        }
+                        .into()],
+                        ..Default::default()
                     })
                 }
+                .into()
             }
         )
     }
@@ -819,12 +1935,16 @@ return true;"#;
                         statements: vec![Statement::Expression {
                             value: Expression::InfixOperator {
                                 operator: Token::PlusSign,
-                                lh_expression: Box::new(Expression::Identifier("x".into())),
-                                rh_expression: Box::new(Expression::Identifier("y".into()))
+                                lh_expression: Box::new(Expression::Identifier("x".into()).into()),
+                                rh_expression: Box::new(Expression::Identifier("y".into()).into())
                             }
-                        }]
+                            .into()
+                        }
+                        .into()],
+                        ..Default::default()
                     }
                 }
+                .into()
             }
         )
     }
@@ -837,7 +1957,7 @@ return true;"#;
             ("fn(x, y, z) {}", vec!["x".into(), "y".into(), "z".into()]),
         ];
 
-        for (input, arguments) in tests.into_iter().cloned() {
+        for (input, arguments) in tests.iter().cloned() {
             let mut parser = Parser::new(Lexer::new(input.into()));
             let program = parser.parse_program().expect("Failed to parse program");
 
@@ -849,8 +1969,12 @@ return true;"#;
                 Statement::Expression {
                     value: Expression::Function {
                         arguments,
-                        body: BlockStatement { statements: vec![] }
+                        body: BlockStatement {
+                            statements: vec![],
+                            ..Default::default()
+                        }
                     }
+                    .into()
                 }
             )
         }
@@ -870,21 +1994,24 @@ return true;"#;
             program.statements[0],
             Statement::Expression {
                 value: Expression::FunctionCall {
-                    name: Box::new(Expression::Identifier("add".into())),
+                    name: Box::new(Expression::Identifier("add".into()).into()),
                     arguments: vec![
-                        Expression::Integer(1),
+                        Expression::Integer(1).into(),
                         Expression::InfixOperator {
                             operator: Token::Asterisk,
-                            lh_expression: Box::new(Expression::Integer(2)),
-                            rh_expression: Box::new(Expression::Integer(3)),
-                        },
+                            lh_expression: Box::new(Expression::Integer(2).into()),
+                            rh_expression: Box::new(Expression::Integer(3).into()),
+                        }
+                        .into(),
                         Expression::InfixOperator {
                             operator: Token::PlusSign,
-                            lh_expression: Box::new(Expression::Integer(4)),
-                            rh_expression: Box::new(Expression::Integer(5)),
-                        },
+                            lh_expression: Box::new(Expression::Integer(4).into()),
+                            rh_expression: Box::new(Expression::Integer(5).into()),
+                        }
+                        .into(),
                     ]
                 }
+                .into()
             }
         )
     }
@@ -902,7 +2029,7 @@ return true;"#;
         assert_eq!(
             program.statements[0],
             Statement::Expression {
-                value: Expression::String("hello world".to_string())
+                value: Expression::String("hello world".to_string()).into()
             }
         )
     }
@@ -921,18 +2048,21 @@ return true;"#;
             program.statements[0],
             Statement::Expression {
                 value: Expression::Array(vec![
-                    Expression::Integer(1),
+                    Expression::Integer(1).into(),
                     Expression::InfixOperator {
                         operator: Token::Asterisk,
-                        lh_expression: Box::new(Expression::Integer(2)),
-                        rh_expression: Box::new(Expression::Integer(2)),
-                    },
+                        lh_expression: Box::new(Expression::Integer(2).into()),
+                        rh_expression: Box::new(Expression::Integer(2).into()),
+                    }
+                    .into(),
                     Expression::InfixOperator {
                         operator: Token::PlusSign,
-                        lh_expression: Box::new(Expression::Integer(3)),
-                        rh_expression: Box::new(Expression::Integer(3)),
-                    },
+                        lh_expression: Box::new(Expression::Integer(3).into()),
+                        rh_expression: Box::new(Expression::Integer(3).into()),
+                    }
+                    .into(),
                 ])
+                .into()
             }
         )
     }
@@ -951,13 +2081,17 @@ return true;"#;
             program.statements[0],
             Statement::Expression {
                 value: Expression::Index {
-                    left: Box::new(Expression::Identifier("myArray".into())),
-                    index: Box::new(Expression::InfixOperator {
-                        operator: Token::PlusSign,
-                        lh_expression: Box::new(Expression::Integer(1)),
-                        rh_expression: Box::new(Expression::Integer(1)),
-                    }),
+                    left: Box::new(Expression::Identifier("myArray".into()).into()),
+                    index: Box::new(
+                        Expression::InfixOperator {
+                            operator: Token::PlusSign,
+                            lh_expression: Box::new(Expression::Integer(1).into()),
+                            rh_expression: Box::new(Expression::Integer(1).into()),
+                        }
+                        .into()
+                    ),
                 }
+                .into()
             }
         )
     }
@@ -975,11 +2109,12 @@ return true;"#;
         assert_eq!(
             program.statements[0],
             Statement::Expression {
-                value: Expression::HashLiteral(BTreeMap::from([
+                value: hash_literal([
                     (Expression::String("one".into()), Expression::Integer(1)),
                     (Expression::String("two".into()), Expression::Integer(2)),
                     (Expression::String("three".into()), Expression::Integer(3)),
-                ]))
+                ])
+                .into()
             }
         )
     }
@@ -997,11 +2132,12 @@ return true;"#;
         assert_eq!(
             program.statements[0],
             Statement::Expression {
-                value: Expression::HashLiteral(BTreeMap::from([
+                value: hash_literal([
                     (Expression::Integer(1), Expression::String("one".into())),
                     (Expression::Integer(2), Expression::String("two".into())),
                     (Expression::Integer(3), Expression::String("three".into())),
-                ]))
+                ])
+                .into()
             }
         )
     }
@@ -1019,7 +2155,7 @@ return true;"#;
         assert_eq!(
             program.statements[0],
             Statement::Expression {
-                value: Expression::HashLiteral(BTreeMap::from([
+                value: hash_literal([
                     (
                         Expression::Boolean(true),
                         Expression::String("true, that's right".into())
@@ -1028,7 +2164,8 @@ return true;"#;
                         Expression::Boolean(false),
                         Expression::String("nope".into())
                     ),
-                ]))
+                ])
+                .into()
             }
         )
     }
@@ -1046,7 +2183,7 @@ return true;"#;
         assert_eq!(
             program.statements[0],
             Statement::Expression {
-                value: Expression::HashLiteral(BTreeMap::new())
+                value: Expression::HashLiteral(BTreeMap::new()).into()
             }
         )
     }
@@ -1064,33 +2201,308 @@ return true;"#;
         assert_eq!(
             program.statements[0],
             Statement::Expression {
-                value: Expression::HashLiteral(BTreeMap::from([
+                value: hash_literal([
                     (
                         Expression::String("one".into()),
                         Expression::InfixOperator {
                             operator: Token::PlusSign,
-                            lh_expression: Box::new(Expression::Integer(0)),
-                            rh_expression: Box::new(Expression::Integer(1)),
+                            lh_expression: Box::new(Expression::Integer(0).into()),
+                            rh_expression: Box::new(Expression::Integer(1).into()),
                         }
                     ),
                     (
                         Expression::String("two".into()),
                         Expression::InfixOperator {
                             operator: Token::MinusSign,
-                            lh_expression: Box::new(Expression::Integer(10)),
-                            rh_expression: Box::new(Expression::Integer(8)),
+                            lh_expression: Box::new(Expression::Integer(10).into()),
+                            rh_expression: Box::new(Expression::Integer(8).into()),
                         }
                     ),
                     (
                         Expression::String("three".into()),
                         Expression::InfixOperator {
                             operator: Token::Slash,
-                            lh_expression: Box::new(Expression::Integer(15)),
-                            rh_expression: Box::new(Expression::Integer(5)),
+                            lh_expression: Box::new(Expression::Integer(15).into()),
+                            rh_expression: Box::new(Expression::Integer(5).into()),
                         }
                     ),
-                ]))
+                ])
+                .into()
             }
         )
     }
+
+    #[test]
+    fn test_trailing_commas() {
+        let tests = &[
+            ("add(1, 2,)", "add(1, 2)"),
+            ("[1, 2,]", "[1, 2]"),
+            (r#"{"one": 1, "two": 2,}"#, r#"{"one": 1, "two": 2}"#),
+            ("fn(x, y,) { x }", "fn(x, y) { x }"),
+            (
+                "add(\n  [1,],\n  {1: 2,},\n  fn(x,) { x },\n)",
+                "add([1], {1: 2}, fn(x) { x })",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+
+            let mut parser = Parser::new(Lexer::new(expected.to_string()));
+            let expected = parser.parse_program().expect("Failed to parse program");
+            assert_eq!(parser.errors.len(), 0, "{:?}", parser.errors);
+
+            assert_eq!(program.statements, expected.statements, "{input}");
+            assert_eq!(program.to_string(), expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_trailing_commas_errors() {
+        let tests = &[
+            "[,]",
+            "[1,,2]",
+            "[1,,]",
+            "add(,)",
+            "fn(,) {}",
+            "fn(x,,) {}",
+            "{,}",
+            "{1: 2,,}",
+        ];
+
+        for input in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            parser.parse_program().expect("Failed to parse program");
+
+            assert!(!parser.errors.is_empty(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_member_expressions() {
+        let tests = &[
+            ("arr.push(x)", "push(arr, x)"),
+            ("s.len()", "len(s)"),
+            ("arr.rest().first()", "first(rest(arr))"),
+            ("person.name", "(person[\"name\"])"),
+            ("person.address.city", "((person[\"address\"])[\"city\"])"),
+            ("people[0].name.len()", "len(((people[0])[\"name\"]))"),
+            ("-a.b", "(-(a[\"b\"]))"),
+            ("a.add(1, 2 * 3) + 1", "(add(a, 1, (2 * 3)) + 1)"),
+            ("[1, 2].len()", "len([1, 2])"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+            assert_eq!(&program.to_string(), expected);
+        }
+
+        let mut parser = Parser::new(Lexer::new("a.1".into()));
+        parser.parse_program().expect("Failed to parse program");
+        assert_eq!(
+            parser.errors.first().map(|error| error.message.as_str()),
+            Some("expected next token to be Identifier, got Some(Integer(1))")
+        );
+    }
+
+    #[test]
+    fn test_optional_index_expressions() {
+        let tests = &[
+            ("a?.b", "(a?[\"b\"])"),
+            ("a?[0]", "(a?[0])"),
+            ("a?.b?[1 + 1].c", "(((a?[\"b\"])?[(1 + 1)])[\"c\"])"),
+            ("-a?.b", "(-(a?[\"b\"]))"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+            assert_eq!(&program.to_string(), expected);
+        }
+
+        let tests = &[
+            (
+                "a?.1",
+                "expected next token to be Identifier, got Some(Integer(1))",
+            ),
+            (
+                "a?[1",
+                "expected next token to be RightBracket, got Some(Eof), to close the index expression",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            parser.parse_program().expect("Failed to parse program");
+
+            assert_eq!(
+                parser.errors.first().map(|error| error.message.as_str()),
+                Some(*expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_range_expressions() {
+        let tests = &[
+            ("1..5", "(1..5)"),
+            ("a..=b", "(a..=b)"),
+            ("0..n - 1", "(0..(n - 1))"),
+            ("-1..len(a) * 2", "((-1)..(len(a) * 2))"),
+            ("a[1..3]", "(a[(1..3)])"),
+            ("0..5 == r", "((0..5) == r)"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+            assert_eq!(&program.to_string(), expected);
+        }
+
+        let mut parser = Parser::new(Lexer::new("1..=5".into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+        assert_eq!(
+            program.statements,
+            vec![Statement::Expression {
+                value: Expression::Range {
+                    start: Box::new(1.into()),
+                    end: Box::new(5.into()),
+                    inclusive: true,
+                }
+                .into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_for_expression() {
+        let input = "for (i in 0..10) { puts(i); }";
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+
+        assert_eq!(parser.errors.len(), 0, "{:?}", parser.errors);
+        assert_eq!(
+            program.statements,
+            vec![Statement::Expression {
+                value: Expression::For {
+                    variable: "i".into(),
+                    iterable: Box::new(
+                        Expression::Range {
+                            start: Box::new(0.into()),
+                            end: Box::new(10.into()),
+                            inclusive: false,
+                        }
+                        .into()
+                    ),
+                    body: BlockStatement {
+                        statements: vec![Statement::Expression {
+                            value: Expression::FunctionCall {
+                                name: Box::new(Expression::Identifier("puts".into()).into()),
+                                arguments: vec![Expression::Identifier("i".into()).into()],
+                            }
+                            .into()
+                        }
+                        .into()],
+                        ..Default::default()
+                    },
+                }
+                .into()
+            }]
+        );
+        assert_eq!(program.to_string(), "for (i in (0..10)) {puts(i)}");
+
+        let tests = &[
+            (
+                "for (i 0..1) {}",
+                "expected next token to be In, got Some(Integer(0))",
+            ),
+            (
+                "for (1 in a) {}",
+                "expected next token to be Identifier, got Some(Integer(1))",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            parser.parse_program().expect("Failed to parse program");
+
+            assert_eq!(
+                parser.errors.first().map(|error| error.message.as_str()),
+                Some(*expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_while_expression() {
+        let input = "while (i < 3) { i++; }";
+        let mut parser = Parser::new(Lexer::new(input.into()));
+        let program = parser.parse_program().expect("Failed to parse program");
+
+        assert_eq!(parser.errors.len(), 0, "{:?}", parser.errors);
+        assert_eq!(
+            program.statements,
+            vec![Statement::Expression {
+                value: Expression::While {
+                    condition: Box::new(
+                        Expression::InfixOperator {
+                            operator: Token::LessThan,
+                            rh_expression: Box::new(3.into()),
+                            lh_expression: Box::new(Expression::Identifier("i".into()).into()),
+                        }
+                        .into()
+                    ),
+                    body: BlockStatement {
+                        statements: vec![Statement::Expression {
+                            value: Expression::Postfix {
+                                operator: Token::Increment,
+                                name: "i".into(),
+                            }
+                            .into()
+                        }
+                        .into()],
+                        ..Default::default()
+                    },
+                }
+                .into()
+            }]
+        );
+        assert_eq!(program.to_string(), "while ((i < 3)) {(i++)}");
+    }
+
+    #[test]
+    fn test_postfix_expressions() {
+        let tests = &[
+            ("i++", "(i++)"),
+            ("i--", "(i--)"),
+            ("-i++", "(-(i++))"),
+            ("i++ + 1", "((i++) + 1)"),
+            ("a - -b", "(a - (-b))"),
+            ("i++ * j--", "((i++) * (j--))"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program().expect("Failed to parse program");
+
+            assert_eq!(parser.errors.len(), 0, "{input}: {:?}", parser.errors);
+            assert_eq!(program.to_string(), *expected);
+        }
+
+        let mut parser = Parser::new(Lexer::new("a[0]++".into()));
+        parser.parse_program();
+        assert_eq!(
+            parser.errors.first().map(|error| error.message.as_str()),
+            Some("++ can only be applied to an identifier, got (a[0])")
+        );
+    }
 }
@@ -1,15 +1,23 @@
-use crate::ast::Statement;
+use std::collections::BTreeMap;
 
-#[derive(Default)]
+use crate::ast::{pretty_lines, write_inline, Spanned, Statement};
+
+#[derive(Default, Debug, Clone)]
 pub struct Program {
-    pub statements: Vec<Statement>,
+    pub statements: Vec<Spanned<Statement>>,
+    /// Lines of the `//` comments preceding the statements, by index, the ones past the last
+    /// statement being at the end. Only kept by [`super::Parser::with_comments`]
+    pub comments: BTreeMap<usize, Vec<String>>,
 }
 
+/// Written on a single line, or with `{:#}` in the pretty mode, with a statement per line and
+/// the comments kept by the parser.
 impl std::fmt::Display for Program {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for statement in &self.statements {
-            write!(f, "{statement}")?;
+        if f.alternate() {
+            return write!(f, "{}", pretty_lines(&self.statements, &self.comments));
         }
-        Ok(())
+
+        write_inline(f, &self.statements)
     }
 }
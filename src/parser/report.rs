@@ -0,0 +1,229 @@
+use crate::{lexer::Span, parser::Program};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Ord, PartialOrd)]
+#[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
+#[cfg_attr(target_family = "wasm", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(target_family = "wasm", derive(serde::Serialize))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable, kebab-case identifier of the kind of diagnostic, e.g. `unexpected-token`
+    pub code: &'static str,
+    pub message: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, message: String, span: Span) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            message,
+            span,
+        }
+    }
+
+    pub fn warning(code: &'static str, message: String, span: Span) -> Self {
+        Self {
+            severity: Severity::Warning,
+            code,
+            message,
+            span,
+        }
+    }
+
+    /// Renders the diagnostic for humans, pointing at its location within `path`.
+    pub fn render(&self, source: &str, path: &str) -> String {
+        let (line, column) = self.span.line_column(source);
+        format!(
+            "{}[{}]: {}\n  --> {path}:{line}:{column}",
+            self.severity, self.code, self.message
+        )
+    }
+
+    pub fn to_json(&self, source: &str) -> String {
+        let (line, column) = self.span.line_column(source);
+        format!(
+            r#"{{"severity":"{}","code":"{}","message":"{}","span":{{"start":{},"end":{}}},"line":{line},"column":{column}}}"#,
+            self.severity,
+            self.code,
+            escape_json(&self.message),
+            self.span.start,
+            self.span.end,
+        )
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Everything the parser has to say about a source, alongside the program whenever it
+/// produced something usable, even if errors were reported.
+#[derive(Debug)]
+pub struct ParseReport {
+    pub diagnostics: Vec<Diagnostic>,
+    pub program: Option<Program>,
+}
+
+impl ParseReport {
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.with_severity(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.with_severity(Severity::Warning)
+    }
+
+    fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(move |diagnostic| diagnostic.severity == severity)
+    }
+
+    /// Promotes every warning to an error, making them fail the report.
+    pub fn deny_warnings(&mut self) {
+        for diagnostic in self.diagnostics.iter_mut() {
+            diagnostic.severity = Severity::Error;
+        }
+    }
+
+    /// Drops the warnings, returning the program only if there were no errors.
+    pub fn into_result(self) -> Result<Program, Vec<Diagnostic>> {
+        match self.program {
+            Some(program) if !self.has_errors() => Ok(program),
+            _ => Err(self
+                .diagnostics
+                .into_iter()
+                .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                .collect()),
+        }
+    }
+
+    pub fn to_json(&self, source: &str) -> String {
+        format!(
+            r#"{{"has_program":{},"diagnostics":[{}]}}"#,
+            self.program.is_some(),
+            self.diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.to_json(source))
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    }
+}
+
+pub(crate) fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_with_diagnostics;
+
+    use super::*;
+
+    const SOURCE: &str = r#"let a = {"x": 1, "x": 2};
+let b = {1: true, 1: false};
+let c = ;"#;
+
+    #[test]
+    fn test_report_with_errors_and_warnings() {
+        let report = parse_with_diagnostics(SOURCE);
+
+        assert!(report.has_errors());
+        assert!(report.program.is_some());
+        assert_eq!(
+            report
+                .diagnostics
+                .iter()
+                .map(|diagnostic| (diagnostic.severity, diagnostic.code))
+                .collect::<Vec<_>>(),
+            vec![
+                (Severity::Warning, "duplicate-key"),
+                (Severity::Warning, "duplicate-key"),
+                (Severity::Error, "no-prefix-parser"),
+            ]
+        );
+        assert_eq!(
+            report.warnings().next().unwrap().render(SOURCE, "main.mk"),
+            "warning[duplicate-key]: duplicate key \"x\" in hash literal, the last value wins\n  --> main.mk:1:18"
+        );
+        assert_eq!(report.into_result().unwrap_err().len(), 1);
+    }
+
+    #[test]
+    fn test_report_deny_warnings() {
+        let mut report = parse_with_diagnostics(r#"{"x": 1, "x": 2}"#);
+
+        assert!(!report.has_errors());
+        assert_eq!(report.warnings().count(), 1);
+
+        report.deny_warnings();
+
+        assert!(report.has_errors());
+        assert_eq!(report.warnings().count(), 0);
+        assert!(report.into_result().is_err());
+    }
+
+    #[test]
+    fn test_report_without_diagnostics() {
+        let report = parse_with_diagnostics("let a = 1;");
+
+        assert!(report.diagnostics.is_empty());
+        assert_eq!(report.into_result().unwrap().statements.len(), 1);
+    }
+
+    #[test]
+    fn test_report_json() {
+        let source = "{1: 1, 1: 2};\nlet x = ;";
+        let report = parse_with_diagnostics(source);
+
+        assert_eq!(
+            report.to_json(source),
+            concat!(
+                r#"{"has_program":true,"diagnostics":["#,
+                r#"{"severity":"warning","code":"duplicate-key","message":"duplicate key 1 in hash literal, the last value wins","span":{"start":7,"end":8},"line":1,"column":8},"#,
+                r#"{"severity":"error","code":"no-prefix-parser","message":"no expression statement parser for ;","span":{"start":22,"end":23},"line":2,"column":9}"#,
+                "]}"
+            )
+        );
+    }
+
+    #[test]
+    fn test_escape_json() {
+        assert_eq!(escape_json("a \"b\"\n\\c\u{1}"), r#"a \"b\"\n\\c\u0001"#);
+    }
+}
@@ -1,63 +1,156 @@
 use crate::{
-    ast,
-    evaluator::{self, object::Object, Evaluator},
-    lexer, parser,
+    analyzer, ast,
+    evaluator::{
+        self,
+        context::{EvalContext, EvalOptions},
+        error::{EvalError, Frame},
+        macros::define_and_expand_macros,
+        Evaluator,
+    },
+    lexer, optimizer, parser,
 };
 use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 pub mod serialization;
 
+/// Estimated bytes a program can allocate before being stopped, so that a runaway script
+/// errors out instead of exhausting the memory of the page.
+const MAX_TOTAL_ALLOCATED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Most elements of an array, and entries of a hash, a program can build, so that a single
+/// collection can't take most of the memory budget at once.
+const MAX_COLLECTION_LENGTH: usize = 1_000_000;
+
+/// Most bytes of a string a program can build.
+const MAX_STRING_LENGTH: usize = 16 * 1024 * 1024;
+
+/// Largest program accepted, so that pasting a huge input errors out before being lexed.
+const MAX_SOURCE_BYTES: usize = 1024 * 1024;
+
+/// Steps a program can take by default before being stopped, so that an infinite loop errors
+/// out instead of freezing the page.
+const MAX_STEPS: usize = 5_000_000;
+
+/// Deepest nesting of function calls, lower than natively as the page only gives the module a
+/// 1 MiB stack, so that a runaway recursion errors out instead of trapping.
+const MAX_CALL_DEPTH: usize = 200;
+
+/// Deepest nesting of expressions being evaluated across function calls, for the same reason,
+/// enough for a recursion as deep as `MAX_CALL_DEPTH` nesting a couple of expressions per call.
+const MAX_NESTING_DEPTH: usize = 400;
+
+/// Stack the evaluation can take, leaving a quarter of the one the page gives the module to the
+/// frames calling into it, as the depths above can take more than that depending on the build.
+const MAX_STACK_BYTES: usize = 768 * 1024;
+
+fn options(max_steps: Option<usize>) -> EvalOptions {
+    EvalOptions {
+        max_total_allocated_bytes: MAX_TOTAL_ALLOCATED_BYTES,
+        max_array_length: MAX_COLLECTION_LENGTH,
+        max_string_length: MAX_STRING_LENGTH,
+        max_hash_entries: MAX_COLLECTION_LENGTH,
+        max_source_bytes: MAX_SOURCE_BYTES,
+        max_steps: max_steps.unwrap_or(MAX_STEPS),
+        max_call_depth: MAX_CALL_DEPTH,
+        max_nesting_depth: MAX_NESTING_DEPTH,
+        max_stack_bytes: MAX_STACK_BYTES,
+    }
+}
+
 #[derive(Serialize, Default)]
 struct EvaluationResult {
-    statements: Vec<ast::Statement>,
+    statements: Vec<ast::Spanned<ast::Statement>>,
+    /// Statements once operators on literals are folded and branches never taken are removed
+    optimized: Vec<ast::Spanned<ast::Statement>>,
     program: String,
     errors: Vec<String>,
+    /// Parser warnings and the problems found by the analysis, e.g. unused bindings
+    warnings: Vec<parser::Diagnostic>,
+    /// Function calls the evaluation error unwound out of, innermost first
+    trace: Vec<Frame>,
     environment: Option<evaluator::environment::Environment>,
     output: Option<String>,
+    /// Code the program called `exit` with, if it did
+    exit_code: Option<i32>,
 }
 
 #[wasm_bindgen(skip_typescript)]
-pub fn execute(input: &str) -> JsValue {
+pub fn execute(input: &str, max_steps: Option<usize>, shadowing: Option<bool>) -> JsValue {
     console_error_panic_hook::set_once();
 
-    let lexer = lexer::Lexer::new(input.into());
-    let mut parser = parser::Parser::new(lexer);
+    let shadowing = shadowing.unwrap_or_default();
+    serde_wasm_bindgen::to_value(&evaluate(input, max_steps, shadowing)).unwrap()
+}
 
+fn evaluate(input: &str, max_steps: Option<usize>, shadowing: bool) -> EvaluationResult {
     let mut result = EvaluationResult::default();
 
+    let options = options(max_steps);
+    if let Err(diagnostic) = options.check_source_size(input) {
+        result.errors.push(diagnostic.to_string());
+        return result;
+    }
+
+    let lexer = lexer::Lexer::new(input.into());
+    let mut parser = parser::Parser::new(lexer);
+
     let Some(program) = parser.parse_program() else {
-        return serde_wasm_bindgen::to_value(&result).unwrap();
+        return result;
     };
 
     result.program = program.to_string();
+    result.warnings = parser.warnings.clone();
 
     if parser.errors.len() > 0 {
         result.statements = program.statements;
-        result.errors = parser.errors;
-        return serde_wasm_bindgen::to_value(&result).unwrap();
+        result.errors = parser.errors.iter().map(ToString::to_string).collect();
+        return result;
     }
 
-    let mut environment = evaluator::environment::Environment::new();
-    let output = program.eval(&mut environment);
+    let environment = evaluator::environment::Environment::shared();
+    let mut context = EvalContext::new(options);
+    let output = match define_and_expand_macros(program, &environment, &mut context) {
+        Ok(program) => {
+            let analysis = analyzer::AnalysisOptions {
+                shadowing: shadowing.then_some(input),
+            };
+            result
+                .warnings
+                .extend(analyzer::analyze_with(&program, analysis));
+            let output = program.eval_with_context(&environment, &mut context);
+            result.optimized = optimizer::optimize(program.clone()).statements;
+            result.statements = program.statements;
+            output
+        }
+        Err(error) => Err(error),
+    };
 
-    result.statements = program.statements;
-    result.errors = parser.errors;
-    result.environment = Some(environment);
+    result.environment = Some(environment.take());
 
-    if let Some(Object::Error(error)) = output {
-        result.errors.push(error.clone());
-    } else {
-        result.output = output.map(|output| output.inspect());
+    match output {
+        Ok(output) => result.output = output.map(|output| output.to_string()),
+        Err(EvalError::Exit(code)) => result.exit_code = Some(code),
+        Err(error) => {
+            match error.cause() {
+                EvalError::Message(message) => result.errors.push(message.clone()),
+                cause => result.errors.push(cause.to_string()),
+            }
+            result.trace = error.trace().to_vec();
+        }
     }
 
-    serde_wasm_bindgen::to_value(&result).unwrap()
+    result
 }
 
 #[wasm_bindgen(js_name = lexer, skip_typescript)]
 pub fn lexer_tokenizer(input: &str) -> JsValue {
     console_error_panic_hook::set_once();
 
+    if input.len() > MAX_SOURCE_BYTES {
+        return serde_wasm_bindgen::to_value(&Vec::<lexer::Token>::new()).unwrap();
+    }
+
     let tokens = lexer::Lexer::new(input.into())
         .into_iter()
         .collect::<Vec<_>>();
@@ -65,5 +158,137 @@ pub fn lexer_tokenizer(input: &str) -> JsValue {
     serde_wasm_bindgen::to_value(&tokens).unwrap()
 }
 
+#[wasm_bindgen(skip_typescript)]
+pub fn capabilities() -> JsValue {
+    serde_wasm_bindgen::to_value(&crate::capabilities::capabilities()).unwrap()
+}
+
 #[wasm_bindgen(typescript_custom_section)]
 const TS_APPEND_CONTENT: &'static str = include_str!("monkey_interpreter.d.ts");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_source_too_large() {
+        let source = format!(r#""{}""#, "a".repeat(MAX_SOURCE_BYTES - 1));
+        let result = evaluate(&source, None, false);
+
+        assert_eq!(
+            result.errors,
+            vec![format!(
+                "source too large ({} bytes, limit {MAX_SOURCE_BYTES})",
+                MAX_SOURCE_BYTES + 1
+            )]
+        );
+        assert!(result.statements.is_empty());
+        assert_eq!(result.output, None);
+
+        let source = format!(r#""{}""#, "a".repeat(MAX_SOURCE_BYTES - 2));
+        assert!(evaluate(&source, None, false).errors.is_empty());
+    }
+
+    /// Same as what the REPL echoes: strings are quoted within collections only.
+    #[test]
+    fn test_evaluate_output_quotes_nested_strings() {
+        let tests = [
+            (r#""a, \"b\"""#, r#"a, "b""#),
+            (r#"["a, \"b\"", "c"]"#, r#"["a, \"b\"", "c"]"#),
+            (
+                r#"fn() { return {"k": "line\nbreak"} }()"#,
+                r#"{"k": "line\nbreak"}"#,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                evaluate(input, None, false).output,
+                Some(expected.into()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluate_exit() {
+        let result = evaluate("let f = fn() { exit(3) }; f(); 1", None, false);
+
+        assert_eq!(result.exit_code, Some(3));
+        assert_eq!(result.output, None);
+        assert!(result.errors.is_empty());
+        assert!(result.trace.is_empty());
+        assert_eq!(evaluate("1", None, false).exit_code, None);
+    }
+
+    #[test]
+    fn test_evaluate_shadowing_warnings() {
+        let source = "let x = 1;\nlet f = fn() { let x = 2; x };\nf() + x";
+        let result = evaluate(source, None, true);
+
+        assert_eq!(result.output, Some("3".into()));
+        assert_eq!(
+            result
+                .warnings
+                .iter()
+                .map(|warning| warning.message.as_str())
+                .collect::<Vec<_>>(),
+            ["let `x` shadows a binding from an enclosing scope at line 1"]
+        );
+
+        let result = evaluate(source, None, false);
+        assert_eq!(result.output, Some("3".into()));
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_evaluate_step_budget() {
+        let fib = "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(15)";
+        let result = evaluate(fib, None, false);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.output, Some("610".into()));
+
+        assert_eq!(
+            evaluate("let f = fn() { f() }; f();", Some(10_000), false).errors,
+            vec!["evaluation budget exceeded after 10000 steps"]
+        );
+        assert_eq!(
+            evaluate("while (true) { 1 }", Some(10_000), false).errors,
+            vec!["evaluation budget exceeded after 10000 steps"]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_error_trace() {
+        let result = evaluate("let f = fn() { 1 + true }; [f()]", None, false);
+
+        assert_eq!(
+            result.errors,
+            vec!["type mismatch: INTEGER + BOOLEAN in '(1 + true)'"]
+        );
+        assert_eq!(
+            result.trace,
+            vec![Frame {
+                function: "f".into(),
+                span: lexer::Span::new(28, 29),
+            }]
+        );
+        assert!(evaluate("1 + true", None, false).trace.is_empty());
+    }
+
+    #[test]
+    fn test_evaluate_deeply_nested() {
+        let source = format!("{}1{}", "(".repeat(5_000), ")".repeat(5_000));
+
+        assert_eq!(
+            evaluate(&source, None, false).errors,
+            vec!["expression nesting too deep (limit 512)"]
+        );
+
+        let source = format!("let a = 1; {}", vec!["a"; 50_000].join(" + "));
+        assert_eq!(
+            evaluate(&source, None, false).errors,
+            vec!["expression nesting too deep (limit 512)"]
+        );
+    }
+}
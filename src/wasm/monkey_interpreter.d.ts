@@ -1,9 +1,17 @@
 /**
- * Parses and executes the provided Monkey code.
+ * Parses and executes the provided Monkey code, stopping it with an error after `maxSteps`
+ * statements and expressions, 5 million by default. With `shadowing`, the warnings also report
+ * the bindings shadowing or redeclaring another one.
  * @param {string} input
+ * @param {number} [maxSteps]
+ * @param {boolean} [shadowing]
  * @returns {EvaluationResult[]}
  */
-export function execute(input: string): EvaluationResult;
+export function execute(
+  input: string,
+  maxSteps?: number,
+  shadowing?: boolean,
+): EvaluationResult;
 
 /**
  * Tokenizes the provided Monkey code.
@@ -12,18 +20,63 @@ export function execute(input: string): EvaluationResult;
  */
 export function lexer(input: string): Token[];
 
+/**
+ * Version and features of the interpreter, e.g. to hide examples relying on unsupported ones.
+ * @returns {Capabilities}
+ */
+export function capabilities(): Capabilities;
+
+interface Capabilities {
+  version: string;
+  features: string[];
+}
+
 interface EvaluationResult {
   statements: Statement[];
+  /** Statements once operators on literals are folded and branches never taken are removed. */
+  optimized: Statement[];
   program: string;
   errors: string[];
+  /** Parser warnings and problems found before running, e.g. undefined identifiers or unused bindings. */
+  warnings: Diagnostic[];
+  /** Function calls the error unwound out of, innermost first. */
+  trace: Frame[];
   environment?: Environment;
   output?: string;
+  /** Code the program called `exit` with, which ends the program rather than the page. */
+  exit_code?: number;
+}
+
+/** Problem found in the source, `code` being a stable kebab-case identifier of its kind. */
+interface Diagnostic {
+  severity: "warning" | "error";
+  code: string;
+  message: string;
+  span: Span;
+}
+
+/** Character offsets of a piece of source code, `end` being exclusive. */
+interface Span {
+  start: number;
+  end: number;
 }
 
-type Expression =
+/** Function call an error unwound out of, `function` being `<anonymous>` when not called by name. */
+interface Frame {
+  function: string;
+  span: Span;
+}
+
+/** Integer as a number, or as a string of its digits past `Number.MAX_SAFE_INTEGER`. */
+type Integer = number | string;
+
+/** A node of the AST along with the span of the source it was parsed from. */
+type Spanned<T> = T & { span: Span };
+
+type Expression = Spanned<
   | {
       kind: "integer";
-      value: number;
+      value: Integer;
     }
   | {
       kind: "identifier";
@@ -33,6 +86,9 @@ type Expression =
       kind: "boolean";
       value: boolean;
     }
+  | {
+      kind: "null";
+    }
   | {
       kind: "string";
       value: string;
@@ -67,6 +123,28 @@ type Expression =
         alternative?: Statement[];
       };
     }
+  | {
+      kind: "for";
+      value: {
+        variable: string;
+        iterable: Expression;
+        body: Statement[];
+      };
+    }
+  | {
+      kind: "while";
+      value: {
+        condition: Expression;
+        body: Statement[];
+      };
+    }
+  | {
+      kind: "postfix";
+      value: {
+        operator: Token;
+        name: string;
+      };
+    }
   | {
       kind: "function";
       value: {
@@ -74,6 +152,13 @@ type Expression =
         body: Statement[];
       };
     }
+  | {
+      kind: "macro";
+      value: {
+        arguments: string[];
+        body: Statement[];
+      };
+    }
   | {
       kind: "array";
       value: Expression[];
@@ -85,12 +170,28 @@ type Expression =
         index: Expression;
       };
     }
+  | {
+      kind: "range";
+      value: {
+        start: Expression;
+        end: Expression;
+        inclusive: boolean;
+      };
+    }
+  | {
+      kind: "optional_index";
+      value: {
+        left: Expression;
+        index: Expression;
+      };
+    }
   | {
       kind: "hash_literal";
       value: { key: Expression; value: Expression }[];
-    };
+    }
+>;
 
-type Statement =
+type Statement = Spanned<
   | {
       kind: "let";
       value: {
@@ -98,24 +199,54 @@ type Statement =
         value: Expression;
       };
     }
+  | {
+      kind: "const";
+      value: {
+        name: string;
+        value: Expression;
+      };
+    }
+  | {
+      kind: "destructure";
+      value: {
+        pattern: Pattern;
+        value: Expression;
+      };
+    }
   | {
       kind: "return";
       value: Expression;
     }
+  | {
+      kind: "throw";
+      value: Expression;
+    }
   | {
       kind: "expression";
       value: Expression;
+    }
+>;
+
+type Pattern =
+  | {
+      kind: "hash";
+      value: [key: string, binding: string][];
+    }
+  | {
+      kind: "array";
+      value: string[];
     };
 
 interface Environment {
   store: Record<string, Object>;
+  constants: string[];
   parent?: Environment;
 }
 
-type Object =
+type HashKey =
   | {
-      kind: "interger";
-      value: number;
+      kind: "integer";
+      value: Integer;
     }
   | {
       kind: "boolean";
@@ -124,20 +255,36 @@ type Object =
   | {
       kind: "string";
       value: string;
+    };
+
+type Object =
+  | {
+      kind: "integer";
+      value: Integer;
     }
   | {
-      kind: "return";
-      value: Object;
+      kind: "boolean";
+      value: boolean;
     }
   | {
-      kind: "error";
+      kind: "string";
       value: string;
     }
   | {
       kind: "function";
       value: {
         parameters: string[];
-        environment: Environment;
+        body: Statement[];
+      };
+    }
+  | {
+      kind: "quote";
+      value: Expression;
+    }
+  | {
+      kind: "macro";
+      value: {
+        parameters: string[];
         body: Statement[];
       };
     }
@@ -147,7 +294,14 @@ type Object =
     }
   | {
       kind: "hash";
-      value: Record<Object, Object>;
+      value: { key: HashKey; value: Object }[];
+    }
+  | {
+      kind: "range";
+      value: {
+        start: Integer;
+        end: Integer;
+      };
     }
   | {
       kind: "null";
@@ -156,7 +310,7 @@ type Object =
 type Token =
   | {
       kind: "integer";
-      value: number;
+      value: Integer;
     }
   | {
       kind: "identifier";
@@ -191,12 +345,27 @@ type Token =
         | "left_bracket"
         | "right_bracket"
         | "colon"
+        | "dot"
+        | "question_dot"
+        | "question_bracket"
+        | "double_question"
+        | "dot_dot"
+        | "dot_dot_equal"
+        | "increment"
+        | "decrement"
         | "function"
+        | "macro"
         | "true"
         | "false"
+        | "null"
         | "if"
         | "else"
+        | "for"
+        | "in"
+        | "while"
         | "let"
+        | "const"
         | "return"
+        | "throw"
         | "eof";
     };
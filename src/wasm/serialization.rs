@@ -1,6 +1,7 @@
+use std::{collections::HashMap, rc::Rc};
+
 use serde::ser::SerializeSeq;
 use serde::{Serialize, Serializer};
-use std::collections::BTreeMap;
 
 #[derive(Serialize)]
 struct Entry<K: Serialize, V: Serialize> {
@@ -8,15 +9,43 @@ struct Entry<K: Serialize, V: Serialize> {
     value: V,
 }
 
-pub fn serialize<S, K, V>(map: &BTreeMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+/// Serializes a map as a list of its entries ordered by key, as objects can't be JavaScript keys.
+pub fn serialize<'a, S, M, K, V>(map: &'a M, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
-    K: Serialize + Ord,
-    V: Serialize,
+    &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+    K: Serialize + Ord + 'a,
+    V: Serialize + 'a,
 {
-    let mut seq = serializer.serialize_seq(Some(map.len()))?;
-    for (key, value) in map {
+    let mut entries = map.into_iter().collect::<Vec<_>>();
+    entries.sort_by_key(|(key, _)| *key);
+
+    let mut seq = serializer.serialize_seq(Some(entries.len()))?;
+    for (key, value) in entries {
         seq.serialize_element(&Entry { key, value })?;
     }
     seq.end()
 }
+
+/// Serializes a shared map as [`serialize`] does.
+pub fn shared<S, K, V>(map: &Rc<HashMap<K, V>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Serialize + Ord,
+    V: Serialize,
+{
+    serialize(&**map, serializer)
+}
+
+/// Largest integer JavaScript numbers hold exactly, `Number.MAX_SAFE_INTEGER`.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Serializes an integer as a number, or as a string of its digits when a JavaScript number
+/// would round it.
+pub fn integer<S: Serializer>(value: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    if value.unsigned_abs() <= MAX_SAFE_INTEGER {
+        serializer.serialize_i64(*value)
+    } else {
+        serializer.collect_str(value)
+    }
+}